@@ -0,0 +1,256 @@
+// std imports
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+// 3rd party imports
+use anyhow::{bail, Context, Result};
+use lazy_static::lazy_static;
+
+// internal imports
+use crate::chemistry::amino_acid::{
+    get_amino_acid_by_one_letter_code, AminoAcid, CANONICAL_AMINO_ACIDS,
+};
+use crate::chemistry::isotope::{Isotope, CARBON_13, HYDROGEN_2, NITROGEN_15};
+
+lazy_static! {
+    /// Lysine with six 13C atoms (Lys6)
+    pub static ref LYSINE_13C6: LabeledAminoAcid =
+        LabeledAminoAcid::new("Lys6", 'K', vec![(&CARBON_13, 6)]).unwrap();
+    /// Lysine with six 13C and two 15N atoms (Lys8)
+    pub static ref LYSINE_13C6_15N2: LabeledAminoAcid =
+        LabeledAminoAcid::new("Lys8", 'K', vec![(&CARBON_13, 6), (&NITROGEN_15, 2)]).unwrap();
+    /// Lysine with four deuterium atoms (Lys4)
+    pub static ref LYSINE_D4: LabeledAminoAcid =
+        LabeledAminoAcid::new("Lys4", 'K', vec![(&HYDROGEN_2, 4)]).unwrap();
+    /// Arginine with six 13C atoms (Arg6)
+    pub static ref ARGININE_13C6: LabeledAminoAcid =
+        LabeledAminoAcid::new("Arg6", 'R', vec![(&CARBON_13, 6)]).unwrap();
+    /// Arginine with six 13C and four 15N atoms (Arg10)
+    pub static ref ARGININE_13C6_15N4: LabeledAminoAcid =
+        LabeledAminoAcid::new("Arg10", 'R', vec![(&CARBON_13, 6), (&NITROGEN_15, 4)]).unwrap();
+}
+
+/// Parses an elemental composition like `C6H12N2O` into element counts
+///
+/// # Arguments
+/// * `composition` - Elemental composition
+///
+fn parse_composition(composition: &str) -> Result<HashMap<String, usize>> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut chars = composition.chars().peekable();
+    while let Some(first) = chars.next() {
+        if !first.is_ascii_uppercase() {
+            bail!("Invalid composition: {}", composition);
+        }
+        let mut symbol = first.to_string();
+        while let Some(next) = chars.next_if(|c| c.is_ascii_lowercase()) {
+            symbol.push(next);
+        }
+        let mut count = String::new();
+        while let Some(next) = chars.next_if(|c| c.is_ascii_digit()) {
+            count.push(next);
+        }
+        let count = match count.is_empty() {
+            true => 1,
+            false => count.parse::<usize>()?,
+        };
+        *counts.entry(symbol).or_default() += count;
+    }
+    Ok(counts)
+}
+
+/// Amino acid where atoms are substituted by stable isotopes, e.g. for SILAC.
+/// It keeps the one letter code of the unlabeled amino acid.
+///
+pub struct LabeledAminoAcid {
+    name: &'static str,
+    amino_acid: &'static dyn AminoAcid,
+    substitutions: Vec<(&'static Isotope, usize)>,
+    mass_shift: f64,
+    mono_mass: f64,
+    average_mass: f64,
+}
+
+impl LabeledAminoAcid {
+    /// Creates a new labeled amino acid.
+    /// For canonical amino acids the substitutions are checked against the elemental composition.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the label, e.g. `Lys8`
+    /// * `code` - One letter code of the unlabeled amino acid
+    /// * `substitutions` - Isotopes and the number of substituted atoms
+    ///
+    pub fn new(
+        name: &'static str,
+        code: char,
+        substitutions: Vec<(&'static Isotope, usize)>,
+    ) -> Result<Self> {
+        let amino_acid = get_amino_acid_by_one_letter_code(code)?;
+        let canonical_amino_acid = CANONICAL_AMINO_ACIDS
+            .iter()
+            .find(|canonical| canonical.get_one_letter_code() == amino_acid.get_one_letter_code());
+        if let Some(canonical_amino_acid) = canonical_amino_acid {
+            let composition =
+                parse_composition(canonical_amino_acid.get_composition()).context(format!(
+                    "Error when parsing composition of {}",
+                    canonical_amino_acid.get_name()
+                ))?;
+            for (isotope, _) in substitutions.iter() {
+                let symbol = isotope.get_element().get_symbol();
+                let available = composition.get(symbol).copied().unwrap_or_default();
+                let substituted: usize = substitutions
+                    .iter()
+                    .filter(|(other, _)| other.get_element().get_symbol() == symbol)
+                    .map(|(_, other_count)| other_count)
+                    .sum();
+                if substituted > available {
+                    bail!(
+                        "Cannot substitute {} {} atoms of {} which has only {}",
+                        substituted,
+                        symbol,
+                        amino_acid.get_name(),
+                        available
+                    );
+                }
+            }
+        }
+        let mass_shift = substitutions
+            .iter()
+            .map(|(isotope, count)| isotope.get_mass_shift() * *count as f64)
+            .sum::<f64>();
+        let average_mass_shift = substitutions
+            .iter()
+            .map(|(isotope, count)| isotope.get_average_mass_shift() * *count as f64)
+            .sum::<f64>();
+        Ok(Self {
+            name,
+            amino_acid,
+            substitutions,
+            mass_shift,
+            mono_mass: amino_acid.get_mono_mass() + mass_shift,
+            average_mass: amino_acid.get_average_mass() + average_mass_shift,
+        })
+    }
+
+    /// Returns the unlabeled amino acid
+    ///
+    pub fn get_unlabeled_amino_acid(&self) -> &'static dyn AminoAcid {
+        self.amino_acid
+    }
+
+    /// Returns the isotope substitutions
+    ///
+    pub fn get_substitutions(&self) -> &Vec<(&'static Isotope, usize)> {
+        &self.substitutions
+    }
+
+    /// Returns the mass shift compared to the unlabeled amino acid
+    ///
+    pub fn get_mass_shift(&self) -> f64 {
+        self.mass_shift
+    }
+}
+
+impl PartialEq for LabeledAminoAcid {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.get_code() == other.get_code()
+            && self.mono_mass == other.mono_mass
+    }
+}
+
+impl Debug for LabeledAminoAcid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LabeledAminoAcid")
+            .field("name", &self.name)
+            .field("code", self.get_code())
+            .field("mass_shift", &self.mass_shift)
+            .finish()
+    }
+}
+
+impl AminoAcid for LabeledAminoAcid {
+    fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn get_one_letter_code(&self) -> &char {
+        self.amino_acid.get_one_letter_code()
+    }
+
+    fn get_code(&self) -> &char {
+        self.amino_acid.get_one_letter_code()
+    }
+
+    fn get_three_letter_code(&self) -> &'static str {
+        self.amino_acid.get_three_letter_code()
+    }
+
+    fn get_abbreviation(&self) -> &'static str {
+        self.amino_acid.get_three_letter_code()
+    }
+
+    fn get_mono_mass(&self) -> &f64 {
+        &self.mono_mass
+    }
+
+    fn get_average_mass(&self) -> &f64 {
+        &self.average_mass
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chemistry::element::CARBON;
+    use crate::chemistry::isotope::OXYGEN_18;
+
+    #[test]
+    fn test_parse_composition() {
+        let composition = parse_composition("C6H12N2O").unwrap();
+        assert_eq!(composition["C"], 6);
+        assert_eq!(composition["H"], 12);
+        assert_eq!(composition["N"], 2);
+        assert_eq!(composition["O"], 1);
+        assert!(parse_composition("6C").is_err());
+    }
+
+    #[test]
+    fn test_labeled_amino_acids() {
+        // Unimod mass shifts
+        assert!((LYSINE_13C6.get_mass_shift() - 6.020129).abs() < 1e-6);
+        assert!((LYSINE_13C6_15N2.get_mass_shift() - 8.014199).abs() < 1e-6);
+        assert!((LYSINE_D4.get_mass_shift() - 4.025107).abs() < 1e-6);
+        assert!((ARGININE_13C6.get_mass_shift() - 6.020129).abs() < 1e-6);
+        assert!((ARGININE_13C6_15N4.get_mass_shift() - 10.008269).abs() < 1e-6);
+
+        assert_eq!(*LYSINE_13C6_15N2.get_code(), 'K');
+        assert_eq!(LYSINE_13C6_15N2.get_name(), "Lys8");
+        assert_eq!(
+            *LYSINE_13C6_15N2.get_mono_mass(),
+            LYSINE_13C6_15N2.get_unlabeled_amino_acid().get_mono_mass()
+                + LYSINE_13C6_15N2.get_mass_shift()
+        );
+
+        // the average mass uses the natural isotope abundance of the substituted atoms
+        assert!(
+            (LYSINE_13C6.get_average_mass()
+                - LYSINE_13C6.get_unlabeled_amino_acid().get_average_mass()
+                - 6.0 * (13.0033548378 - CARBON.get_average_mass()))
+            .abs()
+                < 1e-9
+        );
+        assert!(
+            (LYSINE_13C6.get_average_mass()
+                - LYSINE_13C6.get_unlabeled_amino_acid().get_average_mass()
+                - LYSINE_13C6.get_mass_shift())
+            .abs()
+                > 1e-3
+        );
+
+        // Lysine has only 6 carbon atoms and 1 oxygen atom
+        assert!(LabeledAminoAcid::new("Lys7", 'K', vec![(&CARBON_13, 7)]).is_err());
+        assert!(LabeledAminoAcid::new("Lys", 'K', vec![(&OXYGEN_18, 1)]).is_ok());
+        assert!(LabeledAminoAcid::new("Lys", 'K', vec![(&OXYGEN_18, 2)]).is_err());
+    }
+}
@@ -3,5 +3,7 @@
 pub mod amino_acid;
 /// Hydrophobicity scales
 pub mod hydrophobicity;
+/// Amino acids labeled with stable isotopes, e.g. for SILAC
+pub mod labeled_amino_acid;
 /// Rexport amino acid module for FASTER access
 pub use amino_acid::*;
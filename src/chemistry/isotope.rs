@@ -0,0 +1,114 @@
+// 3rd party imports
+use anyhow::{bail, Result};
+
+// internal imports
+use crate::chemistry::element::{Element, CARBON, HYDROGEN, NITROGEN, OXYGEN};
+
+/// Stable isotope used for metabolic or chemical labelling
+///
+pub struct Isotope {
+    element: &'static Element,
+    mass_number: u16,
+    mono_mass: f64,
+}
+
+impl Isotope {
+    /// Returns the element
+    ///
+    pub fn get_element(&self) -> &'static Element {
+        self.element
+    }
+
+    /// Returns the mass number
+    ///
+    pub fn get_mass_number(&self) -> u16 {
+        self.mass_number
+    }
+
+    /// Returns the monoisotopic mass of the isotope.
+    ///
+    pub fn get_mono_mass(&self) -> &f64 {
+        &self.mono_mass
+    }
+
+    /// Returns the mass shift when substituting the most abundant isotope of the element with this isotope.
+    ///
+    pub fn get_mass_shift(&self) -> f64 {
+        self.mono_mass - self.element.get_mono_mass()
+    }
+
+    /// Returns the average mass shift when substituting an atom of natural isotope abundance with this isotope.
+    ///
+    pub fn get_average_mass_shift(&self) -> f64 {
+        self.mono_mass - self.element.get_average_mass()
+    }
+
+    /// Returns the isotope notation, e.g. `13C`
+    ///
+    pub fn get_notation(&self) -> String {
+        format!("{}{}", self.mass_number, self.element.get_symbol())
+    }
+}
+
+/// Deuterium
+pub const HYDROGEN_2: Isotope = Isotope {
+    element: &HYDROGEN,
+    mass_number: 2,
+    mono_mass: 2.0141017778,
+};
+
+/// Carbon-13
+pub const CARBON_13: Isotope = Isotope {
+    element: &CARBON,
+    mass_number: 13,
+    mono_mass: 13.0033548378,
+};
+
+/// Nitrogen-15
+pub const NITROGEN_15: Isotope = Isotope {
+    element: &NITROGEN,
+    mass_number: 15,
+    mono_mass: 15.0001088982,
+};
+
+/// Oxygen-18
+pub const OXYGEN_18: Isotope = Isotope {
+    element: &OXYGEN,
+    mass_number: 18,
+    mono_mass: 17.9991610,
+};
+
+/// Array of stable isotopes
+///
+pub const ISOTOPES: [Isotope; 4] = [HYDROGEN_2, CARBON_13, NITROGEN_15, OXYGEN_18];
+
+/// Get isotope by notation, e.g. `13C` or `2H`
+///
+/// # Arguments
+/// * `notation` - Mass number followed by the element symbol
+///
+pub fn get_isotope_by_notation(notation: &str) -> Result<&'static Isotope> {
+    match notation.to_ascii_lowercase().as_str() {
+        "2h" | "d" => Ok(&HYDROGEN_2),
+        "13c" => Ok(&CARBON_13),
+        "15n" => Ok(&NITROGEN_15),
+        "18o" => Ok(&OXYGEN_18),
+        _ => bail!("Invalid isotope notation: {}", notation),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_isotopes() {
+        for isotope in ISOTOPES.iter() {
+            let other = get_isotope_by_notation(&isotope.get_notation()).unwrap();
+            assert_eq!(other.get_mono_mass(), isotope.get_mono_mass());
+            assert!(isotope.get_mass_shift() > 0.99);
+        }
+        assert!((CARBON_13.get_mass_shift() - 1.0033548378).abs() < 1e-9);
+        assert!(get_isotope_by_notation("14C").is_err());
+    }
+}
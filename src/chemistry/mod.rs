@@ -3,5 +3,7 @@
 pub mod amino_acid;
 /// Incomplete periodic table
 pub mod element;
+/// Stable isotopes for labelling
+pub mod isotope;
 /// Masses of subatomic particles
 pub mod subatomic_particle;
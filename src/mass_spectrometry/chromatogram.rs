@@ -0,0 +1,457 @@
+// std imports
+use std::io::{BufRead, Seek};
+
+// 3rd party imports
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::mass_spectrometry::spectrum::{get_seconds_per_time_unit, SimpleSpectrum, Spectrum};
+use crate::proteomics::io::mzml::elements::{
+    chromatogram::Chromatogram as MzMlChromatogram, has_cv_params::HasCvParams,
+    isolation_window::IsolationWindow,
+};
+use crate::proteomics::io::mzml::reader::File;
+
+/// PSI ontology accession for time array
+const TIME_ARRAY_ACCESSION: &str = "MS:1000595";
+
+/// PSI ontology accession for total ion current chromatogram
+const TOTAL_ION_CURRENT_CHROMATOGRAM_ACCESSION: &str = "MS:1000235";
+
+/// PSI ontology accession for isolation window target m/z
+const ISOLATION_WINDOW_TARGET_MZ_ACCESSION: &str = "MS:1000827";
+
+/// PSI ontology accession for selected ion m/z
+const SELECTED_ION_MZ_ACCESSION: &str = "MS:1000744";
+
+/// ID of computed total ion current chromatograms, conventionally also used in mzML files
+pub const TIC_CHROMATOGRAM_ID: &str = "TIC";
+
+/// ID of computed base peak chromatograms
+pub const BPC_CHROMATOGRAM_ID: &str = "BPC";
+
+/// Simplest version of a chromatogram.
+/// Contains the id, time and intensity values and for SRM transitions
+/// the precursor and product m/z.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SimpleChromatogram {
+    id: String,
+    /// Time in seconds
+    time: Vec<f64>,
+    intensity: Vec<f64>,
+    precursor_mz: Option<f64>,
+    product_mz: Option<f64>,
+}
+
+impl SimpleChromatogram {
+    /// Creates a new chromatogram
+    ///
+    /// # Arguments
+    /// * `id` - Chromatogram ID
+    /// * `time` - Time values in seconds
+    /// * `intensity` - Intensity values
+    ///
+    pub fn new(id: String, time: Vec<f64>, intensity: Vec<f64>) -> Self {
+        Self {
+            id,
+            time,
+            intensity,
+            precursor_mz: None,
+            product_mz: None,
+        }
+    }
+
+    /// Sets the precursor m/z, e.g. of an SRM transition
+    ///
+    /// # Arguments
+    /// * `precursor_mz` - Precursor m/z
+    ///
+    pub fn with_precursor_mz(mut self, precursor_mz: Option<f64>) -> Self {
+        self.precursor_mz = precursor_mz;
+        self
+    }
+
+    /// Sets the product m/z, e.g. of an SRM transition
+    ///
+    /// # Arguments
+    /// * `product_mz` - Product m/z
+    ///
+    pub fn with_product_mz(mut self, product_mz: Option<f64>) -> Self {
+        self.product_mz = product_mz;
+        self
+    }
+
+    /// Returns the ID
+    ///
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+
+    /// Returns the time values in seconds
+    ///
+    pub fn get_time(&self) -> &Vec<f64> {
+        &self.time
+    }
+
+    /// Returns the intensity values
+    ///
+    pub fn get_intensity(&self) -> &Vec<f64> {
+        &self.intensity
+    }
+
+    /// Returns the precursor m/z, if available
+    ///
+    pub fn get_precursor_mz(&self) -> Option<f64> {
+        self.precursor_mz
+    }
+
+    /// Returns the product m/z, if available
+    ///
+    pub fn get_product_mz(&self) -> Option<f64> {
+        self.product_mz
+    }
+}
+
+/// Returns the isolation window target m/z
+///
+fn get_target_mz(isolation_window: &IsolationWindow) -> Result<Option<f64>> {
+    isolation_window
+        .get_cv_param(ISOLATION_WINDOW_TARGET_MZ_ACCESSION)
+        .first()
+        .map(|cv_param| {
+            cv_param
+                .value
+                .parse::<f64>()
+                .context("Error when parsing isolation window target m/z")
+        })
+        .transpose()
+}
+
+impl TryFrom<MzMlChromatogram> for SimpleChromatogram {
+    type Error = anyhow::Error;
+
+    fn try_from(chromatogram: MzMlChromatogram) -> Result<Self> {
+        let time_array = chromatogram
+            .binary_data_array_list
+            .get_time_array()
+            .context(format!(
+                "Chromatogram {} - Failed to get time binary data array",
+                chromatogram.id
+            ))?;
+        let seconds_per_time_unit = match time_array.get_cv_param(TIME_ARRAY_ACCESSION).first() {
+            Some(cv_param) => get_seconds_per_time_unit(cv_param)?,
+            None => 1.0,
+        };
+        let time: Vec<f64> = time_array
+            .decode_data()
+            .context(format!(
+                "Chromatogram {} - Failed to deflate time binary data array",
+                chromatogram.id
+            ))?
+            .into_iter()
+            .map(|time| time * seconds_per_time_unit)
+            .collect();
+        let intensity = chromatogram
+            .binary_data_array_list
+            .get_intensity_array()
+            .and_then(|intensity_array| intensity_array.decode_data())
+            .context(format!(
+                "Chromatogram {} - Failed to get intensity binary data array",
+                chromatogram.id
+            ))?;
+        if time.len() != intensity.len() {
+            bail!(
+                "Chromatogram {} - Number of time values ({}) does not match the number of intensities ({})",
+                chromatogram.id,
+                time.len(),
+                intensity.len()
+            );
+        }
+
+        // precursor m/z from the isolation window, falling back to the selected ion
+        let precursor_mz = match chromatogram.precursor.as_ref() {
+            Some(precursor) => match precursor.isolation_window.as_ref() {
+                Some(isolation_window) => get_target_mz(isolation_window)?,
+                None => None,
+            }
+            .or(match precursor.selected_ion_list.as_ref() {
+                Some(selected_ion_list) => selected_ion_list
+                    .selected_ions
+                    .first()
+                    .and_then(|ion| ion.get_cv_param(SELECTED_ION_MZ_ACCESSION).first().cloned())
+                    .map(|cv_param| {
+                        cv_param
+                            .value
+                            .parse::<f64>()
+                            .context("Error when parsing selected ion m/z")
+                    })
+                    .transpose()?,
+                None => None,
+            }),
+            None => None,
+        };
+        let product_mz = match chromatogram
+            .product
+            .as_ref()
+            .and_then(|product| product.isolation_window.as_ref())
+        {
+            Some(isolation_window) => get_target_mz(isolation_window)?,
+            None => None,
+        };
+
+        Ok(SimpleChromatogram::new(chromatogram.id, time, intensity)
+            .with_precursor_mz(precursor_mz)
+            .with_product_mz(product_mz))
+    }
+}
+
+/// Computes the total ion current (TIC) and base peak chromatogram (BPC) within a single pass
+/// over the spectra with the given MS level.
+/// Spectra need to be ordered by retention time.
+///
+/// # Arguments
+/// * `spectra` - Spectra, e.g. of a run
+/// * `ms_level` - MS level of the spectra to use
+///
+pub fn compute_tic_and_bpc<S, I>(
+    spectra: I,
+    ms_level: u8,
+) -> Result<(SimpleChromatogram, SimpleChromatogram)>
+where
+    S: Spectrum,
+    I: IntoIterator<Item = Result<S>>,
+{
+    let mut time: Vec<f64> = Vec::new();
+    let mut tic: Vec<f64> = Vec::new();
+    let mut bpc: Vec<f64> = Vec::new();
+    for spectrum in spectra {
+        let spectrum = spectrum?;
+        if spectrum.get_ms_level() != ms_level {
+            continue;
+        }
+        let retention_time = match spectrum.get_retention_time() {
+            Some(retention_time) => retention_time,
+            None => bail!("Spectrum {} has no retention time", spectrum.get_id()),
+        };
+        time.push(retention_time);
+        tic.push(spectrum.get_intensity().iter().sum());
+        bpc.push(spectrum.get_intensity().iter().cloned().fold(0.0, f64::max));
+    }
+    Ok((
+        SimpleChromatogram::new(TIC_CHROMATOGRAM_ID.to_string(), time.clone(), tic),
+        SimpleChromatogram::new(BPC_CHROMATOGRAM_ID.to_string(), time, bpc),
+    ))
+}
+
+/// Computes the total ion current (TIC) and base peak chromatogram (BPC) within a single
+/// sequential pass over the spectra of the mzML file with the given MS level.
+///
+/// # Arguments
+/// * `file` - Open mzML file
+/// * `ms_level` - MS level of the spectra to use
+///
+pub fn compute_tic_and_bpc_from_mzml<F>(
+    file: &mut File<'_, F>,
+    ms_level: u8,
+) -> Result<(SimpleChromatogram, SimpleChromatogram)>
+where
+    F: BufRead + Seek,
+{
+    let spectra = file
+        .iter_spectra()
+        .filter(|spectrum| match spectrum {
+            Ok(spectrum) => spectrum.get_ms_level() == Some(ms_level),
+            Err(_) => true,
+        })
+        .map(|spectrum| {
+            spectrum.and_then(|spectrum| {
+                let spectrum_id = spectrum.id.clone();
+                SimpleSpectrum::try_from(spectrum)
+                    .context(format!("Failed to convert spectrum {}", spectrum_id))
+            })
+        });
+    compute_tic_and_bpc(spectra, ms_level)
+}
+
+/// Returns the total ion current chromatogram of the mzML file. The chromatogram with ID `TIC`
+/// is checked first, then the first chromatogram of the chromatogramList annotated
+/// as total ion current chromatogram is used. If the file contains none,
+/// it is computed from the MS1 spectra.
+///
+/// # Arguments
+/// * `file` - Open mzML file
+///
+pub fn get_tic_from_mzml<F>(file: &mut File<'_, F>) -> Result<SimpleChromatogram>
+where
+    F: BufRead + Seek,
+{
+    let mut chromatograms: Vec<(String, usize)> = file
+        .get_index()
+        .get_chromatograms()
+        .iter()
+        .map(|(chromatogram_id, offset)| (chromatogram_id.clone(), *offset))
+        .collect();
+    // file order, with the conventional TIC ID first
+    chromatograms
+        .sort_by_key(|(chromatogram_id, offset)| (chromatogram_id != TIC_CHROMATOGRAM_ID, *offset));
+    for (chromatogram_id, _) in chromatograms {
+        let chromatogram = file.get_chromatogram(&chromatogram_id)?;
+        if !chromatogram
+            .get_cv_param(TOTAL_ION_CURRENT_CHROMATOGRAM_ACCESSION)
+            .is_empty()
+        {
+            return SimpleChromatogram::try_from(chromatogram);
+        }
+    }
+    Ok(compute_tic_and_bpc_from_mzml(file, 1)?.0)
+}
+
+#[cfg(test)]
+mod test {
+    // std imports
+    use std::io::BufReader;
+
+    // internal imports
+    use super::*;
+    use crate::mass_spectrometry::spectrum::SpectrumMetadata;
+    use crate::proteomics::io::mzml::elements::{
+        has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
+        referenceable_param_group_list::ReferenceableParamGroupList,
+    };
+    use crate::proteomics::io::mzml::reader::Reader as MzMlReader;
+
+    #[test]
+    fn test_chromatogram_from_mzml() {
+        let mut mzml_bytes_reader =
+            BufReader::new(std::fs::File::open("test_files/spectra_small.mzML").unwrap());
+        let mut mzml_file =
+            MzMlReader::read_indexed(&mut mzml_bytes_reader, None, false, false).unwrap();
+
+        let tic = SimpleChromatogram::try_from(mzml_file.get_chromatogram("TIC").unwrap()).unwrap();
+        assert_eq!(tic.get_id(), "TIC");
+        // defaultArrayLength
+        assert_eq!(tic.get_time().len(), 39881);
+        assert_eq!(tic.get_intensity().len(), 39881);
+        assert!(tic.get_precursor_mz().is_none());
+        assert!(tic.get_product_mz().is_none());
+        // time array is given in minutes
+        assert!(tic.get_time().last().unwrap() > &60.0);
+
+        assert_eq!(get_tic_from_mzml(&mut mzml_file).unwrap(), tic);
+
+        let (computed_tic, computed_bpc) =
+            compute_tic_and_bpc_from_mzml(&mut mzml_file, 1).unwrap();
+        // the test file contains a single MS1 spectrum
+        assert_eq!(computed_tic.get_time(), &vec![11.920181 * 60.0]);
+        assert_eq!(computed_bpc.get_time().len(), 1);
+        assert!(computed_bpc.get_intensity()[0] <= computed_tic.get_intensity()[0]);
+    }
+
+    #[test]
+    fn test_compute_tic_and_bpc() {
+        let spectra = vec![
+            SimpleSpectrum::new("1".to_string(), 1, vec![100.0, 200.0], vec![1.0, 3.0]),
+            SimpleSpectrum::new("2".to_string(), 2, vec![100.0], vec![5.0]),
+            SimpleSpectrum::new("3".to_string(), 1, vec![100.0, 200.0], vec![2.0, 1.0]),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(idx, spectrum)| {
+            Ok(spectrum.with_metadata(SpectrumMetadata::new(
+                Some(idx as f64),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )))
+        });
+        let (tic, bpc) = compute_tic_and_bpc(spectra, 1).unwrap();
+        assert_eq!(tic.get_time(), &vec![0.0, 2.0]);
+        assert_eq!(tic.get_intensity(), &vec![4.0, 3.0]);
+        assert_eq!(bpc.get_id(), BPC_CHROMATOGRAM_ID);
+        assert_eq!(bpc.get_intensity(), &vec![3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_srm_chromatogram() {
+        let xml = r#"<chromatogram index="0" id="SRM SIC Q1=500.5 Q3=600.3" defaultArrayLength="2">
+            <cvParam cvRef="MS" accession="MS:1001473" name="selected reaction monitoring chromatogram" value=""/>
+            <precursor>
+              <isolationWindow>
+                <cvParam cvRef="MS" accession="MS:1000827" name="isolation window target m/z" value="500.5" unitCvRef="MS" unitAccession="MS:1000040" unitName="m/z"/>
+              </isolationWindow>
+              <activation>
+                <cvParam cvRef="MS" accession="MS:1000133" name="collision-induced dissociation" value=""/>
+              </activation>
+            </precursor>
+            <product>
+              <isolationWindow>
+                <cvParam cvRef="MS" accession="MS:1000827" name="isolation window target m/z" value="600.3" unitCvRef="MS" unitAccession="MS:1000040" unitName="m/z"/>
+              </isolationWindow>
+            </product>
+            <binaryDataArrayList count="2">
+              <binaryDataArray encodedLength="24">
+                <cvParam cvRef="MS" accession="MS:1000523" name="64-bit float" value=""/>
+                <cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>
+                <cvParam cvRef="MS" accession="MS:1000595" name="time array" value="" unitCvRef="UO" unitAccession="UO:0000031" unitName="minute"/>
+                <binary>AAAAAAAA4D8AAAAAAADwPw==</binary>
+              </binaryDataArray>
+              <binaryDataArray encodedLength="24">
+                <cvParam cvRef="MS" accession="MS:1000523" name="64-bit float" value=""/>
+                <cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>
+                <cvParam cvRef="MS" accession="MS:1000515" name="intensity array" value="" unitCvRef="MS" unitAccession="MS:1000131" unitName="number of detector counts"/>
+                <binary>AAAAAAAAJEAAAAAAAAA0QA==</binary>
+              </binaryDataArray>
+            </binaryDataArrayList>
+          </chromatogram>"#;
+        let chromatogram = quick_xml::de::from_str::<MzMlChromatogram>(xml).unwrap();
+        let chromatogram = SimpleChromatogram::try_from(chromatogram).unwrap();
+        assert_eq!(chromatogram.get_time(), &vec![30.0, 60.0]);
+        assert_eq!(chromatogram.get_intensity(), &vec![10.0, 20.0]);
+        assert_eq!(chromatogram.get_precursor_mz(), Some(500.5));
+        assert_eq!(chromatogram.get_product_mz(), Some(600.3));
+    }
+
+    #[test]
+    fn test_chromatogram_with_referenced_array_params() {
+        let referenceable_param_group_list = quick_xml::de::from_str::<ReferenceableParamGroupList>(
+            r#"<referenceableParamGroupList count="2">
+              <referenceableParamGroup id="timeArray">
+                <cvParam cvRef="MS" accession="MS:1000523" name="64-bit float" value=""/>
+                <cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>
+                <cvParam cvRef="MS" accession="MS:1000595" name="time array" value="" unitCvRef="UO" unitAccession="UO:0000031" unitName="minute"/>
+              </referenceableParamGroup>
+              <referenceableParamGroup id="intensityArray">
+                <cvParam cvRef="MS" accession="MS:1000523" name="64-bit float" value=""/>
+                <cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>
+                <cvParam cvRef="MS" accession="MS:1000515" name="intensity array" value="" unitCvRef="MS" unitAccession="MS:1000131" unitName="number of detector counts"/>
+              </referenceableParamGroup>
+            </referenceableParamGroupList>"#,
+        )
+        .unwrap();
+        let xml = r#"<chromatogram index="0" id="TIC" defaultArrayLength="2">
+            <cvParam cvRef="MS" accession="MS:1000235" name="total ion current chromatogram" value=""/>
+            <binaryDataArrayList count="2">
+              <binaryDataArray encodedLength="24">
+                <referenceableParamGroupRef ref="timeArray"/>
+                <binary>AAAAAAAA4D8AAAAAAADwPw==</binary>
+              </binaryDataArray>
+              <binaryDataArray encodedLength="24">
+                <referenceableParamGroupRef ref="intensityArray"/>
+                <binary>AAAAAAAAJEAAAAAAAAA0QA==</binary>
+              </binaryDataArray>
+            </binaryDataArrayList>
+          </chromatogram>"#;
+        let mut chromatogram = quick_xml::de::from_str::<MzMlChromatogram>(xml).unwrap();
+        assert!(SimpleChromatogram::try_from(chromatogram.clone()).is_err());
+        chromatogram
+            .resolve_referenceable_param_groups(&referenceable_param_group_list)
+            .unwrap();
+        let chromatogram = SimpleChromatogram::try_from(chromatogram).unwrap();
+        assert_eq!(chromatogram.get_time(), &vec![30.0, 60.0]);
+        assert_eq!(chromatogram.get_intensity(), &vec![10.0, 20.0]);
+    }
+}
@@ -0,0 +1,426 @@
+// std imports
+use std::collections::{HashMap, HashSet};
+
+// 3rd party imports
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::mass_spectrometry::spectrum::{MsNSpectrum, Precursor};
+
+/// Precision (in m/z) at which isolation windows are considered identical
+const WINDOW_PRECISION: f64 = 0.01;
+
+/// Isolation window of a DIA scheme
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiaWindow {
+    target_mz: f64,
+    lower_mz: f64,
+    upper_mz: f64,
+}
+
+impl DiaWindow {
+    /// Creates a new window
+    ///
+    /// # Arguments
+    /// * `target_mz` - Isolation window target m/z
+    /// * `lower_offset` - Isolation window lower offset
+    /// * `upper_offset` - Isolation window upper offset
+    ///
+    pub fn new(target_mz: f64, lower_offset: f64, upper_offset: f64) -> Self {
+        Self {
+            target_mz,
+            lower_mz: target_mz - lower_offset,
+            upper_mz: target_mz + upper_offset,
+        }
+    }
+
+    /// Returns the target m/z
+    ///
+    pub fn get_target_mz(&self) -> f64 {
+        self.target_mz
+    }
+
+    /// Returns the lower bound
+    ///
+    pub fn get_lower_mz(&self) -> f64 {
+        self.lower_mz
+    }
+
+    /// Returns the upper bound
+    ///
+    pub fn get_upper_mz(&self) -> f64 {
+        self.upper_mz
+    }
+
+    /// Returns the width
+    ///
+    pub fn get_width(&self) -> f64 {
+        self.upper_mz - self.lower_mz
+    }
+
+    /// Returns true if the m/z is within the window (inclusive)
+    ///
+    /// # Arguments
+    /// * `mz` - m/z
+    ///
+    pub fn contains(&self, mz: f64) -> bool {
+        self.lower_mz <= mz && mz <= self.upper_mz
+    }
+
+    /// Returns true if both windows overlap by more than the window precision
+    ///
+    /// # Arguments
+    /// * `other` - Other window
+    ///
+    pub fn overlaps(&self, other: &DiaWindow) -> bool {
+        self.lower_mz.max(other.lower_mz) < self.upper_mz.min(other.upper_mz) - WINDOW_PRECISION
+    }
+
+    /// Returns the key for identifying identical windows
+    ///
+    fn get_key(&self) -> (i64, i64) {
+        (
+            (self.lower_mz / WINDOW_PRECISION).round() as i64,
+            (self.upper_mz / WINDOW_PRECISION).round() as i64,
+        )
+    }
+}
+
+/// One DIA cycle, consisting of the MS2 spectra acquired after an MS1 spectrum
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiaCycle {
+    ms1: Option<usize>,
+    /// (window index, spectrum index)
+    ms2: Vec<(usize, usize)>,
+}
+
+impl DiaCycle {
+    /// Returns the index of the preceding MS1 spectrum, if any
+    ///
+    pub fn get_ms1(&self) -> Option<usize> {
+        self.ms1
+    }
+
+    /// Returns the MS2 spectra of the cycle (window index, spectrum index)
+    ///
+    pub fn get_ms2(&self) -> &Vec<(usize, usize)> {
+        &self.ms2
+    }
+
+    /// Returns the index of the MS2 spectrum of the given window, if acquired in this cycle
+    ///
+    /// # Arguments
+    /// * `window` - Window index
+    ///
+    pub fn get_ms2_of_window(&self, window: usize) -> Option<usize> {
+        self.ms2
+            .iter()
+            .find(|(window_idx, _)| *window_idx == window)
+            .map(|(_, spectrum_idx)| *spectrum_idx)
+    }
+}
+
+/// Isolation window scheme of a DIA run and the MS2 spectra grouped by window and cycle.
+/// Spectra are referenced by their index in the analyzed spectra.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiaScheme {
+    /// Sorted by lower bound
+    windows: Vec<DiaWindow>,
+    cycles: Vec<DiaCycle>,
+    is_variable: bool,
+    is_overlapping: bool,
+    is_staggered: bool,
+}
+
+impl DiaScheme {
+    /// Derives the isolation window scheme from the precursors of the MS2 spectra.
+    /// Cycles start with each MS1 spectrum. Without MS1 spectra a new cycle starts when a
+    /// window is acquired a second time.
+    /// MS2 spectra without isolation window and repeated acquisitions of a window after
+    /// the same MS1 spectrum are not assigned to any cycle.
+    /// The spectra are consumed one by one, so they can be streamed from a file.
+    ///
+    /// # Arguments
+    /// * `spectra` - Spectra of the run in acquisition order
+    ///
+    pub fn from_spectra<S, P, I>(spectra: I) -> Result<Self>
+    where
+        S: MsNSpectrum<P>,
+        P: Precursor,
+        I: IntoIterator<Item = Result<S>>,
+    {
+        // collect the MS level and window of each spectrum
+        let mut spectrum_windows: Vec<(u8, Option<DiaWindow>)> = Vec::new();
+        for spectrum in spectra {
+            let spectrum = spectrum?;
+            let window = match spectrum.get_ms_level() {
+                2 => spectrum
+                    .get_precursors()
+                    .first()
+                    .and_then(|precursor| *precursor.get_isolation_windows())
+                    .map(|(target_mz, lower_offset, upper_offset)| {
+                        DiaWindow::new(target_mz, lower_offset, upper_offset)
+                    }),
+                _ => None,
+            };
+            spectrum_windows.push((spectrum.get_ms_level(), window));
+        }
+
+        let mut windows: Vec<DiaWindow> = Vec::new();
+        let mut seen_keys: HashSet<(i64, i64)> = HashSet::new();
+        for window in spectrum_windows
+            .iter()
+            .filter_map(|(_, window)| window.as_ref())
+        {
+            if seen_keys.insert(window.get_key()) {
+                windows.push(*window);
+            }
+        }
+        windows.sort_by(|x, y| {
+            x.lower_mz
+                .total_cmp(&y.lower_mz)
+                .then(x.upper_mz.total_cmp(&y.upper_mz))
+        });
+        let window_indices: HashMap<(i64, i64), usize> = windows
+            .iter()
+            .enumerate()
+            .map(|(idx, window)| (window.get_key(), idx))
+            .collect();
+
+        // group MS2 spectra into cycles
+        let mut cycles: Vec<DiaCycle> = Vec::new();
+        let mut current_cycle: Option<DiaCycle> = None;
+        for (spectrum_idx, (ms_level, window)) in spectrum_windows.iter().enumerate() {
+            if *ms_level == 1 {
+                cycles.extend(current_cycle.take());
+                current_cycle = Some(DiaCycle {
+                    ms1: Some(spectrum_idx),
+                    ms2: Vec::new(),
+                });
+                continue;
+            }
+            let window_idx = match window {
+                Some(window) => window_indices[&window.get_key()],
+                None => continue,
+            };
+            let cycle = match current_cycle.as_mut() {
+                Some(cycle) if cycle.get_ms2_of_window(window_idx).is_none() => cycle,
+                // repeated window after the same MS1 spectrum, keep the first acquisition
+                Some(cycle) if cycle.ms1.is_some() => continue,
+                _ => {
+                    cycles.extend(current_cycle.take());
+                    current_cycle.insert(DiaCycle {
+                        ms1: None,
+                        ms2: Vec::new(),
+                    })
+                }
+            };
+            cycle.ms2.push((window_idx, spectrum_idx));
+        }
+        cycles.extend(current_cycle);
+
+        let is_variable = match windows.first() {
+            Some(first) => windows
+                .iter()
+                .any(|window| (window.get_width() - first.get_width()).abs() > WINDOW_PRECISION),
+            None => false,
+        };
+        let is_overlapping = windows
+            .windows(2)
+            .any(|neighbours| neighbours[0].overlaps(&neighbours[1]));
+        // staggered schemes alternate between non-overlapping window sets shifted against each other
+        let is_staggered = is_overlapping
+            && cycles.iter().all(|cycle| {
+                cycle.ms2.iter().enumerate().all(|(idx, (window_x, _))| {
+                    cycle.ms2[idx + 1..]
+                        .iter()
+                        .all(|(window_y, _)| !windows[*window_x].overlaps(&windows[*window_y]))
+                })
+            });
+
+        Ok(Self {
+            windows,
+            cycles,
+            is_variable,
+            is_overlapping,
+            is_staggered,
+        })
+    }
+
+    /// Returns the distinct isolation windows sorted by their lower bound
+    ///
+    pub fn get_windows(&self) -> &Vec<DiaWindow> {
+        &self.windows
+    }
+
+    /// Returns the cycles in acquisition order
+    ///
+    pub fn get_cycles(&self) -> &Vec<DiaCycle> {
+        &self.cycles
+    }
+
+    /// Returns true if the windows have different widths
+    ///
+    pub fn is_variable(&self) -> bool {
+        self.is_variable
+    }
+
+    /// Returns true if windows overlap
+    ///
+    pub fn is_overlapping(&self) -> bool {
+        self.is_overlapping
+    }
+
+    /// Returns true if overlapping windows are acquired in alternating cycles
+    ///
+    pub fn is_staggered(&self) -> bool {
+        self.is_staggered
+    }
+
+    /// Returns the indices of the windows containing the m/z
+    ///
+    /// # Arguments
+    /// * `mz` - m/z, e.g. of a precursor
+    ///
+    pub fn find_windows(&self, mz: f64) -> Vec<usize> {
+        self.windows
+            .iter()
+            .enumerate()
+            .filter(|(_, window)| window.contains(mz))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Returns the MS2 spectra of the window over all cycles (preceding MS1 index, spectrum index)
+    ///
+    /// # Arguments
+    /// * `window` - Window index
+    ///
+    pub fn get_spectra_of_window(&self, window: usize) -> Vec<(Option<usize>, usize)> {
+        self.cycles
+            .iter()
+            .filter_map(|cycle| {
+                cycle
+                    .get_ms2_of_window(window)
+                    .map(|spectrum_idx| (cycle.ms1, spectrum_idx))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    // std imports
+    use std::collections::HashMap;
+
+    // internal imports
+    use super::*;
+    use crate::mass_spectrometry::spectrum::{SimpleMsNSpectrum, SimplePrecursor};
+
+    fn ms1(id: usize) -> SimpleMsNSpectrum {
+        SimpleMsNSpectrum::new(id.to_string(), 1, Vec::new(), Vec::new(), Vec::new())
+    }
+
+    fn ms2(id: usize, lower: f64, upper: f64) -> SimpleMsNSpectrum {
+        let half_width = (upper - lower) / 2.0;
+        let precursor = SimplePrecursor::new(
+            String::new(),
+            Some((lower + half_width, half_width, half_width)),
+            None,
+            (Vec::new(), HashMap::new()),
+        );
+        SimpleMsNSpectrum::new(id.to_string(), 2, Vec::new(), Vec::new(), vec![precursor])
+    }
+
+    #[test]
+    fn test_variable_scheme() {
+        let bounds = [(400.0, 420.0), (420.0, 450.0), (450.0, 500.0)];
+        let mut spectra = Vec::new();
+        for _ in 0..3 {
+            spectra.push(ms1(spectra.len()));
+            // acquisition order differs from the m/z order
+            for (lower, upper) in bounds.iter().rev() {
+                spectra.push(ms2(spectra.len(), *lower, *upper));
+            }
+        }
+        let scheme = DiaScheme::from_spectra(spectra.into_iter().map(Ok)).unwrap();
+        assert_eq!(scheme.get_windows().len(), 3);
+        assert_eq!(scheme.get_windows()[0].get_lower_mz(), 400.0);
+        assert!(scheme.is_variable());
+        assert!(!scheme.is_overlapping());
+        assert!(!scheme.is_staggered());
+        assert_eq!(scheme.get_cycles().len(), 3);
+        assert_eq!(scheme.get_cycles()[1].get_ms1(), Some(4));
+        assert_eq!(scheme.get_cycles()[1].get_ms2_of_window(0), Some(7));
+        assert_eq!(
+            scheme.get_spectra_of_window(2),
+            vec![(Some(0), 1), (Some(4), 5), (Some(8), 9)]
+        );
+        assert_eq!(scheme.find_windows(430.0), vec![1]);
+    }
+
+    #[test]
+    fn test_staggered_scheme() {
+        let mut spectra = Vec::new();
+        for cycle in 0..4 {
+            spectra.push(ms1(spectra.len()));
+            let offset = if cycle % 2 == 0 { 0.0 } else { 10.0 };
+            for window in 0..3 {
+                let lower = 400.0 + offset + window as f64 * 20.0;
+                spectra.push(ms2(spectra.len(), lower, lower + 20.0));
+            }
+        }
+        let scheme = DiaScheme::from_spectra(spectra.into_iter().map(Ok)).unwrap();
+        assert_eq!(scheme.get_windows().len(), 6);
+        assert!(!scheme.is_variable());
+        assert!(scheme.is_overlapping());
+        assert!(scheme.is_staggered());
+        assert_eq!(scheme.get_cycles().len(), 4);
+        assert_eq!(scheme.find_windows(425.0), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_scheme_without_ms1() {
+        let mut spectra = Vec::new();
+        for _ in 0..2 {
+            for window in 0..3 {
+                let lower = 400.0 + window as f64 * 20.0;
+                // 1 m/z overlap
+                spectra.push(ms2(spectra.len(), lower - 0.5, lower + 20.5));
+            }
+        }
+        let scheme = DiaScheme::from_spectra(spectra.into_iter().map(Ok)).unwrap();
+        assert_eq!(scheme.get_cycles().len(), 2);
+        assert_eq!(scheme.get_cycles()[1].get_ms1(), None);
+        assert!(scheme.is_overlapping());
+        assert!(!scheme.is_staggered());
+    }
+
+    #[test]
+    fn test_scheme_with_irregular_spectra() {
+        let spectra = vec![
+            ms1(0),
+            ms2(1, 400.0, 420.0),
+            // no isolation window
+            SimpleMsNSpectrum::new("2".to_string(), 2, Vec::new(), Vec::new(), Vec::new()),
+            ms2(3, 420.0, 440.0),
+            // repeated within the cycle
+            ms2(4, 400.0, 420.0),
+            ms1(5),
+            ms2(6, 400.0, 420.0),
+            ms2(7, 420.0, 440.0),
+        ];
+        let scheme = DiaScheme::from_spectra(spectra.into_iter().map(Ok)).unwrap();
+        assert_eq!(scheme.get_windows().len(), 2);
+        assert_eq!(scheme.get_cycles().len(), 2);
+        assert_eq!(scheme.get_cycles()[0].get_ms2(), &vec![(0, 1), (1, 3)]);
+        assert_eq!(
+            scheme.get_spectra_of_window(0),
+            vec![(Some(0), 1), (Some(5), 6)]
+        );
+    }
+}
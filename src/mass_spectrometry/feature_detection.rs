@@ -0,0 +1,458 @@
+// std imports
+use std::io::{BufRead, Seek};
+
+// 3rd party imports
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::chemistry::subatomic_particle::PROTON;
+use crate::mass_spectrometry::processing::deisotoping::{
+    get_averagine_isotope_distribution, Deisotoper,
+};
+use crate::mass_spectrometry::similarity::vector_cosine_similarity;
+use crate::mass_spectrometry::spectrum::{SimpleSpectrum, Spectrum};
+use crate::mass_spectrometry::tolerance::Tolerance;
+use crate::proteomics::io::mzml::reader::File;
+
+/// MS1 feature, an isotope envelope traced over retention time.
+/// The fields are flat, so a list of features can be written as table, e.g. with the csv crate.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Feature {
+    monoisotopic_mz: f64,
+    charge: u8,
+    retention_time_apex: f64,
+    retention_time_start: f64,
+    retention_time_end: f64,
+    intensity: f64,
+    isotope_fit_score: f64,
+    scan_count: usize,
+}
+
+impl Feature {
+    /// Returns the intensity weighted monoisotopic m/z
+    ///
+    pub fn get_monoisotopic_mz(&self) -> f64 {
+        self.monoisotopic_mz
+    }
+
+    /// Returns the charge
+    ///
+    pub fn get_charge(&self) -> u8 {
+        self.charge
+    }
+
+    /// Returns the neutral monoisotopic mass
+    ///
+    pub fn get_neutral_mass(&self) -> f64 {
+        (self.monoisotopic_mz - PROTON.get_mass()) * self.charge as f64
+    }
+
+    /// Returns the retention time of the most intense envelope in seconds
+    ///
+    pub fn get_retention_time_apex(&self) -> f64 {
+        self.retention_time_apex
+    }
+
+    /// Returns the retention time of the first envelope in seconds
+    ///
+    pub fn get_retention_time_start(&self) -> f64 {
+        self.retention_time_start
+    }
+
+    /// Returns the retention time of the last envelope in seconds
+    ///
+    pub fn get_retention_time_end(&self) -> f64 {
+        self.retention_time_end
+    }
+
+    /// Returns the envelope intensities integrated over the retention time (trapezoidal rule).
+    /// Features of a single spectrum have no retention time extent, their intensity is the apex envelope intensity.
+    ///
+    pub fn get_intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// Returns the cosine similarity of the summed isotope intensities to the averagine distribution
+    ///
+    pub fn get_isotope_fit_score(&self) -> f64 {
+        self.isotope_fit_score
+    }
+
+    /// Returns the number of MS1 spectra the feature was detected in
+    ///
+    pub fn get_scan_count(&self) -> usize {
+        self.scan_count
+    }
+}
+
+/// Isotope envelopes of a feature in progress
+///
+struct Trace {
+    charge: u8,
+    /// Index of the last MS1 spectrum which extended the trace
+    last_scan: usize,
+    /// (retention time, monoisotopic m/z, isotope intensities)
+    envelopes: Vec<(f64, f64, Vec<f64>)>,
+}
+
+impl Trace {
+    fn get_last_mz(&self) -> f64 {
+        self.envelopes.last().map(|(_, mz, _)| *mz).unwrap_or(0.0)
+    }
+}
+
+/// Detects MS1 features by tracing isotope envelopes through consecutive MS1 spectra.
+///
+/// In each MS1 spectrum the isotope envelopes are detected with a [Deisotoper]. Envelopes
+/// are linked to a trace of the previous spectra if charge and monoisotopic m/z
+/// (within the tolerance) match, allowing a number of spectra without a match.
+/// Traces with enough spectra are reported as features.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeatureFinder {
+    tolerance: Tolerance,
+    deisotoper: Deisotoper,
+    max_gap: usize,
+    min_scans: usize,
+}
+
+impl FeatureFinder {
+    /// Creates a new feature finder, with envelopes of 2 to 6 isotope peaks, a maximum gap
+    /// of 1 spectrum and at least 3 spectra per feature.
+    ///
+    /// # Arguments
+    /// * `tolerance` - Tolerance for the isotope peaks and the linking of envelopes
+    /// * `min_charge` - Minimum charge
+    /// * `max_charge` - Maximum charge
+    ///
+    pub fn new(tolerance: Tolerance, min_charge: u8, max_charge: u8) -> Result<Self> {
+        Ok(Self {
+            tolerance,
+            deisotoper: Deisotoper::new(tolerance, min_charge, max_charge)?,
+            max_gap: 1,
+            min_scans: 3,
+        })
+    }
+
+    /// Sets the deisotoper used for the envelope detection in each MS1 spectrum
+    ///
+    /// # Arguments
+    /// * `deisotoper` - Deisotoper
+    ///
+    pub fn with_deisotoper(mut self, deisotoper: Deisotoper) -> Self {
+        self.deisotoper = deisotoper;
+        self
+    }
+
+    /// Sets the maximum number of consecutive MS1 spectra without a matching envelope
+    ///
+    /// # Arguments
+    /// * `max_gap` - Maximum gap
+    ///
+    pub fn with_max_gap(mut self, max_gap: usize) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /// Sets the minimum number of MS1 spectra per feature
+    ///
+    /// # Arguments
+    /// * `min_scans` - Minimum number of spectra
+    ///
+    pub fn with_min_scans(mut self, min_scans: usize) -> Result<Self> {
+        if min_scans == 0 {
+            bail!("The minimum number of spectra per feature must be positive");
+        }
+        self.min_scans = min_scans;
+        Ok(self)
+    }
+
+    /// Converts the trace into a feature, if it spans enough spectra
+    ///
+    fn finalize(&self, trace: Trace) -> Option<Feature> {
+        if trace.envelopes.len() < self.min_scans {
+            return None;
+        }
+        let envelope_intensities: Vec<f64> = trace
+            .envelopes
+            .iter()
+            .map(|(_, _, isotopes)| isotopes.iter().sum())
+            .collect();
+        let total_intensity: f64 = envelope_intensities.iter().sum();
+        let monoisotopic_mz = trace
+            .envelopes
+            .iter()
+            .zip(envelope_intensities.iter())
+            .map(|((_, mz, _), intensity)| mz * intensity)
+            .sum::<f64>()
+            / total_intensity;
+        let (apex_idx, _) = envelope_intensities
+            .iter()
+            .enumerate()
+            .max_by(|(_, x), (_, y)| x.total_cmp(y))?;
+        let intensity = if trace.envelopes.len() == 1 {
+            envelope_intensities[apex_idx]
+        } else {
+            trace
+                .envelopes
+                .windows(2)
+                .zip(envelope_intensities.windows(2))
+                .map(|(envelopes, intensities)| {
+                    (envelopes[1].0 - envelopes[0].0) * (intensities[0] + intensities[1]) / 2.0
+                })
+                .sum()
+        };
+        let isotope_count = trace
+            .envelopes
+            .iter()
+            .map(|(_, _, isotopes)| isotopes.len())
+            .max()
+            .unwrap_or(0);
+        let mut summed_isotopes = vec![0.0; isotope_count];
+        for (_, _, isotopes) in trace.envelopes.iter() {
+            for (sum, isotope) in summed_isotopes.iter_mut().zip(isotopes.iter()) {
+                *sum += isotope;
+            }
+        }
+        let neutral_mass = (monoisotopic_mz - PROTON.get_mass()) * trace.charge as f64;
+        let isotope_fit_score = vector_cosine_similarity(
+            &summed_isotopes,
+            &get_averagine_isotope_distribution(neutral_mass, isotope_count),
+        );
+        Some(Feature {
+            monoisotopic_mz,
+            charge: trace.charge,
+            retention_time_apex: trace.envelopes[apex_idx].0,
+            retention_time_start: trace.envelopes[0].0,
+            retention_time_end: trace.envelopes[trace.envelopes.len() - 1].0,
+            intensity,
+            isotope_fit_score,
+            scan_count: trace.envelopes.len(),
+        })
+    }
+
+    /// Detects the features in the given spectra. Only MS1 spectra are considered.
+    /// The spectra need to be centroided, ordered by retention time and have sorted m/z values.
+    /// Features are returned sorted by retention time apex.
+    ///
+    /// # Arguments
+    /// * `spectra` - Spectra, e.g. of a run
+    ///
+    pub fn find_features<S, I>(&self, spectra: I) -> Result<Vec<Feature>>
+    where
+        S: Spectrum,
+        I: IntoIterator<Item = Result<S>>,
+    {
+        let mut active_traces: Vec<Trace> = Vec::new();
+        let mut features: Vec<Feature> = Vec::new();
+        let mut scan = 0;
+        for spectrum in spectra {
+            let spectrum = spectrum?;
+            if spectrum.get_ms_level() != 1 {
+                continue;
+            }
+            let retention_time = match spectrum.get_retention_time() {
+                Some(retention_time) => retention_time,
+                None => bail!("Spectrum {} has no retention time", spectrum.get_id()),
+            };
+            let mz = spectrum.get_mz();
+            let intensity = spectrum.get_intensity();
+            let mut envelopes = self.deisotoper.find_envelopes(mz, intensity);
+            // most intense envelopes pick their trace first
+            envelopes.sort_by(|x, y| y.get_intensity().total_cmp(&x.get_intensity()));
+            let mut new_traces: Vec<Trace> = Vec::new();
+            for envelope in envelopes {
+                let isotopes: Vec<f64> = envelope
+                    .get_peak_indices()
+                    .iter()
+                    .map(|idx| intensity[*idx])
+                    .collect();
+                let monoisotopic_mz = envelope.get_monoisotopic_mz();
+                let matching_trace = active_traces
+                    .iter_mut()
+                    .filter(|trace| {
+                        trace.charge == envelope.get_charge()
+                            && trace.last_scan < scan
+                            && self
+                                .tolerance
+                                .contains(trace.get_last_mz(), monoisotopic_mz)
+                    })
+                    .min_by(|x, y| {
+                        (x.get_last_mz() - monoisotopic_mz)
+                            .abs()
+                            .total_cmp(&(y.get_last_mz() - monoisotopic_mz).abs())
+                    });
+                match matching_trace {
+                    Some(trace) => {
+                        trace.last_scan = scan;
+                        trace
+                            .envelopes
+                            .push((retention_time, monoisotopic_mz, isotopes));
+                    }
+                    None => new_traces.push(Trace {
+                        charge: envelope.get_charge(),
+                        last_scan: scan,
+                        envelopes: vec![(retention_time, monoisotopic_mz, isotopes)],
+                    }),
+                }
+            }
+            // close traces which exceeded the gap
+            let (closed_traces, open_traces): (Vec<Trace>, Vec<Trace>) = active_traces
+                .into_iter()
+                .partition(|trace| scan - trace.last_scan > self.max_gap);
+            features.extend(
+                closed_traces
+                    .into_iter()
+                    .filter_map(|trace| self.finalize(trace)),
+            );
+            active_traces = open_traces;
+            active_traces.extend(new_traces);
+            scan += 1;
+        }
+        features.extend(
+            active_traces
+                .into_iter()
+                .filter_map(|trace| self.finalize(trace)),
+        );
+        features.sort_by(|x, y| x.retention_time_apex.total_cmp(&y.retention_time_apex));
+        Ok(features)
+    }
+
+    /// Detects the features in the MS1 spectra of the mzML file within a single sequential pass.
+    /// The MS1 spectra need to be centroided.
+    ///
+    /// # Arguments
+    /// * `file` - Open mzML file
+    ///
+    pub fn find_features_in_mzml<F>(&self, file: &mut File<'_, F>) -> Result<Vec<Feature>>
+    where
+        F: BufRead + Seek,
+    {
+        let spectra = file
+            .iter_spectra()
+            .filter(|spectrum| match spectrum {
+                Ok(spectrum) => spectrum.get_ms_level() == Some(1),
+                Err(_) => true,
+            })
+            .map(|spectrum| {
+                spectrum.and_then(|spectrum| {
+                    let spectrum_id = spectrum.id.clone();
+                    SimpleSpectrum::try_from(spectrum)
+                        .context(format!("Failed to convert spectrum {}", spectrum_id))
+                })
+            });
+        self.find_features(spectra)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    // std imports
+    use std::io::BufReader;
+
+    // internal imports
+    use super::*;
+    use crate::mass_spectrometry::processing::deisotoping::ISOTOPE_MASS_DIFFERENCE;
+    use crate::mass_spectrometry::spectrum::SpectrumMetadata;
+    use crate::proteomics::io::mzml::reader::Reader as MzMlReader;
+
+    /// Returns an MS1 spectrum with an averagine envelope (m/z 500, charge 2) of the given
+    /// height and a single noise peak
+    ///
+    fn ms1_spectrum(scan: usize, height: f64) -> SimpleSpectrum {
+        let charge = 2.0;
+        let neutral_mass = (500.0 - PROTON.get_mass()) * charge;
+        let mut mz = vec![300.0];
+        let mut intensity = vec![50.0];
+        if height > 0.0 {
+            for (isotope, relative_intensity) in get_averagine_isotope_distribution(neutral_mass, 4)
+                .iter()
+                .enumerate()
+            {
+                mz.push(500.0 + isotope as f64 * ISOTOPE_MASS_DIFFERENCE / charge);
+                intensity.push(relative_intensity * height);
+            }
+        }
+        SimpleSpectrum::new(scan.to_string(), 1, mz, intensity).with_metadata(
+            SpectrumMetadata::new(Some(scan as f64 * 2.0), None, None, None, None, None),
+        )
+    }
+
+    #[test]
+    fn test_find_features() {
+        let heights = [0.0, 100.0, 400.0, 1000.0, 400.0, 0.0, 100.0, 0.0, 0.0, 0.0];
+        let mut spectra: Vec<SimpleSpectrum> = heights
+            .iter()
+            .enumerate()
+            .map(|(scan, height)| ms1_spectrum(scan, *height))
+            .collect();
+        // MS2 spectra are ignored
+        spectra.insert(
+            3,
+            SimpleSpectrum::new("ms2".to_string(), 2, vec![500.0], vec![1.0]),
+        );
+
+        let feature_finder = FeatureFinder::new(Tolerance::Ppm(10.0), 1, 4).unwrap();
+        let features = feature_finder
+            .find_features(spectra.into_iter().map(Ok))
+            .unwrap();
+        assert_eq!(features.len(), 1);
+        let feature = &features[0];
+        assert!((feature.get_monoisotopic_mz() - 500.0).abs() < 1e-9);
+        assert_eq!(feature.get_charge(), 2);
+        assert_eq!(feature.get_retention_time_apex(), 6.0);
+        assert_eq!(feature.get_retention_time_start(), 2.0);
+        // gap of one spectrum is bridged
+        assert_eq!(feature.get_retention_time_end(), 12.0);
+        assert_eq!(feature.get_scan_count(), 5);
+        assert!(feature.get_isotope_fit_score() > 0.999);
+        assert!(feature.get_intensity() > 0.0);
+
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(b'\t')
+            .from_writer(Vec::new());
+        writer.serialize(feature).unwrap();
+        let table = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert!(table.starts_with(
+            "monoisotopic_mz\tcharge\tretention_time_apex\tretention_time_start\tretention_time_end\tintensity\tisotope_fit_score\tscan_count\n"
+        ));
+
+        // no gap allowed
+        let feature_finder = feature_finder.with_max_gap(0);
+        let features = feature_finder
+            .find_features(
+                heights
+                    .iter()
+                    .enumerate()
+                    .map(|(scan, height)| Ok(ms1_spectrum(scan, *height))),
+            )
+            .unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].get_retention_time_end(), 8.0);
+    }
+
+    #[test]
+    fn test_find_features_in_mzml() {
+        let mut mzml_bytes_reader =
+            BufReader::new(std::fs::File::open("test_files/spectra_small.mzML").unwrap());
+        let mut mzml_file =
+            MzMlReader::read_indexed(&mut mzml_bytes_reader, None, false, false).unwrap();
+        // the test file contains a single MS1 spectrum
+        let feature_finder = FeatureFinder::new(Tolerance::Ppm(10.0), 1, 4)
+            .unwrap()
+            .with_min_scans(1)
+            .unwrap();
+        let features = feature_finder
+            .find_features_in_mzml(&mut mzml_file)
+            .unwrap();
+        assert!(!features.is_empty());
+        for feature in features.iter() {
+            assert_eq!(feature.get_scan_count(), 1);
+            // no integration over a single spectrum, the apex intensity is used
+            assert!(feature.get_intensity() > 0.0);
+            assert!((feature.get_retention_time_apex() - 11.920181 * 60.0).abs() < 1e-9);
+        }
+    }
+}
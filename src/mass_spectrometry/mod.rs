@@ -1,4 +1,20 @@
+/// Chromatogram entities and computation of TIC and base peak chromatograms
+pub mod chromatogram;
+/// DIA isolation window schemes and window-aware spectrum grouping
+pub mod dia;
+/// MS1 feature detection across retention time
+pub mod feature_detection;
+/// Refinement of precursor m/z and charge from surrounding MS1 spectra
+pub mod precursor_refinement;
+/// Spectrum processing like peak picking and filtering
+pub mod processing;
+/// Similarity metrics between spectra
+pub mod similarity;
 /// Spectrum entities
 pub mod spectrum;
+/// Mass tolerances and tolerance-based peak lookup
+pub mod tolerance;
 /// Conversion for different units in mass spectrometry
 pub mod unit_conversions;
+/// Extracted ion chromatograms
+pub mod xic;
@@ -0,0 +1,476 @@
+// std imports
+use std::collections::HashMap;
+
+// 3rd party imports
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::chemistry::subatomic_particle::PROTON;
+use crate::mass_spectrometry::processing::deisotoping::{
+    get_averagine_isotope_distribution, ISOTOPE_MASS_DIFFERENCE,
+};
+use crate::mass_spectrometry::similarity::vector_cosine_similarity;
+use crate::mass_spectrometry::spectrum::{MsNSpectrum, Precursor, SimplePrecursor, Spectrum};
+use crate::mass_spectrometry::tolerance::{find_closest_peak, Tolerance};
+
+/// Precursor of an MSn spectrum collected for refinement
+/// (parent ID, position of the preceding MS1 spectrum, reported m/z, isolation window)
+///
+type PendingPrecursor = (String, Option<usize>, f64, (f64, f64));
+
+/// Precursor with corrected monoisotopic m/z, assigned charge and purity
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RefinedPrecursor {
+    reported_mz: f64,
+    monoisotopic_mz: f64,
+    charge: u8,
+    isotope_count: usize,
+    similarity: f64,
+    purity: f64,
+}
+
+impl RefinedPrecursor {
+    /// Returns the m/z reported by the instrument
+    ///
+    pub fn get_reported_mz(&self) -> f64 {
+        self.reported_mz
+    }
+
+    /// Returns the corrected monoisotopic m/z
+    ///
+    pub fn get_monoisotopic_mz(&self) -> f64 {
+        self.monoisotopic_mz
+    }
+
+    /// Returns the assigned charge
+    ///
+    pub fn get_charge(&self) -> u8 {
+        self.charge
+    }
+
+    /// Returns the number of detected isotope peaks
+    ///
+    pub fn get_isotope_count(&self) -> usize {
+        self.isotope_count
+    }
+
+    /// Returns the cosine similarity of the envelope to the averagine distribution
+    ///
+    pub fn get_similarity(&self) -> f64 {
+        self.similarity
+    }
+
+    /// Returns the fraction of the intensity within the isolation window
+    /// which belongs to the precursor envelope
+    ///
+    pub fn get_purity(&self) -> f64 {
+        self.purity
+    }
+
+    /// Returns a copy of the precursor with the refined ion (monoisotopic m/z, charge)
+    ///
+    /// # Arguments
+    /// * `precursor` - Precursor which was refined
+    ///
+    pub fn apply<P>(&self, precursor: &P) -> SimplePrecursor
+    where
+        P: Precursor,
+    {
+        SimplePrecursor::new(
+            precursor.get_parent_id().clone(),
+            *precursor.get_isolation_windows(),
+            Some(vec![(self.monoisotopic_mz, vec![self.charge])]),
+            precursor.get_activation().clone(),
+        )
+    }
+}
+
+/// Refines precursor m/z and charge by detecting the isotope envelope of the precursor
+/// in the parent MS1 spectrum and its neighbours.
+///
+/// The reported m/z is assumed to be any of the first isotope peaks. For each charge and
+/// isotope shift the envelope is extended from the assumed monoisotopic peak as long as the
+/// next isotope peak is found in the summed MS1 intensities. The envelope with the most
+/// isotope peaks and a sufficient similarity to the averagine distribution is assigned.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PrecursorRefiner {
+    tolerance: Tolerance,
+    min_charge: u8,
+    max_charge: u8,
+    max_isotope_shift: usize,
+    max_isotopes: usize,
+    min_similarity: f64,
+    neighbour_count: usize,
+    default_isolation_half_width: f64,
+}
+
+impl PrecursorRefiner {
+    /// Creates a new refiner, assuming the reported m/z is one of the first 4 isotope peaks,
+    /// with envelopes of up to 6 isotope peaks, a minimum averagine similarity of 0.7,
+    /// one neighbouring MS1 spectrum on each side of the parent and an isolation
+    /// half width of 1.0 if the precursor has no isolation window.
+    ///
+    /// # Arguments
+    /// * `tolerance` - Tolerance for the isotope peaks
+    /// * `min_charge` - Minimum charge
+    /// * `max_charge` - Maximum charge
+    ///
+    pub fn new(tolerance: Tolerance, min_charge: u8, max_charge: u8) -> Result<Self> {
+        if min_charge == 0 || min_charge > max_charge {
+            bail!(
+                "Invalid charge range {}-{}, charges must be positive and the minimum not greater than the maximum",
+                min_charge,
+                max_charge
+            );
+        }
+        Ok(Self {
+            tolerance,
+            min_charge,
+            max_charge,
+            max_isotope_shift: 3,
+            max_isotopes: 6,
+            min_similarity: 0.7,
+            neighbour_count: 1,
+            default_isolation_half_width: 1.0,
+        })
+    }
+
+    /// Sets the maximum number of isotope peaks the reported m/z may be above the monoisotopic peak
+    ///
+    /// # Arguments
+    /// * `max_isotope_shift` - Maximum isotope shift
+    ///
+    pub fn with_max_isotope_shift(mut self, max_isotope_shift: usize) -> Self {
+        self.max_isotope_shift = max_isotope_shift;
+        self
+    }
+
+    /// Sets the minimum cosine similarity to the averagine isotope distribution
+    ///
+    /// # Arguments
+    /// * `min_similarity` - Minimum similarity (0.0 - 1.0)
+    ///
+    pub fn with_min_similarity(mut self, min_similarity: f64) -> Self {
+        self.min_similarity = min_similarity;
+        self
+    }
+
+    /// Sets the number of MS1 spectra before and after the parent spectrum which are summed up
+    ///
+    /// # Arguments
+    /// * `neighbour_count` - Number of neighbouring MS1 spectra on each side
+    ///
+    pub fn with_neighbour_count(mut self, neighbour_count: usize) -> Self {
+        self.neighbour_count = neighbour_count;
+        self
+    }
+
+    /// Sets the isolation half width used for precursors without isolation window
+    ///
+    /// # Arguments
+    /// * `default_isolation_half_width` - Isolation half width in m/z
+    ///
+    pub fn with_default_isolation_half_width(mut self, default_isolation_half_width: f64) -> Self {
+        self.default_isolation_half_width = default_isolation_half_width;
+        self
+    }
+
+    /// Returns the summed intensity of the peak at the given m/z in all MS1 spectra
+    ///
+    fn get_summed_intensity<S>(&self, ms1_spectra: &[&S], mz: f64) -> f64
+    where
+        S: Spectrum,
+    {
+        ms1_spectra
+            .iter()
+            .filter_map(|spectrum| {
+                find_closest_peak(spectrum.get_mz(), mz, &self.tolerance)
+                    .map(|idx| spectrum.get_intensity()[idx])
+            })
+            .sum()
+    }
+
+    /// Refines the precursor reported at the given m/z.
+    /// Returns None if no envelope is found.
+    ///
+    /// # Arguments
+    /// * `reported_mz` - Reported precursor m/z
+    /// * `isolation_window` - Isolation window (lower, upper)
+    /// * `ms1_spectra` - Parent MS1 spectrum and its neighbours with sorted m/z values
+    ///
+    pub fn refine<S>(
+        &self,
+        reported_mz: f64,
+        isolation_window: (f64, f64),
+        ms1_spectra: &[&S],
+    ) -> Option<RefinedPrecursor>
+    where
+        S: Spectrum,
+    {
+        // (monoisotopic m/z, charge, isotope m/z, similarity)
+        let mut best_envelope: Option<(f64, u8, Vec<f64>, f64)> = None;
+        for charge in self.min_charge..=self.max_charge {
+            let isotope_spacing = ISOTOPE_MASS_DIFFERENCE / charge as f64;
+            for isotope_shift in 0..=self.max_isotope_shift {
+                let monoisotopic_mz = reported_mz - isotope_shift as f64 * isotope_spacing;
+                let mut isotope_mzs: Vec<f64> = Vec::new();
+                let mut observed: Vec<f64> = Vec::new();
+                for isotope in 0..self.max_isotopes.max(isotope_shift + 1) {
+                    let isotope_mz = monoisotopic_mz + isotope as f64 * isotope_spacing;
+                    let intensity = self.get_summed_intensity(ms1_spectra, isotope_mz);
+                    if intensity <= 0.0 {
+                        break;
+                    }
+                    isotope_mzs.push(isotope_mz);
+                    observed.push(intensity);
+                }
+                // the envelope has to contain the reported peak and at least one other isotope
+                if observed.len() < 2 || observed.len() <= isotope_shift {
+                    continue;
+                }
+                let neutral_mass = (monoisotopic_mz - PROTON.get_mass()) * charge as f64;
+                let similarity = vector_cosine_similarity(
+                    &observed,
+                    &get_averagine_isotope_distribution(neutral_mass, observed.len()),
+                );
+                if similarity < self.min_similarity {
+                    continue;
+                }
+                let is_better = match &best_envelope {
+                    Some((_, _, best_isotope_mzs, best_similarity)) => {
+                        isotope_mzs.len() > best_isotope_mzs.len()
+                            || (isotope_mzs.len() == best_isotope_mzs.len()
+                                && similarity > *best_similarity)
+                    }
+                    None => true,
+                };
+                if is_better {
+                    best_envelope = Some((monoisotopic_mz, charge, isotope_mzs, similarity));
+                }
+            }
+        }
+        let (monoisotopic_mz, charge, isotope_mzs, similarity) = best_envelope?;
+
+        // purity within the isolation window
+        let (lower, upper) = isolation_window;
+        let total_intensity: f64 = ms1_spectra
+            .iter()
+            .flat_map(|spectrum| {
+                spectrum
+                    .get_mz()
+                    .iter()
+                    .zip(spectrum.get_intensity().iter())
+            })
+            .filter(|(mz, _)| lower <= **mz && **mz <= upper)
+            .map(|(_, intensity)| *intensity)
+            .sum();
+        let precursor_intensity: f64 = isotope_mzs
+            .iter()
+            .filter(|mz| lower <= **mz && **mz <= upper)
+            .map(|mz| self.get_summed_intensity(ms1_spectra, *mz))
+            .sum();
+        let purity = if total_intensity > 0.0 {
+            (precursor_intensity / total_intensity).min(1.0)
+        } else {
+            0.0
+        };
+
+        Some(RefinedPrecursor {
+            reported_mz,
+            monoisotopic_mz,
+            charge,
+            isotope_count: isotope_mzs.len(),
+            similarity,
+            purity,
+        })
+    }
+
+    /// Refines the precursors of all MSn spectra of a run.
+    /// The parent MS1 spectrum is looked up by the precursor's parent ID, falling back to the
+    /// last MS1 spectrum acquired before the MSn spectrum. The returned list contains
+    /// the refined precursors of each spectrum (empty for MS1 spectra).
+    /// The spectra are consumed one by one and only the MS1 spectra are kept,
+    /// so they can be streamed from a file.
+    ///
+    /// # Arguments
+    /// * `spectra` - Spectra of the run in acquisition order
+    ///
+    pub fn refine_run<S, P, I>(&self, spectra: I) -> Result<Vec<Vec<Option<RefinedPrecursor>>>>
+    where
+        S: MsNSpectrum<P>,
+        P: Precursor,
+        I: IntoIterator<Item = Result<S>>,
+    {
+        let mut ms1_spectra: Vec<S> = Vec::new();
+        let mut msn_precursors: Vec<Vec<PendingPrecursor>> = Vec::new();
+        for spectrum in spectra {
+            let spectrum = spectrum?;
+            if spectrum.get_ms_level() == 1 {
+                ms1_spectra.push(spectrum);
+                msn_precursors.push(Vec::new());
+                continue;
+            }
+            let preceding_ms1_position = ms1_spectra.len().checked_sub(1);
+            let mut precursors = Vec::with_capacity(spectrum.get_precursors().len());
+            for precursor in spectrum.get_precursors() {
+                let reported_mz = match (precursor.get_ions(), precursor.get_isolation_windows()) {
+                    (Some(ions), _) if !ions.is_empty() => ions[0].0,
+                    (_, Some((target_mz, _, _))) => *target_mz,
+                    _ => bail!(
+                        "Spectrum {} - Precursor has neither selected ion nor isolation window",
+                        spectrum.get_id()
+                    ),
+                };
+                let isolation_window = match precursor.get_isolation_windows() {
+                    Some((target_mz, lower_offset, upper_offset))
+                        if *lower_offset > 0.0 || *upper_offset > 0.0 =>
+                    {
+                        (target_mz - lower_offset, target_mz + upper_offset)
+                    }
+                    _ => (
+                        reported_mz - self.default_isolation_half_width,
+                        reported_mz + self.default_isolation_half_width,
+                    ),
+                };
+                precursors.push((
+                    precursor.get_parent_id().clone(),
+                    preceding_ms1_position,
+                    reported_mz,
+                    isolation_window,
+                ));
+            }
+            msn_precursors.push(precursors);
+        }
+
+        // spectrum ID => position in the MS1 spectra
+        let ms1_positions: HashMap<&String, usize> = ms1_spectra
+            .iter()
+            .enumerate()
+            .map(|(position, spectrum)| (spectrum.get_id(), position))
+            .collect();
+        Ok(msn_precursors
+            .into_iter()
+            .map(|precursors| {
+                precursors
+                    .into_iter()
+                    .map(
+                        |(parent_id, preceding_ms1_position, reported_mz, isolation_window)| {
+                            let parent_position = ms1_positions
+                                .get(&parent_id)
+                                .copied()
+                                .or(preceding_ms1_position)?;
+                            let start = parent_position.saturating_sub(self.neighbour_count);
+                            let end =
+                                (parent_position + self.neighbour_count + 1).min(ms1_spectra.len());
+                            let neighbour_spectra: Vec<&S> =
+                                ms1_spectra[start..end].iter().collect();
+                            self.refine(reported_mz, isolation_window, &neighbour_spectra)
+                        },
+                    )
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    // std imports
+    use std::collections::HashMap;
+
+    // internal imports
+    use super::*;
+    use crate::mass_spectrometry::spectrum::{SimpleMsNSpectrum, SimpleSpectrum};
+
+    /// Returns an MS1 spectrum with an averagine envelope (m/z 600, charge 2) and
+    /// a contaminant at m/z 601.3
+    ///
+    fn ms1_spectrum(id: &str, scale: f64) -> SimpleMsNSpectrum {
+        let neutral_mass = (600.0 - PROTON.get_mass()) * 2.0;
+        let mut peaks: Vec<(f64, f64)> = get_averagine_isotope_distribution(neutral_mass, 5)
+            .iter()
+            .enumerate()
+            .map(|(isotope, intensity)| {
+                (
+                    600.0 + isotope as f64 * ISOTOPE_MASS_DIFFERENCE / 2.0,
+                    intensity * 1000.0 * scale,
+                )
+            })
+            .collect();
+        peaks.push((601.3, 200.0 * scale));
+        peaks.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let (mz, intensity) = peaks.into_iter().unzip();
+        SimpleMsNSpectrum::new(id.to_string(), 1, mz, intensity, Vec::new())
+    }
+
+    #[test]
+    fn test_refine() {
+        let ms1 = ms1_spectrum("ms1", 1.0);
+        let refiner = PrecursorRefiner::new(Tolerance::Ppm(10.0), 1, 4).unwrap();
+        // reported at the second isotope
+        let reported_mz = 600.0 + ISOTOPE_MASS_DIFFERENCE / 2.0;
+        let refined = refiner
+            .refine(reported_mz, (599.5, 602.5), &[&ms1])
+            .unwrap();
+        assert_eq!(refined.get_monoisotopic_mz(), 600.0);
+        assert_eq!(refined.get_charge(), 2);
+        assert_eq!(refined.get_isotope_count(), 5);
+        assert!(refined.get_similarity() > 0.999);
+        // everything but the contaminant is within the window
+        let envelope_intensity = ms1.get_intensity().iter().sum::<f64>() - 200.0;
+        let expected_purity = envelope_intensity / (envelope_intensity + 200.0);
+        assert!((refined.get_purity() - expected_purity).abs() < 1e-9);
+
+        // nothing to find
+        let empty = SimpleSpectrum::new("empty".to_string(), 1, Vec::new(), Vec::new());
+        assert!(refiner
+            .refine(reported_mz, (599.5, 601.5), &[&empty])
+            .is_none());
+    }
+
+    #[test]
+    fn test_refine_run() {
+        let ms2 = |id: &str, parent_id: &str| {
+            let precursor = SimplePrecursor::new(
+                parent_id.to_string(),
+                Some((600.8, 0.6, 0.6)),
+                Some(vec![(
+                    600.0 + 2.0 * ISOTOPE_MASS_DIFFERENCE / 2.0,
+                    Vec::new(),
+                )]),
+                (Vec::new(), HashMap::new()),
+            );
+            SimpleMsNSpectrum::new(id.to_string(), 2, Vec::new(), Vec::new(), vec![precursor])
+        };
+        let spectra = vec![
+            ms1_spectrum("ms1_a", 1.0),
+            ms2("ms2_a", "ms1_a"),
+            ms1_spectrum("ms1_b", 2.0),
+            // unknown parent, falls back to the preceding MS1
+            ms2("ms2_b", "unknown"),
+        ];
+        let refiner = PrecursorRefiner::new(Tolerance::Ppm(10.0), 1, 4)
+            .unwrap()
+            .with_neighbour_count(0);
+        let refined = refiner.refine_run(spectra.into_iter().map(Ok)).unwrap();
+        assert_eq!(refined.len(), 4);
+        assert!(refined[0].is_empty());
+        for spectrum_idx in [1, 3] {
+            let refined_precursor = refined[spectrum_idx][0].unwrap();
+            assert_eq!(refined_precursor.get_monoisotopic_mz(), 600.0);
+            assert_eq!(refined_precursor.get_charge(), 2);
+            // window 600.2 - 601.4 contains the contaminant
+            assert!(refined_precursor.get_purity() > 0.5);
+            assert!(refined_precursor.get_purity() < 1.0);
+        }
+
+        let precursor = refined[1][0]
+            .unwrap()
+            .apply(&ms2("ms2_a", "ms1_a").get_precursors()[0]);
+        assert_eq!(precursor.get_ions(), &Some(vec![(600.0, vec![2])]));
+        assert_eq!(precursor.get_parent_id(), "ms1_a");
+    }
+}
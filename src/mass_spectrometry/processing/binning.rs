@@ -0,0 +1,377 @@
+// std imports
+use std::fmt::Display;
+use std::str::FromStr;
+
+// 3rd party imports
+use anyhow::{bail, Error, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::mass_spectrometry::processing::pipeline::Pipeline;
+use crate::mass_spectrometry::processing::transform::SpectrumTransform;
+use crate::mass_spectrometry::spectrum::Spectrum;
+
+/// Combination of the intensities of peaks falling into the same bin
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BinAggregation {
+    /// Highest intensity
+    Max,
+    /// Sum of the intensities
+    Sum,
+}
+
+impl FromStr for BinAggregation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "max" => Ok(Self::Max),
+            "sum" => Ok(Self::Sum),
+            _ => bail!("Invalid bin aggregation: valid aggregations are `Max` or `Sum`"),
+        }
+    }
+}
+
+impl Display for BinAggregation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Max => write!(f, "Max"),
+            Self::Sum => write!(f, "Sum"),
+        }
+    }
+}
+
+/// Row-major matrix of binned spectra, one row per spectrum
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpectrumMatrix {
+    ids: Vec<String>,
+    bin_count: usize,
+    values: Vec<f32>,
+}
+
+impl SpectrumMatrix {
+    /// Returns the spectrum IDs in row order
+    ///
+    pub fn get_ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    /// Returns the number of bins, i.e. the number of columns
+    ///
+    pub fn get_bin_count(&self) -> usize {
+        self.bin_count
+    }
+
+    /// Returns the flat row-major values
+    ///
+    pub fn get_values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// Returns the binned intensities of the given row
+    ///
+    /// # Arguments
+    /// * `row` - Row index
+    ///
+    pub fn get_row(&self, row: usize) -> Option<&[f32]> {
+        if row >= self.ids.len() {
+            return None;
+        }
+        Some(&self.values[row * self.bin_count..(row + 1) * self.bin_count])
+    }
+
+    /// Returns the shape (rows, columns)
+    ///
+    pub fn get_shape(&self) -> (usize, usize) {
+        (self.ids.len(), self.bin_count)
+    }
+}
+
+/// Converts spectra into fixed-length vectors of binned intensities.
+/// The bin of an m/z is `floor(mz / bin_width + 1 - bin_offset)`, as used for the XCorr.
+/// Before binning the spectra are processed by the pipeline, e.g. to scale the intensities.
+/// With precursor-relative binning the m/z values are shifted by the negative precursor m/z,
+/// so the m/z range needs to be given relative to the precursor, e.g. -200 to 0 for losses.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Binner {
+    bin_width: f64,
+    bin_offset: f64,
+    min_mz: f64,
+    max_mz: f64,
+    pipeline: Pipeline,
+    aggregation: BinAggregation,
+    precursor_relative: bool,
+}
+
+impl Binner {
+    /// Creates a new binner without preprocessing, keeping the max intensity per bin.
+    ///
+    /// # Arguments
+    /// * `bin_width` - Width of a bin, e.g. 1.0005079
+    /// * `bin_offset` - Offset of the bins, e.g. 0.4
+    /// * `min_mz` - Lower bound of the m/z range (inclusive)
+    /// * `max_mz` - Upper bound of the m/z range (inclusive)
+    ///
+    pub fn new(bin_width: f64, bin_offset: f64, min_mz: f64, max_mz: f64) -> Result<Self> {
+        if bin_width <= 0.0 {
+            bail!("Bin width must be greater than 0");
+        }
+        if min_mz > max_mz {
+            bail!(
+                "Lower bound of the m/z range ({}) is greater than the upper bound ({})",
+                min_mz,
+                max_mz
+            );
+        }
+        Ok(Self {
+            bin_width,
+            bin_offset,
+            min_mz,
+            max_mz,
+            pipeline: Pipeline::new(),
+            aggregation: BinAggregation::Max,
+            precursor_relative: false,
+        })
+    }
+
+    /// Sets the pipeline which is applied to the spectra before binning
+    ///
+    /// # Arguments
+    /// * `pipeline` - Pipeline
+    ///
+    pub fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Sets how intensities of peaks falling into the same bin are combined
+    ///
+    /// # Arguments
+    /// * `aggregation` - Aggregation
+    ///
+    pub fn with_aggregation(mut self, aggregation: BinAggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Enables or disables precursor-relative binning
+    ///
+    /// # Arguments
+    /// * `precursor_relative` - Bin relative to the precursor m/z
+    ///
+    pub fn with_precursor_relative(mut self, precursor_relative: bool) -> Self {
+        self.precursor_relative = precursor_relative;
+        self
+    }
+
+    /// Returns the global bin number of the m/z
+    ///
+    fn get_global_bin(&self, mz: f64) -> i64 {
+        (mz / self.bin_width + 1.0 - self.bin_offset).floor() as i64
+    }
+
+    /// Returns the number of bins
+    ///
+    pub fn get_bin_count(&self) -> usize {
+        (self.get_global_bin(self.max_mz) - self.get_global_bin(self.min_mz) + 1) as usize
+    }
+
+    /// Returns the index of the bin for the m/z (relative to the precursor if enabled)
+    /// or None if it is outside of the m/z range
+    ///
+    /// # Arguments
+    /// * `mz` - m/z
+    ///
+    pub fn get_bin(&self, mz: f64) -> Option<usize> {
+        if mz < self.min_mz || mz > self.max_mz {
+            return None;
+        }
+        Some((self.get_global_bin(mz) - self.get_global_bin(self.min_mz)) as usize)
+    }
+
+    /// Returns the preprocessed peaks with the m/z shifted for precursor-relative binning
+    ///
+    fn get_peaks<S>(&self, spectrum: &S, precursor_mz: Option<f64>) -> Result<(Vec<f64>, Vec<f64>)>
+    where
+        S: Spectrum,
+    {
+        let precursor_ions: Vec<(f64, Vec<u8>)> = precursor_mz
+            .iter()
+            .map(|precursor_mz| (*precursor_mz, Vec::new()))
+            .collect();
+        let (mz, intensity) = self.pipeline.transform_peaks(
+            spectrum.get_mz().clone(),
+            spectrum.get_intensity().clone(),
+            &precursor_ions,
+        )?;
+        if !self.precursor_relative {
+            return Ok((mz, intensity));
+        }
+        match precursor_mz {
+            Some(precursor_mz) => Ok((
+                mz.into_iter().map(|mz| mz - precursor_mz).collect(),
+                intensity,
+            )),
+            None => bail!(
+                "Spectrum {} - Precursor m/z is required for precursor-relative binning",
+                spectrum.get_id()
+            ),
+        }
+    }
+
+    /// Bins the spectrum into the given slice
+    ///
+    fn bin_into<S>(&self, spectrum: &S, precursor_mz: Option<f64>, bins: &mut [f32]) -> Result<()>
+    where
+        S: Spectrum,
+    {
+        let (mz, intensity) = self.get_peaks(spectrum, precursor_mz)?;
+        for (mz, intensity) in mz.into_iter().zip(intensity) {
+            if let Some(bin) = self.get_bin(mz) {
+                let intensity = intensity as f32;
+                bins[bin] = match self.aggregation {
+                    BinAggregation::Max => bins[bin].max(intensity),
+                    BinAggregation::Sum => bins[bin] + intensity,
+                };
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the dense vector of binned intensities
+    ///
+    /// # Arguments
+    /// * `spectrum` - Spectrum
+    /// * `precursor_mz` - Precursor m/z, required for precursor-relative binning and precursor removal
+    ///
+    pub fn to_dense<S>(&self, spectrum: &S, precursor_mz: Option<f64>) -> Result<Vec<f32>>
+    where
+        S: Spectrum,
+    {
+        let mut bins = vec![0.0; self.get_bin_count()];
+        self.bin_into(spectrum, precursor_mz, &mut bins)?;
+        Ok(bins)
+    }
+
+    /// Returns the non-empty bins as (bin index, intensity), sorted by bin index
+    ///
+    /// # Arguments
+    /// * `spectrum` - Spectrum
+    /// * `precursor_mz` - Precursor m/z, required for precursor-relative binning and precursor removal
+    ///
+    pub fn to_sparse<S>(&self, spectrum: &S, precursor_mz: Option<f64>) -> Result<Vec<(usize, f32)>>
+    where
+        S: Spectrum,
+    {
+        let (mz, intensity) = self.get_peaks(spectrum, precursor_mz)?;
+        let mut bins: Vec<(usize, f32)> = mz
+            .into_iter()
+            .zip(intensity)
+            .filter_map(|(mz, intensity)| self.get_bin(mz).map(|bin| (bin, intensity as f32)))
+            .collect();
+        bins.sort_by_key(|(bin, _)| *bin);
+        let mut sparse: Vec<(usize, f32)> = Vec::with_capacity(bins.len());
+        for (bin, intensity) in bins {
+            match sparse.last_mut() {
+                Some((last_bin, last_intensity)) if *last_bin == bin => {
+                    *last_intensity = match self.aggregation {
+                        BinAggregation::Max => last_intensity.max(intensity),
+                        BinAggregation::Sum => *last_intensity + intensity,
+                    }
+                }
+                _ => sparse.push((bin, intensity)),
+            }
+        }
+        Ok(sparse)
+    }
+
+    /// Bins multiple spectra into a row-major matrix
+    ///
+    /// # Arguments
+    /// * `spectra` - Spectra with their optional precursor m/z
+    ///
+    pub fn to_matrix<'a, S, I>(&self, spectra: I) -> Result<SpectrumMatrix>
+    where
+        S: Spectrum + 'a,
+        I: IntoIterator<Item = (&'a S, Option<f64>)>,
+    {
+        let bin_count = self.get_bin_count();
+        let mut ids = Vec::new();
+        let mut values = Vec::new();
+        for (spectrum, precursor_mz) in spectra {
+            let row_start = values.len();
+            values.resize(row_start + bin_count, 0.0);
+            self.bin_into(spectrum, precursor_mz, &mut values[row_start..])?;
+            ids.push(spectrum.get_id().clone());
+        }
+        Ok(SpectrumMatrix {
+            ids,
+            bin_count,
+            values,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mass_spectrometry::processing::transform::Transform;
+    use crate::mass_spectrometry::spectrum::SimpleSpectrum;
+
+    #[test]
+    fn test_binner() {
+        let binner = Binner::new(1.0, 0.0, 100.0, 109.5).unwrap();
+        assert_eq!(binner.get_bin_count(), 10);
+        assert_eq!(binner.get_bin(100.0), Some(0));
+        assert_eq!(binner.get_bin(109.5), Some(9));
+        assert_eq!(binner.get_bin(99.0), None);
+        assert!(Binner::new(0.0, 0.0, 100.0, 200.0).is_err());
+        assert!(Binner::new(1.0, 0.0, 200.0, 100.0).is_err());
+
+        let spectrum = SimpleSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![99.0, 100.2, 100.7, 105.0, 120.0],
+            vec![1.0, 4.0, 9.0, 16.0, 25.0],
+        );
+        let dense = binner.to_dense(&spectrum, None).unwrap();
+        assert_eq!(
+            dense,
+            vec![9.0, 0.0, 0.0, 0.0, 0.0, 16.0, 0.0, 0.0, 0.0, 0.0]
+        );
+
+        let binner = binner
+            .with_aggregation(BinAggregation::Sum)
+            .with_pipeline(Pipeline::new().then(Transform::SqrtScaling));
+        let sparse = binner.to_sparse(&spectrum, None).unwrap();
+        assert_eq!(sparse, vec![(0, 5.0), (5, 4.0)]);
+
+        let matrix = binner
+            .to_matrix([(&spectrum, None), (&spectrum, None)])
+            .unwrap();
+        assert_eq!(matrix.get_shape(), (2, 10));
+        assert_eq!(matrix.get_ids(), &["test".to_string(), "test".to_string()]);
+        assert_eq!(matrix.get_row(1).unwrap()[5], 4.0);
+        assert!(matrix.get_row(2).is_none());
+    }
+
+    #[test]
+    fn test_precursor_relative_binning() {
+        let binner = Binner::new(1.0, 0.0, -20.0, -0.5)
+            .unwrap()
+            .with_precursor_relative(true);
+        let spectrum = SimpleSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![482.0, 499.0, 500.0],
+            vec![1.0, 2.0, 3.0],
+        );
+        assert!(binner.to_dense(&spectrum, None).is_err());
+        let sparse = binner.to_sparse(&spectrum, Some(500.0)).unwrap();
+        // water loss at -18 and -1, the precursor itself is out of range
+        assert_eq!(sparse, vec![(2, 1.0), (19, 2.0)]);
+    }
+}
@@ -0,0 +1,412 @@
+// std imports
+use std::fmt::Display;
+use std::str::FromStr;
+
+// 3rd party imports
+use anyhow::{bail, Error, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::mass_spectrometry::spectrum::CopyWithPeaks;
+use crate::proteomics::io::mzml::elements::{
+    has_cv_params::HasCvParams, spectrum::Spectrum as MzMlSpectrum,
+};
+use crate::tools::statistics::median;
+
+/// PSI ontology accession for centroid spectrum
+pub const CENTROID_SPECTRUM_ACCESSION: &str = "MS:1000127";
+
+/// PSI ontology accession for profile spectrum
+pub const PROFILE_SPECTRUM_ACCESSION: &str = "MS:1000128";
+
+/// Method to determine the apex of a profile peak from the local maximum and its two neighbours
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ApexFitting {
+    /// Parabola through the three points
+    Parabolic,
+    /// Gaussian through the three points, i.e. a parabola through the logarithmic intensities
+    Gaussian,
+}
+
+impl FromStr for ApexFitting {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "parabolic" => Ok(Self::Parabolic),
+            "gaussian" => Ok(Self::Gaussian),
+            _ => bail!("Invalid apex fitting: valid methods are `Parabolic` or `Gaussian`"),
+        }
+    }
+}
+
+impl Display for ApexFitting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parabolic => write!(f, "Parabolic"),
+            Self::Gaussian => write!(f, "Gaussian"),
+        }
+    }
+}
+
+/// Peak picked from a profile spectrum
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CentroidedPeak {
+    mz: f64,
+    intensity: f64,
+    signal_to_noise: f64,
+    fwhm: f64,
+}
+
+impl CentroidedPeak {
+    /// Returns the m/z of the apex
+    ///
+    pub fn get_mz(&self) -> f64 {
+        self.mz
+    }
+
+    /// Returns the intensity of the apex
+    ///
+    pub fn get_intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// Returns the ratio of the apex intensity to the noise level
+    ///
+    pub fn get_signal_to_noise(&self) -> f64 {
+        self.signal_to_noise
+    }
+
+    /// Returns the full width at half maximum in m/z
+    ///
+    pub fn get_fwhm(&self) -> f64 {
+        self.fwhm
+    }
+}
+
+/// Returns true if the mzML spectrum is centroided (MS:1000127) and false
+/// if it is a profile spectrum (MS:1000128).
+///
+/// # Arguments
+/// * `spectrum` - mzML spectrum
+///
+pub fn is_centroided(spectrum: &MzMlSpectrum) -> Result<bool> {
+    if !spectrum
+        .get_cv_param(CENTROID_SPECTRUM_ACCESSION)
+        .is_empty()
+    {
+        return Ok(true);
+    }
+    if !spectrum.get_cv_param(PROFILE_SPECTRUM_ACCESSION).is_empty() {
+        return Ok(false);
+    }
+    bail!(
+        "Spectrum {} - Neither centroid ({}) nor profile ({}) representation is annotated",
+        spectrum.id,
+        CENTROID_SPECTRUM_ACCESSION,
+        PROFILE_SPECTRUM_ACCESSION
+    )
+}
+
+/// Estimates the noise level as median of all positive intensities.
+/// Returns None if there are no positive intensities.
+///
+/// # Arguments
+/// * `intensity` - Intensities
+///
+pub fn estimate_noise(intensity: &[f64]) -> Option<f64> {
+    let mut positive_intensities: Vec<f64> = intensity
+        .iter()
+        .filter(|intensity| **intensity > 0.0)
+        .cloned()
+        .collect();
+    median(&mut positive_intensities)
+}
+
+/// Returns the vertex (x, y) of the parabola through the three points
+/// or None if the points are collinear.
+///
+fn fit_parabola(x: [f64; 3], y: [f64; 3]) -> Option<(f64, f64)> {
+    let denominator = (x[0] - x[1]) * (x[0] - x[2]) * (x[1] - x[2]);
+    if denominator == 0.0 {
+        return None;
+    }
+    let a = (x[2] * (y[1] - y[0]) + x[1] * (y[0] - y[2]) + x[0] * (y[2] - y[1])) / denominator;
+    if a >= 0.0 {
+        return None;
+    }
+    let b = (x[2].powi(2) * (y[0] - y[1])
+        + x[1].powi(2) * (y[2] - y[0])
+        + x[0].powi(2) * (y[1] - y[2]))
+        / denominator;
+    let c = (x[1] * x[2] * (x[1] - x[2]) * y[0]
+        + x[2] * x[0] * (x[2] - x[0]) * y[1]
+        + x[0] * x[1] * (x[0] - x[1]) * y[2])
+        / denominator;
+    let vertex_x = -b / (2.0 * a);
+    Some((vertex_x, c - b.powi(2) / (4.0 * a)))
+}
+
+/// Returns the apex (m/z, intensity) of the local maximum at the given index
+///
+fn fit_apex(mz: &[f64], intensity: &[f64], idx: usize, apex_fitting: ApexFitting) -> (f64, f64) {
+    if idx == 0 || idx == mz.len() - 1 {
+        return (mz[idx], intensity[idx]);
+    }
+    let x = [mz[idx - 1], mz[idx], mz[idx + 1]];
+    let y = [intensity[idx - 1], intensity[idx], intensity[idx + 1]];
+    let apex = match apex_fitting {
+        ApexFitting::Parabolic => fit_parabola(x, y),
+        ApexFitting::Gaussian if y.iter().all(|y| *y > 0.0) => {
+            fit_parabola(x, y.map(f64::ln)).map(|(mz, ln_intensity)| (mz, ln_intensity.exp()))
+        }
+        // logarithm not defined, fall back to the parabola
+        ApexFitting::Gaussian => fit_parabola(x, y),
+    };
+    match apex {
+        // the apex must not leave the interval spanned by the neighbours
+        Some((apex_mz, apex_intensity)) if apex_mz >= x[0] && apex_mz <= x[2] => {
+            (apex_mz, apex_intensity.max(y[1]))
+        }
+        _ => (mz[idx], intensity[idx]),
+    }
+}
+
+/// Returns the m/z where the peak falls below the half maximum, starting at the local maximum
+/// and walking in the given direction as long as the intensity is decreasing.
+/// The position is linearly interpolated between the two enclosing points.
+///
+fn find_half_maximum_mz(
+    mz: &[f64],
+    intensity: &[f64],
+    idx: usize,
+    half_maximum: f64,
+    walk_right: bool,
+) -> f64 {
+    let mut current = idx;
+    loop {
+        let next = if walk_right {
+            if current + 1 >= mz.len() {
+                return mz[current];
+            }
+            current + 1
+        } else {
+            if current == 0 {
+                return mz[current];
+            }
+            current - 1
+        };
+        if intensity[next] < half_maximum {
+            let fraction =
+                (intensity[current] - half_maximum) / (intensity[current] - intensity[next]);
+            return mz[current] + fraction * (mz[next] - mz[current]);
+        }
+        if intensity[next] > intensity[current] {
+            // next peak starts before the half maximum is reached
+            return mz[current];
+        }
+        current = next;
+    }
+}
+
+/// Picks the peaks of a profile spectrum.
+/// Each local maximum is fitted to determine the apex, signal-to-noise ratio and FWHM.
+/// Plateaus are reported once at their first point.
+///
+/// # Arguments
+/// * `mz` - Sorted m/z values of the profile spectrum
+/// * `intensity` - Intensities of the profile spectrum
+/// * `apex_fitting` - Method to determine the apex
+/// * `min_signal_to_noise` - Minimum signal-to-noise ratio of reported peaks
+///
+pub fn pick_peaks(
+    mz: &[f64],
+    intensity: &[f64],
+    apex_fitting: ApexFitting,
+    min_signal_to_noise: f64,
+) -> Vec<CentroidedPeak> {
+    // without any positive intensity there is no local maximum
+    let noise = match estimate_noise(intensity) {
+        Some(noise) => noise,
+        None => return Vec::new(),
+    };
+    let mut peaks = Vec::new();
+    for idx in 0..mz.len() {
+        let is_local_maximum = intensity[idx] > 0.0
+            && (idx == 0 || intensity[idx] > intensity[idx - 1])
+            && (idx == mz.len() - 1 || intensity[idx] >= intensity[idx + 1]);
+        if !is_local_maximum {
+            continue;
+        }
+        let (apex_mz, apex_intensity) = fit_apex(mz, intensity, idx, apex_fitting);
+        let signal_to_noise = apex_intensity / noise;
+        if signal_to_noise < min_signal_to_noise {
+            continue;
+        }
+        let half_maximum = apex_intensity / 2.0;
+        let fwhm = find_half_maximum_mz(mz, intensity, idx, half_maximum, true)
+            - find_half_maximum_mz(mz, intensity, idx, half_maximum, false);
+        peaks.push(CentroidedPeak {
+            mz: apex_mz,
+            intensity: apex_intensity,
+            signal_to_noise,
+            fwhm,
+        });
+    }
+    peaks
+}
+
+/// Centroids a profile spectrum, see `pick_peaks`.
+/// Everything except the peaks, e.g. the precursors, is kept.
+///
+/// # Arguments
+/// * `spectrum` - Profile spectrum with sorted m/z values
+/// * `apex_fitting` - Method to determine the apex
+/// * `min_signal_to_noise` - Minimum signal-to-noise ratio of reported peaks
+///
+pub fn centroid_spectrum<S>(spectrum: &S, apex_fitting: ApexFitting, min_signal_to_noise: f64) -> S
+where
+    S: CopyWithPeaks,
+{
+    let peaks = pick_peaks(
+        spectrum.get_mz(),
+        spectrum.get_intensity(),
+        apex_fitting,
+        min_signal_to_noise,
+    );
+    spectrum.copy_with_peaks(
+        peaks.iter().map(|peak| peak.mz).collect(),
+        peaks.iter().map(|peak| peak.intensity).collect(),
+    )
+}
+
+/// Converts the mzML spectrum into a centroided spectrum, e.g. a `SimpleMsNSpectrum`
+/// to keep the precursors. Spectra which are already centroided are converted without peak picking.
+///
+/// # Arguments
+/// * `spectrum` - mzML spectrum
+/// * `apex_fitting` - Method to determine the apex
+/// * `min_signal_to_noise` - Minimum signal-to-noise ratio of reported peaks
+///
+pub fn centroid_mzml_spectrum<S>(
+    spectrum: &MzMlSpectrum,
+    apex_fitting: ApexFitting,
+    min_signal_to_noise: f64,
+) -> Result<S>
+where
+    S: CopyWithPeaks + TryFrom<MzMlSpectrum, Error = Error>,
+{
+    let converted_spectrum = S::try_from(spectrum.clone())?;
+    if is_centroided(spectrum)? {
+        return Ok(converted_spectrum);
+    }
+    Ok(centroid_spectrum(
+        &converted_spectrum,
+        apex_fitting,
+        min_signal_to_noise,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    // std imports
+    use std::collections::HashMap;
+    use std::io::BufReader;
+
+    // internal imports
+    use super::*;
+    use crate::mass_spectrometry::spectrum::{
+        MsNSpectrum, Precursor, SimpleMsNSpectrum, SimplePrecursor, SimpleSpectrum, Spectrum,
+    };
+    use crate::proteomics::io::mzml::reader::Reader as MzMlReader;
+
+    /// Returns a profile spectrum with two Gaussian peaks
+    ///
+    fn get_profile() -> (Vec<f64>, Vec<f64>) {
+        let mz: Vec<f64> = (0..400).map(|i| 500.0 + i as f64 * 0.005).collect();
+        let intensity = mz
+            .iter()
+            .map(|mz| {
+                // sigma 0.01 => FWHM 0.02355
+                1000.0 * (-(mz - 500.5012_f64).powi(2) / (2.0 * 0.01_f64.powi(2))).exp()
+                    + 200.0 * (-(mz - 501.3037_f64).powi(2) / (2.0 * 0.01_f64.powi(2))).exp()
+                    + 1.0
+            })
+            .collect();
+        (mz, intensity)
+    }
+
+    #[test]
+    fn test_apex_fitting_from_str() {
+        assert_eq!(
+            ApexFitting::from_str("gaussian").unwrap(),
+            ApexFitting::Gaussian
+        );
+        assert_eq!(ApexFitting::Parabolic.to_string(), "Parabolic");
+        assert!(ApexFitting::from_str("spline").is_err());
+    }
+
+    #[test]
+    fn test_pick_peaks() {
+        let (mz, intensity) = get_profile();
+        let peaks = pick_peaks(&mz, &intensity, ApexFitting::Gaussian, 10.0);
+        assert_eq!(peaks.len(), 2);
+        assert!((peaks[0].get_mz() - 500.5012).abs() < 1e-4);
+        assert!((peaks[0].get_intensity() - 1001.0).abs() < 5.0);
+        assert!((peaks[0].get_fwhm() - 0.02355).abs() < 0.002);
+        assert!((peaks[1].get_mz() - 501.3037).abs() < 1e-4);
+        assert!(peaks[0].get_signal_to_noise() > peaks[1].get_signal_to_noise());
+
+        let peaks = pick_peaks(&mz, &intensity, ApexFitting::Parabolic, 10.0);
+        assert_eq!(peaks.len(), 2);
+        assert!((peaks[0].get_mz() - 500.5012).abs() < 1e-3);
+
+        let spectrum = SimpleSpectrum::new("profile".to_string(), 1, mz.clone(), intensity.clone());
+        let centroided = centroid_spectrum(&spectrum, ApexFitting::Gaussian, 10.0);
+        assert_eq!(centroided.get_id(), "profile");
+        assert_eq!(centroided.get_mz().len(), 2);
+
+        // precursors are kept
+        let spectrum = SimpleMsNSpectrum::new(
+            "profile".to_string(),
+            2,
+            mz,
+            intensity,
+            vec![SimplePrecursor::new(
+                "parent".to_string(),
+                None,
+                Some(vec![(600.0, vec![2])]),
+                (Vec::new(), HashMap::new()),
+            )],
+        );
+        let centroided = centroid_spectrum(&spectrum, ApexFitting::Gaussian, 10.0);
+        assert_eq!(centroided.get_mz().len(), 2);
+        assert_eq!(centroided.get_precursors().len(), 1);
+        assert_eq!(centroided.get_precursors()[0].get_parent_id(), "parent");
+    }
+
+    #[test]
+    fn test_estimate_noise() {
+        assert_eq!(estimate_noise(&[0.0, 4.0, 1.0, 2.0]), Some(2.0));
+        // no noise level without positive intensities
+        assert_eq!(estimate_noise(&[0.0, 0.0]), None);
+        assert!(pick_peaks(&[100.0, 101.0], &[0.0, 0.0], ApexFitting::Gaussian, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_centroid_mzml_spectrum() {
+        let mut mzml_bytes_reader =
+            BufReader::new(std::fs::File::open("test_files/spectra_small.mzML").unwrap());
+        let mut mzml_file =
+            MzMlReader::read_indexed(&mut mzml_bytes_reader, None, false, false).unwrap();
+        let spectrum = mzml_file
+            .get_spectrum("controllerType=0 controllerNumber=1 scan=3865")
+            .unwrap();
+        assert!(is_centroided(&spectrum).unwrap());
+        let centroided: SimpleSpectrum =
+            centroid_mzml_spectrum(&spectrum, ApexFitting::Gaussian, 3.0).unwrap();
+        assert_eq!(centroided.get_mz().len(), 51);
+    }
+}
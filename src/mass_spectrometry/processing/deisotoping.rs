@@ -0,0 +1,552 @@
+// std imports
+use std::fmt::Display;
+use std::str::FromStr;
+
+// 3rd party imports
+use anyhow::{bail, Error, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::chemistry::element::{CARBON, HYDROGEN, NITROGEN, OXYGEN, SULPHUR};
+use crate::chemistry::subatomic_particle::PROTON;
+use crate::mass_spectrometry::similarity::vector_cosine_similarity;
+use crate::mass_spectrometry::spectrum::CopyWithPeaks;
+use crate::mass_spectrometry::tolerance::{find_closest_peak, Tolerance};
+
+/// Mass difference between 13C and 12C, used as spacing of isotope peaks
+///
+pub const ISOTOPE_MASS_DIFFERENCE: f64 = 1.0033548378;
+
+/// Averagine composition (C, H, N, O, S) per 111.1254 Da
+///
+/// > Senko MW, Beu SC, McLafferty FW. Determination of monoisotopic masses and ion populations
+/// > for large biomolecules from resolved isotopic distributions.
+/// > J Am Soc Mass Spectrom. 1995;6(4):229-233. https://doi.org/10.1016/1044-0305(95)00017-8
+///
+const AVERAGINE_COMPOSITION: [f64; 5] = [4.9384, 7.7583, 1.3577, 1.4773, 0.0417];
+
+/// Natural isotope abundances of C, H, N, O and S, indexed by the nominal mass shift
+///
+const ISOTOPE_ABUNDANCES: [&[f64]; 5] = [
+    &[0.9893, 0.0107],
+    &[0.999885, 0.000115],
+    &[0.99636, 0.00364],
+    &[0.99757, 0.00038, 0.00205],
+    &[0.9499, 0.0075, 0.0425, 0.0, 0.0001],
+];
+
+/// Returns the convolution of both distributions, truncated to the given length
+///
+fn convolve(x: &[f64], y: &[f64], length: usize) -> Vec<f64> {
+    let mut result = vec![0.0; length];
+    for (i, x_i) in x.iter().enumerate().take(length) {
+        for (j, y_j) in y.iter().enumerate().take(length - i) {
+            result[i + j] += x_i * y_j;
+        }
+    }
+    result
+}
+
+/// Returns the isotope distribution of the given number of atoms of one element
+///
+fn get_element_isotope_distribution(abundances: &[f64], atoms: usize, length: usize) -> Vec<f64> {
+    // exponentiation by squaring
+    let mut result = vec![1.0];
+    let mut base = abundances.to_vec();
+    let mut exponent = atoms;
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = convolve(&result, &base, length);
+        }
+        base = convolve(&base, &base, length);
+        exponent /= 2;
+    }
+    result.resize(length, 0.0);
+    result
+}
+
+/// Returns the relative intensities of the first isotope peaks of an averagine molecule
+/// of the given neutral mass, normalized to a maximum of 1.
+///
+/// # Arguments
+/// * `mass` - Neutral mass
+/// * `isotope_count` - Number of isotope peaks
+///
+pub fn get_averagine_isotope_distribution(mass: f64, isotope_count: usize) -> Vec<f64> {
+    if isotope_count == 0 {
+        return Vec::new();
+    }
+    let averagine_mass: f64 = AVERAGINE_COMPOSITION
+        .iter()
+        .zip([&CARBON, &HYDROGEN, &NITROGEN, &OXYGEN, &SULPHUR])
+        .map(|(count, element)| count * element.get_mono_mass())
+        .sum();
+    let units = mass / averagine_mass;
+    let mut distribution = vec![1.0];
+    for (count, abundances) in AVERAGINE_COMPOSITION.iter().zip(ISOTOPE_ABUNDANCES) {
+        let atoms = (count * units).round().max(0.0) as usize;
+        distribution = convolve(
+            &distribution,
+            &get_element_isotope_distribution(abundances, atoms, isotope_count),
+            isotope_count,
+        );
+    }
+    distribution.resize(isotope_count, 0.0);
+    let max_intensity = distribution.iter().cloned().fold(0.0, f64::max);
+    if max_intensity > 0.0 {
+        distribution
+            .iter_mut()
+            .for_each(|intensity| *intensity /= max_intensity);
+    }
+    distribution
+}
+
+/// Representation of the monoisotopic peaks in the deisotoped spectrum
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MassRepresentation {
+    /// m/z at the detected charge
+    MassToCharge,
+    /// m/z of the singly charged ion
+    SinglyCharged,
+    /// Neutral mass
+    Neutral,
+}
+
+impl FromStr for MassRepresentation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "masstocharge" | "mz" => Ok(Self::MassToCharge),
+            "singlycharged" => Ok(Self::SinglyCharged),
+            "neutral" => Ok(Self::Neutral),
+            _ => bail!(
+                "Invalid mass representation: valid representations are `MassToCharge`, `SinglyCharged` or `Neutral`"
+            ),
+        }
+    }
+}
+
+impl Display for MassRepresentation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MassToCharge => write!(f, "MassToCharge"),
+            Self::SinglyCharged => write!(f, "SinglyCharged"),
+            Self::Neutral => write!(f, "Neutral"),
+        }
+    }
+}
+
+/// Isotope envelope collapsed to its monoisotopic peak
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IsotopeEnvelope {
+    monoisotopic_mz: f64,
+    charge: u8,
+    intensity: f64,
+    peak_indices: Vec<usize>,
+    similarity: f64,
+}
+
+impl IsotopeEnvelope {
+    /// Returns the m/z of the monoisotopic peak
+    ///
+    pub fn get_monoisotopic_mz(&self) -> f64 {
+        self.monoisotopic_mz
+    }
+
+    /// Returns the charge
+    ///
+    pub fn get_charge(&self) -> u8 {
+        self.charge
+    }
+
+    /// Returns the summed intensity of all isotope peaks
+    ///
+    pub fn get_intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// Returns the indices of the isotope peaks in the original spectrum, starting with the monoisotopic peak
+    ///
+    pub fn get_peak_indices(&self) -> &[usize] {
+        &self.peak_indices
+    }
+
+    /// Returns the cosine similarity to the averagine isotope distribution
+    ///
+    pub fn get_similarity(&self) -> f64 {
+        self.similarity
+    }
+
+    /// Returns the neutral monoisotopic mass
+    ///
+    pub fn get_neutral_mass(&self) -> f64 {
+        (self.monoisotopic_mz - PROTON.get_mass()) * self.charge as f64
+    }
+
+    /// Returns the monoisotopic mass in the given representation
+    ///
+    /// # Arguments
+    /// * `mass_representation` - Mass representation
+    ///
+    pub fn get_mass(&self, mass_representation: MassRepresentation) -> f64 {
+        match mass_representation {
+            MassRepresentation::MassToCharge => self.monoisotopic_mz,
+            MassRepresentation::SinglyCharged => self.get_neutral_mass() + PROTON.get_mass(),
+            MassRepresentation::Neutral => self.get_neutral_mass(),
+        }
+    }
+}
+
+/// Groups centroided peaks into isotope envelopes based on the averagine model,
+/// assigns a charge to each envelope and collapses it to its monoisotopic peak.
+///
+/// Peaks are tested in ascending m/z order as monoisotopic peak. For each charge the envelope
+/// is extended as long as the next isotope peak is found. The charge explaining the most
+/// isotope peaks with a sufficient similarity to the averagine distribution is assigned.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Deisotoper {
+    tolerance: Tolerance,
+    min_charge: u8,
+    max_charge: u8,
+    min_isotopes: usize,
+    max_isotopes: usize,
+    min_similarity: f64,
+    mass_representation: MassRepresentation,
+    keep_unassigned_peaks: bool,
+}
+
+impl Deisotoper {
+    /// Creates a new deisotoper, with envelopes of 2 to 6 isotope peaks, a minimum averagine
+    /// similarity of 0.8, m/z output and unassigned peaks kept.
+    ///
+    /// # Arguments
+    /// * `tolerance` - Tolerance for the isotope peaks
+    /// * `min_charge` - Minimum charge
+    /// * `max_charge` - Maximum charge
+    ///
+    pub fn new(tolerance: Tolerance, min_charge: u8, max_charge: u8) -> Result<Self> {
+        if min_charge == 0 || min_charge > max_charge {
+            bail!(
+                "Invalid charge range {}-{}, charges must be positive and the minimum not greater than the maximum",
+                min_charge,
+                max_charge
+            );
+        }
+        Ok(Self {
+            tolerance,
+            min_charge,
+            max_charge,
+            min_isotopes: 2,
+            max_isotopes: 6,
+            min_similarity: 0.8,
+            mass_representation: MassRepresentation::MassToCharge,
+            keep_unassigned_peaks: true,
+        })
+    }
+
+    /// Sets the minimum and maximum number of isotope peaks per envelope
+    ///
+    /// # Arguments
+    /// * `min_isotopes` - Minimum number of isotope peaks (including the monoisotopic peak)
+    /// * `max_isotopes` - Maximum number of isotope peaks (including the monoisotopic peak)
+    ///
+    pub fn with_isotope_range(mut self, min_isotopes: usize, max_isotopes: usize) -> Result<Self> {
+        if min_isotopes == 0 || min_isotopes > max_isotopes {
+            bail!(
+                "Invalid isotope range {}-{}, the minimum must be positive and not greater than the maximum",
+                min_isotopes,
+                max_isotopes
+            );
+        }
+        self.min_isotopes = min_isotopes;
+        self.max_isotopes = max_isotopes;
+        Ok(self)
+    }
+
+    /// Sets the minimum cosine similarity to the averagine isotope distribution
+    ///
+    /// # Arguments
+    /// * `min_similarity` - Minimum similarity (0.0 - 1.0)
+    ///
+    pub fn with_min_similarity(mut self, min_similarity: f64) -> Self {
+        self.min_similarity = min_similarity;
+        self
+    }
+
+    /// Sets the representation of the monoisotopic peaks in the deisotoped spectrum
+    ///
+    /// # Arguments
+    /// * `mass_representation` - Mass representation
+    ///
+    pub fn with_mass_representation(mut self, mass_representation: MassRepresentation) -> Self {
+        self.mass_representation = mass_representation;
+        self
+    }
+
+    /// Sets if peaks which are not part of an envelope are kept in the deisotoped spectrum.
+    /// Their charge is unknown, so they are treated as singly charged for the
+    /// neutral mass representation.
+    ///
+    /// # Arguments
+    /// * `keep_unassigned_peaks` - Keep unassigned peaks
+    ///
+    pub fn with_keep_unassigned_peaks(mut self, keep_unassigned_peaks: bool) -> Self {
+        self.keep_unassigned_peaks = keep_unassigned_peaks;
+        self
+    }
+
+    /// Returns the isotope peaks of the envelope starting at the given peak
+    ///
+    fn extend_envelope(&self, mz: &[f64], used: &[bool], start: usize, charge: u8) -> Vec<usize> {
+        let mut peak_indices = vec![start];
+        for isotope in 1..self.max_isotopes {
+            let isotope_mz = mz[start] + isotope as f64 * ISOTOPE_MASS_DIFFERENCE / charge as f64;
+            match find_closest_peak(mz, isotope_mz, &self.tolerance) {
+                Some(idx) if !used[idx] => peak_indices.push(idx),
+                _ => break,
+            }
+        }
+        peak_indices
+    }
+
+    /// Returns the isotope envelopes of the given centroided peaks
+    ///
+    /// # Arguments
+    /// * `mz` - Sorted m/z values
+    /// * `intensity` - Intensities
+    ///
+    pub fn find_envelopes(&self, mz: &[f64], intensity: &[f64]) -> Vec<IsotopeEnvelope> {
+        let mut used = vec![false; mz.len()];
+        let mut envelopes = Vec::new();
+        for start in 0..mz.len() {
+            if used[start] {
+                continue;
+            }
+            let mut best_envelope: Option<IsotopeEnvelope> = None;
+            for charge in (self.min_charge..=self.max_charge).rev() {
+                let peak_indices = self.extend_envelope(mz, &used, start, charge);
+                if peak_indices.len() < self.min_isotopes {
+                    continue;
+                }
+                let observed: Vec<f64> = peak_indices.iter().map(|idx| intensity[*idx]).collect();
+                let neutral_mass = (mz[start] - PROTON.get_mass()) * charge as f64;
+                let theoretical =
+                    get_averagine_isotope_distribution(neutral_mass, peak_indices.len());
+                let similarity = vector_cosine_similarity(&observed, &theoretical);
+                if similarity < self.min_similarity {
+                    continue;
+                }
+                let is_better = match &best_envelope {
+                    Some(best_envelope) => {
+                        peak_indices.len() > best_envelope.peak_indices.len()
+                            || (peak_indices.len() == best_envelope.peak_indices.len()
+                                && similarity > best_envelope.similarity)
+                    }
+                    None => true,
+                };
+                if is_better {
+                    best_envelope = Some(IsotopeEnvelope {
+                        monoisotopic_mz: mz[start],
+                        charge,
+                        intensity: observed.iter().sum(),
+                        peak_indices,
+                        similarity,
+                    });
+                }
+            }
+            if let Some(envelope) = best_envelope {
+                envelope
+                    .peak_indices
+                    .iter()
+                    .for_each(|idx| used[*idx] = true);
+                envelopes.push(envelope);
+            }
+        }
+        envelopes
+    }
+
+    /// Deisotopes the spectrum. Each envelope is replaced by its monoisotopic peak in the
+    /// configured mass representation, carrying the summed intensity of the envelope.
+    /// The resulting peaks are sorted by m/z. Everything except the peaks, e.g. the precursors, is kept.
+    ///
+    /// # Arguments
+    /// * `spectrum` - Centroided spectrum with sorted m/z values
+    ///
+    pub fn deisotope<S>(&self, spectrum: &S) -> S
+    where
+        S: CopyWithPeaks,
+    {
+        let mz = spectrum.get_mz();
+        let intensity = spectrum.get_intensity();
+        let envelopes = self.find_envelopes(mz, intensity);
+        let mut peaks: Vec<(f64, f64)> = envelopes
+            .iter()
+            .map(|envelope| {
+                (
+                    envelope.get_mass(self.mass_representation),
+                    envelope.get_intensity(),
+                )
+            })
+            .collect();
+        if self.keep_unassigned_peaks {
+            let mut used = vec![false; mz.len()];
+            envelopes
+                .iter()
+                .flat_map(|envelope| envelope.peak_indices.iter())
+                .for_each(|idx| used[*idx] = true);
+            peaks.extend(
+                mz.iter()
+                    .zip(intensity.iter())
+                    .zip(used)
+                    .filter(|(_, used)| !used)
+                    .map(|((mz, intensity), _)| match self.mass_representation {
+                        MassRepresentation::Neutral => (mz - PROTON.get_mass(), *intensity),
+                        _ => (*mz, *intensity),
+                    }),
+            );
+        }
+        peaks.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let (mz, intensity) = peaks.into_iter().unzip();
+        spectrum.copy_with_peaks(mz, intensity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    // std imports
+    use std::collections::HashMap;
+
+    // internal imports
+    use super::*;
+    use crate::mass_spectrometry::spectrum::{
+        MsNSpectrum, SimpleMsNSpectrum, SimplePrecursor, SimpleSpectrum, Spectrum,
+    };
+
+    /// Returns the averagine envelope of the given neutral mass and charge
+    ///
+    fn get_envelope(neutral_mass: f64, charge: u8, intensity: f64) -> Vec<(f64, f64)> {
+        get_averagine_isotope_distribution(neutral_mass, 4)
+            .into_iter()
+            .enumerate()
+            .map(|(isotope, relative_intensity)| {
+                (
+                    (neutral_mass + isotope as f64 * ISOTOPE_MASS_DIFFERENCE) / charge as f64
+                        + PROTON.get_mass(),
+                    relative_intensity * intensity,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_get_averagine_isotope_distribution() {
+        let distribution = get_averagine_isotope_distribution(1000.0, 3);
+        assert_eq!(distribution.len(), 3);
+        assert_eq!(distribution[0], 1.0);
+        // ~55% for the second isotope of a 1 kDa peptide
+        assert!((distribution[1] - 0.55).abs() < 0.03);
+        // heavy molecules have their most abundant peak after the monoisotopic peak
+        let distribution = get_averagine_isotope_distribution(5000.0, 5);
+        assert!(distribution[0] < 1.0);
+    }
+
+    #[test]
+    fn test_deisotope() {
+        let mut peaks = get_envelope(1500.0, 2, 1000.0);
+        peaks.extend(get_envelope(800.0, 1, 500.0));
+        peaks.push((900.0, 10.0));
+        peaks.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let (mz, intensity): (Vec<f64>, Vec<f64>) = peaks.into_iter().unzip();
+        let spectrum = SimpleSpectrum::new("test".to_string(), 2, mz, intensity);
+
+        let deisotoper = Deisotoper::new(Tolerance::Ppm(10.0), 1, 4).unwrap();
+        let envelopes = deisotoper.find_envelopes(spectrum.get_mz(), spectrum.get_intensity());
+        assert_eq!(envelopes.len(), 2);
+        // 1500 Da at charge 2 (m/z 751) comes before 800 Da at charge 1 (m/z 801)
+        assert_eq!(envelopes[0].get_charge(), 2);
+        assert!((envelopes[0].get_neutral_mass() - 1500.0).abs() < 1e-6);
+        assert_eq!(envelopes[0].get_peak_indices().len(), 4);
+        assert_eq!(envelopes[1].get_charge(), 1);
+        assert!((envelopes[1].get_neutral_mass() - 800.0).abs() < 1e-6);
+
+        let deisotoped = deisotoper.deisotope(&spectrum);
+        assert_eq!(deisotoped.get_mz().len(), 3);
+
+        let deisotoped = deisotoper
+            .with_mass_representation(MassRepresentation::SinglyCharged)
+            .with_keep_unassigned_peaks(false)
+            .deisotope(&spectrum);
+        assert_eq!(deisotoped.get_mz().len(), 2);
+        assert!((deisotoped.get_mz()[1] - 1500.0 - PROTON.get_mass()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_deisotope_keeps_precursors() {
+        let (mz, intensity): (Vec<f64>, Vec<f64>) =
+            get_envelope(1500.0, 2, 1000.0).into_iter().unzip();
+        let spectrum = SimpleMsNSpectrum::new(
+            "test".to_string(),
+            2,
+            mz,
+            intensity,
+            vec![SimplePrecursor::new(
+                "parent".to_string(),
+                None,
+                Some(vec![(751.0, vec![2])]),
+                (Vec::new(), HashMap::new()),
+            )],
+        );
+        let deisotoped = Deisotoper::new(Tolerance::Ppm(10.0), 1, 4)
+            .unwrap()
+            .deisotope(&spectrum);
+        assert_eq!(deisotoped.get_id(), "test");
+        assert_eq!(deisotoped.get_mz().len(), 1);
+        assert_eq!(deisotoped.get_precursors().len(), 1);
+    }
+
+    #[test]
+    fn test_lone_peak() {
+        let spectrum = SimpleSpectrum::new("test".to_string(), 2, vec![500.0], vec![100.0]);
+        // a single peak is not an envelope, although its similarity to any distribution is 1
+        let deisotoper = Deisotoper::new(Tolerance::Ppm(10.0), 1, 4).unwrap();
+        assert!(deisotoper
+            .find_envelopes(spectrum.get_mz(), spectrum.get_intensity())
+            .is_empty());
+        // kept as unassigned peak
+        assert_eq!(deisotoper.deisotope(&spectrum).get_mz(), &vec![500.0]);
+        let deisotoper = deisotoper.with_keep_unassigned_peaks(false);
+        assert!(deisotoper.deisotope(&spectrum).get_mz().is_empty());
+
+        // two isotope peaks are not enough if at least three are required
+        let (mz, intensity): (Vec<f64>, Vec<f64>) =
+            get_envelope(800.0, 1, 500.0).into_iter().take(2).unzip();
+        let deisotoper = deisotoper.with_isotope_range(3, 6).unwrap();
+        assert!(deisotoper.find_envelopes(&mz, &intensity).is_empty());
+        // but with a minimum of one isotope peak a lone peak forms an envelope
+        let deisotoper = deisotoper.with_isotope_range(1, 6).unwrap();
+        assert_eq!(
+            deisotoper
+                .find_envelopes(spectrum.get_mz(), spectrum.get_intensity())
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_deisotoper_parameters() {
+        assert!(Deisotoper::new(Tolerance::Ppm(10.0), 0, 4).is_err());
+        assert!(Deisotoper::new(Tolerance::Ppm(10.0), 3, 2).is_err());
+        assert!(Deisotoper::new(Tolerance::Ppm(10.0), 1, 2)
+            .unwrap()
+            .with_isotope_range(3, 2)
+            .is_err());
+        assert_eq!(
+            MassRepresentation::from_str("neutral").unwrap(),
+            MassRepresentation::Neutral
+        );
+    }
+}
@@ -0,0 +1,85 @@
+// internal imports
+use crate::mass_spectrometry::spectrum::{SimpleSpectrum, Spectrum};
+
+/// Returns the indices of the `n` most intense peaks of each m/z window, sorted by m/z.
+///
+/// # Arguments
+/// * `mz` - Sorted m/z values
+/// * `intensity` - Intensities
+/// * `n` - Number of peaks per window
+/// * `window_width` - m/z width of the window
+///
+pub fn top_n_peaks_per_window(
+    mz: &[f64],
+    intensity: &[f64],
+    n: usize,
+    window_width: f64,
+) -> Vec<usize> {
+    let mut selected: Vec<usize> = Vec::with_capacity(mz.len());
+    let mut window_start = 0;
+    while window_start < mz.len() {
+        let window_number = (mz[window_start] / window_width).floor();
+        let window_end = window_start
+            + mz[window_start..]
+                .iter()
+                .take_while(|peak_mz| (**peak_mz / window_width).floor() == window_number)
+                .count();
+        let mut window: Vec<usize> = (window_start..window_end).collect();
+        window.sort_by(|x, y| intensity[*y].total_cmp(&intensity[*x]));
+        selected.extend(window.into_iter().take(n));
+        window_start = window_end;
+    }
+    selected.sort();
+    selected
+}
+
+/// Returns a copy of the spectrum containing only the `n` most intense peaks of each m/z window.
+///
+/// # Arguments
+/// * `spectrum` - Spectrum with sorted m/z values
+/// * `n` - Number of peaks per window
+/// * `window_width` - m/z width of the window
+///
+pub fn filter_top_n_peaks_per_window<S>(spectrum: &S, n: usize, window_width: f64) -> SimpleSpectrum
+where
+    S: Spectrum,
+{
+    let selected_peaks =
+        top_n_peaks_per_window(spectrum.get_mz(), spectrum.get_intensity(), n, window_width);
+    SimpleSpectrum::new(
+        spectrum.get_id().clone(),
+        spectrum.get_ms_level(),
+        selected_peaks
+            .iter()
+            .map(|idx| spectrum.get_mz()[*idx])
+            .collect(),
+        selected_peaks
+            .iter()
+            .map(|idx| spectrum.get_intensity()[*idx])
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_top_n_peaks_per_window() {
+        let mz = [10.0, 20.0, 30.0, 110.0, 120.0, 250.0];
+        let intensity = [1.0, 3.0, 2.0, 5.0, 4.0, 1.0];
+        assert_eq!(
+            top_n_peaks_per_window(&mz, &intensity, 2, 100.0),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            top_n_peaks_per_window(&mz, &intensity, 1, 100.0),
+            vec![1, 3, 5]
+        );
+
+        let spectrum = SimpleSpectrum::new("test".to_string(), 2, mz.to_vec(), intensity.to_vec());
+        let filtered = filter_top_n_peaks_per_window(&spectrum, 1, 100.0);
+        assert_eq!(filtered.get_mz(), &vec![20.0, 110.0, 250.0]);
+        assert_eq!(filtered.get_intensity(), &vec![3.0, 5.0, 1.0]);
+    }
+}
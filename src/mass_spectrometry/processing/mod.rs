@@ -0,0 +1,12 @@
+/// Conversion of spectra into fixed-length vectors, e.g. for machine learning
+pub mod binning;
+/// Peak picking of profile spectra
+pub mod centroid;
+/// Deisotoping and charge state deconvolution of centroided spectra
+pub mod deisotoping;
+/// Peak filters, e.g. keeping the most intense peaks per m/z window
+pub mod filter;
+/// Chainable and serializable pipelines of spectrum transformations
+pub mod pipeline;
+/// Spectrum transformations like peak filtering, intensity scaling and normalization
+pub mod transform;
@@ -0,0 +1,137 @@
+// 3rd party imports
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::mass_spectrometry::processing::transform::{SpectrumTransform, Transform};
+
+/// Chain of spectrum transformations which are applied in order.
+/// Pipelines are serializable, e.g. to JSON, to document and reproduce the processing.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pipeline {
+    transforms: Vec<Transform>,
+}
+
+impl Pipeline {
+    /// Creates a new empty pipeline
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the transformation to the pipeline
+    ///
+    /// # Arguments
+    /// * `transform` - Transformation
+    ///
+    pub fn then(mut self, transform: Transform) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Returns the transformations in order of application
+    ///
+    pub fn get_transforms(&self) -> &[Transform] {
+        &self.transforms
+    }
+}
+
+impl SpectrumTransform for Pipeline {
+    fn transform_peaks(
+        &self,
+        mz: Vec<f64>,
+        intensity: Vec<f64>,
+        precursor_ions: &[(f64, Vec<u8>)],
+    ) -> Result<(Vec<f64>, Vec<f64>)> {
+        self.transforms
+            .iter()
+            .try_fold((mz, intensity), |(mz, intensity), transform| {
+                transform.transform_peaks(mz, intensity, precursor_ions)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    // std imports
+    use std::collections::HashMap;
+
+    // internal imports
+    use super::*;
+    use crate::mass_spectrometry::spectrum::{
+        MsNSpectrum, Precursor, SimpleMsNSpectrum, SimplePrecursor, SimpleSpectrum, Spectrum,
+        SpectrumMetadata,
+    };
+    use crate::mass_spectrometry::tolerance::Tolerance;
+
+    #[test]
+    fn test_pipeline() {
+        let pipeline = Pipeline::new()
+            .then(Transform::MzRange {
+                min_mz: 150.0,
+                max_mz: 600.0,
+            })
+            .then(Transform::SqrtScaling)
+            .then(Transform::BasePeakNormalization);
+        let spectrum = SimpleSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![100.0, 200.0, 300.0],
+            vec![100.0, 4.0, 16.0],
+        );
+        let transformed = pipeline.transform(&spectrum).unwrap();
+        assert_eq!(transformed.get_mz(), &vec![200.0, 300.0]);
+        assert_eq!(transformed.get_intensity(), &vec![0.5, 1.0]);
+
+        let json = serde_json::to_string(&pipeline).unwrap();
+        assert_eq!(
+            json,
+            r#"{"transforms":[{"type":"MzRange","min_mz":150.0,"max_mz":600.0},{"type":"SqrtScaling"},{"type":"BasePeakNormalization"}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Pipeline>(&json).unwrap(), pipeline);
+    }
+
+    #[test]
+    fn test_pipeline_msn() {
+        let pipeline = Pipeline::new()
+            .then(Transform::PrecursorRemoval {
+                tolerance: Tolerance::Da(0.02),
+            })
+            .then(Transform::BasePeakNormalization);
+        let spectrum = SimpleMsNSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![100.0, 200.0, 500.0],
+            vec![2.0, 4.0, 16.0],
+            vec![SimplePrecursor::new(
+                "parent".to_string(),
+                Some((500.0, 1.0, 1.0)),
+                Some(vec![(500.0, vec![2])]),
+                (Vec::new(), HashMap::new()),
+            )],
+        )
+        .with_metadata(SpectrumMetadata::new(
+            Some(1234.5),
+            None,
+            Some(42),
+            None,
+            None,
+            None,
+        ));
+        let transformed = pipeline.transform_msn(&spectrum).unwrap();
+        assert_eq!(transformed.get_id(), "test");
+        assert_eq!(transformed.get_ms_level(), 2);
+        assert_eq!(transformed.get_mz(), &vec![100.0, 200.0]);
+        assert_eq!(transformed.get_intensity(), &vec![0.5, 1.0]);
+        assert_eq!(transformed.get_precursors().len(), 1);
+        assert_eq!(transformed.get_precursors()[0].get_parent_id(), "parent");
+        let metadata = transformed.get_metadata().unwrap();
+        assert_eq!(metadata.get_retention_time(), Some(1234.5));
+        assert_eq!(metadata.get_scan_number(), Some(42));
+        assert_eq!(
+            transformed.get_precursors()[0].get_ions(),
+            &Some(vec![(500.0, vec![2])])
+        );
+    }
+}
@@ -0,0 +1,335 @@
+// 3rd party imports
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::mass_spectrometry::processing::centroid::{estimate_noise, pick_peaks, ApexFitting};
+use crate::mass_spectrometry::processing::deisotoping::Deisotoper;
+use crate::mass_spectrometry::processing::filter::top_n_peaks_per_window;
+use crate::mass_spectrometry::spectrum::{
+    CopyWithPeaks, MsNSpectrum, Precursor, SimpleSpectrum, Spectrum,
+};
+use crate::mass_spectrometry::tolerance::Tolerance;
+
+/// Transformation of the peaks of a spectrum, e.g. filtering or intensity scaling.
+///
+pub trait SpectrumTransform {
+    /// Transforms the peaks of a spectrum. The returned m/z values need to be sorted ascending.
+    ///
+    /// # Arguments
+    /// * `mz` - Sorted m/z values
+    /// * `intensity` - Intensities
+    /// * `precursor_ions` - Precursor ions (m/z, charges), empty for MS1 spectra
+    ///
+    fn transform_peaks(
+        &self,
+        mz: Vec<f64>,
+        intensity: Vec<f64>,
+        precursor_ions: &[(f64, Vec<u8>)],
+    ) -> Result<(Vec<f64>, Vec<f64>)>;
+
+    /// Transforms the spectrum without precursor information.
+    /// Everything except the peaks is kept.
+    ///
+    /// # Arguments
+    /// * `spectrum` - Spectrum with sorted m/z values
+    ///
+    fn transform<S>(&self, spectrum: &S) -> Result<S>
+    where
+        S: CopyWithPeaks,
+        Self: Sized,
+    {
+        let (mz, intensity) = self.transform_peaks(
+            spectrum.get_mz().clone(),
+            spectrum.get_intensity().clone(),
+            &[],
+        )?;
+        Ok(spectrum.copy_with_peaks(mz, intensity))
+    }
+
+    /// Transforms the spectrum using the ions of all its precursors.
+    /// Everything except the peaks, e.g. the precursors, is kept.
+    ///
+    /// # Arguments
+    /// * `spectrum` - MSn spectrum with sorted m/z values
+    ///
+    fn transform_msn<S, P>(&self, spectrum: &S) -> Result<S>
+    where
+        S: MsNSpectrum<P> + CopyWithPeaks,
+        P: Precursor,
+        Self: Sized,
+    {
+        let precursor_ions: Vec<(f64, Vec<u8>)> = spectrum
+            .get_precursors()
+            .iter()
+            .filter_map(|precursor| precursor.get_ions().as_ref())
+            .flatten()
+            .cloned()
+            .collect();
+        let (mz, intensity) = self.transform_peaks(
+            spectrum.get_mz().clone(),
+            spectrum.get_intensity().clone(),
+            &precursor_ions,
+        )?;
+        Ok(spectrum.copy_with_peaks(mz, intensity))
+    }
+}
+
+/// Keeps only the peaks which are flagged to be kept
+///
+fn select_peaks(mz: Vec<f64>, intensity: Vec<f64>, keep: &[bool]) -> (Vec<f64>, Vec<f64>) {
+    mz.into_iter()
+        .zip(intensity)
+        .zip(keep.iter())
+        .filter(|(_, keep)| **keep)
+        .map(|(peak, _)| peak)
+        .unzip()
+}
+
+/// Keeps only the peaks matching the predicate
+///
+fn filter_peaks<F>(mz: Vec<f64>, intensity: Vec<f64>, predicate: F) -> (Vec<f64>, Vec<f64>)
+where
+    F: Fn(f64, f64) -> bool,
+{
+    let keep: Vec<bool> = mz
+        .iter()
+        .zip(intensity.iter())
+        .map(|(mz, intensity)| predicate(*mz, *intensity))
+        .collect();
+    select_peaks(mz, intensity, &keep)
+}
+
+/// Predefined spectrum transformations which can be serialized, e.g. as part of a pipeline
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Transform {
+    /// Keeps the `n` most intense peaks per m/z window
+    TopNPeaksPerWindow { n: usize, window_width: f64 },
+    /// Removes peaks below the given intensity
+    AbsoluteIntensityThreshold { min_intensity: f64 },
+    /// Removes peaks below the given fraction of the base peak intensity
+    RelativeIntensityThreshold { min_fraction: f64 },
+    /// Removes peaks below the given signal-to-noise ratio,
+    /// using the median intensity as noise level
+    NoiseThreshold { min_signal_to_noise: f64 },
+    /// Removes the peaks of the precursor ions
+    PrecursorRemoval { tolerance: Tolerance },
+    /// Removes peaks outside of the given m/z range (inclusive)
+    MzRange { min_mz: f64, max_mz: f64 },
+    /// Replaces the intensities with their square root
+    SqrtScaling,
+    /// Replaces the intensities with `ln(1 + intensity)`
+    LogScaling,
+    /// Replaces the intensities with their rank, the least intense peak has rank 1
+    RankScaling,
+    /// Scales the intensities to a total ion current of 1
+    TicNormalization,
+    /// Scales the intensities to a base peak intensity of 1
+    BasePeakNormalization,
+    /// Picks the peaks of a profile spectrum
+    Centroid {
+        apex_fitting: ApexFitting,
+        min_signal_to_noise: f64,
+    },
+    /// Collapses isotope envelopes to their monoisotopic peak
+    Deisotope(Deisotoper),
+}
+
+impl SpectrumTransform for Transform {
+    fn transform_peaks(
+        &self,
+        mz: Vec<f64>,
+        intensity: Vec<f64>,
+        precursor_ions: &[(f64, Vec<u8>)],
+    ) -> Result<(Vec<f64>, Vec<f64>)> {
+        if mz.len() != intensity.len() {
+            bail!(
+                "Number of m/z values ({}) and intensities ({}) differ",
+                mz.len(),
+                intensity.len()
+            );
+        }
+        match self {
+            Self::TopNPeaksPerWindow { n, window_width } => {
+                if *window_width <= 0.0 {
+                    bail!("Window width must be greater than 0");
+                }
+                let mut keep = vec![false; mz.len()];
+                top_n_peaks_per_window(&mz, &intensity, *n, *window_width)
+                    .into_iter()
+                    .for_each(|idx| keep[idx] = true);
+                Ok(select_peaks(mz, intensity, &keep))
+            }
+            Self::AbsoluteIntensityThreshold { min_intensity } => {
+                Ok(filter_peaks(mz, intensity, |_, intensity| {
+                    intensity >= *min_intensity
+                }))
+            }
+            Self::RelativeIntensityThreshold { min_fraction } => {
+                let min_intensity = intensity.iter().cloned().fold(0.0, f64::max) * min_fraction;
+                Ok(filter_peaks(mz, intensity, |_, intensity| {
+                    intensity >= min_intensity
+                }))
+            }
+            Self::NoiseThreshold {
+                min_signal_to_noise,
+            } => {
+                let min_intensity = estimate_noise(&intensity).unwrap_or(0.0) * min_signal_to_noise;
+                Ok(filter_peaks(mz, intensity, |_, intensity| {
+                    intensity >= min_intensity
+                }))
+            }
+            Self::PrecursorRemoval { tolerance } => Ok(filter_peaks(mz, intensity, |mz, _| {
+                !precursor_ions
+                    .iter()
+                    .any(|(precursor_mz, _)| tolerance.contains(*precursor_mz, mz))
+            })),
+            Self::MzRange { min_mz, max_mz } => Ok(filter_peaks(mz, intensity, |mz, _| {
+                mz >= *min_mz && mz <= *max_mz
+            })),
+            Self::SqrtScaling => Ok((mz, intensity.into_iter().map(f64::sqrt).collect())),
+            Self::LogScaling => Ok((mz, intensity.into_iter().map(f64::ln_1p).collect())),
+            Self::RankScaling => {
+                let mut order: Vec<usize> = (0..intensity.len()).collect();
+                order.sort_by(|x, y| intensity[*x].total_cmp(&intensity[*y]));
+                let mut ranks = vec![0.0; intensity.len()];
+                order
+                    .into_iter()
+                    .enumerate()
+                    .for_each(|(rank, idx)| ranks[idx] = (rank + 1) as f64);
+                Ok((mz, ranks))
+            }
+            Self::TicNormalization => {
+                let total_ion_current: f64 = intensity.iter().sum();
+                if total_ion_current <= 0.0 {
+                    return Ok((mz, intensity));
+                }
+                Ok((
+                    mz,
+                    intensity
+                        .into_iter()
+                        .map(|intensity| intensity / total_ion_current)
+                        .collect(),
+                ))
+            }
+            Self::BasePeakNormalization => {
+                let base_peak_intensity = intensity.iter().cloned().fold(0.0, f64::max);
+                if base_peak_intensity <= 0.0 {
+                    return Ok((mz, intensity));
+                }
+                Ok((
+                    mz,
+                    intensity
+                        .into_iter()
+                        .map(|intensity| intensity / base_peak_intensity)
+                        .collect(),
+                ))
+            }
+            Self::Centroid {
+                apex_fitting,
+                min_signal_to_noise,
+            } => Ok(
+                pick_peaks(&mz, &intensity, *apex_fitting, *min_signal_to_noise)
+                    .into_iter()
+                    .map(|peak| (peak.get_mz(), peak.get_intensity()))
+                    .unzip(),
+            ),
+            Self::Deisotope(deisotoper) => {
+                let spectrum = SimpleSpectrum::new(String::new(), 0, mz, intensity);
+                let deisotoped = deisotoper.deisotope(&spectrum);
+                Ok((
+                    deisotoped.get_mz().clone(),
+                    deisotoped.get_intensity().clone(),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::mass_spectrometry::spectrum::{SimpleMsNSpectrum, SimplePrecursor};
+
+    /// Returns the peaks after applying the transform to the test spectrum
+    ///
+    fn apply(transform: Transform) -> (Vec<f64>, Vec<f64>) {
+        transform
+            .transform_peaks(
+                vec![100.0, 150.0, 220.0, 500.0, 510.0],
+                vec![4.0, 1.0, 9.0, 16.0, 2.0],
+                &[(500.0, vec![2])],
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_filters() {
+        let (mz, _) = apply(Transform::TopNPeaksPerWindow {
+            n: 1,
+            window_width: 100.0,
+        });
+        assert_eq!(mz, vec![100.0, 220.0, 500.0]);
+        let (mz, _) = apply(Transform::AbsoluteIntensityThreshold { min_intensity: 4.0 });
+        assert_eq!(mz, vec![100.0, 220.0, 500.0]);
+        let (mz, _) = apply(Transform::RelativeIntensityThreshold { min_fraction: 0.5 });
+        assert_eq!(mz, vec![220.0, 500.0]);
+        // median 4
+        let (mz, _) = apply(Transform::NoiseThreshold {
+            min_signal_to_noise: 2.0,
+        });
+        assert_eq!(mz, vec![220.0, 500.0]);
+        let (mz, _) = apply(Transform::PrecursorRemoval {
+            tolerance: Tolerance::Da(0.02),
+        });
+        assert_eq!(mz, vec![100.0, 150.0, 220.0, 510.0]);
+        let (mz, _) = apply(Transform::MzRange {
+            min_mz: 150.0,
+            max_mz: 500.0,
+        });
+        assert_eq!(mz, vec![150.0, 220.0, 500.0]);
+    }
+
+    #[test]
+    fn test_scaling() {
+        let (_, intensity) = apply(Transform::SqrtScaling);
+        assert_eq!(intensity, vec![2.0, 1.0, 3.0, 4.0, 2.0_f64.sqrt()]);
+        let (_, intensity) = apply(Transform::LogScaling);
+        assert_eq!(intensity[1], 2.0_f64.ln());
+        let (_, intensity) = apply(Transform::RankScaling);
+        assert_eq!(intensity, vec![3.0, 1.0, 4.0, 5.0, 2.0]);
+        let (_, intensity) = apply(Transform::TicNormalization);
+        assert_eq!(intensity.iter().sum::<f64>(), 1.0);
+        let (_, intensity) = apply(Transform::BasePeakNormalization);
+        assert_eq!(intensity, vec![0.25, 0.0625, 0.5625, 1.0, 0.125]);
+    }
+
+    #[test]
+    fn test_transform_msn() {
+        let spectrum = SimpleMsNSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![100.0, 500.0],
+            vec![1.0, 2.0],
+            vec![SimplePrecursor::new(
+                "parent".to_string(),
+                None,
+                Some(vec![(500.0, vec![2])]),
+                (Vec::new(), HashMap::new()),
+            )],
+        );
+        let transform = Transform::PrecursorRemoval {
+            tolerance: Tolerance::Ppm(10.0),
+        };
+        let transformed = transform.transform_msn(&spectrum).unwrap();
+        assert_eq!(transformed.get_id(), "test");
+        assert_eq!(transformed.get_mz(), &vec![100.0]);
+        assert_eq!(transformed.get_precursors().len(), 1);
+        // without precursor information nothing is removed
+        assert_eq!(transform.transform(&spectrum).unwrap().get_mz().len(), 2);
+    }
+}
@@ -0,0 +1,531 @@
+// std imports
+use std::f64::consts::PI;
+
+// internal imports
+use crate::mass_spectrometry::spectrum::Spectrum;
+use crate::mass_spectrometry::tolerance::Tolerance;
+
+/// Entropy threshold below which the intensities are reweighted for the weighted entropy similarity
+///
+const ENTROPY_WEIGHT_THRESHOLD: f64 = 3.0;
+
+/// Appends all pairs of peaks of both spectra which are within the tolerance after
+/// shifting the m/z values of the second spectrum by the given value.
+/// Pairs are (peak index spectrum A, peak index spectrum B, intensity product).
+///
+fn collect_candidate_pairs<A, B>(
+    spectrum_a: &A,
+    spectrum_b: &B,
+    tolerance: &Tolerance,
+    shift: f64,
+    candidate_pairs: &mut Vec<(usize, usize, f64)>,
+) where
+    A: Spectrum,
+    B: Spectrum,
+{
+    let mz_b = spectrum_b.get_mz();
+    for (idx_a, mz_a) in spectrum_a.get_mz().iter().enumerate() {
+        let (lower, upper) = tolerance.get_bounds(*mz_a);
+        let start = mz_b.partition_point(|mz| mz + shift < lower);
+        for (idx_b, _) in mz_b[start..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, mz)| **mz + shift <= upper)
+        {
+            let idx_b = start + idx_b;
+            candidate_pairs.push((
+                idx_a,
+                idx_b,
+                spectrum_a.get_intensity()[idx_a] * spectrum_b.get_intensity()[idx_b],
+            ));
+        }
+    }
+}
+
+/// Assigns the candidate pairs greedily, highest intensity product first,
+/// so each peak is matched at most once. Returns the pairs sorted by the peak index of spectrum A.
+///
+fn assign_greedy(
+    mut candidate_pairs: Vec<(usize, usize, f64)>,
+    peaks_a: usize,
+    peaks_b: usize,
+) -> Vec<(usize, usize)> {
+    candidate_pairs.sort_by(|x, y| y.2.total_cmp(&x.2));
+    let mut used_a = vec![false; peaks_a];
+    let mut used_b = vec![false; peaks_b];
+    let mut matches = Vec::new();
+    for (idx_a, idx_b, _) in candidate_pairs {
+        if used_a[idx_a] || used_b[idx_b] {
+            continue;
+        }
+        used_a[idx_a] = true;
+        used_b[idx_b] = true;
+        matches.push((idx_a, idx_b));
+    }
+    matches.sort();
+    matches
+}
+
+/// Matches the peaks of both spectra greedily by their intensity product.
+/// Each peak is matched at most once. Returns a list of (peak index A, peak index B).
+///
+/// # Arguments
+/// * `spectrum_a` - Spectrum with sorted m/z values
+/// * `spectrum_b` - Spectrum with sorted m/z values
+/// * `tolerance` - Tolerance, relative tolerances are applied to the m/z of spectrum A
+///
+pub fn match_peaks<A, B>(
+    spectrum_a: &A,
+    spectrum_b: &B,
+    tolerance: &Tolerance,
+) -> Vec<(usize, usize)>
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    let mut candidate_pairs = Vec::new();
+    collect_candidate_pairs(spectrum_a, spectrum_b, tolerance, 0.0, &mut candidate_pairs);
+    assign_greedy(
+        candidate_pairs,
+        spectrum_a.get_mz().len(),
+        spectrum_b.get_mz().len(),
+    )
+}
+
+/// Matches the peaks of both spectra greedily by their intensity product, either directly
+/// or shifted by the precursor m/z difference. Each peak is matched at most once.
+/// Returns a list of (peak index A, peak index B).
+///
+/// # Arguments
+/// * `spectrum_a` - Spectrum with sorted m/z values
+/// * `precursor_mz_a` - Precursor m/z of spectrum A
+/// * `spectrum_b` - Spectrum with sorted m/z values
+/// * `precursor_mz_b` - Precursor m/z of spectrum B
+/// * `tolerance` - Tolerance, relative tolerances are applied to the m/z of spectrum A
+///
+pub fn match_peaks_with_precursor_shift<A, B>(
+    spectrum_a: &A,
+    precursor_mz_a: f64,
+    spectrum_b: &B,
+    precursor_mz_b: f64,
+    tolerance: &Tolerance,
+) -> Vec<(usize, usize)>
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    let mut candidate_pairs = Vec::new();
+    collect_candidate_pairs(spectrum_a, spectrum_b, tolerance, 0.0, &mut candidate_pairs);
+    let shift = precursor_mz_a - precursor_mz_b;
+    if !tolerance.contains(precursor_mz_a, precursor_mz_b) {
+        collect_candidate_pairs(
+            spectrum_a,
+            spectrum_b,
+            tolerance,
+            shift,
+            &mut candidate_pairs,
+        );
+    }
+    assign_greedy(
+        candidate_pairs,
+        spectrum_a.get_mz().len(),
+        spectrum_b.get_mz().len(),
+    )
+}
+
+/// Returns the cosine of the intensity vectors of the matched peaks
+///
+fn cosine_of_matches<A, B>(spectrum_a: &A, spectrum_b: &B, matches: &[(usize, usize)]) -> f64
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    let norm_a = spectrum_a
+        .get_intensity()
+        .iter()
+        .map(|intensity| intensity * intensity)
+        .sum::<f64>()
+        .sqrt();
+    let norm_b = spectrum_b
+        .get_intensity()
+        .iter()
+        .map(|intensity| intensity * intensity)
+        .sum::<f64>()
+        .sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    let dot_product: f64 = matches
+        .iter()
+        .map(|(idx_a, idx_b)| {
+            spectrum_a.get_intensity()[*idx_a] * spectrum_b.get_intensity()[*idx_b]
+        })
+        .sum();
+    (dot_product / (norm_a * norm_b)).min(1.0)
+}
+
+/// Calculates the cosine similarity of both spectra using greedy peak matching.
+/// Intensity scaling, e.g. square root, should be done beforehand.
+///
+/// # Arguments
+/// * `spectrum_a` - Spectrum with sorted m/z values
+/// * `spectrum_b` - Spectrum with sorted m/z values
+/// * `tolerance` - Tolerance
+///
+pub fn cosine_similarity<A, B>(spectrum_a: &A, spectrum_b: &B, tolerance: &Tolerance) -> f64
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    let matches = match_peaks(spectrum_a, spectrum_b, tolerance);
+    cosine_of_matches(spectrum_a, spectrum_b, &matches)
+}
+
+/// Calculates the modified cosine similarity of both spectra, where peaks are also matched
+/// when they are shifted by the precursor m/z difference, e.g. caused by a modification.
+///
+/// > Watrous J, Roach P, Alexandrov T, et al. Mass spectral molecular networking of living
+/// > microbial colonies. Proc Natl Acad Sci USA. 2012;109(26):E1743-E1752.
+/// > https://doi.org/10.1073/pnas.1203689109
+///
+/// # Arguments
+/// * `spectrum_a` - Spectrum with sorted m/z values
+/// * `precursor_mz_a` - Precursor m/z of spectrum A
+/// * `spectrum_b` - Spectrum with sorted m/z values
+/// * `precursor_mz_b` - Precursor m/z of spectrum B
+/// * `tolerance` - Tolerance
+///
+pub fn modified_cosine_similarity<A, B>(
+    spectrum_a: &A,
+    precursor_mz_a: f64,
+    spectrum_b: &B,
+    precursor_mz_b: f64,
+    tolerance: &Tolerance,
+) -> f64
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    let matches = match_peaks_with_precursor_shift(
+        spectrum_a,
+        precursor_mz_a,
+        spectrum_b,
+        precursor_mz_b,
+        tolerance,
+    );
+    cosine_of_matches(spectrum_a, spectrum_b, &matches)
+}
+
+/// Calculates the normalized spectral contrast angle `1 - 2 * acos(cosine) / pi`,
+/// which is 1 for identical and 0 for orthogonal spectra.
+///
+/// > Toprak UH, Gillet LC, Maiolica A, et al. Conserved peptide fragmentation as a benchmarking
+/// > tool for mass spectrometers and a discriminating feature for targeted proteomics.
+/// > Mol Cell Proteomics. 2014;13(8):2056-2071. https://doi.org/10.1074/mcp.O113.036475
+///
+/// # Arguments
+/// * `spectrum_a` - Spectrum with sorted m/z values
+/// * `spectrum_b` - Spectrum with sorted m/z values
+/// * `tolerance` - Tolerance
+///
+pub fn spectral_contrast_angle<A, B>(spectrum_a: &A, spectrum_b: &B, tolerance: &Tolerance) -> f64
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    1.0 - 2.0 * cosine_similarity(spectrum_a, spectrum_b, tolerance).acos() / PI
+}
+
+/// Returns the Shannon entropy of the intensities, which need to sum up to 1
+///
+fn entropy(intensities: &[f64]) -> f64 {
+    -intensities
+        .iter()
+        .filter(|intensity| **intensity > 0.0)
+        .map(|intensity| intensity * intensity.ln())
+        .sum::<f64>()
+}
+
+/// Returns the intensities normalized to a sum of 1 and optionally reweighted
+/// as in the weighted entropy similarity
+///
+fn get_entropy_intensities(intensity: &[f64], weighted: bool) -> Vec<f64> {
+    let normalize = |intensity: Vec<f64>| {
+        let total: f64 = intensity.iter().sum();
+        if total <= 0.0 {
+            return intensity;
+        }
+        intensity.into_iter().map(|x| x / total).collect()
+    };
+    let normalized: Vec<f64> = normalize(intensity.iter().map(|x| x.max(0.0)).collect());
+    if !weighted {
+        return normalized;
+    }
+    let spectral_entropy = entropy(&normalized);
+    if spectral_entropy >= ENTROPY_WEIGHT_THRESHOLD {
+        return normalized;
+    }
+    let weight = 0.25 + spectral_entropy * 0.25;
+    normalize(normalized.into_iter().map(|x| x.powf(weight)).collect())
+}
+
+/// Calculates the entropy similarity `1 - (2 * S_AB - S_A - S_B) / ln(4)` where `S` is the
+/// spectral entropy and `AB` is the merged spectrum of both. The weighted version increases
+/// the intensity of low intensity peaks in spectra with an entropy below 3.
+///
+/// > Li Y, Kind T, Folz J, et al. Spectral entropy outperforms MS/MS dot product similarity for
+/// > small-molecule compound identification. Nat Methods. 2021;18(12):1524-1531.
+/// > https://doi.org/10.1038/s41592-021-01331-z
+///
+/// # Arguments
+/// * `spectrum_a` - Spectrum with sorted m/z values
+/// * `spectrum_b` - Spectrum with sorted m/z values
+/// * `tolerance` - Tolerance
+/// * `weighted` - Use the weighted entropy similarity
+///
+pub fn entropy_similarity<A, B>(
+    spectrum_a: &A,
+    spectrum_b: &B,
+    tolerance: &Tolerance,
+    weighted: bool,
+) -> f64
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    let intensity_a = get_entropy_intensities(spectrum_a.get_intensity(), weighted);
+    let intensity_b = get_entropy_intensities(spectrum_b.get_intensity(), weighted);
+    if intensity_a.iter().sum::<f64>() <= 0.0 || intensity_b.iter().sum::<f64>() <= 0.0 {
+        return 0.0;
+    }
+    // unmatched peaks contribute ln(2) * intensity to 2 * S_AB - S_A - S_B, so the similarity
+    // reduces to a sum over the matched peaks of (a + b) * ln(a + b) - a * ln(a) - b * ln(b)
+    let x_ln_x = |x: f64| if x > 0.0 { x * x.ln() } else { 0.0 };
+    let similarity: f64 = match_peaks(spectrum_a, spectrum_b, tolerance)
+        .into_iter()
+        .map(|(idx_a, idx_b)| {
+            let a = intensity_a[idx_a];
+            let b = intensity_b[idx_b];
+            x_ln_x(a + b) - x_ln_x(a) - x_ln_x(b)
+        })
+        .sum::<f64>()
+        / 4.0_f64.ln();
+    similarity.clamp(0.0, 1.0)
+}
+
+/// Returns the matched intensities of both spectra
+///
+fn get_matched_intensities<A, B>(
+    spectrum_a: &A,
+    spectrum_b: &B,
+    tolerance: &Tolerance,
+) -> (Vec<f64>, Vec<f64>)
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    match_peaks(spectrum_a, spectrum_b, tolerance)
+        .into_iter()
+        .map(|(idx_a, idx_b)| {
+            (
+                spectrum_a.get_intensity()[idx_a],
+                spectrum_b.get_intensity()[idx_b],
+            )
+        })
+        .unzip()
+}
+
+/// Returns the cosine similarity of two equally long intensity vectors, e.g. an observed
+/// and a theoretical isotope distribution, or 0.0 if one of them is all zero
+///
+/// # Arguments
+/// * `x` - First intensity vector
+/// * `y` - Second intensity vector
+///
+pub fn vector_cosine_similarity(x: &[f64], y: &[f64]) -> f64 {
+    let dot_product: f64 = x.iter().zip(y.iter()).map(|(x, y)| x * y).sum();
+    let norm =
+        x.iter().map(|x| x * x).sum::<f64>().sqrt() * y.iter().map(|y| y * y).sum::<f64>().sqrt();
+    if norm == 0.0 {
+        return 0.0;
+    }
+    dot_product / norm
+}
+
+/// Returns the Pearson correlation coefficient or 0.0 if one of the values has no variance
+///
+fn pearson(x: &[f64], y: &[f64]) -> f64 {
+    if x.len() < 2 {
+        return 0.0;
+    }
+    let mean_x = x.iter().sum::<f64>() / x.len() as f64;
+    let mean_y = y.iter().sum::<f64>() / y.len() as f64;
+    let covariance: f64 = x
+        .iter()
+        .zip(y.iter())
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance_x: f64 = x.iter().map(|x| (x - mean_x).powi(2)).sum();
+    let variance_y: f64 = y.iter().map(|y| (y - mean_y).powi(2)).sum();
+    if variance_x == 0.0 || variance_y == 0.0 {
+        return 0.0;
+    }
+    covariance / (variance_x * variance_y).sqrt()
+}
+
+/// Returns the ranks of the values, ties get their average rank
+///
+fn rank(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|x, y| values[*x].total_cmp(&values[*y]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let end = start
+            + order[start..]
+                .iter()
+                .take_while(|idx| values[**idx] == values[order[start]])
+                .count();
+        // average of the 1-based ranks start + 1 ..= end
+        let average_rank = (start + 1 + end) as f64 / 2.0;
+        order[start..end]
+            .iter()
+            .for_each(|idx| ranks[*idx] = average_rank);
+        start = end;
+    }
+    ranks
+}
+
+/// Calculates the Pearson correlation of the intensities of the matched peaks.
+/// Returns 0.0 if less than two peaks are matched or the intensities have no variance.
+///
+/// # Arguments
+/// * `spectrum_a` - Spectrum with sorted m/z values
+/// * `spectrum_b` - Spectrum with sorted m/z values
+/// * `tolerance` - Tolerance
+///
+pub fn pearson_correlation<A, B>(spectrum_a: &A, spectrum_b: &B, tolerance: &Tolerance) -> f64
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    let (intensity_a, intensity_b) = get_matched_intensities(spectrum_a, spectrum_b, tolerance);
+    pearson(&intensity_a, &intensity_b)
+}
+
+/// Calculates the Spearman rank correlation of the intensities of the matched peaks.
+/// Returns 0.0 if less than two peaks are matched or the intensities have no variance.
+///
+/// # Arguments
+/// * `spectrum_a` - Spectrum with sorted m/z values
+/// * `spectrum_b` - Spectrum with sorted m/z values
+/// * `tolerance` - Tolerance
+///
+pub fn spearman_correlation<A, B>(spectrum_a: &A, spectrum_b: &B, tolerance: &Tolerance) -> f64
+where
+    A: Spectrum,
+    B: Spectrum,
+{
+    let (intensity_a, intensity_b) = get_matched_intensities(spectrum_a, spectrum_b, tolerance);
+    pearson(&rank(&intensity_a), &rank(&intensity_b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mass_spectrometry::spectrum::SimpleSpectrum;
+
+    /// Returns a spectrum with the given peaks
+    ///
+    fn get_spectrum(mz: Vec<f64>, intensity: Vec<f64>) -> SimpleSpectrum {
+        SimpleSpectrum::new("test".to_string(), 2, mz, intensity)
+    }
+
+    #[test]
+    fn test_match_peaks() {
+        let spectrum_a = get_spectrum(vec![100.0, 100.01, 200.0], vec![1.0, 10.0, 5.0]);
+        let spectrum_b = get_spectrum(vec![100.005, 250.0], vec![3.0, 2.0]);
+        // the more intense peak wins
+        assert_eq!(
+            match_peaks(&spectrum_a, &spectrum_b, &Tolerance::Da(0.02)),
+            vec![(1, 0)]
+        );
+        // shifted by the precursor difference of 50
+        assert_eq!(
+            match_peaks_with_precursor_shift(
+                &spectrum_a,
+                500.0,
+                &spectrum_b,
+                550.0,
+                &Tolerance::Da(0.02)
+            ),
+            vec![(1, 0), (2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_vector_cosine_similarity() {
+        assert!((vector_cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-12);
+        assert_eq!(vector_cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(vector_cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        let spectrum_a = get_spectrum(vec![100.0, 200.0, 300.0], vec![1.0, 2.0, 3.0]);
+        let spectrum_b = get_spectrum(vec![100.001, 200.001, 300.001], vec![2.0, 4.0, 6.0]);
+        let tolerance = Tolerance::Ppm(20.0);
+        assert!((cosine_similarity(&spectrum_a, &spectrum_b, &tolerance) - 1.0).abs() < 1e-12);
+        assert!((spectral_contrast_angle(&spectrum_a, &spectrum_b, &tolerance) - 1.0).abs() < 1e-6);
+
+        let spectrum_c = get_spectrum(vec![150.0, 250.0], vec![1.0, 1.0]);
+        assert_eq!(cosine_similarity(&spectrum_a, &spectrum_c, &tolerance), 0.0);
+        assert_eq!(
+            spectral_contrast_angle(&spectrum_a, &spectrum_c, &tolerance),
+            0.0
+        );
+
+        // modification of +20 on the precursor, the 300 peak carries it
+        let spectrum_d = get_spectrum(vec![100.0, 200.0, 320.0], vec![1.0, 2.0, 3.0]);
+        let cosine = cosine_similarity(&spectrum_a, &spectrum_d, &tolerance);
+        let modified_cosine =
+            modified_cosine_similarity(&spectrum_a, 400.0, &spectrum_d, 420.0, &tolerance);
+        assert!(cosine < 0.5);
+        assert!((modified_cosine - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_entropy_similarity() {
+        let tolerance = Tolerance::Da(0.02);
+        let spectrum_a = get_spectrum(vec![100.0, 200.0, 300.0], vec![1.0, 2.0, 3.0]);
+        let spectrum_b = get_spectrum(vec![100.0, 200.0, 300.0], vec![10.0, 20.0, 30.0]);
+        let spectrum_c = get_spectrum(vec![150.0, 250.0], vec![1.0, 1.0]);
+        for weighted in [false, true] {
+            assert!(
+                (entropy_similarity(&spectrum_a, &spectrum_b, &tolerance, weighted) - 1.0).abs()
+                    < 1e-12
+            );
+            assert_eq!(
+                entropy_similarity(&spectrum_a, &spectrum_c, &tolerance, weighted),
+                0.0
+            );
+        }
+        // one of two equally intense peaks matched
+        let spectrum_d = get_spectrum(vec![100.0, 400.0], vec![1.0, 1.0]);
+        let spectrum_e = get_spectrum(vec![100.0, 500.0], vec![1.0, 1.0]);
+        let similarity = entropy_similarity(&spectrum_d, &spectrum_e, &tolerance, false);
+        assert!((similarity - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_correlations() {
+        let tolerance = Tolerance::Da(0.02);
+        let spectrum_a = get_spectrum(vec![100.0, 200.0, 300.0, 400.0], vec![1.0, 2.0, 3.0, 4.0]);
+        let spectrum_b = get_spectrum(vec![100.0, 200.0, 300.0, 400.0], vec![1.0, 4.0, 9.0, 16.0]);
+        assert!(pearson_correlation(&spectrum_a, &spectrum_b, &tolerance) < 1.0);
+        assert!((spearman_correlation(&spectrum_a, &spectrum_b, &tolerance) - 1.0).abs() < 1e-12);
+        let spectrum_c = get_spectrum(vec![100.0, 200.0, 300.0, 400.0], vec![4.0, 3.0, 2.0, 1.0]);
+        assert!((pearson_correlation(&spectrum_a, &spectrum_c, &tolerance) + 1.0).abs() < 1e-12);
+        assert_eq!(rank(&[5.0, 1.0, 5.0]), vec![2.5, 1.0, 2.5]);
+    }
+}
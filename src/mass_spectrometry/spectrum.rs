@@ -1,10 +1,11 @@
 use std::collections::HashMap;
 
 use anyhow::{anyhow, bail, Context, Error, Result};
+use serde::{Deserialize, Serialize};
 
 use crate::proteomics::io::mzml::elements::is_list::IsList;
 use crate::proteomics::io::mzml::elements::{
-    has_cv_params::HasCvParams, spectrum::Spectrum as MzMlSpectrum,
+    cv_param::CvParam, has_cv_params::HasCvParams, spectrum::Spectrum as MzMlSpectrum,
 };
 use crate::proteomics::ontology::Ontology;
 
@@ -32,6 +33,157 @@ const ISOLATION_WINDOW_LOWER_OFFSET_ACCESSION: &str = "MS:1000828";
 /// PSI ontology accession for isolation window upper offset
 const ISOLATION_WINDOW_UPPER_OFFSET_ACCESSION: &str = "MS:1000829";
 
+/// PSI ontology accession for scan start time
+const SCAN_START_TIME_ACCESSION: &str = "MS:1000016";
+
+/// PSI ontology accession for ion injection time
+const ION_INJECTION_TIME_ACCESSION: &str = "MS:1000927";
+
+/// PSI ontology accession for positive scan
+const POSITIVE_SCAN_ACCESSION: &str = "MS:1000130";
+
+/// PSI ontology accession for negative scan
+const NEGATIVE_SCAN_ACCESSION: &str = "MS:1000129";
+
+/// PSI ontology accession for total ion current
+const TOTAL_ION_CURRENT_ACCESSION: &str = "MS:1000285";
+
+/// PSI ontology accession for base peak m/z
+const BASE_PEAK_MZ_ACCESSION: &str = "MS:1000504";
+
+/// PSI ontology accession for base peak intensity
+const BASE_PEAK_INTENSITY_ACCESSION: &str = "MS:1000505";
+
+/// PSI ontology accession for FAIMS compensation voltage
+const FAIMS_COMPENSATION_VOLTAGE_ACCESSION: &str = "MS:1001581";
+
+/// PSI ontology accession for inverse reduced ion mobility of a scan
+const INVERSE_REDUCED_ION_MOBILITY_ACCESSION: &str = "MS:1002815";
+
+/// PSI ontology accession for ion mobility drift time of a scan
+const ION_MOBILITY_DRIFT_TIME_ACCESSION: &str = "MS:1002476";
+
+/// Unit ontology accession for second
+const SECOND_UNIT_ACCESSION: &str = "UO:0000010";
+
+/// Unit ontology accession for minute
+const MINUTE_UNIT_ACCESSION: &str = "UO:0000031";
+
+/// Unit ontology accession for millisecond
+const MILLISECOND_UNIT_ACCESSION: &str = "UO:0000028";
+
+/// Unit ontology accession for hour
+const HOUR_UNIT_ACCESSION: &str = "UO:0000032";
+
+/// Scan polarity
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Polarity {
+    Positive,
+    Negative,
+}
+
+/// Optional spectrum information beyond the peaks, like retention time or polarity.
+/// Times are stored in seconds (retention time) and milliseconds (ion injection time).
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpectrumMetadata {
+    retention_time: Option<f64>,
+    polarity: Option<Polarity>,
+    scan_number: Option<u64>,
+    ion_injection_time: Option<f64>,
+    total_ion_current: Option<f64>,
+    /// (mz, intensity)
+    base_peak: Option<(f64, f64)>,
+    faims_compensation_voltage: Option<f64>,
+}
+
+impl SpectrumMetadata {
+    /// Creates new spectrum metadata
+    ///
+    /// # Arguments
+    /// * `retention_time` - Retention time in seconds
+    /// * `polarity` - Scan polarity
+    /// * `scan_number` - Scan number
+    /// * `ion_injection_time` - Ion injection time in milliseconds
+    /// * `total_ion_current` - Total ion current
+    /// * `base_peak` - Base peak (mz, intensity)
+    ///
+    pub fn new(
+        retention_time: Option<f64>,
+        polarity: Option<Polarity>,
+        scan_number: Option<u64>,
+        ion_injection_time: Option<f64>,
+        total_ion_current: Option<f64>,
+        base_peak: Option<(f64, f64)>,
+    ) -> Self {
+        Self {
+            retention_time,
+            polarity,
+            scan_number,
+            ion_injection_time,
+            total_ion_current,
+            base_peak,
+            faims_compensation_voltage: None,
+        }
+    }
+
+    /// Sets the FAIMS compensation voltage
+    ///
+    /// # Arguments
+    /// * `faims_compensation_voltage` - FAIMS compensation voltage in volt
+    ///
+    pub fn with_faims_compensation_voltage(
+        mut self,
+        faims_compensation_voltage: Option<f64>,
+    ) -> Self {
+        self.faims_compensation_voltage = faims_compensation_voltage;
+        self
+    }
+
+    /// Returns the retention time in seconds
+    ///
+    pub fn get_retention_time(&self) -> Option<f64> {
+        self.retention_time
+    }
+
+    /// Returns the scan polarity
+    ///
+    pub fn get_polarity(&self) -> Option<Polarity> {
+        self.polarity
+    }
+
+    /// Returns the scan number
+    ///
+    pub fn get_scan_number(&self) -> Option<u64> {
+        self.scan_number
+    }
+
+    /// Returns the ion injection time in milliseconds
+    ///
+    pub fn get_ion_injection_time(&self) -> Option<f64> {
+        self.ion_injection_time
+    }
+
+    /// Returns the total ion current
+    ///
+    pub fn get_total_ion_current(&self) -> Option<f64> {
+        self.total_ion_current
+    }
+
+    /// Returns the base peak (mz, intensity)
+    ///
+    pub fn get_base_peak(&self) -> Option<(f64, f64)> {
+        self.base_peak
+    }
+
+    /// Returns the FAIMS compensation voltage in volt
+    ///
+    pub fn get_faims_compensation_voltage(&self) -> Option<f64> {
+        self.faims_compensation_voltage
+    }
+}
+
 /// Trait defining a basic spectrum
 /// Should at least contain mz and intensity vectors, the ms level and identifier.
 ///
@@ -51,6 +203,80 @@ pub trait Spectrum {
     /// Returns the intensity values
     ///
     fn get_intensity(&self) -> &Vec<f64>;
+
+    /// Returns the optional metadata like retention time, if available
+    ///
+    fn get_metadata(&self) -> Option<&SpectrumMetadata> {
+        None
+    }
+
+    /// Returns the retention time in seconds, if available
+    ///
+    fn get_retention_time(&self) -> Option<f64> {
+        self.get_metadata()
+            .and_then(|metadata| metadata.get_retention_time())
+    }
+
+    /// Returns the scan polarity, if available
+    ///
+    fn get_polarity(&self) -> Option<Polarity> {
+        self.get_metadata()
+            .and_then(|metadata| metadata.get_polarity())
+    }
+
+    /// Returns the scan number, if available
+    ///
+    fn get_scan_number(&self) -> Option<u64> {
+        self.get_metadata()
+            .and_then(|metadata| metadata.get_scan_number())
+    }
+
+    /// Returns the ion injection time in milliseconds, if available
+    ///
+    fn get_ion_injection_time(&self) -> Option<f64> {
+        self.get_metadata()
+            .and_then(|metadata| metadata.get_ion_injection_time())
+    }
+
+    /// Returns the total ion current, if available
+    ///
+    fn get_total_ion_current(&self) -> Option<f64> {
+        self.get_metadata()
+            .and_then(|metadata| metadata.get_total_ion_current())
+    }
+
+    /// Returns the base peak (mz, intensity), if available
+    ///
+    fn get_base_peak(&self) -> Option<(f64, f64)> {
+        self.get_metadata()
+            .and_then(|metadata| metadata.get_base_peak())
+    }
+
+    /// Returns the ion mobility value of each peak, if available
+    ///
+    fn get_ion_mobility(&self) -> Option<&Vec<f64>> {
+        None
+    }
+
+    /// Returns the FAIMS compensation voltage in volt, if available
+    ///
+    fn get_faims_compensation_voltage(&self) -> Option<f64> {
+        self.get_metadata()
+            .and_then(|metadata| metadata.get_faims_compensation_voltage())
+    }
+}
+
+/// Trait for spectra which can be copied with other peaks, e.g. after peak picking or filtering.
+/// Everything else, like the precursors, is kept.
+///
+pub trait CopyWithPeaks: Spectrum + Sized {
+    /// Returns a copy of the spectrum with the given peaks
+    ///
+    /// # Arguments
+    /// * `mz` - Sorted m/z values
+    /// * `intensity` - Intensities
+    ///
+    fn copy_with_peaks(&self, mz: Vec<f64>, intensity: Vec<f64>) -> Self;
 }
 
 /// Trait defining a n-level spectrum with a parent ID, precursor m/z and precursor charge
@@ -82,6 +308,7 @@ pub trait Precursor {
 
 /// Simplest version of a Precursor (for now)
 ///
+#[derive(Clone)]
 pub struct SimplePrecursor {
     parent_id: String,
     /// (mz, offset lower, offset upper)
@@ -135,6 +362,8 @@ pub struct SimpleSpectrum {
     ms_level: u8,
     mz: Vec<f64>,
     intensity: Vec<f64>,
+    ion_mobility: Option<Vec<f64>>,
+    metadata: SpectrumMetadata,
 }
 
 impl SimpleSpectrum {
@@ -144,8 +373,38 @@ impl SimpleSpectrum {
             ms_level,
             mz,
             intensity,
+            ion_mobility: None,
+            metadata: SpectrumMetadata::default(),
         }
     }
+
+    /// Sets the metadata
+    ///
+    /// # Arguments
+    /// * `metadata` - Metadata like retention time
+    ///
+    pub fn with_metadata(mut self, metadata: SpectrumMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Sets the ion mobility value of each peak
+    ///
+    /// # Arguments
+    /// * `ion_mobility` - Ion mobility values, one per peak
+    ///
+    pub fn with_ion_mobility(mut self, ion_mobility: Vec<f64>) -> Result<Self> {
+        if ion_mobility.len() != self.mz.len() {
+            bail!(
+                "Spectrum {} - Number of ion mobility values ({}) does not match the number of peaks ({})",
+                self.id,
+                ion_mobility.len(),
+                self.mz.len()
+            );
+        }
+        self.ion_mobility = Some(ion_mobility);
+        Ok(self)
+    }
 }
 
 impl Spectrum for SimpleSpectrum {
@@ -164,6 +423,27 @@ impl Spectrum for SimpleSpectrum {
     fn get_intensity(&self) -> &Vec<f64> {
         &self.intensity
     }
+
+    fn get_metadata(&self) -> Option<&SpectrumMetadata> {
+        Some(&self.metadata)
+    }
+
+    fn get_ion_mobility(&self) -> Option<&Vec<f64>> {
+        self.ion_mobility.as_ref()
+    }
+}
+
+impl CopyWithPeaks for SimpleSpectrum {
+    fn copy_with_peaks(&self, mz: Vec<f64>, intensity: Vec<f64>) -> Self {
+        Self {
+            id: self.id.clone(),
+            ms_level: self.ms_level,
+            ion_mobility: copy_ion_mobility(&self.mz, self.ion_mobility.as_ref(), &mz),
+            mz,
+            intensity,
+            metadata: self.metadata.clone(),
+        }
+    }
 }
 
 /// Simplest version of an MsNSpectrum
@@ -177,6 +457,8 @@ pub struct SimpleMsNSpectrum {
     mz: Vec<f64>,
     intensity: Vec<f64>,
     precursors: Vec<SimplePrecursor>,
+    ion_mobility: Option<Vec<f64>>,
+    metadata: SpectrumMetadata,
 }
 
 impl SimpleMsNSpectrum {
@@ -193,8 +475,38 @@ impl SimpleMsNSpectrum {
             mz,
             intensity,
             precursors,
+            ion_mobility: None,
+            metadata: SpectrumMetadata::default(),
         }
     }
+
+    /// Sets the metadata
+    ///
+    /// # Arguments
+    /// * `metadata` - Metadata like retention time
+    ///
+    pub fn with_metadata(mut self, metadata: SpectrumMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Sets the ion mobility value of each peak
+    ///
+    /// # Arguments
+    /// * `ion_mobility` - Ion mobility values, one per peak
+    ///
+    pub fn with_ion_mobility(mut self, ion_mobility: Vec<f64>) -> Result<Self> {
+        if ion_mobility.len() != self.mz.len() {
+            bail!(
+                "Spectrum {} - Number of ion mobility values ({}) does not match the number of peaks ({})",
+                self.id,
+                ion_mobility.len(),
+                self.mz.len()
+            );
+        }
+        self.ion_mobility = Some(ion_mobility);
+        Ok(self)
+    }
 }
 
 impl Spectrum for SimpleMsNSpectrum {
@@ -213,6 +525,14 @@ impl Spectrum for SimpleMsNSpectrum {
     fn get_intensity(&self) -> &Vec<f64> {
         &self.intensity
     }
+
+    fn get_metadata(&self) -> Option<&SpectrumMetadata> {
+        Some(&self.metadata)
+    }
+
+    fn get_ion_mobility(&self) -> Option<&Vec<f64>> {
+        self.ion_mobility.as_ref()
+    }
 }
 
 impl MsNSpectrum<SimplePrecursor> for SimpleMsNSpectrum {
@@ -221,60 +541,327 @@ impl MsNSpectrum<SimplePrecursor> for SimpleMsNSpectrum {
     }
 }
 
-impl TryFrom<MzMlSpectrum> for SimpleMsNSpectrum {
-    type Error = anyhow::Error;
+impl CopyWithPeaks for SimpleMsNSpectrum {
+    fn copy_with_peaks(&self, mz: Vec<f64>, intensity: Vec<f64>) -> Self {
+        Self {
+            id: self.id.clone(),
+            ms_level: self.ms_level,
+            ion_mobility: copy_ion_mobility(&self.mz, self.ion_mobility.as_ref(), &mz),
+            mz,
+            intensity,
+            precursors: self.precursors.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
 
-    fn try_from(spectrum: MzMlSpectrum) -> Result<Self> {
-        let ms_level = match spectrum.get_ms_level() {
-            Some(ms_level) => ms_level,
-            None => {
-                bail!("Spectrum {} - Failed to get ms level", spectrum.id)
+/// Returns the ion mobility values of the new peaks, taken from the original peaks with the same m/z.
+/// Returns None if any new peak has no original counterpart, e.g. after peak picking.
+///
+/// # Arguments
+/// * `mz` - Sorted m/z values of the original peaks
+/// * `ion_mobility` - Ion mobility values of the original peaks
+/// * `new_mz` - Sorted m/z values of the new peaks
+///
+fn copy_ion_mobility(
+    mz: &[f64],
+    ion_mobility: Option<&Vec<f64>>,
+    new_mz: &[f64],
+) -> Option<Vec<f64>> {
+    let ion_mobility = ion_mobility?;
+    let mut idx = 0;
+    new_mz
+        .iter()
+        .map(|new_mz| {
+            while idx < mz.len() && mz[idx] < *new_mz {
+                idx += 1;
             }
-        };
-
-        let mz_binary_array = match spectrum.binary_data_array_list.get_mz_array() {
-            Ok(mz_binary_array) => mz_binary_array,
-            Err(err) => {
-                bail!(
-                    "Spectrum {} - Failed to get mz binary data array: {}",
-                    spectrum.id,
-                    err
-                )
+            match mz.get(idx) {
+                Some(mz) if mz == new_mz => Some(ion_mobility[idx]),
+                _ => None,
             }
-        };
+        })
+        .collect()
+}
 
-        let mz_vec = match mz_binary_array.deflate_data() {
-            Ok(mz_vec) => mz_vec,
-            Err(err) => {
-                bail!(
-                    "Spectrum {} - Failed to deflate mz binary data array: {}",
-                    spectrum.id,
-                    err
-                )
-            }
-        };
+/// Returns the ms level, m/z and intensity values of the mzML spectrum
+///
+/// # Arguments
+/// * `spectrum` - mzML spectrum
+///
+fn get_ms_level_and_peaks(spectrum: &MzMlSpectrum) -> Result<(u8, Vec<f64>, Vec<f64>)> {
+    let ms_level = match spectrum.get_ms_level() {
+        Some(ms_level) => ms_level,
+        None => {
+            bail!("Spectrum {} - Failed to get ms level", spectrum.id)
+        }
+    };
 
-        let intensity_binary_array = match spectrum.binary_data_array_list.get_intensity_array() {
-            Ok(intensity_binary_array) => intensity_binary_array,
-            Err(err) => {
-                bail!(
-                    "Spectrum {} - Failed to get intensity binary data array: {}",
-                    spectrum.id,
-                    err
-                )
-            }
+    let binary_data_array_list = match spectrum.binary_data_array_list.as_ref() {
+        Some(binary_data_array_list) => binary_data_array_list,
+        None => bail!("Spectrum {} - Missing binary data array list", spectrum.id),
+    };
+
+    let mz_binary_array = match binary_data_array_list.get_mz_array() {
+        Ok(mz_binary_array) => mz_binary_array,
+        Err(err) => {
+            bail!(
+                "Spectrum {} - Failed to get mz binary data array: {}",
+                spectrum.id,
+                err
+            )
+        }
+    };
+
+    let mz_vec = match mz_binary_array.decode_data() {
+        Ok(mz_vec) => mz_vec,
+        Err(err) => {
+            bail!(
+                "Spectrum {} - Failed to deflate mz binary data array: {}",
+                spectrum.id,
+                err
+            )
+        }
+    };
+
+    let intensity_binary_array = match binary_data_array_list.get_intensity_array() {
+        Ok(intensity_binary_array) => intensity_binary_array,
+        Err(err) => {
+            bail!(
+                "Spectrum {} - Failed to get intensity binary data array: {}",
+                spectrum.id,
+                err
+            )
+        }
+    };
+
+    let intensity_vec = match intensity_binary_array.decode_data() {
+        Ok(intensity_vec) => intensity_vec,
+        Err(err) => {
+            bail!(
+                "Spectrum {} - Failed to deflate intensity binary data array: {}",
+                spectrum.id,
+                err
+            )
+        }
+    };
+
+    Ok((ms_level, mz_vec, intensity_vec))
+}
+
+/// Returns the ion mobility value of each peak of the mzML spectrum.
+/// If no ion mobility array is present, the ion mobility of the scan (e.g. a single
+/// timsTOF mobility scan) is assigned to all peaks.
+///
+/// # Arguments
+/// * `spectrum` - mzML spectrum
+/// * `peak_count` - Number of peaks
+///
+fn get_ion_mobility(spectrum: &MzMlSpectrum, peak_count: usize) -> Result<Option<Vec<f64>>> {
+    if let Some(ion_mobility_array) = spectrum
+        .binary_data_array_list
+        .as_ref()
+        .and_then(|binary_data_array_list| binary_data_array_list.get_ion_mobility_array().ok())
+    {
+        return match ion_mobility_array.decode_data() {
+            Ok(ion_mobility_vec) => Ok(Some(ion_mobility_vec)),
+            Err(err) => bail!(
+                "Spectrum {} - Failed to deflate ion mobility binary data array: {}",
+                spectrum.id,
+                err
+            ),
         };
+    }
+    let scan_ion_mobility = match spectrum
+        .scan_list
+        .as_ref()
+        .and_then(|scan_list| scan_list.scans.first())
+    {
+        Some(scan) => match parse_cv_param_value(scan, INVERSE_REDUCED_ION_MOBILITY_ACCESSION)? {
+            Some(ion_mobility) => Some(ion_mobility),
+            None => parse_cv_param_value(scan, ION_MOBILITY_DRIFT_TIME_ACCESSION)?,
+        },
+        None => None,
+    };
+    Ok(scan_ion_mobility.map(|ion_mobility| vec![ion_mobility; peak_count]))
+}
 
-        let intensity_vec = match intensity_binary_array.deflate_data() {
-            Ok(intensity_vec) => intensity_vec,
-            Err(err) => {
-                bail!(
-                    "Spectrum {} - Failed to deflate intensity binary data array: {}",
-                    spectrum.id,
-                    err
-                )
-            }
+/// Returns the factor converting the time unit of the cvParam into seconds.
+/// Supported units are seconds, minutes, milliseconds and hours, missing units are interpreted as seconds.
+/// Other units result in an error.
+///
+/// # Arguments
+/// * `cv_param` - Time cvParam, e.g. scan start time or time array
+///
+pub(crate) fn get_seconds_per_time_unit(cv_param: &CvParam) -> Result<f64> {
+    let unit = match (&cv_param.unit_accession, &cv_param.unit_name) {
+        (Some(unit_accession), _) => unit_accession.as_str(),
+        (None, Some(unit_name)) => match unit_name.as_str() {
+            "second" => SECOND_UNIT_ACCESSION,
+            "minute" => MINUTE_UNIT_ACCESSION,
+            "millisecond" => MILLISECOND_UNIT_ACCESSION,
+            "hour" => HOUR_UNIT_ACCESSION,
+            _ => unit_name.as_str(),
+        },
+        (None, None) => SECOND_UNIT_ACCESSION,
+    };
+    match unit {
+        SECOND_UNIT_ACCESSION => Ok(1.0),
+        MINUTE_UNIT_ACCESSION => Ok(60.0),
+        MILLISECOND_UNIT_ACCESSION => Ok(0.001),
+        HOUR_UNIT_ACCESSION => Ok(3600.0),
+        _ => bail!("Unsupported time unit `{}` of {}", unit, cv_param.name),
+    }
+}
+
+/// Returns the value of the time cvParam in seconds.
+///
+/// # Arguments
+/// * `cv_param` - Time cvParam, e.g. scan start time
+///
+fn get_time_in_seconds(cv_param: &CvParam) -> Result<f64> {
+    let value = cv_param
+        .value
+        .parse::<f64>()
+        .context(format!("Error when parsing {}", cv_param.name))?;
+    Ok(value * get_seconds_per_time_unit(cv_param)?)
+}
+
+/// Returns the value of the first cvParam with the given accession as f64
+///
+fn parse_cv_param_value<E>(element: &E, accession: &str) -> Result<Option<f64>>
+where
+    E: HasCvParams,
+{
+    element
+        .get_cv_param(accession)
+        .first()
+        .map(|cv_param| {
+            cv_param
+                .value
+                .parse::<f64>()
+                .context(format!("Error when parsing {}", cv_param.name))
+        })
+        .transpose()
+}
+
+/// Returns the scan number from native IDs containing `scan=<number>`
+///
+fn get_scan_number_from_id(id: &str) -> Option<u64> {
+    id.split_whitespace()
+        .find_map(|part| part.strip_prefix("scan="))
+        .and_then(|scan_number| scan_number.parse::<u64>().ok())
+}
+
+/// Returns the retention time in seconds of the mzML spectrum, taken from the scan start time
+/// of the first scan. This is cheap compared to converting the whole spectrum,
+/// e.g. for skipping spectra outside of a retention time window.
+///
+/// # Arguments
+/// * `spectrum` - mzML spectrum
+///
+pub(crate) fn get_retention_time(spectrum: &MzMlSpectrum) -> Result<Option<f64>> {
+    spectrum
+        .scan_list
+        .as_ref()
+        .and_then(|scan_list| scan_list.scans.first())
+        .and_then(|scan| {
+            scan.get_cv_param(SCAN_START_TIME_ACCESSION)
+                .first()
+                .cloned()
+        })
+        .map(get_time_in_seconds)
+        .transpose()
+        .context(format!(
+            "Spectrum {} - Invalid scan start time",
+            spectrum.id
+        ))
+}
+
+/// Returns the metadata of the mzML spectrum.
+/// Retention time and ion injection time are taken from the first scan.
+///
+/// # Arguments
+/// * `spectrum` - mzML spectrum
+///
+fn get_metadata(spectrum: &MzMlSpectrum) -> Result<SpectrumMetadata> {
+    let scan = spectrum
+        .scan_list
+        .as_ref()
+        .and_then(|scan_list| scan_list.scans.first());
+    let retention_time = get_retention_time(spectrum)?;
+    let ion_injection_time = scan
+        .and_then(|scan| {
+            scan.get_cv_param(ION_INJECTION_TIME_ACCESSION)
+                .first()
+                .cloned()
+        })
+        .map(|cv_param| get_time_in_seconds(cv_param).map(|seconds| seconds * 1000.0))
+        .transpose()
+        .context(format!(
+            "Spectrum {} - Invalid ion injection time",
+            spectrum.id
+        ))?;
+    let polarity = if !spectrum.get_cv_param(POSITIVE_SCAN_ACCESSION).is_empty() {
+        Some(Polarity::Positive)
+    } else if !spectrum.get_cv_param(NEGATIVE_SCAN_ACCESSION).is_empty() {
+        Some(Polarity::Negative)
+    } else {
+        None
+    };
+    let total_ion_current = parse_cv_param_value(spectrum, TOTAL_ION_CURRENT_ACCESSION).context(
+        format!("Spectrum {} - Invalid total ion current", spectrum.id),
+    )?;
+    let base_peak = match (
+        parse_cv_param_value(spectrum, BASE_PEAK_MZ_ACCESSION)?,
+        parse_cv_param_value(spectrum, BASE_PEAK_INTENSITY_ACCESSION)?,
+    ) {
+        (Some(mz), Some(intensity)) => Some((mz, intensity)),
+        _ => None,
+    };
+    // FAIMS compensation voltage is either annotated at the spectrum or the scan
+    let faims_compensation_voltage =
+        match parse_cv_param_value(spectrum, FAIMS_COMPENSATION_VOLTAGE_ACCESSION)? {
+            Some(voltage) => Some(voltage),
+            None => match scan {
+                Some(scan) => parse_cv_param_value(scan, FAIMS_COMPENSATION_VOLTAGE_ACCESSION)?,
+                None => None,
+            },
         };
+    Ok(SpectrumMetadata::new(
+        retention_time,
+        polarity,
+        get_scan_number_from_id(&spectrum.id),
+        ion_injection_time,
+        total_ion_current,
+        base_peak,
+    )
+    .with_faims_compensation_voltage(faims_compensation_voltage))
+}
+
+impl TryFrom<MzMlSpectrum> for SimpleSpectrum {
+    type Error = anyhow::Error;
+
+    fn try_from(spectrum: MzMlSpectrum) -> Result<Self> {
+        let (ms_level, mz_vec, intensity_vec) = get_ms_level_and_peaks(&spectrum)?;
+        let metadata = get_metadata(&spectrum)?;
+        let ion_mobility = get_ion_mobility(&spectrum, mz_vec.len())?;
+        let simple_spectrum = SimpleSpectrum::new(spectrum.id, ms_level, mz_vec, intensity_vec)
+            .with_metadata(metadata);
+        match ion_mobility {
+            Some(ion_mobility) => simple_spectrum.with_ion_mobility(ion_mobility),
+            None => Ok(simple_spectrum),
+        }
+    }
+}
+
+impl TryFrom<MzMlSpectrum> for SimpleMsNSpectrum {
+    type Error = anyhow::Error;
+
+    fn try_from(spectrum: MzMlSpectrum) -> Result<Self> {
+        let (ms_level, mz_vec, intensity_vec) = get_ms_level_and_peaks(&spectrum)?;
+        let metadata = get_metadata(&spectrum)?;
+        let ion_mobility = get_ion_mobility(&spectrum, mz_vec.len())?;
 
         let mut precursors: Vec<SimplePrecursor> = Vec::new();
 
@@ -410,13 +997,13 @@ impl TryFrom<MzMlSpectrum> for SimpleMsNSpectrum {
             precursors = precs
         }
 
-        Ok(SimpleMsNSpectrum::new(
-            spectrum.id,
-            ms_level,
-            mz_vec,
-            intensity_vec,
-            precursors,
-        ))
+        let simple_spectrum =
+            SimpleMsNSpectrum::new(spectrum.id, ms_level, mz_vec, intensity_vec, precursors)
+                .with_metadata(metadata);
+        match ion_mobility {
+            Some(ion_mobility) => simple_spectrum.with_ion_mobility(ion_mobility),
+            None => Ok(simple_spectrum),
+        }
     }
 }
 
@@ -424,6 +1011,8 @@ impl TryFrom<MzMlSpectrum> for SimpleMsNSpectrum {
 mod tests {
     use std::io::BufReader;
 
+    use base64::{prelude::BASE64_STANDARD, Engine};
+
     use super::*;
     use crate::proteomics::io::mzml::reader::Reader as MzMlReader;
 
@@ -474,4 +1063,146 @@ mod tests {
         assert!(!simple_spectrum.get_mz().is_empty());
         assert!(!simple_spectrum.get_intensity().is_empty());
     }
+
+    #[test]
+    fn test_simple_spectrum_from_mzml_spectrum() {
+        let mut mzml_bytes_reader =
+            BufReader::new(std::fs::File::open("test_files/spectra_small.mzML").unwrap());
+        let mut mzml_file =
+            MzMlReader::read_indexed(&mut mzml_bytes_reader, None, false, false).unwrap();
+
+        let mzml_spectrum = mzml_file
+            .get_spectrum("controllerType=0 controllerNumber=1 scan=3865")
+            .unwrap();
+
+        let simple_spectrum = SimpleSpectrum::try_from(mzml_spectrum).unwrap();
+        assert_eq!(simple_spectrum.get_ms_level(), 2);
+        // defaultArrayLength
+        assert_eq!(simple_spectrum.get_mz().len(), 51);
+        assert_eq!(simple_spectrum.get_intensity().len(), 51);
+
+        // scan start time is given in minutes
+        assert!((simple_spectrum.get_retention_time().unwrap() - 15.638805 * 60.0).abs() < 1e-9);
+        assert_eq!(simple_spectrum.get_polarity(), Some(Polarity::Positive));
+        assert_eq!(simple_spectrum.get_scan_number(), Some(3865));
+        assert_eq!(
+            simple_spectrum.get_ion_injection_time(),
+            Some(119.999997317791)
+        );
+        assert_eq!(simple_spectrum.get_total_ion_current(), Some(97190.586));
+        assert_eq!(
+            simple_spectrum.get_base_peak(),
+            Some((361.0252224, 26945.59))
+        );
+    }
+
+    #[test]
+    fn test_ion_mobility_from_mzml_spectrum() {
+        let mut mzml_bytes_reader =
+            BufReader::new(std::fs::File::open("test_files/spectra_small.mzML").unwrap());
+        let mut mzml_file =
+            MzMlReader::read_indexed(&mut mzml_bytes_reader, None, false, false).unwrap();
+
+        let mut mzml_spectrum = mzml_file
+            .get_spectrum("controllerType=0 controllerNumber=1 scan=3865")
+            .unwrap();
+
+        // no ion mobility in the Orbitrap data
+        let simple_spectrum = SimpleSpectrum::try_from(mzml_spectrum.clone()).unwrap();
+        assert!(simple_spectrum.get_ion_mobility().is_none());
+        assert!(simple_spectrum.get_faims_compensation_voltage().is_none());
+
+        // uncompressed 64-bit ion mobility array with one distinct value per peak
+        let peak_count = simple_spectrum.get_mz().len();
+        let ion_mobility: Vec<f64> = (0..peak_count)
+            .map(|idx| 0.6 + idx as f64 * 0.001)
+            .collect();
+        let mut ion_mobility_array = mzml_spectrum
+            .binary_data_array_list
+            .as_ref()
+            .unwrap()
+            .get_mz_array()
+            .unwrap()
+            .clone();
+        ion_mobility_array.binary.data = BASE64_STANDARD.encode(
+            ion_mobility
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect::<Vec<u8>>(),
+        );
+        ion_mobility_array.encoded_length = ion_mobility_array.binary.data.len();
+        for cv_param in ion_mobility_array.cv_params.iter_mut() {
+            match cv_param.accession.as_str() {
+                // m/z array => inverse reduced ion mobility array
+                "MS:1000514" => cv_param.accession = "MS:1003008".to_string(),
+                // zlib => no compression
+                "MS:1000574" => cv_param.accession = "MS:1000576".to_string(),
+                // 32-bit => 64-bit float
+                "MS:1000521" => cv_param.accession = "MS:1000523".to_string(),
+                _ => (),
+            }
+        }
+        let binary_data_array_list = mzml_spectrum.binary_data_array_list.as_mut().unwrap();
+        binary_data_array_list
+            .binary_data_arrays
+            .push(ion_mobility_array);
+        binary_data_array_list.count += 1;
+        mzml_spectrum.cv_params.push(CvParam {
+            cv_ref: "MS".to_string(),
+            accession: FAIMS_COMPENSATION_VOLTAGE_ACCESSION.to_string(),
+            name: "FAIMS compensation voltage".to_string(),
+            value: "-45".to_string(),
+            unit_cv_ref: Some("UO".to_string()),
+            unit_accession: Some("UO:0000218".to_string()),
+            unit_name: Some("volt".to_string()),
+        });
+
+        let simple_spectrum = SimpleSpectrum::try_from(mzml_spectrum).unwrap();
+        assert_eq!(simple_spectrum.get_ion_mobility(), Some(&ion_mobility));
+        assert_eq!(
+            simple_spectrum.get_faims_compensation_voltage(),
+            Some(-45.0)
+        );
+
+        // filtering keeps the ion mobility of the remaining peaks
+        let mz = simple_spectrum.get_mz()[1..3].to_vec();
+        let intensity = simple_spectrum.get_intensity()[1..3].to_vec();
+        let filtered = simple_spectrum.copy_with_peaks(mz, intensity);
+        assert_eq!(
+            filtered.get_ion_mobility(),
+            Some(&ion_mobility[1..3].to_vec())
+        );
+        // new peaks have no ion mobility
+        let shifted = simple_spectrum.copy_with_peaks(vec![100.5], vec![1.0]);
+        assert!(shifted.get_ion_mobility().is_none());
+    }
+
+    #[test]
+    fn test_get_time_in_seconds() {
+        let mut cv_param = CvParam {
+            cv_ref: "MS".to_string(),
+            accession: SCAN_START_TIME_ACCESSION.to_string(),
+            name: "scan start time".to_string(),
+            value: "1.5".to_string(),
+            unit_cv_ref: Some("UO".to_string()),
+            unit_accession: Some(MINUTE_UNIT_ACCESSION.to_string()),
+            unit_name: Some("minute".to_string()),
+        };
+        assert_eq!(get_time_in_seconds(&cv_param).unwrap(), 90.0);
+        cv_param.unit_accession = None;
+        cv_param.unit_name = Some("millisecond".to_string());
+        assert_eq!(get_time_in_seconds(&cv_param).unwrap(), 0.0015);
+        cv_param.unit_name = None;
+        assert_eq!(get_time_in_seconds(&cv_param).unwrap(), 1.5);
+        cv_param.unit_accession = Some(HOUR_UNIT_ACCESSION.to_string());
+        assert_eq!(get_time_in_seconds(&cv_param).unwrap(), 5400.0);
+        // unsupported units, e.g. day
+        cv_param.unit_accession = Some("UO:0000033".to_string());
+        assert!(get_time_in_seconds(&cv_param).is_err());
+        assert_eq!(
+            get_scan_number_from_id("controllerType=0 controllerNumber=1 scan=42"),
+            Some(42)
+        );
+        assert_eq!(get_scan_number_from_id("index=42"), None);
+    }
 }
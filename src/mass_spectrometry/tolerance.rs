@@ -0,0 +1,167 @@
+// std imports
+use std::fmt::Display;
+use std::str::FromStr;
+
+// 3rd party imports
+use anyhow::{bail, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Symmetric mass tolerance, either relative (ppm) or absolute (Dalton / Thomson)
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Tolerance {
+    /// Parts per million
+    Ppm(f64),
+    /// Absolute tolerance in Dalton (or Thomson when used on m/z values)
+    Da(f64),
+}
+
+impl Tolerance {
+    /// Returns the absolute tolerance at the given reference value
+    ///
+    /// # Arguments
+    /// * `reference` - Reference mass or m/z
+    ///
+    pub fn get_absolute(&self, reference: f64) -> f64 {
+        match self {
+            Self::Ppm(ppm) => reference.abs() * ppm / 1_000_000.0,
+            Self::Da(da) => *da,
+        }
+    }
+
+    /// Returns the lower and upper bound (inclusive) around the given reference value
+    ///
+    /// # Arguments
+    /// * `reference` - Reference mass or m/z
+    ///
+    pub fn get_bounds(&self, reference: f64) -> (f64, f64) {
+        let absolute = self.get_absolute(reference);
+        (reference - absolute, reference + absolute)
+    }
+
+    /// Returns true if the observed value is within the tolerance of the reference value
+    ///
+    /// # Arguments
+    /// * `reference` - Reference (e.g. theoretical) mass or m/z
+    /// * `observed` - Observed mass or m/z
+    ///
+    pub fn contains(&self, reference: f64, observed: f64) -> bool {
+        (observed - reference).abs() <= self.get_absolute(reference)
+    }
+}
+
+impl FromStr for Tolerance {
+    type Err = Error;
+
+    /// Parses tolerances like `10ppm`, `10 ppm`, `0.02Da` or `0.02 Th`
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s_lower = s.trim().to_lowercase();
+        let (value, unit) = match s_lower.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(unit_start) => s_lower.split_at(unit_start),
+            None => bail!(
+                "Tolerance `{}` has no unit. Valid units are `ppm`, `Da` or `Th`",
+                s
+            ),
+        };
+        let value = value
+            .trim()
+            .parse::<f64>()
+            .context(format!("Cannot parse tolerance value of `{}`", s))?;
+        if value < 0.0 {
+            bail!("Tolerance `{}` must not be negative", s);
+        }
+        match unit.trim() {
+            "ppm" => Ok(Self::Ppm(value)),
+            "da" | "th" => Ok(Self::Da(value)),
+            _ => bail!(
+                "Invalid tolerance unit `{}`. Valid units are `ppm`, `Da` or `Th`",
+                unit
+            ),
+        }
+    }
+}
+
+impl Display for Tolerance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ppm(ppm) => write!(f, "{}ppm", ppm),
+            Self::Da(da) => write!(f, "{}Da", da),
+        }
+    }
+}
+
+/// Calculates the mass error of the observed value in ppm
+///
+/// # Arguments
+/// * `reference` - Reference (e.g. theoretical) mass or m/z
+/// * `observed` - Observed mass or m/z
+///
+pub fn calculate_ppm_error(reference: f64, observed: f64) -> f64 {
+    (observed - reference) / reference * 1_000_000.0
+}
+
+/// Returns the index of the peak closest to the given m/z which is within the tolerance.
+/// The m/z values need to be sorted ascending.
+///
+/// # Arguments
+/// * `mz` - Sorted m/z values
+/// * `reference` - m/z to search for
+/// * `tolerance` - Tolerance
+///
+pub fn find_closest_peak(mz: &[f64], reference: f64, tolerance: &Tolerance) -> Option<usize> {
+    let (lower, upper) = tolerance.get_bounds(reference);
+    let start = mz.partition_point(|peak_mz| *peak_mz < lower);
+    mz[start..]
+        .iter()
+        .take_while(|peak_mz| **peak_mz <= upper)
+        .enumerate()
+        .min_by(|(_, x), (_, y)| (*x - reference).abs().total_cmp(&(*y - reference).abs()))
+        .map(|(idx, _)| start + idx)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tolerance_from_str() {
+        assert_eq!(Tolerance::from_str("10ppm").unwrap(), Tolerance::Ppm(10.0));
+        assert_eq!(Tolerance::from_str("10 PPM").unwrap(), Tolerance::Ppm(10.0));
+        assert_eq!(Tolerance::from_str("0.02Da").unwrap(), Tolerance::Da(0.02));
+        assert_eq!(Tolerance::from_str("0.5 Th").unwrap(), Tolerance::Da(0.5));
+        assert!(Tolerance::from_str("0.5").is_err());
+        assert!(Tolerance::from_str("-0.5Da").is_err());
+        assert!(Tolerance::from_str("5 mmu").is_err());
+        assert_eq!(Tolerance::Ppm(10.0).to_string(), "10ppm");
+    }
+
+    #[test]
+    fn test_tolerance_contains() {
+        let tolerance = Tolerance::Ppm(10.0);
+        assert_eq!(tolerance.get_absolute(1000.0), 0.01);
+        assert!(tolerance.contains(1000.0, 1000.009));
+        assert!(!tolerance.contains(1000.0, 1000.011));
+
+        let tolerance = Tolerance::Da(0.5);
+        assert_eq!(tolerance.get_bounds(100.0), (99.5, 100.5));
+        assert!(tolerance.contains(100.0, 99.6));
+        assert!(!tolerance.contains(100.0, 100.6));
+    }
+
+    #[test]
+    fn test_find_closest_peak() {
+        let mz = [100.0, 200.0, 200.005, 200.02, 300.0];
+        assert_eq!(
+            find_closest_peak(&mz, 200.004, &Tolerance::Da(0.02)),
+            Some(2)
+        );
+        assert_eq!(find_closest_peak(&mz, 250.0, &Tolerance::Da(0.02)), None);
+        assert_eq!(find_closest_peak(&mz, 300.0, &Tolerance::Ppm(5.0)), Some(4));
+    }
+
+    #[test]
+    fn test_calculate_ppm_error() {
+        assert_eq!(calculate_ppm_error(1000.0, 1000.01).round(), 10.0);
+    }
+}
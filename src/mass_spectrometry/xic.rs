@@ -0,0 +1,315 @@
+// std imports
+use std::io::{BufRead, Seek};
+
+// 3rd party imports
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::mass_spectrometry::spectrum::{get_retention_time, SimpleSpectrum, Spectrum};
+use crate::mass_spectrometry::tolerance::Tolerance;
+use crate::proteomics::io::mzml::reader::File;
+
+/// Target of an extracted ion chromatogram
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct XicTarget {
+    mz: f64,
+    tolerance: Tolerance,
+    /// (start, end) in seconds
+    retention_time_window: Option<(f64, f64)>,
+    ms_level: u8,
+}
+
+impl XicTarget {
+    /// Creates a new XIC target without retention time restriction
+    ///
+    /// # Arguments
+    /// * `mz` - Target m/z
+    /// * `tolerance` - m/z tolerance
+    /// * `ms_level` - MS level of the spectra to extract from
+    ///
+    pub fn new(mz: f64, tolerance: Tolerance, ms_level: u8) -> Self {
+        Self {
+            mz,
+            tolerance,
+            retention_time_window: None,
+            ms_level,
+        }
+    }
+
+    /// Restricts the extraction to the given retention time window (inclusive)
+    ///
+    /// # Arguments
+    /// * `start` - Start of the window in seconds
+    /// * `end` - End of the window in seconds
+    ///
+    pub fn with_retention_time_window(mut self, start: f64, end: f64) -> Result<Self> {
+        if start > end {
+            bail!(
+                "Retention time window start ({}) is greater than its end ({})",
+                start,
+                end
+            );
+        }
+        self.retention_time_window = Some((start, end));
+        Ok(self)
+    }
+
+    /// Returns the target m/z
+    ///
+    pub fn get_mz(&self) -> f64 {
+        self.mz
+    }
+
+    /// Returns the m/z tolerance
+    ///
+    pub fn get_tolerance(&self) -> &Tolerance {
+        &self.tolerance
+    }
+
+    /// Returns the retention time window (start, end) in seconds
+    ///
+    pub fn get_retention_time_window(&self) -> Option<(f64, f64)> {
+        self.retention_time_window
+    }
+
+    /// Returns the MS level
+    ///
+    pub fn get_ms_level(&self) -> u8 {
+        self.ms_level
+    }
+
+    /// Returns true if spectra with the given MS level and retention time are extracted
+    ///
+    /// # Arguments
+    /// * `ms_level` - MS level of the spectrum
+    /// * `retention_time` - Retention time of the spectrum in seconds
+    ///
+    pub fn accepts(&self, ms_level: u8, retention_time: f64) -> bool {
+        self.ms_level == ms_level
+            && match self.retention_time_window {
+                Some((start, end)) => start <= retention_time && retention_time <= end,
+                None => true,
+            }
+    }
+}
+
+/// Extracted ion chromatogram
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Xic {
+    target: XicTarget,
+    /// (retention time in seconds, intensity)
+    trace: Vec<(f64, f64)>,
+}
+
+impl Xic {
+    /// Creates a new XIC
+    ///
+    /// # Arguments
+    /// * `target` - XIC target
+    /// * `trace` - (retention time in seconds, intensity)
+    ///
+    pub fn new(target: XicTarget, trace: Vec<(f64, f64)>) -> Self {
+        Self { target, trace }
+    }
+
+    /// Returns the target
+    ///
+    pub fn get_target(&self) -> &XicTarget {
+        &self.target
+    }
+
+    /// Returns the trace (retention time in seconds, intensity)
+    ///
+    pub fn get_trace(&self) -> &Vec<(f64, f64)> {
+        &self.trace
+    }
+
+    /// Returns the point with the highest intensity (retention time in seconds, intensity)
+    ///
+    pub fn get_apex(&self) -> Option<(f64, f64)> {
+        self.trace
+            .iter()
+            .copied()
+            .max_by(|(_, x), (_, y)| x.total_cmp(y))
+    }
+}
+
+/// Returns the summed intensity of all peaks within the tolerance of the target m/z.
+/// The m/z values need to be sorted ascending.
+///
+/// # Arguments
+/// * `mz` - Sorted m/z values
+/// * `intensity` - Intensities
+/// * `target` - XIC target
+///
+fn get_summed_intensity(mz: &[f64], intensity: &[f64], target: &XicTarget) -> f64 {
+    let (lower, upper) = target.tolerance.get_bounds(target.mz);
+    let start = mz.partition_point(|peak_mz| *peak_mz < lower);
+    mz[start..]
+        .iter()
+        .zip(intensity[start..].iter())
+        .take_while(|(peak_mz, _)| **peak_mz <= upper)
+        .map(|(_, peak_intensity)| *peak_intensity)
+        .sum()
+}
+
+/// Extracts the XICs of all targets within a single pass over the spectra.
+/// Each spectrum matching the target's MS level and retention time window adds a point
+/// with the summed intensity of the peaks within the tolerance (0.0 if there is none).
+/// Spectra without retention time are skipped.
+/// Spectra need to be ordered by retention time and have sorted m/z values.
+///
+/// # Arguments
+/// * `spectra` - Spectra, e.g. of a run
+/// * `targets` - XIC targets
+///
+pub fn extract_xics<S, I>(spectra: I, targets: &[XicTarget]) -> Result<Vec<Xic>>
+where
+    S: Spectrum,
+    I: IntoIterator<Item = Result<S>>,
+{
+    let mut traces: Vec<Vec<(f64, f64)>> = vec![Vec::new(); targets.len()];
+    for spectrum in spectra {
+        let spectrum = spectrum?;
+        if !targets
+            .iter()
+            .any(|target| target.ms_level == spectrum.get_ms_level())
+        {
+            continue;
+        }
+        let retention_time = match spectrum.get_retention_time() {
+            Some(retention_time) => retention_time,
+            None => continue,
+        };
+        for (target, trace) in targets.iter().zip(traces.iter_mut()) {
+            if !target.accepts(spectrum.get_ms_level(), retention_time) {
+                continue;
+            }
+            trace.push((
+                retention_time,
+                get_summed_intensity(spectrum.get_mz(), spectrum.get_intensity(), target),
+            ));
+        }
+    }
+    Ok(targets
+        .iter()
+        .cloned()
+        .zip(traces)
+        .map(|(target, trace)| Xic::new(target, trace))
+        .collect())
+}
+
+/// Extracts the XICs of all targets within a single sequential pass over the spectra of the mzML file.
+/// Only spectra accepted by any target, judged by MS level and scan start time, are decoded.
+/// Spectra without scan start time are skipped.
+///
+/// # Arguments
+/// * `file` - Open mzML file
+/// * `targets` - XIC targets
+///
+pub fn extract_xics_from_mzml<F>(file: &mut File<'_, F>, targets: &[XicTarget]) -> Result<Vec<Xic>>
+where
+    F: BufRead + Seek,
+{
+    let spectra = file.iter_spectra().filter_map(|spectrum| {
+        let spectrum = match spectrum {
+            Ok(spectrum) => spectrum,
+            Err(err) => return Some(Err(err)),
+        };
+        let ms_level = spectrum.get_ms_level()?;
+        let retention_time = match get_retention_time(&spectrum) {
+            Ok(retention_time) => retention_time?,
+            Err(err) => return Some(Err(err)),
+        };
+        if !targets
+            .iter()
+            .any(|target| target.accepts(ms_level, retention_time))
+        {
+            return None;
+        }
+        let spectrum_id = spectrum.id.clone();
+        Some(
+            SimpleSpectrum::try_from(spectrum)
+                .context(format!("Failed to convert spectrum {}", spectrum_id)),
+        )
+    });
+    extract_xics(spectra, targets)
+}
+
+#[cfg(test)]
+mod test {
+    // std imports
+    use std::io::BufReader;
+
+    // internal imports
+    use super::*;
+    use crate::mass_spectrometry::spectrum::SpectrumMetadata;
+    use crate::proteomics::io::mzml::reader::Reader as MzMlReader;
+
+    fn spectrum(id: &str, ms_level: u8, retention_time: f64, mz: Vec<f64>) -> SimpleSpectrum {
+        let intensity = vec![10.0; mz.len()];
+        SimpleSpectrum::new(id.to_string(), ms_level, mz, intensity).with_metadata(
+            SpectrumMetadata::new(Some(retention_time), None, None, None, None, None),
+        )
+    }
+
+    #[test]
+    fn test_extract_xics() {
+        let spectra = vec![
+            spectrum("1", 1, 10.0, vec![500.0, 500.004, 600.0]),
+            spectrum("2", 2, 11.0, vec![500.0]),
+            spectrum("3", 1, 12.0, vec![600.0]),
+            spectrum("4", 1, 13.0, vec![499.999, 600.0]),
+        ];
+        let targets = vec![
+            XicTarget::new(500.0, Tolerance::Ppm(10.0), 1),
+            XicTarget::new(600.0, Tolerance::Da(0.01), 1)
+                .with_retention_time_window(11.5, 20.0)
+                .unwrap(),
+            XicTarget::new(500.0, Tolerance::Da(0.01), 2),
+        ];
+        let xics = extract_xics(spectra.into_iter().map(Ok), &targets).unwrap();
+        assert_eq!(xics.len(), 3);
+        assert_eq!(
+            xics[0].get_trace(),
+            &vec![(10.0, 20.0), (12.0, 0.0), (13.0, 10.0)]
+        );
+        assert_eq!(xics[0].get_apex(), Some((10.0, 20.0)));
+        assert_eq!(xics[1].get_trace(), &vec![(12.0, 10.0), (13.0, 10.0)]);
+        assert_eq!(xics[2].get_trace(), &vec![(11.0, 10.0)]);
+
+        // spectra without retention time are skipped
+        let spectra = vec![
+            SimpleSpectrum::new("1".to_string(), 1, vec![500.0], vec![10.0]),
+            spectrum("2", 1, 12.0, vec![500.0]),
+        ];
+        let xics = extract_xics(spectra.into_iter().map(Ok), &targets[..1]).unwrap();
+        assert_eq!(xics[0].get_trace(), &vec![(12.0, 10.0)]);
+
+        assert!(XicTarget::new(500.0, Tolerance::Ppm(10.0), 1)
+            .with_retention_time_window(20.0, 10.0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_extract_xics_from_mzml() {
+        let mut mzml_bytes_reader =
+            BufReader::new(std::fs::File::open("test_files/spectra_small.mzML").unwrap());
+        let mut mzml_file =
+            MzMlReader::read_indexed(&mut mzml_bytes_reader, None, false, false).unwrap();
+
+        // base peak of scan 3865
+        let targets = vec![XicTarget::new(361.0252224, Tolerance::Ppm(10.0), 2)
+            .with_retention_time_window(15.0 * 60.0, 16.0 * 60.0)
+            .unwrap()];
+        let xics = extract_xics_from_mzml(&mut mzml_file, &targets).unwrap();
+        let (apex_retention_time, apex_intensity) = xics[0].get_apex().unwrap();
+        assert!((apex_retention_time - 15.638805 * 60.0).abs() < 1e-9);
+        assert!(apex_intensity >= 26945.59 - 1e-2);
+        // only scan 3865 is within the retention time window
+        assert_eq!(xics[0].get_trace().len(), 1);
+    }
+}
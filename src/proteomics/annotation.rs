@@ -0,0 +1,448 @@
+// std imports
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+// 3rd party imports
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::mass_spectrometry::spectrum::Spectrum;
+use crate::mass_spectrometry::tolerance::{calculate_ppm_error, Tolerance};
+use crate::proteomics::fragmentation::{
+    calculate_fragment_ions_with_neutral_losses, IonType, NeutralLoss,
+};
+use crate::proteomics::peptide::ModifiedPeptide;
+
+/// Label of peaks without annotation
+///
+pub const UNANNOTATED_LABEL: &str = "?";
+
+/// Separator for multiple annotations of a single peak
+///
+pub const ANNOTATION_SEPARATOR: &str = ",";
+
+/// Fragment ion explaining a peak
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FragmentAnnotation {
+    ion_type: IonType,
+    ordinal: usize,
+    charge: u8,
+    neutral_loss: Option<NeutralLoss>,
+    theoretical_mz: f64,
+    /// Observed - theoretical in ppm
+    mass_error_ppm: f64,
+}
+
+impl FragmentAnnotation {
+    /// Returns the ion type
+    ///
+    pub fn get_ion_type(&self) -> IonType {
+        self.ion_type
+    }
+
+    /// Returns the number of residues in the fragment
+    ///
+    pub fn get_ordinal(&self) -> usize {
+        self.ordinal
+    }
+
+    /// Returns the charge
+    ///
+    pub fn get_charge(&self) -> u8 {
+        self.charge
+    }
+
+    /// Returns the neutral loss
+    ///
+    pub fn get_neutral_loss(&self) -> Option<NeutralLoss> {
+        self.neutral_loss
+    }
+
+    /// Returns the theoretical m/z
+    ///
+    pub fn get_theoretical_mz(&self) -> f64 {
+        self.theoretical_mz
+    }
+
+    /// Returns the mass error (observed - theoretical) in ppm
+    ///
+    pub fn get_mass_error_ppm(&self) -> f64 {
+        self.mass_error_ppm
+    }
+}
+
+impl Display for FragmentAnnotation {
+    /// Writes the annotation in PSI peak annotation notation, e.g. `b3^2-H2O/1.2ppm`.
+    /// The charge is omitted for singly charged ions.
+    ///
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.ion_type, self.ordinal)?;
+        if self.charge > 1 {
+            write!(f, "^{}", self.charge)?;
+        }
+        if let Some(neutral_loss) = self.neutral_loss {
+            write!(f, "-{}", neutral_loss)?;
+        }
+        write!(f, "/{:.1}ppm", self.mass_error_ppm)
+    }
+}
+
+/// Peak with all fragment ions explaining it
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeakAnnotation {
+    mz: f64,
+    intensity: f64,
+    annotations: Vec<FragmentAnnotation>,
+}
+
+impl PeakAnnotation {
+    /// Returns the m/z
+    ///
+    pub fn get_mz(&self) -> f64 {
+        self.mz
+    }
+
+    /// Returns the intensity
+    ///
+    pub fn get_intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// Returns the annotations, sorted by absolute mass error
+    ///
+    pub fn get_annotations(&self) -> &[FragmentAnnotation] {
+        &self.annotations
+    }
+
+    /// Returns true if at least one fragment ion explains the peak
+    ///
+    pub fn is_annotated(&self) -> bool {
+        !self.annotations.is_empty()
+    }
+
+    /// Returns the label of the peak, `?` for unannotated peaks.
+    /// Multiple annotations are separated by comma.
+    ///
+    pub fn get_label(&self) -> String {
+        if self.annotations.is_empty() {
+            return UNANNOTATED_LABEL.to_string();
+        }
+        self.annotations
+            .iter()
+            .map(|annotation| annotation.to_string())
+            .collect::<Vec<String>>()
+            .join(ANNOTATION_SEPARATOR)
+    }
+}
+
+/// Coverage of an ion series, e.g. all b-ions
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IonSeriesCoverage {
+    ion_type: IonType,
+    /// Ordinals with at least one matched ion (any charge or neutral loss), sorted
+    observed_ordinals: Vec<usize>,
+    /// Number of possible fragments, sequence length - 1
+    possible_ordinals: usize,
+}
+
+impl IonSeriesCoverage {
+    /// Returns the ion type
+    ///
+    pub fn get_ion_type(&self) -> IonType {
+        self.ion_type
+    }
+
+    /// Returns the sorted ordinals with at least one matched ion
+    ///
+    pub fn get_observed_ordinals(&self) -> &[usize] {
+        &self.observed_ordinals
+    }
+
+    /// Returns the number of possible fragments
+    ///
+    pub fn get_possible_ordinals(&self) -> usize {
+        self.possible_ordinals
+    }
+
+    /// Returns the fraction of observed fragments
+    ///
+    pub fn get_coverage(&self) -> f64 {
+        match self.possible_ordinals {
+            0 => 0.0,
+            _ => self.observed_ordinals.len() as f64 / self.possible_ordinals as f64,
+        }
+    }
+}
+
+/// Spectrum annotated with the fragment ions of a peptide
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnnotatedSpectrum {
+    spectrum_id: String,
+    /// Peptide with modification names, see `ModifiedPeptide`'s `Display`
+    peptide: String,
+    peaks: Vec<PeakAnnotation>,
+    coverage: Vec<IonSeriesCoverage>,
+}
+
+impl AnnotatedSpectrum {
+    /// Returns the spectrum ID
+    ///
+    pub fn get_spectrum_id(&self) -> &str {
+        &self.spectrum_id
+    }
+
+    /// Returns the peptide
+    ///
+    pub fn get_peptide(&self) -> &str {
+        &self.peptide
+    }
+
+    /// Returns the annotated peaks, in the same order as the spectrum
+    ///
+    pub fn get_peaks(&self) -> &[PeakAnnotation] {
+        &self.peaks
+    }
+
+    /// Returns the coverage of each ion series
+    ///
+    pub fn get_coverage(&self) -> &[IonSeriesCoverage] {
+        &self.coverage
+    }
+
+    /// Returns the labels of each peak
+    ///
+    pub fn get_labels(&self) -> Vec<String> {
+        self.peaks.iter().map(|peak| peak.get_label()).collect()
+    }
+
+    /// Returns the intensity of the annotated peaks divided by the total intensity
+    ///
+    pub fn get_annotated_intensity_fraction(&self) -> f64 {
+        let total_intensity: f64 = self.peaks.iter().map(|peak| peak.intensity).sum();
+        if total_intensity <= 0.0 {
+            return 0.0;
+        }
+        self.peaks
+            .iter()
+            .filter(|peak| peak.is_annotated())
+            .map(|peak| peak.intensity)
+            .sum::<f64>()
+            / total_intensity
+    }
+}
+
+/// Annotates each peak of the spectrum with the fragment ions within the tolerance.
+/// A peak can be explained by multiple fragment ions.
+/// For mzML spectra use `SimpleSpectrum::try_from` or `SimpleMsNSpectrum::try_from` first.
+///
+/// # Arguments
+/// * `spectrum` - Spectrum with sorted m/z values
+/// * `peptide` - Peptide with modifications
+/// * `tolerance` - Fragment tolerance
+/// * `ion_types` - Ion types to annotate
+/// * `neutral_losses` - Neutral losses to annotate
+/// * `max_fragment_charge` - Maximum fragment charge
+///
+pub fn annotate_spectrum<S>(
+    spectrum: &S,
+    peptide: &ModifiedPeptide,
+    tolerance: &Tolerance,
+    ion_types: &[IonType],
+    neutral_losses: &[NeutralLoss],
+    max_fragment_charge: u8,
+) -> Result<AnnotatedSpectrum>
+where
+    S: Spectrum,
+{
+    let fragment_ions = calculate_fragment_ions_with_neutral_losses(
+        peptide,
+        ion_types,
+        neutral_losses,
+        max_fragment_charge,
+    )?;
+    let mz = spectrum.get_mz();
+
+    let mut peaks: Vec<PeakAnnotation> = mz
+        .iter()
+        .zip(spectrum.get_intensity().iter())
+        .map(|(mz, intensity)| PeakAnnotation {
+            mz: *mz,
+            intensity: *intensity,
+            annotations: Vec::new(),
+        })
+        .collect();
+
+    let mut observed_ordinals: HashMap<IonType, HashSet<usize>> = HashMap::new();
+    for ion in fragment_ions.iter() {
+        let (lower, upper) = tolerance.get_bounds(ion.get_mz());
+        let start = mz.partition_point(|peak_mz| *peak_mz < lower);
+        for (peak_idx, peak_mz) in mz.iter().enumerate().skip(start) {
+            if *peak_mz > upper {
+                break;
+            }
+            peaks[peak_idx].annotations.push(FragmentAnnotation {
+                ion_type: ion.get_ion_type(),
+                ordinal: ion.get_ordinal(),
+                charge: ion.get_charge(),
+                neutral_loss: ion.get_neutral_loss(),
+                theoretical_mz: ion.get_mz(),
+                mass_error_ppm: calculate_ppm_error(ion.get_mz(), *peak_mz),
+            });
+            observed_ordinals
+                .entry(ion.get_ion_type())
+                .or_default()
+                .insert(ion.get_ordinal());
+        }
+    }
+
+    for peak in peaks.iter_mut() {
+        peak.annotations
+            .sort_by(|x, y| x.mass_error_ppm.abs().total_cmp(&y.mass_error_ppm.abs()));
+    }
+
+    let possible_ordinals = peptide.get_sequence().len().saturating_sub(1);
+    let coverage = ion_types
+        .iter()
+        .map(|ion_type| {
+            let mut ordinals: Vec<usize> = observed_ordinals
+                .get(ion_type)
+                .map(|ordinals| ordinals.iter().cloned().collect())
+                .unwrap_or_default();
+            ordinals.sort();
+            IonSeriesCoverage {
+                ion_type: *ion_type,
+                observed_ordinals: ordinals,
+                possible_ordinals,
+            }
+        })
+        .collect();
+
+    Ok(AnnotatedSpectrum {
+        spectrum_id: spectrum.get_id().clone(),
+        peptide: peptide.to_string(),
+        peaks,
+        coverage,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    // std imports
+    use std::io::BufReader;
+
+    // internal imports
+    use super::*;
+    use crate::chemistry::amino_acid::get_amino_acid_by_one_letter_code;
+    use crate::mass_spectrometry::spectrum::SimpleSpectrum;
+    use crate::proteomics::io::mzml::reader::Reader as MzMlReader;
+    use crate::proteomics::peptide::Peptide;
+    use crate::proteomics::post_translational_modifications::{
+        ModificationType, Position, PostTranslationalModification,
+    };
+
+    #[test]
+    fn test_fragment_annotation_to_string() {
+        let annotation = FragmentAnnotation {
+            ion_type: IonType::B,
+            ordinal: 3,
+            charge: 2,
+            neutral_loss: Some(NeutralLoss::Water),
+            theoretical_mz: 100.0,
+            mass_error_ppm: 1.234,
+        };
+        assert_eq!(annotation.to_string(), "b3^2-H2O/1.2ppm");
+        let annotation = FragmentAnnotation {
+            ion_type: IonType::Y,
+            ordinal: 1,
+            charge: 1,
+            neutral_loss: None,
+            theoretical_mz: 100.0,
+            mass_error_ppm: -0.56,
+        };
+        assert_eq!(annotation.to_string(), "y1/-0.6ppm");
+    }
+
+    #[test]
+    fn test_annotate_spectrum() {
+        let oxidation = PostTranslationalModification::new(
+            "Oxidation",
+            get_amino_acid_by_one_letter_code('M').unwrap(),
+            15.994915,
+            ModificationType::Variable,
+            Position::Anywhere,
+        );
+        let peptide = ModifiedPeptide::new(
+            Peptide::new("PMEPTIDE".to_string(), 0).unwrap(),
+            vec![(1, oxidation)],
+        )
+        .unwrap();
+        // b2 (P + oxidized M), b2-H2O, y1, noise
+        let b2 = 97.052763875 + 131.040484645 + 15.994915 + 1.007276466621;
+        let spectrum = SimpleSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![148.06043, b2 - 18.010564684, b2 + 0.0001, 500.0],
+            vec![10.0, 20.0, 30.0, 40.0],
+        );
+        let annotated_spectrum = annotate_spectrum(
+            &spectrum,
+            &peptide,
+            &Tolerance::Ppm(10.0),
+            &[IonType::B, IonType::Y],
+            &[NeutralLoss::Water],
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(annotated_spectrum.get_peptide(), "PM[Oxidation]EPTIDE");
+        let labels = annotated_spectrum.get_labels();
+        assert!(labels[0].starts_with("y1/"));
+        assert!(labels[1].starts_with("b2-H2O/"));
+        assert!(labels[2].starts_with("b2/0.4ppm"));
+        assert_eq!(labels[3], "?");
+        assert!((annotated_spectrum.get_annotated_intensity_fraction() - 0.6).abs() < 1e-12);
+
+        let b_coverage = &annotated_spectrum.get_coverage()[0];
+        assert_eq!(b_coverage.get_ion_type(), IonType::B);
+        assert_eq!(b_coverage.get_observed_ordinals(), &[2]);
+        assert_eq!(b_coverage.get_possible_ordinals(), 7);
+        assert!((b_coverage.get_coverage() - 1.0 / 7.0).abs() < 1e-12);
+
+        // serializable
+        let json = serde_json::to_string(&annotated_spectrum).unwrap();
+        let deserialized: AnnotatedSpectrum = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, annotated_spectrum);
+    }
+
+    #[test]
+    fn test_annotate_mzml_spectrum() {
+        let mut mzml_bytes_reader =
+            BufReader::new(std::fs::File::open("test_files/spectra_small.mzML").unwrap());
+        let mut mzml_file =
+            MzMlReader::read_indexed(&mut mzml_bytes_reader, None, false, false).unwrap();
+        let mzml_spectrum = mzml_file
+            .get_spectrum("controllerType=0 controllerNumber=1 scan=3865")
+            .unwrap();
+        let spectrum = SimpleSpectrum::try_from(mzml_spectrum).unwrap();
+        let peptide: ModifiedPeptide = Peptide::new("PEPTIDEK".to_string(), 0).unwrap().into();
+        let annotated_spectrum = annotate_spectrum(
+            &spectrum,
+            &peptide,
+            &Tolerance::Ppm(10.0),
+            &[IonType::B, IonType::Y],
+            &[],
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            annotated_spectrum.get_peaks().len(),
+            spectrum.get_mz().len()
+        );
+        assert_eq!(annotated_spectrum.get_coverage().len(), 2);
+    }
+}
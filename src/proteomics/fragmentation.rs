@@ -0,0 +1,347 @@
+// std imports
+use std::fmt::Display;
+use std::str::FromStr;
+
+// 3rd party imports
+use anyhow::{bail, Error, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::chemistry::element::{CARBON, HYDROGEN, NITROGEN, OXYGEN};
+use crate::chemistry::subatomic_particle::PROTON;
+use crate::proteomics::peptide::{ModifiedPeptide, WATER_MASS};
+
+lazy_static! {
+    /// Monoisotopic mass of carbon monoxide
+    ///
+    pub static ref CARBON_MONOXIDE_MASS: f64 = CARBON.get_mono_mass() + OXYGEN.get_mono_mass();
+
+    /// Monoisotopic mass of ammonia
+    ///
+    pub static ref AMMONIA_MASS: f64 = NITROGEN.get_mono_mass() + HYDROGEN.get_mono_mass() * 3.0;
+}
+
+/// Peptide fragment ion types
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IonType {
+    A,
+    B,
+    C,
+    X,
+    Y,
+    /// z-dot ion (z+1)
+    Z,
+}
+
+impl IonType {
+    /// Returns true if the fragment contains the N-terminus
+    ///
+    pub fn is_n_terminal(&self) -> bool {
+        matches!(self, Self::A | Self::B | Self::C)
+    }
+
+    /// Returns the neutral mass which is added to the sum of the residue masses
+    ///
+    pub fn get_mass_offset(&self) -> f64 {
+        match self {
+            Self::A => -*CARBON_MONOXIDE_MASS,
+            Self::B => 0.0,
+            Self::C => *AMMONIA_MASS,
+            Self::X => *WATER_MASS + *CARBON_MONOXIDE_MASS - 2.0 * HYDROGEN.get_mono_mass(),
+            Self::Y => *WATER_MASS,
+            Self::Z => *WATER_MASS - *AMMONIA_MASS + HYDROGEN.get_mono_mass(),
+        }
+    }
+}
+
+impl FromStr for IonType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "a" => Ok(Self::A),
+            "b" => Ok(Self::B),
+            "c" => Ok(Self::C),
+            "x" => Ok(Self::X),
+            "y" => Ok(Self::Y),
+            "z" => Ok(Self::Z),
+            _ => bail!("Invalid ion type. Valid ion types are `a`, `b`, `c`, `x`, `y` or `z`"),
+        }
+    }
+}
+
+impl Display for IonType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::A => write!(f, "a"),
+            Self::B => write!(f, "b"),
+            Self::C => write!(f, "c"),
+            Self::X => write!(f, "x"),
+            Self::Y => write!(f, "y"),
+            Self::Z => write!(f, "z"),
+        }
+    }
+}
+
+/// Neutral losses of fragment ions
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NeutralLoss {
+    Water,
+    Ammonia,
+}
+
+impl NeutralLoss {
+    /// Returns the monoisotopic mass of the loss
+    ///
+    pub fn get_mass(&self) -> f64 {
+        match self {
+            Self::Water => *WATER_MASS,
+            Self::Ammonia => *AMMONIA_MASS,
+        }
+    }
+}
+
+impl FromStr for NeutralLoss {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "H2O" => Ok(Self::Water),
+            "NH3" => Ok(Self::Ammonia),
+            _ => bail!("Invalid neutral loss. Valid neutral losses are `H2O` or `NH3`"),
+        }
+    }
+}
+
+impl Display for NeutralLoss {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Water => write!(f, "H2O"),
+            Self::Ammonia => write!(f, "NH3"),
+        }
+    }
+}
+
+/// Theoretical fragment ion of a peptide
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FragmentIon {
+    ion_type: IonType,
+    /// Number of residues in the fragment, e.g. 3 for b3
+    ordinal: usize,
+    charge: u8,
+    mz: f64,
+    #[serde(default)]
+    neutral_loss: Option<NeutralLoss>,
+}
+
+impl FragmentIon {
+    pub fn new(ion_type: IonType, ordinal: usize, charge: u8, mz: f64) -> Self {
+        Self {
+            ion_type,
+            ordinal,
+            charge,
+            mz,
+            neutral_loss: None,
+        }
+    }
+
+    /// Returns a copy of the ion with the given neutral loss.
+    /// Losses are not accumulated, a previous loss is replaced.
+    ///
+    /// # Arguments
+    /// * `neutral_loss` - Neutral loss
+    ///
+    pub fn with_neutral_loss(&self, neutral_loss: NeutralLoss) -> Self {
+        let previous_loss = self.neutral_loss.map_or(0.0, |loss| loss.get_mass());
+        Self {
+            ion_type: self.ion_type,
+            ordinal: self.ordinal,
+            charge: self.charge,
+            mz: self.mz + (previous_loss - neutral_loss.get_mass()) / self.charge as f64,
+            neutral_loss: Some(neutral_loss),
+        }
+    }
+
+    /// Returns the neutral loss
+    ///
+    pub fn get_neutral_loss(&self) -> Option<NeutralLoss> {
+        self.neutral_loss
+    }
+
+    /// Returns the ion type
+    ///
+    pub fn get_ion_type(&self) -> IonType {
+        self.ion_type
+    }
+
+    /// Returns the number of residues in the fragment
+    ///
+    pub fn get_ordinal(&self) -> usize {
+        self.ordinal
+    }
+
+    /// Returns the charge
+    ///
+    pub fn get_charge(&self) -> u8 {
+        self.charge
+    }
+
+    /// Returns the m/z
+    ///
+    pub fn get_mz(&self) -> f64 {
+        self.mz
+    }
+}
+
+/// Calculates the theoretical fragment ions of a peptide, ordered by ion type, ordinal and charge.
+///
+/// # Arguments
+/// * `peptide` - Peptide to fragment
+/// * `ion_types` - Ion types to calculate
+/// * `max_charge` - Maximum fragment charge (inclusive)
+///
+pub fn calculate_fragment_ions(
+    peptide: &ModifiedPeptide,
+    ion_types: &[IonType],
+    max_charge: u8,
+) -> Result<Vec<FragmentIon>> {
+    if max_charge == 0 {
+        bail!("Maximum fragment charge must be at least 1");
+    }
+    let residue_masses = peptide.get_residue_masses()?;
+    let fragment_count = residue_masses.len().saturating_sub(1);
+    let mut fragment_ions =
+        Vec::with_capacity(ion_types.len() * fragment_count * max_charge as usize);
+    for ion_type in ion_types {
+        let mut residue_mass_sum = 0.0;
+        for ordinal in 1..=fragment_count {
+            residue_mass_sum += match ion_type.is_n_terminal() {
+                true => residue_masses[ordinal - 1],
+                false => residue_masses[residue_masses.len() - ordinal],
+            };
+            let neutral_mass = residue_mass_sum + ion_type.get_mass_offset();
+            for charge in 1..=max_charge {
+                fragment_ions.push(FragmentIon::new(
+                    *ion_type,
+                    ordinal,
+                    charge,
+                    (neutral_mass + PROTON.get_mass() * charge as f64) / charge as f64,
+                ));
+            }
+        }
+    }
+    Ok(fragment_ions)
+}
+
+/// Calculates the theoretical fragment ions of a peptide including ions with neutral losses.
+/// Each loss is applied to each ion once, losses are not combined.
+///
+/// # Arguments
+/// * `peptide` - Peptide to fragment
+/// * `ion_types` - Ion types to calculate
+/// * `neutral_losses` - Neutral losses
+/// * `max_charge` - Maximum fragment charge (inclusive)
+///
+pub fn calculate_fragment_ions_with_neutral_losses(
+    peptide: &ModifiedPeptide,
+    ion_types: &[IonType],
+    neutral_losses: &[NeutralLoss],
+    max_charge: u8,
+) -> Result<Vec<FragmentIon>> {
+    let fragment_ions = calculate_fragment_ions(peptide, ion_types, max_charge)?;
+    let loss_ions: Vec<FragmentIon> = fragment_ions
+        .iter()
+        .flat_map(|ion| {
+            neutral_losses
+                .iter()
+                .map(|neutral_loss| ion.with_neutral_loss(*neutral_loss))
+        })
+        .collect();
+    Ok(fragment_ions.into_iter().chain(loss_ions).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proteomics::peptide::Peptide;
+
+    #[test]
+    fn test_ion_type_from_str() {
+        assert_eq!(IonType::from_str("b").unwrap(), IonType::B);
+        assert_eq!(IonType::from_str("Y").unwrap(), IonType::Y);
+        assert!(IonType::from_str("q").is_err());
+        assert_eq!(IonType::Z.to_string(), "z");
+        assert_eq!(NeutralLoss::from_str("h2o").unwrap(), NeutralLoss::Water);
+        assert_eq!(NeutralLoss::Ammonia.to_string(), "NH3");
+    }
+
+    #[test]
+    fn test_calculate_fragment_ions_with_neutral_losses() {
+        let peptide: ModifiedPeptide = Peptide::new("PEPTIDE".to_string(), 0).unwrap().into();
+        let ions = calculate_fragment_ions_with_neutral_losses(
+            &peptide,
+            &[IonType::B],
+            &[NeutralLoss::Water, NeutralLoss::Ammonia],
+            2,
+        )
+        .unwrap();
+        assert_eq!(ions.len(), 36);
+        let b2 = &ions[2];
+        assert_eq!(b2.get_ordinal(), 2);
+        assert_eq!(b2.get_charge(), 1);
+        let b2_water = b2.with_neutral_loss(NeutralLoss::Water);
+        assert!((b2.get_mz() - b2_water.get_mz() - *WATER_MASS).abs() < 1e-9);
+        // replacing a loss
+        let b2_ammonia = b2_water.with_neutral_loss(NeutralLoss::Ammonia);
+        assert!((b2.get_mz() - b2_ammonia.get_mz() - *AMMONIA_MASS).abs() < 1e-9);
+        assert!(ions.contains(&b2_water));
+        assert!(ions.contains(&b2.with_neutral_loss(NeutralLoss::Ammonia)));
+    }
+
+    #[test]
+    fn test_calculate_fragment_ions() {
+        let peptide: ModifiedPeptide = Peptide::new("PEPTIDE".to_string(), 0).unwrap().into();
+        let ions = calculate_fragment_ions(&peptide, &[IonType::B, IonType::Y], 2).unwrap();
+        // 6 fragments per ion type and 2 charges
+        assert_eq!(ions.len(), 24);
+
+        // Reference values from http://db.systemsbiology.net/proteomicsToolkit/FragIonServlet.html
+        let b2 = ions
+            .iter()
+            .find(|ion| ion.get_ion_type() == IonType::B && ion.get_ordinal() == 2)
+            .unwrap();
+        assert!((b2.get_mz() - 227.10263).abs() < 0.001);
+        let y1 = ions
+            .iter()
+            .find(|ion| ion.get_ion_type() == IonType::Y && ion.get_ordinal() == 1)
+            .unwrap();
+        assert!((y1.get_mz() - 148.06043).abs() < 0.001);
+        let y6_2 = ions
+            .iter()
+            .find(|ion| {
+                ion.get_ion_type() == IonType::Y && ion.get_ordinal() == 6 && ion.get_charge() == 2
+            })
+            .unwrap();
+        assert!((y6_2.get_mz() - 352.16087).abs() < 0.001);
+
+        // b + y of complementary ions is the precursor mass plus two protons
+        let b3 = ions
+            .iter()
+            .find(|ion| {
+                ion.get_ion_type() == IonType::B && ion.get_ordinal() == 3 && ion.get_charge() == 1
+            })
+            .unwrap();
+        let y4 = ions
+            .iter()
+            .find(|ion| {
+                ion.get_ion_type() == IonType::Y && ion.get_ordinal() == 4 && ion.get_charge() == 1
+            })
+            .unwrap();
+        assert!(
+            (b3.get_mz() + y4.get_mz() - peptide.get_mass() - 2.0 * PROTON.get_mass()).abs() < 1e-9
+        );
+    }
+}
@@ -2,7 +2,8 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    cv_param::CvParam, is_element::IsElement,
+    cv_param::CvParam, has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
+    is_element::IsElement, referenceable_param_group_list::ReferenceableParamGroupList,
     referenceable_param_group_ref::ReferenceableParamGroupRef, user_param::UserParam,
 };
 use crate::has_cv_params;
@@ -11,10 +12,13 @@ use crate::has_cv_params;
 pub struct Analyzer {
     #[serde(rename = "@order")]
     pub order: usize,
-    #[serde(default, rename = "cvParam")]
-    pub cv_params: Vec<CvParam>,
     #[serde(default, rename = "referenceableParamGroupRef")]
     pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
+    #[serde(default, rename = "cvParam")]
+    pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
     #[serde(default, rename = "userParam")]
     pub user_params: Vec<UserParam>,
 }
@@ -26,9 +30,21 @@ impl IsElement for Analyzer {
     }
 }
 
+impl HasReferenceableParamGroupRefs for Analyzer {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        Ok(())
+    }
+}
+
 has_cv_params! {
     Analyzer,
     cv_params,
+    referenced_cv_params,
     [
         "MS:1000443", // mass analyzer type
     ],
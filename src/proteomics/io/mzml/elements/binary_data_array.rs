@@ -1,15 +1,395 @@
-use std::io::Read;
+use std::io::{Read, Write};
 
 use anyhow::{bail, Result};
 use base64::{prelude::BASE64_STANDARD, Engine};
-use flate2::read::ZlibDecoder;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
 use serde::{Deserialize, Serialize};
 
 use super::{
-    binary::Binary, cv_param::CvParam, is_element::IsElement,
+    binary::Binary, cv_param::CvParam,
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    referenceable_param_group_list::ReferenceableParamGroupList,
     referenceable_param_group_ref::ReferenceableParamGroupRef, user_param::UserParam,
 };
 use crate::has_cv_params;
+use crate::proteomics::io::mzml::numpress;
+
+/// PSI ontology accession for zlib compression
+const ZLIB_COMPRESSION_ACCESSION: &str = "MS:1000574";
+
+/// PSI ontology accession for no compression
+const NO_COMPRESSION_ACCESSION: &str = "MS:1000576";
+
+/// PSI ontology accession for MS-Numpress linear prediction compression
+const NUMPRESS_LINEAR_ACCESSION: &str = "MS:1002312";
+
+/// PSI ontology accession for MS-Numpress positive integer compression
+const NUMPRESS_PIC_ACCESSION: &str = "MS:1002313";
+
+/// PSI ontology accession for MS-Numpress short logged float compression
+const NUMPRESS_SLOF_ACCESSION: &str = "MS:1002314";
+
+/// PSI ontology accession for MS-Numpress linear prediction compression followed by zlib compression
+const NUMPRESS_LINEAR_ZLIB_ACCESSION: &str = "MS:1002746";
+
+/// PSI ontology accession for MS-Numpress positive integer compression followed by zlib compression
+const NUMPRESS_PIC_ZLIB_ACCESSION: &str = "MS:1002747";
+
+/// PSI ontology accession for MS-Numpress short logged float compression followed by zlib compression
+const NUMPRESS_SLOF_ZLIB_ACCESSION: &str = "MS:1002748";
+
+/// PSI ontology accession for 32-bit float
+const FLOAT_32_ACCESSION: &str = "MS:1000521";
+
+/// PSI ontology accession for 64-bit float
+const FLOAT_64_ACCESSION: &str = "MS:1000523";
+
+/// PSI ontology accession for 32-bit integer
+const INTEGER_32_ACCESSION: &str = "MS:1000519";
+
+/// PSI ontology accession for 64-bit integer
+const INTEGER_64_ACCESSION: &str = "MS:1000522";
+
+/// PSI ontology accession for null-terminated ASCII string
+const NULL_TERMINATED_STRING_ACCESSION: &str = "MS:1001479";
+
+/// PSI ontology accession for non-standard data array, the value is the name of the array
+pub const NON_STANDARD_DATA_ARRAY_ACCESSION: &str = "MS:1000786";
+
+/// Data type of the encoded values
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryDataType {
+    Float32,
+    Float64,
+    Integer32,
+    Integer64,
+    /// Null-terminated ASCII strings
+    String,
+}
+
+impl BinaryDataType {
+    /// Returns all data types
+    ///
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::Float32,
+            Self::Float64,
+            Self::Integer32,
+            Self::Integer64,
+            Self::String,
+        ]
+    }
+
+    /// Returns the PSI ontology accession
+    ///
+    pub fn get_accession(&self) -> &'static str {
+        match self {
+            Self::Float32 => FLOAT_32_ACCESSION,
+            Self::Float64 => FLOAT_64_ACCESSION,
+            Self::Integer32 => INTEGER_32_ACCESSION,
+            Self::Integer64 => INTEGER_64_ACCESSION,
+            Self::String => NULL_TERMINATED_STRING_ACCESSION,
+        }
+    }
+
+    /// Returns the PSI ontology name
+    ///
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Float32 => "32-bit float",
+            Self::Float64 => "64-bit float",
+            Self::Integer32 => "32-bit integer",
+            Self::Integer64 => "64-bit integer",
+            Self::String => "null-terminated ASCII string",
+        }
+    }
+
+    /// Returns the data type with the given accession
+    ///
+    /// # Arguments
+    /// * `accession` - PSI ontology accession
+    ///
+    pub fn from_accession(accession: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .find(|data_type| data_type.get_accession() == accession)
+            .copied()
+    }
+}
+
+/// Decoded values of a binary data array in their original data type
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum BinaryData {
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+    Integer32(Vec<i32>),
+    Integer64(Vec<i64>),
+    String(Vec<String>),
+}
+
+impl BinaryData {
+    /// Converts the values to the given data type, integers are rounded
+    ///
+    /// # Arguments
+    /// * `values` - Values
+    /// * `data_type` - Target data type
+    ///
+    pub fn from_f64(values: &[f64], data_type: BinaryDataType) -> Result<Self> {
+        Ok(match data_type {
+            BinaryDataType::Float32 => {
+                Self::Float32(values.iter().map(|value| *value as f32).collect())
+            }
+            BinaryDataType::Float64 => Self::Float64(values.to_vec()),
+            BinaryDataType::Integer32 => {
+                Self::Integer32(values.iter().map(|value| value.round() as i32).collect())
+            }
+            BinaryDataType::Integer64 => {
+                Self::Integer64(values.iter().map(|value| value.round() as i64).collect())
+            }
+            BinaryDataType::String => bail!("Numbers cannot be encoded as strings"),
+        })
+    }
+
+    /// Returns the data type
+    ///
+    pub fn get_data_type(&self) -> BinaryDataType {
+        match self {
+            Self::Float32(_) => BinaryDataType::Float32,
+            Self::Float64(_) => BinaryDataType::Float64,
+            Self::Integer32(_) => BinaryDataType::Integer32,
+            Self::Integer64(_) => BinaryDataType::Integer64,
+            Self::String(_) => BinaryDataType::String,
+        }
+    }
+
+    /// Returns the number of values
+    ///
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Float32(values) => values.len(),
+            Self::Float64(values) => values.len(),
+            Self::Integer32(values) => values.len(),
+            Self::Integer64(values) => values.len(),
+            Self::String(values) => values.len(),
+        }
+    }
+
+    /// Returns true if there are no values
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the values as f64. 64-bit integers beyond 2^53 lose precision.
+    /// Fails for strings.
+    ///
+    pub fn to_f64(&self) -> Result<Vec<f64>> {
+        Ok(match self {
+            Self::Float32(values) => values.iter().map(|value| *value as f64).collect(),
+            Self::Float64(values) => values.clone(),
+            Self::Integer32(values) => values.iter().map(|value| *value as f64).collect(),
+            Self::Integer64(values) => values.iter().map(|value| *value as f64).collect(),
+            Self::String(_) => bail!("Strings cannot be converted to numbers"),
+        })
+    }
+
+    /// Returns the little endian bytes, strings are null-terminated
+    ///
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(match self {
+            Self::Float32(values) => values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+            Self::Float64(values) => values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+            Self::Integer32(values) => values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+            Self::Integer64(values) => values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+            Self::String(values) => {
+                let mut bytes: Vec<u8> = Vec::new();
+                for value in values.iter() {
+                    if !value.is_ascii() || value.contains('\0') {
+                        bail!("`{}` is not a valid null-terminated ASCII string", value);
+                    }
+                    bytes.extend_from_slice(value.as_bytes());
+                    bytes.push(0);
+                }
+                bytes
+            }
+        })
+    }
+
+    /// Parses little endian bytes
+    ///
+    /// # Arguments
+    /// * `bytes` - Bytes
+    /// * `data_type` - Data type of the bytes
+    ///
+    fn from_bytes(bytes: &[u8], data_type: BinaryDataType) -> Result<Self> {
+        let value_size = match data_type {
+            BinaryDataType::Float32 | BinaryDataType::Integer32 => 4,
+            BinaryDataType::Float64 | BinaryDataType::Integer64 => 8,
+            BinaryDataType::String => 1,
+        };
+        if !bytes.len().is_multiple_of(value_size) {
+            bail!(
+                "Uncompressed data array is not a multiple of {}",
+                value_size
+            );
+        }
+        let chunks = bytes.chunks(value_size);
+        Ok(match data_type {
+            BinaryDataType::Float32 => Self::Float32(
+                chunks
+                    .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+                    .collect(),
+            ),
+            BinaryDataType::Float64 => Self::Float64(
+                chunks
+                    .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()))
+                    .collect(),
+            ),
+            BinaryDataType::Integer32 => Self::Integer32(
+                chunks
+                    .map(|chunk| i32::from_le_bytes(chunk.try_into().unwrap()))
+                    .collect(),
+            ),
+            BinaryDataType::Integer64 => Self::Integer64(
+                chunks
+                    .map(|chunk| i64::from_le_bytes(chunk.try_into().unwrap()))
+                    .collect(),
+            ),
+            BinaryDataType::String => {
+                // the last string may miss its terminator
+                let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+                if bytes.is_empty() {
+                    return Ok(Self::String(Vec::new()));
+                }
+                Self::String(
+                    bytes
+                        .split(|byte| *byte == 0)
+                        .map(|string| Ok(String::from_utf8(string.to_vec())?))
+                        .collect::<Result<Vec<String>>>()?,
+                )
+            }
+        })
+    }
+}
+
+/// Compression of the encoded values.
+/// MS-Numpress encodes the values itself, so the data type is only informative,
+/// see [crate::proteomics::io::mzml::numpress].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryCompression {
+    Zlib,
+    None,
+    /// MS-Numpress linear prediction, e.g. for m/z or retention times
+    NumpressLinear,
+    /// MS-Numpress positive integer, e.g. for ion counts
+    NumpressPic,
+    /// MS-Numpress short logged float, e.g. for intensities
+    NumpressSlof,
+    NumpressLinearZlib,
+    NumpressPicZlib,
+    NumpressSlofZlib,
+}
+
+impl BinaryCompression {
+    /// Returns all compressions
+    ///
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::Zlib,
+            Self::None,
+            Self::NumpressLinear,
+            Self::NumpressPic,
+            Self::NumpressSlof,
+            Self::NumpressLinearZlib,
+            Self::NumpressPicZlib,
+            Self::NumpressSlofZlib,
+        ]
+    }
+
+    /// Returns the PSI ontology accession
+    ///
+    pub fn get_accession(&self) -> &'static str {
+        match self {
+            Self::Zlib => ZLIB_COMPRESSION_ACCESSION,
+            Self::None => NO_COMPRESSION_ACCESSION,
+            Self::NumpressLinear => NUMPRESS_LINEAR_ACCESSION,
+            Self::NumpressPic => NUMPRESS_PIC_ACCESSION,
+            Self::NumpressSlof => NUMPRESS_SLOF_ACCESSION,
+            Self::NumpressLinearZlib => NUMPRESS_LINEAR_ZLIB_ACCESSION,
+            Self::NumpressPicZlib => NUMPRESS_PIC_ZLIB_ACCESSION,
+            Self::NumpressSlofZlib => NUMPRESS_SLOF_ZLIB_ACCESSION,
+        }
+    }
+
+    /// Returns the PSI ontology name
+    ///
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Zlib => "zlib compression",
+            Self::None => "no compression",
+            Self::NumpressLinear => "MS-Numpress linear prediction compression",
+            Self::NumpressPic => "MS-Numpress positive integer compression",
+            Self::NumpressSlof => "MS-Numpress short logged float compression",
+            Self::NumpressLinearZlib => {
+                "MS-Numpress linear prediction compression followed by zlib compression"
+            }
+            Self::NumpressPicZlib => {
+                "MS-Numpress positive integer compression followed by zlib compression"
+            }
+            Self::NumpressSlofZlib => {
+                "MS-Numpress short logged float compression followed by zlib compression"
+            }
+        }
+    }
+
+    /// Returns true if the (MS-Numpress encoded) data is zlib compressed
+    ///
+    pub fn is_zlib(&self) -> bool {
+        matches!(
+            self,
+            Self::Zlib | Self::NumpressLinearZlib | Self::NumpressPicZlib | Self::NumpressSlofZlib
+        )
+    }
+
+    /// Returns the MS-Numpress compression followed by zlib compression
+    /// or `None` if this is not an MS-Numpress compression without zlib
+    ///
+    pub fn with_zlib(&self) -> Option<Self> {
+        match self {
+            Self::NumpressLinear => Some(Self::NumpressLinearZlib),
+            Self::NumpressPic => Some(Self::NumpressPicZlib),
+            Self::NumpressSlof => Some(Self::NumpressSlofZlib),
+            _ => None,
+        }
+    }
+
+    /// Returns the compression with the given accession
+    ///
+    /// # Arguments
+    /// * `accession` - PSI ontology accession
+    ///
+    pub fn from_accession(accession: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .find(|compression| compression.get_accession() == accession)
+            .copied()
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BinaryDataArray {
@@ -23,6 +403,9 @@ pub struct BinaryDataArray {
     pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
     #[serde(default, rename = "cvParam")]
     pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
     #[serde(default, rename = "userParam")]
     pub user_params: Vec<UserParam>,
     #[serde(rename = "binary")]
@@ -30,61 +413,211 @@ pub struct BinaryDataArray {
 }
 
 impl BinaryDataArray {
-    pub fn deflate_data(&self) -> Result<Vec<f64>> {
-        let compression_cv_param = self.cv_params.iter().find(|cv_param| {
-            cv_param.accession == "MS:1000574" || cv_param.accession == "MS:1000576"
-        });
+    /// Creates a new binary data array with the encoded values
+    ///
+    /// # Arguments
+    /// * `values` - Values to encode
+    /// * `array_type` - cvParam of the array type, e.g. `MS:1000514` (m/z array) including the unit
+    /// * `data_type` - Data type of the encoded values
+    /// * `compression` - Compression of the encoded values
+    ///
+    pub fn new(
+        values: &[f64],
+        array_type: CvParam,
+        data_type: BinaryDataType,
+        compression: BinaryCompression,
+    ) -> Result<Self> {
+        let mut binary_data_array = Self {
+            encoded_length: 0,
+            array_length: None,
+            data_processing_ref: None,
+            referenceable_param_group_ref: Vec::new(),
+            cv_params: vec![array_type],
+            referenced_cv_params: Vec::new(),
+            user_params: Vec::new(),
+            binary: Binary {
+                data: String::new(),
+            },
+        };
+        binary_data_array.encode_data(values, data_type, compression)?;
+        Ok(binary_data_array)
+    }
 
-        if compression_cv_param.is_none() {
-            bail!("Compression cvParam not found");
-        }
+    /// Replaces the data with the encoded values and updates
+    /// the lengths and the cvParams for data type and compression.
+    /// The array type is kept.
+    ///
+    /// # Arguments
+    /// * `values` - Values to encode
+    /// * `data_type` - Data type of the encoded values
+    /// * `compression` - Compression of the encoded values
+    ///
+    pub fn encode_data(
+        &mut self,
+        values: &[f64],
+        data_type: BinaryDataType,
+        compression: BinaryCompression,
+    ) -> Result<()> {
+        self.encode_typed_data(&BinaryData::from_f64(values, data_type)?, compression)
+    }
 
-        let compression_cv_param = compression_cv_param.unwrap();
+    /// Replaces the data with the encoded typed values and updates
+    /// the lengths and the cvParams for data type and compression.
+    /// MS-Numpress only supports numbers.
+    ///
+    /// # Arguments
+    /// * `data` - Values to encode
+    /// * `compression` - Compression of the encoded values
+    ///
+    pub fn encode_typed_data(
+        &mut self,
+        data: &BinaryData,
+        compression: BinaryCompression,
+    ) -> Result<()> {
+        let uncompressed_data: Vec<u8> = match compression {
+            BinaryCompression::NumpressLinear | BinaryCompression::NumpressLinearZlib => {
+                let values = data.to_f64()?;
+                numpress::encode_linear(&values, numpress::optimal_linear_fixed_point(&values))?
+            }
+            BinaryCompression::NumpressPic | BinaryCompression::NumpressPicZlib => {
+                numpress::encode_pic(&data.to_f64()?)?
+            }
+            BinaryCompression::NumpressSlof | BinaryCompression::NumpressSlofZlib => {
+                let values = data.to_f64()?;
+                numpress::encode_slof(&values, numpress::optimal_slof_fixed_point(&values))?
+            }
+            BinaryCompression::Zlib | BinaryCompression::None => data.to_bytes()?,
+        };
 
-        let uncompressed_data = match compression_cv_param.accession.as_str() {
-            "MS:1000574" => {
-                let decoded_data = BASE64_STANDARD.decode(&self.binary.data)?;
-                let mut deflated_buffer: Vec<u8> = Vec::new();
-                let mut decoder = ZlibDecoder::new(decoded_data.as_slice());
-                decoder.read_to_end(&mut deflated_buffer)?;
-                deflated_buffer
+        let compressed_data = match compression.is_zlib() {
+            true => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&uncompressed_data)?;
+                encoder.finish()?
             }
-            "MS:1000576" => BASE64_STANDARD.decode(&self.binary.data)?,
-            _ => bail!("Unknown compression cvParam"),
+            false => uncompressed_data,
         };
 
-        let type_cv_param = self.cv_params.iter().find(|cv_param| {
-            cv_param.accession == "MS:1000521" || cv_param.accession == "MS:1000523"
+        self.binary.data = BASE64_STANDARD.encode(compressed_data);
+        self.encoded_length = self.binary.data.len();
+        self.array_length = Some(data.len());
+
+        self.cv_params.retain(|cv_param| {
+            BinaryDataType::from_accession(&cv_param.accession).is_none()
+                && BinaryCompression::from_accession(&cv_param.accession).is_none()
         });
+        let data_type = data.get_data_type();
+        for (accession, name) in [
+            (data_type.get_accession(), data_type.get_name()),
+            (compression.get_accession(), compression.get_name()),
+        ] {
+            self.cv_params.push(CvParam {
+                cv_ref: "MS".to_string(),
+                accession: accession.to_string(),
+                name: name.to_string(),
+                value: String::new(),
+                unit_cv_ref: None,
+                unit_accession: None,
+                unit_name: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the data type given by the cvParams
+    ///
+    pub fn get_data_type(&self) -> Result<BinaryDataType> {
+        match self
+            .iter_all_cv_params()
+            .find_map(|cv_param| BinaryDataType::from_accession(&cv_param.accession))
+        {
+            Some(data_type) => Ok(data_type),
+            None => bail!("Data type cvParam not found"),
+        }
+    }
 
-        if type_cv_param.is_none() {
-            bail!("Data type cvParam not found");
+    /// Returns the compression of the encoded values.
+    /// MS-Numpress followed by zlib compression may also be annotated
+    /// by separate MS-Numpress and zlib compression cvParams.
+    ///
+    pub fn get_compression(&self) -> Result<BinaryCompression> {
+        let compressions: Vec<BinaryCompression> = self
+            .iter_all_cv_params()
+            .filter_map(|cv_param| BinaryCompression::from_accession(&cv_param.accession))
+            .collect();
+        match compressions.as_slice() {
+            [] => bail!("Compression cvParam not found"),
+            [compression] => Ok(*compression),
+            [BinaryCompression::Zlib, numpress] | [numpress, BinaryCompression::Zlib]
+                if numpress.with_zlib().is_some() =>
+            {
+                Ok(numpress.with_zlib().unwrap())
+            }
+            _ => bail!(
+                "Unsupported combination of compression cvParams: {}",
+                compressions
+                    .iter()
+                    .map(|compression| compression.get_accession())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
+    }
 
-        let type_cv_param = type_cv_param.unwrap();
+    /// Returns the name of a non-standard data array (MS:1000786)
+    ///
+    pub fn get_non_standard_name(&self) -> Option<&str> {
+        self.iter_all_cv_params()
+            .find(|cv_param| cv_param.accession == NON_STANDARD_DATA_ARRAY_ACCESSION)
+            .map(|cv_param| cv_param.value.as_str())
+    }
 
-        match type_cv_param.accession.as_str() {
-            "MS:1000521" => {
-                if uncompressed_data.len() % 4 != 0 {
-                    bail!("Uncompressed data array is not a multiple of 4");
-                }
-                Ok(uncompressed_data
-                    .chunks(4)
-                    .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()) as f64)
-                    .collect())
-            }
-            "MS:1000523" => {
-                if uncompressed_data.len() % 8 != 0 {
-                    bail!("Uncompressed data array is not a multiple of 8");
-                }
-                Ok(uncompressed_data
-                    .chunks(8)
-                    .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()))
-                    .collect())
+    /// Decodes the data in its original data type, i.e. base64 decoding,
+    /// decompression (including MS-Numpress which always results in f64) and parsing of the values
+    ///
+    pub fn decode_typed_data(&self) -> Result<BinaryData> {
+        let compression = self.get_compression()?;
+
+        let decoded_data = BASE64_STANDARD.decode(&self.binary.data)?;
+        let uncompressed_data = match compression.is_zlib() {
+            true => {
+                let mut inflated_buffer: Vec<u8> = Vec::new();
+                let mut decoder = ZlibDecoder::new(decoded_data.as_slice());
+                decoder.read_to_end(&mut inflated_buffer)?;
+                inflated_buffer
+            }
+            false => decoded_data,
+        };
+
+        match compression {
+            BinaryCompression::NumpressLinear | BinaryCompression::NumpressLinearZlib => Ok(
+                BinaryData::Float64(numpress::decode_linear(&uncompressed_data)?),
+            ),
+            BinaryCompression::NumpressPic | BinaryCompression::NumpressPicZlib => Ok(
+                BinaryData::Float64(numpress::decode_pic(&uncompressed_data)?),
+            ),
+            BinaryCompression::NumpressSlof | BinaryCompression::NumpressSlofZlib => Ok(
+                BinaryData::Float64(numpress::decode_slof(&uncompressed_data)?),
+            ),
+            BinaryCompression::Zlib | BinaryCompression::None => {
+                BinaryData::from_bytes(&uncompressed_data, self.get_data_type()?)
             }
-            _ => bail!("Unknown data type cvParam"),
         }
     }
+
+    /// Decodes the data, i.e. base64 decoding, decompression (including MS-Numpress) and conversion to f64.
+    /// Fails for string arrays, use [BinaryDataArray::decode_typed_data] instead.
+    ///
+    pub fn decode_data(&self) -> Result<Vec<f64>> {
+        self.decode_typed_data()?.to_f64()
+    }
+
+    /// Decodes the data, kept for compatibility, despite the name it inflates the data.
+    ///
+    #[deprecated(note = "Use `decode_data` instead")]
+    pub fn deflate_data(&self) -> Result<Vec<f64>> {
+        self.decode_data()
+    }
 }
 
 impl IsElement for BinaryDataArray {
@@ -97,15 +630,326 @@ impl IsElement for BinaryDataArray {
     }
 }
 
+impl HasReferenceableParamGroupRefs for BinaryDataArray {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        Ok(())
+    }
+}
+
 has_cv_params! {
     BinaryDataArray,
     cv_params,
+    referenced_cv_params,
     [
-        "MS:1000572", // binary data compression type
         "MS:1000513", // binary data array
         "MS:1000518", // binary data type
     ],
-    [],
+    [
+        "MS:1000572", // binary data compression type, MS-Numpress and zlib may be given separately
+    ],
     [],
     []
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns the cvParam for a m/z array
+    ///
+    fn get_mz_array_cv_param() -> CvParam {
+        CvParam {
+            cv_ref: "MS".to_string(),
+            accession: "MS:1000514".to_string(),
+            name: "m/z array".to_string(),
+            value: String::new(),
+            unit_cv_ref: Some("MS".to_string()),
+            unit_accession: Some("MS:1000040".to_string()),
+            unit_name: Some("m/z".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let values = vec![100.0, 250.125, 1234.5678901234, 0.0];
+        for data_type in [BinaryDataType::Float32, BinaryDataType::Float64].iter() {
+            for compression in [BinaryCompression::Zlib, BinaryCompression::None].iter() {
+                let binary_data_array = BinaryDataArray::new(
+                    &values,
+                    get_mz_array_cv_param(),
+                    *data_type,
+                    *compression,
+                )
+                .unwrap();
+                assert_eq!(binary_data_array.array_length, Some(values.len()));
+                assert_eq!(
+                    binary_data_array.encoded_length,
+                    binary_data_array.binary.data.len()
+                );
+                let accessions: Vec<&str> = binary_data_array
+                    .cv_params
+                    .iter()
+                    .map(|cv_param| cv_param.accession.as_str())
+                    .collect();
+                assert_eq!(
+                    accessions,
+                    vec![
+                        "MS:1000514",
+                        data_type.get_accession(),
+                        compression.get_accession()
+                    ]
+                );
+                let decoded = binary_data_array.decode_data().unwrap();
+                if *data_type == BinaryDataType::Float32 {
+                    for (decoded_value, value) in decoded.iter().zip(values.iter()) {
+                        assert_eq!(*decoded_value, *value as f32 as f64);
+                    }
+                } else {
+                    assert_eq!(decoded, values);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_numpress() {
+        let values = vec![100.0, 200.0, 300.00005, 400.0001];
+        for compression in [
+            BinaryCompression::NumpressLinear,
+            BinaryCompression::NumpressLinearZlib,
+        ] {
+            let binary_data_array = BinaryDataArray::new(
+                &values,
+                get_mz_array_cv_param(),
+                BinaryDataType::Float64,
+                compression,
+            )
+            .unwrap();
+            for (decoded_value, value) in binary_data_array
+                .decode_data()
+                .unwrap()
+                .iter()
+                .zip(values.iter())
+            {
+                assert!((decoded_value - value).abs() < 1e-6);
+            }
+        }
+        for compression in [
+            BinaryCompression::NumpressPic,
+            BinaryCompression::NumpressPicZlib,
+        ] {
+            let binary_data_array = BinaryDataArray::new(
+                &values,
+                get_mz_array_cv_param(),
+                BinaryDataType::Float32,
+                compression,
+            )
+            .unwrap();
+            assert_eq!(
+                binary_data_array.decode_data().unwrap(),
+                vec![100.0, 200.0, 300.0, 400.0]
+            );
+        }
+        for compression in [
+            BinaryCompression::NumpressSlof,
+            BinaryCompression::NumpressSlofZlib,
+        ] {
+            let binary_data_array = BinaryDataArray::new(
+                &values,
+                get_mz_array_cv_param(),
+                BinaryDataType::Float32,
+                compression,
+            )
+            .unwrap();
+            for (decoded_value, value) in binary_data_array
+                .decode_data()
+                .unwrap()
+                .iter()
+                .zip(values.iter())
+            {
+                assert!((decoded_value - value).abs() < value * 1e-3);
+            }
+        }
+
+        // reference encoding of 100, 200, 300.00005 and 400.0001 with fixed point 100000
+        let mut binary_data_array = BinaryDataArray::new(
+            &[],
+            get_mz_array_cv_param(),
+            BinaryDataType::Float64,
+            BinaryCompression::NumpressLinear,
+        )
+        .unwrap();
+        let mut encoded: Vec<u8> = 100000.0_f64.to_be_bytes().to_vec();
+        encoded.extend_from_slice(&[0x80, 0x96, 0x98, 0x00, 0x00, 0x2d, 0x31, 0x01, 0x75, 0x80]);
+        binary_data_array.binary.data = BASE64_STANDARD.encode(encoded);
+        assert_eq!(
+            binary_data_array.decode_data().unwrap(),
+            vec![100.0, 200.0, 300.00005, 400.0001]
+        );
+    }
+
+    #[test]
+    fn test_numpress_with_separate_zlib_cv_param() {
+        let xml = std::fs::read_to_string(
+            "test_files/numpress_zlib_separate_cv_params.binaryDataArray.xml",
+        )
+        .unwrap();
+        let mut binary_data_array = quick_xml::de::from_str::<BinaryDataArray>(&xml).unwrap();
+        assert_eq!(
+            binary_data_array.get_compression().unwrap(),
+            BinaryCompression::NumpressLinearZlib
+        );
+        for (decoded_value, value) in binary_data_array
+            .decode_data()
+            .unwrap()
+            .iter()
+            .zip([100.0, 200.0, 300.00005, 400.0001].iter())
+        {
+            assert!((decoded_value - value).abs() < 1e-6);
+        }
+
+        // zlib only applies to MS-Numpress
+        binary_data_array
+            .cv_params
+            .retain(|cv_param| cv_param.accession != NUMPRESS_LINEAR_ACCESSION);
+        binary_data_array.cv_params.push(CvParam {
+            cv_ref: "MS".to_string(),
+            accession: NO_COMPRESSION_ACCESSION.to_string(),
+            name: "no compression".to_string(),
+            value: String::new(),
+            unit_cv_ref: None,
+            unit_accession: None,
+            unit_name: None,
+        });
+        assert!(binary_data_array.get_compression().is_err());
+    }
+
+    #[test]
+    fn test_typed_data() {
+        let typed_data = [
+            BinaryData::Float32(vec![1.5, -2.25]),
+            BinaryData::Float64(vec![1.0e-300, 2.5]),
+            BinaryData::Integer32(vec![1, -2, i32::MAX]),
+            BinaryData::Integer64(vec![1, -2, i64::MAX]),
+            BinaryData::String(vec!["b1".to_string(), String::new(), "y2".to_string()]),
+        ];
+        let mut binary_data_array = BinaryDataArray::new(
+            &[],
+            get_mz_array_cv_param(),
+            BinaryDataType::Float64,
+            BinaryCompression::None,
+        )
+        .unwrap();
+        for data in typed_data.iter() {
+            for compression in [BinaryCompression::Zlib, BinaryCompression::None] {
+                binary_data_array
+                    .encode_typed_data(data, compression)
+                    .unwrap();
+                assert_eq!(
+                    binary_data_array.get_data_type().unwrap(),
+                    data.get_data_type()
+                );
+                assert_eq!(binary_data_array.array_length, Some(data.len()));
+                assert_eq!(&binary_data_array.decode_typed_data().unwrap(), data);
+            }
+        }
+        // lossless 64 bit integers
+        binary_data_array
+            .encode_typed_data(
+                &BinaryData::Integer64(vec![i64::MAX - 1]),
+                BinaryCompression::Zlib,
+            )
+            .unwrap();
+        assert_eq!(
+            binary_data_array.decode_typed_data().unwrap(),
+            BinaryData::Integer64(vec![i64::MAX - 1])
+        );
+        // strings are not numbers
+        binary_data_array
+            .encode_typed_data(&typed_data[4], BinaryCompression::None)
+            .unwrap();
+        assert!(binary_data_array.decode_data().is_err());
+        assert!(binary_data_array
+            .encode_typed_data(&typed_data[4], BinaryCompression::NumpressPic)
+            .is_err());
+        // integers from f64 are rounded
+        binary_data_array
+            .encode_data(
+                &[1.4, 2.6],
+                BinaryDataType::Integer32,
+                BinaryCompression::None,
+            )
+            .unwrap();
+        assert_eq!(
+            binary_data_array.decode_typed_data().unwrap(),
+            BinaryData::Integer32(vec![1, 3])
+        );
+    }
+
+    #[test]
+    fn test_encode_replaces_data() {
+        let mut binary_data_array = BinaryDataArray::new(
+            &[1.0, 2.0],
+            get_mz_array_cv_param(),
+            BinaryDataType::Float32,
+            BinaryCompression::Zlib,
+        )
+        .unwrap();
+        binary_data_array
+            .encode_data(&[3.0], BinaryDataType::Float64, BinaryCompression::None)
+            .unwrap();
+        assert_eq!(binary_data_array.cv_params.len(), 3);
+        assert_eq!(binary_data_array.array_length, Some(1));
+        // 8 bytes are 12 base64 characters
+        assert_eq!(binary_data_array.encoded_length, 12);
+        assert_eq!(binary_data_array.decode_data().unwrap(), vec![3.0]);
+    }
+
+    #[test]
+    fn test_resolve_referenceable_param_groups() {
+        let referenceable_param_group_list: ReferenceableParamGroupList = quick_xml::de::from_str(
+            r#"<referenceableParamGroupList count="1">
+                <referenceableParamGroup id="mzArray">
+                    <cvParam cvRef="MS" accession="MS:1000523" name="64-bit float" value=""/>
+                    <cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>
+                </referenceableParamGroup>
+            </referenceableParamGroupList>"#,
+        )
+        .unwrap();
+        let mut binary_data_array: BinaryDataArray = quick_xml::de::from_str(
+            r#"<binaryDataArray encodedLength="24">
+                <referenceableParamGroupRef ref="mzArray"/>
+                <cvParam cvRef="MS" accession="MS:1000514" name="m/z array" value=""/>
+                <binary>AAAAAAAAWUAAAAAAAABpQA==</binary>
+            </binaryDataArray>"#,
+        )
+        .unwrap();
+        assert!(binary_data_array.decode_data().is_err());
+
+        binary_data_array
+            .resolve_referenceable_param_groups(&referenceable_param_group_list)
+            .unwrap();
+        assert_eq!(
+            binary_data_array.get_data_type().unwrap(),
+            BinaryDataType::Float64
+        );
+        assert_eq!(binary_data_array.decode_data().unwrap(), vec![100.0, 200.0]);
+
+        // referenced cvParams are not serialized
+        let xml =
+            quick_xml::se::to_string_with_root("binaryDataArray", &binary_data_array).unwrap();
+        assert!(xml.contains(r#"<referenceableParamGroupRef ref="mzArray"/>"#));
+        assert!(!xml.contains("MS:1000523"));
+
+        let mut unknown_ref = binary_data_array.clone();
+        unknown_ref.referenceable_param_group_ref[0].r#ref = "unknown".to_string();
+        assert!(unknown_ref
+            .resolve_referenceable_param_groups(&referenceable_param_group_list)
+            .is_err());
+    }
+}
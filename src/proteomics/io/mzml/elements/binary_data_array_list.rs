@@ -1,7 +1,30 @@
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use super::{binary_data_array::BinaryDataArray, is_element::IsElement, is_list::IsList};
+use super::{
+    binary_data_array::{BinaryDataArray, NON_STANDARD_DATA_ARRAY_ACCESSION},
+    has_cv_params::HasCvParams,
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
+    is_element::IsElement,
+    is_list::IsList,
+    referenceable_param_group_list::ReferenceableParamGroupList,
+};
+
+/// Accessions of binary data arrays containing ion mobility values, e.g.
+/// mean inverse reduced ion mobility array (MS:1003008), mean ion mobility array (MS:1002816)
+/// or the drift time arrays
+///
+pub const ION_MOBILITY_ARRAY_ACCESSIONS: [&str; 9] = [
+    "MS:1002816",
+    "MS:1002893",
+    "MS:1003006",
+    "MS:1003007",
+    "MS:1003008",
+    "MS:1003153",
+    "MS:1003154",
+    "MS:1003155",
+    "MS:1003156",
+];
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BinaryDataArrayList {
@@ -12,13 +35,15 @@ pub struct BinaryDataArrayList {
 }
 
 impl BinaryDataArrayList {
+    /// Returns the binary data array with the given array type accession, e.g. MS:1000516 (charge array).
+    /// The array type may also be given by a referenced referenceableParamGroup.
+    ///
+    /// # Arguments
+    /// * `accession` - Accession of the array type
+    ///
     pub fn get_binary_data_array(&self, accession: &str) -> Result<&BinaryDataArray> {
         for binary_data_array in &self.binary_data_arrays {
-            if binary_data_array
-                .cv_params
-                .iter()
-                .any(|cv_param| cv_param.accession == accession)
-            {
+            if !binary_data_array.get_cv_param(accession).is_empty() {
                 return Ok(binary_data_array);
             }
         }
@@ -32,6 +57,51 @@ impl BinaryDataArrayList {
     pub fn get_intensity_array(&self) -> Result<&BinaryDataArray> {
         self.get_binary_data_array("MS:1000515")
     }
+
+    pub fn get_time_array(&self) -> Result<&BinaryDataArray> {
+        self.get_binary_data_array("MS:1000595")
+    }
+
+    pub fn get_charge_array(&self) -> Result<&BinaryDataArray> {
+        self.get_binary_data_array("MS:1000516")
+    }
+
+    pub fn get_signal_to_noise_array(&self) -> Result<&BinaryDataArray> {
+        self.get_binary_data_array("MS:1000517")
+    }
+
+    pub fn get_resolution_array(&self) -> Result<&BinaryDataArray> {
+        self.get_binary_data_array("MS:1002529")
+    }
+
+    /// Returns the non-standard data array (MS:1000786) with the given name
+    ///
+    /// # Arguments
+    /// * `name` - Name of the array, the value of the cvParam
+    ///
+    pub fn get_non_standard_data_array(&self, name: &str) -> Result<&BinaryDataArray> {
+        match self
+            .binary_data_arrays
+            .iter()
+            .find(|binary_data_array| binary_data_array.get_non_standard_name() == Some(name))
+        {
+            Some(binary_data_array) => Ok(binary_data_array),
+            None => bail!(
+                "No binaryDataArray found with accession {} and name: {}",
+                NON_STANDARD_DATA_ARRAY_ACCESSION,
+                name
+            ),
+        }
+    }
+
+    pub fn get_ion_mobility_array(&self) -> Result<&BinaryDataArray> {
+        for accession in ION_MOBILITY_ARRAY_ACCESSIONS {
+            if let Ok(binary_data_array) = self.get_binary_data_array(accession) {
+                return Ok(binary_data_array);
+            }
+        }
+        bail!("No ion mobility binaryDataArray found");
+    }
 }
 
 impl IsElement for BinaryDataArrayList {
@@ -53,8 +123,140 @@ impl IsElement for BinaryDataArrayList {
     }
 }
 
+impl HasReferenceableParamGroupRefs for BinaryDataArrayList {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        for binary_data_array in self.binary_data_arrays.iter_mut() {
+            binary_data_array.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
+
 impl IsList<'_, BinaryDataArray> for BinaryDataArrayList {
     fn iter(&self) -> std::slice::Iter<'_, BinaryDataArray> {
         self.binary_data_arrays.iter()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proteomics::io::mzml::elements::{
+        binary_data_array::{BinaryCompression, BinaryData, BinaryDataType},
+        cv_param::CvParam,
+    };
+
+    /// Returns a cvParam without unit
+    ///
+    fn create_cv_param(accession: &str, name: &str, value: &str) -> CvParam {
+        CvParam {
+            cv_ref: "MS".to_string(),
+            accession: accession.to_string(),
+            name: name.to_string(),
+            value: value.to_string(),
+            unit_cv_ref: None,
+            unit_accession: None,
+            unit_name: None,
+        }
+    }
+
+    #[test]
+    fn test_get_binary_data_array() {
+        let mut annotation_array = BinaryDataArray::new(
+            &[],
+            create_cv_param("MS:1000786", "non-standard data array", "annotation"),
+            BinaryDataType::Float64,
+            BinaryCompression::None,
+        )
+        .unwrap();
+        annotation_array
+            .encode_typed_data(
+                &BinaryData::String(vec!["b2".to_string(), "y1".to_string()]),
+                BinaryCompression::Zlib,
+            )
+            .unwrap();
+        let list = BinaryDataArrayList {
+            count: 3,
+            binary_data_arrays: vec![
+                BinaryDataArray::new(
+                    &[100.0, 200.0],
+                    create_cv_param("MS:1000514", "m/z array", ""),
+                    BinaryDataType::Float64,
+                    BinaryCompression::Zlib,
+                )
+                .unwrap(),
+                BinaryDataArray::new(
+                    &[2.0, 3.0],
+                    create_cv_param("MS:1000516", "charge array", ""),
+                    BinaryDataType::Integer32,
+                    BinaryCompression::None,
+                )
+                .unwrap(),
+                annotation_array,
+            ],
+        };
+        assert_eq!(
+            list.get_charge_array()
+                .unwrap()
+                .decode_typed_data()
+                .unwrap(),
+            BinaryData::Integer32(vec![2, 3])
+        );
+        assert_eq!(
+            list.get_non_standard_data_array("annotation")
+                .unwrap()
+                .decode_typed_data()
+                .unwrap(),
+            BinaryData::String(vec!["b2".to_string(), "y1".to_string()])
+        );
+        assert!(list.get_non_standard_data_array("unknown").is_err());
+        assert!(list.get_intensity_array().is_err());
+    }
+
+    #[test]
+    fn test_get_referenced_binary_data_array() {
+        let referenceable_param_group_list: ReferenceableParamGroupList = quick_xml::de::from_str(
+            r#"<referenceableParamGroupList count="2">
+                <referenceableParamGroup id="mzArray">
+                    <cvParam cvRef="MS" accession="MS:1000514" name="m/z array" value="" unitCvRef="MS" unitAccession="MS:1000040" unitName="m/z"/>
+                    <cvParam cvRef="MS" accession="MS:1000523" name="64-bit float" value=""/>
+                    <cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>
+                </referenceableParamGroup>
+                <referenceableParamGroup id="intensityArray">
+                    <cvParam cvRef="MS" accession="MS:1000515" name="intensity array" value=""/>
+                    <cvParam cvRef="MS" accession="MS:1000521" name="32-bit float" value=""/>
+                    <cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>
+                </referenceableParamGroup>
+            </referenceableParamGroupList>"#,
+        )
+        .unwrap();
+        let mut list: BinaryDataArrayList = quick_xml::de::from_str(
+            r#"<binaryDataArrayList count="2">
+                <binaryDataArray encodedLength="24">
+                    <referenceableParamGroupRef ref="mzArray"/>
+                    <binary>AAAAAAAAWUAAAAAAAABpQA==</binary>
+                </binaryDataArray>
+                <binaryDataArray encodedLength="12">
+                    <referenceableParamGroupRef ref="intensityArray"/>
+                    <binary>AACAPwAAAEA=</binary>
+                </binaryDataArray>
+            </binaryDataArrayList>"#,
+        )
+        .unwrap();
+        assert!(list.get_mz_array().is_err());
+
+        list.resolve_referenceable_param_groups(&referenceable_param_group_list)
+            .unwrap();
+        let mz_array = list.get_mz_array().unwrap();
+        assert_eq!(mz_array.get_data_type().unwrap(), BinaryDataType::Float64);
+        assert_eq!(mz_array.get_compression().unwrap(), BinaryCompression::None);
+        assert_eq!(mz_array.decode_data().unwrap(), vec![100.0, 200.0]);
+        assert_eq!(
+            list.get_intensity_array().unwrap().decode_data().unwrap(),
+            vec![1.0, 2.0]
+        );
+    }
+}
@@ -4,7 +4,11 @@ use serde::{Deserialize, Serialize};
 use crate::has_cv_params;
 
 use super::{
-    binary_data_array_list::BinaryDataArrayList, cv_param::CvParam, is_element::IsElement,
+    binary_data_array_list::BinaryDataArrayList, cv_param::CvParam,
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    precursor::Precursor, product::Product,
+    referenceable_param_group_list::ReferenceableParamGroupList,
+    referenceable_param_group_ref::ReferenceableParamGroupRef, user_param::UserParam,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -15,25 +19,61 @@ pub struct Chromatogram {
     pub id: String,
     #[serde(rename = "@defaultArrayLength")]
     pub default_array_length: String,
+    #[serde(rename = "@dataProcessingRef", skip_serializing_if = "Option::is_none")]
+    pub data_processing_ref: Option<String>,
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
     #[serde(default, rename = "cvParam")]
     pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
+    #[serde(rename = "precursor", skip_serializing_if = "Option::is_none")]
+    pub precursor: Option<Precursor>,
+    #[serde(rename = "product", skip_serializing_if = "Option::is_none")]
+    pub product: Option<Product>,
     #[serde(rename = "binaryDataArrayList")]
-    pub binary_data_array_list: Vec<BinaryDataArrayList>,
+    pub binary_data_array_list: BinaryDataArrayList,
 }
 
 impl IsElement for Chromatogram {
     fn validate(&self) -> Result<()> {
-        for binary_data_array_list in &self.binary_data_array_list {
-            binary_data_array_list.validate()?;
+        if let Some(ref precursor) = self.precursor {
+            precursor.validate()?;
         }
+        if let Some(ref product) = self.product {
+            product.validate()?;
+        }
+        self.binary_data_array_list.validate()?;
         self.validate_cv_params("chromatogram")?;
         Ok(())
     }
 }
 
+impl HasReferenceableParamGroupRefs for Chromatogram {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        if let Some(precursor) = self.precursor.as_mut() {
+            precursor.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        if let Some(product) = self.product.as_mut() {
+            product.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        self.binary_data_array_list
+            .resolve_referenceable_param_groups(referenceable_param_group_list)
+    }
+}
+
 has_cv_params! {
     Chromatogram,
     cv_params,
+    referenced_cv_params,
     [
         "MS:1000626", // chromatogram type
     ],
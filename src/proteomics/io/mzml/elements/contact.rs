@@ -0,0 +1,52 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cv_param::CvParam, has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
+    is_element::IsElement, referenceable_param_group_list::ReferenceableParamGroupList,
+    referenceable_param_group_ref::ReferenceableParamGroupRef, user_param::UserParam,
+};
+use crate::has_cv_params;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Contact {
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
+    #[serde(default, rename = "cvParam")]
+    pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
+}
+
+impl IsElement for Contact {
+    fn validate(&self) -> Result<()> {
+        self.validate_cv_params("contact")?;
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for Contact {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        Ok(())
+    }
+}
+
+has_cv_params! {
+    Contact,
+    cv_params,
+    referenced_cv_params,
+    [],
+    [],
+    [],
+    [
+        "MS:1000585", // contact attribute
+    ]
+}
@@ -9,7 +9,7 @@ pub struct Cv {
     pub id: String,
     #[serde(rename = "@fullName")]
     pub full_name: String,
-    #[serde(rename = "@version")]
+    #[serde(default, rename = "@version", skip_serializing_if = "String::is_empty")]
     pub version: String,
     #[serde(rename = "@URI")]
     pub uri: String,
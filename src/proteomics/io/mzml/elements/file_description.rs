@@ -1,20 +1,30 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use super::{file_content::FileContent, is_element::IsElement, source_file_list::SourceFileList};
+use super::{
+    contact::Contact, file_content::FileContent, is_element::IsElement,
+    source_file_list::SourceFileList,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FileDescription {
     #[serde(rename = "fileContent")]
     pub file_content: FileContent,
-    #[serde(rename = "sourceFileList")]
-    pub source_file_list: SourceFileList,
+    #[serde(rename = "sourceFileList", skip_serializing_if = "Option::is_none")]
+    pub source_file_list: Option<SourceFileList>,
+    #[serde(default, rename = "contact")]
+    pub contacts: Vec<Contact>,
 }
 
 impl IsElement for FileDescription {
     fn validate(&self) -> Result<()> {
         self.file_content.validate()?;
-        self.source_file_list.validate()?;
+        if let Some(ref source_file_list) = self.source_file_list {
+            source_file_list.validate()?;
+        }
+        for contact in &self.contacts {
+            contact.validate()?;
+        }
         Ok(())
     }
 }
@@ -1,4 +1,8 @@
 // std imports
+use std::iter::Chain;
+use std::slice::Iter;
+
+// 3rd party imports
 use anyhow::{bail, Result};
 
 // Local imports
@@ -11,6 +15,21 @@ pub trait HasCvParams {
     ///
     fn get_cv_params(&self) -> &[CvParam];
 
+    /// Returns a reference to the cvParams of the referenced referenceableParamGroups.
+    /// Empty until they are resolved, see [super::has_referenceable_param_group_refs::HasReferenceableParamGroupRefs].
+    ///
+    fn get_referenced_cv_params(&self) -> &[CvParam] {
+        &[]
+    }
+
+    /// Returns an iterator over the element's own cvParams followed by the referenced ones.
+    ///
+    fn iter_all_cv_params(&self) -> Chain<Iter<'_, CvParam>, Iter<'_, CvParam>> {
+        self.get_cv_params()
+            .iter()
+            .chain(self.get_referenced_cv_params().iter())
+    }
+
     /// Returns a list of cvParam accessions from which one child must be present in the element.
     ///
     fn get_parent_accession_for_must_once(&self) -> &'static Vec<&'static str>;
@@ -27,7 +46,7 @@ pub trait HasCvParams {
     ///
     fn get_parent_accession_for_zero_or_many(&self) -> &'static Vec<&'static str>;
 
-    /// Returns a reference to the cvParam with the given accession.
+    /// Returns a reference to the cvParam with the given accession, including referenced ones.
     /// Some accessions like MS:1000633 (possibly charge state can occur multiple times),
     /// therefore a vector is returned.
     ///
@@ -35,8 +54,7 @@ pub trait HasCvParams {
     /// * `accession` - The accession of the cvParam to be retrieved.
     ///
     fn get_cv_param(&self, accession: &str) -> Vec<&CvParam> {
-        self.get_cv_params()
-            .iter()
+        self.iter_all_cv_params()
             .filter(|cv_param| cv_param.accession == accession)
             .collect()
     }
@@ -54,7 +72,7 @@ pub trait HasCvParams {
             let mut child_matches = vec![false; children.len()];
 
             for (child_idx, child) in children.iter().enumerate() {
-                for cv_param in self.get_cv_params().iter() {
+                for cv_param in self.iter_all_cv_params() {
                     if &cv_param.accession == child {
                         child_matches[child_idx] = true;
                     }
@@ -93,7 +111,7 @@ pub trait HasCvParams {
             let mut child_matches = vec![false; children.len()];
 
             for (child_idx, child) in children.iter().enumerate() {
-                for cv_param in self.get_cv_params().iter() {
+                for cv_param in self.iter_all_cv_params() {
                     if &cv_param.accession == child {
                         child_matches[child_idx] = true;
                     }
@@ -127,7 +145,7 @@ pub trait HasCvParams {
             let mut child_matches = vec![false; children.len()];
 
             for (child_idx, child) in children.iter().enumerate() {
-                for cv_param in self.get_cv_params().iter() {
+                for cv_param in self.iter_all_cv_params() {
                     if &cv_param.accession == child {
                         child_matches[child_idx] = true;
                     }
@@ -172,6 +190,7 @@ pub trait HasCvParams {
 /// # Arguments
 /// * `$name` - The name of the struct for which the implementation is being generated.
 /// * `$cv_vec` - The name of the field in the struct that contains the cvParams.
+/// * `$referenced_cv_vec` - Optional, the name of the field containing the cvParams of referenced referenceableParamGroups.
 /// * `$must_once` - Array of cvParam accession from which one child's accession must be present in the list.
 /// * `$must_once_or_many` - Array of cvParam accession from which at least one child's accession must be present in the list.
 /// * `$may_once` - Array of cvParam accession from which none or one child's accession must be present in the list.
@@ -180,6 +199,16 @@ pub trait HasCvParams {
 #[macro_export]
 macro_rules! has_cv_params {
     ($name:ty, $cv_vec:ident, $must_once:tt, $must_once_or_many:tt, $may_once:tt, $may_one_or_many:tt) => {
+        $crate::has_cv_params!(@impl $name, $cv_vec, $must_once, $must_once_or_many, $may_once, $may_one_or_many, {});
+    };
+    ($name:ty, $cv_vec:ident, $referenced_cv_vec:ident, $must_once:tt, $must_once_or_many:tt, $may_once:tt, $may_one_or_many:tt) => {
+        $crate::has_cv_params!(@impl $name, $cv_vec, $must_once, $must_once_or_many, $may_once, $may_one_or_many, {
+            fn get_referenced_cv_params(&self) -> &[CvParam] {
+                self.$referenced_cv_vec.as_ref()
+            }
+        });
+    };
+    (@impl $name:ty, $cv_vec:ident, $must_once:tt, $must_once_or_many:tt, $may_once:tt, $may_one_or_many:tt, { $($referenced:tt)* }) => {
         use $crate::proteomics::io::mzml::elements::has_cv_params::HasCvParams;
 
         lazy_static! {
@@ -209,6 +238,8 @@ macro_rules! has_cv_params {
             fn get_parent_accession_for_zero_or_many(&self) -> &'static Vec<&'static str> {
                 &CV_PARAMS_MAY_ONCE_OR_MANY
             }
+
+            $($referenced)*
         }
     };
 }
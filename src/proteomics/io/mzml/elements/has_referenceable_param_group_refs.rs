@@ -0,0 +1,19 @@
+// 3rd party imports
+use anyhow::Result;
+
+// Local imports
+use super::referenceable_param_group_list::ReferenceableParamGroupList;
+
+pub trait HasReferenceableParamGroupRefs {
+    /// Copies the cvParams of the referenced referenceableParamGroups into the element and its children,
+    /// so they are included in cvParam queries and validation.
+    /// The copied cvParams are not serialized, the references are kept.
+    ///
+    /// # Arguments
+    /// * `referenceable_param_group_list` - The referenceableParamGroupList of the mzML header.
+    ///
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()>;
+}
@@ -2,24 +2,76 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    component_list::ComponentList, is_element::IsElement,
-    referenceable_param_group_ref::ReferenceableParamGroupRef,
+    component_list::ComponentList, cv_param::CvParam,
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    referenceable_param_group_list::ReferenceableParamGroupList,
+    referenceable_param_group_ref::ReferenceableParamGroupRef, software_ref::SoftwareRef,
+    user_param::UserParam,
 };
+use crate::has_cv_params;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InstrumentConfiguration {
     #[serde(rename = "@id")]
     pub id: String,
-    #[serde(rename = "referenceableParamGroupRef")]
-    pub referenceable_param_group_ref: ReferenceableParamGroupRef,
-    #[serde(rename = "componentList")]
-    pub component_list: ComponentList,
+    #[serde(rename = "@scanSettingsRef", skip_serializing_if = "Option::is_none")]
+    pub scan_settings_ref: Option<String>,
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
+    #[serde(default, rename = "cvParam")]
+    pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
+    #[serde(rename = "componentList", skip_serializing_if = "Option::is_none")]
+    pub component_list: Option<ComponentList>,
+    #[serde(rename = "softwareRef", skip_serializing_if = "Option::is_none")]
+    pub software_ref: Option<SoftwareRef>,
 }
 
 impl IsElement for InstrumentConfiguration {
     fn validate(&self) -> Result<()> {
-        self.referenceable_param_group_ref.validate()?;
-        self.component_list.validate()?;
+        for referenceable_param_group_ref in &self.referenceable_param_group_ref {
+            referenceable_param_group_ref.validate()?;
+        }
+        if let Some(ref component_list) = self.component_list {
+            component_list.validate()?;
+        }
+        self.validate_cv_params("instrumentConfiguration")?;
         Ok(())
     }
 }
+
+impl HasReferenceableParamGroupRefs for InstrumentConfiguration {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        if let Some(component_list) = self.component_list.as_mut() {
+            for source in component_list.sources.iter_mut() {
+                source.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+            }
+            for analyzer in component_list.analyzers.iter_mut() {
+                analyzer.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+            }
+            for detector in component_list.detectors.iter_mut() {
+                detector.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+has_cv_params! {
+    InstrumentConfiguration,
+    cv_params,
+    referenced_cv_params,
+    [],
+    [],
+    [],
+    []
+}
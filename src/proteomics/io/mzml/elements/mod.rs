@@ -6,6 +6,7 @@ pub mod binary_data_array_list;
 pub mod chromatogram;
 pub mod chromatogram_list;
 pub mod component_list;
+pub mod contact;
 pub mod cv;
 pub mod cv_list;
 pub mod cv_param;
@@ -17,6 +18,8 @@ pub mod file_content;
 pub mod file_description;
 /// Trait describing behavior for elements with cv params
 pub mod has_cv_params;
+/// Trait for elements referencing referenceableParamGroups
+pub mod has_referenceable_param_group_refs;
 pub mod index;
 pub mod index_list;
 pub mod index_list_offset;
@@ -30,23 +33,34 @@ pub mod offset;
 pub mod precursor;
 pub mod precursor_list;
 pub mod processing_method;
+pub mod product;
+pub mod product_list;
 pub mod referenceable_param_group;
 pub mod referenceable_param_group_list;
 pub mod referenceable_param_group_ref;
 pub mod run;
+pub mod sample;
+pub mod sample_list;
 pub mod scan;
 pub mod scan_list;
+pub mod scan_settings;
+pub mod scan_settings_list;
 pub mod scan_window;
 pub mod scan_window_list;
 pub mod selected_ion;
 pub mod selected_ion_list;
 pub mod software;
 pub mod software_list;
+pub mod software_ref;
 pub mod source;
 pub mod source_file;
 pub mod source_file_list;
+pub mod source_file_ref;
+pub mod source_file_ref_list;
 pub mod spectrum;
 pub mod spectrum_list;
+pub mod target;
+pub mod target_list;
 pub mod user_param;
 
 /// Trait for list elements
@@ -5,10 +5,13 @@ use super::{
     cv_list::CvList,
     data_processing_list::DataProcessingList,
     file_description::FileDescription,
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
     instrument_configuration_list::InstrumentConfigurationList,
     is_element::IsElement,
     referenceable_param_group_list::ReferenceableParamGroupList,
     run::{IndexedRun, IsRun, Run},
+    sample_list::SampleList,
+    scan_settings_list::ScanSettingsList,
     software_list::SoftwareList,
 };
 
@@ -26,7 +29,9 @@ where
     #[serde(rename = "@xsi:schemaLocation")]
     #[serde(alias = "@schemaLocation")]
     pub xsi_schema_location: String,
-    #[serde(rename = "@id")]
+    #[serde(rename = "@accession", skip_serializing_if = "Option::is_none")]
+    pub accession: Option<String>,
+    #[serde(default, rename = "@id", skip_serializing_if = "String::is_empty")]
     pub id: String,
     #[serde(rename = "@version")]
     pub version: String,
@@ -34,10 +39,14 @@ where
     pub cv_list: CvList,
     #[serde(rename = "fileDescription")]
     pub file_description: FileDescription,
-    #[serde(rename = "referenceableParamGroupList")]
+    #[serde(default, rename = "referenceableParamGroupList")]
     pub referenceable_param_group_list: ReferenceableParamGroupList,
+    #[serde(rename = "sampleList", skip_serializing_if = "Option::is_none")]
+    pub sample_list: Option<SampleList>,
     #[serde(rename = "softwareList")]
     pub software_list: SoftwareList,
+    #[serde(rename = "scanSettingsList", skip_serializing_if = "Option::is_none")]
+    pub scan_settings_list: Option<ScanSettingsList>,
     #[serde(rename = "instrumentConfigurationList")]
     pub instrument_configuration_list: InstrumentConfigurationList,
     #[serde(rename = "dataProcessingList")]
@@ -56,7 +65,13 @@ where
         }
         self.file_description.validate()?;
         self.referenceable_param_group_list.validate()?;
+        if let Some(ref sample_list) = self.sample_list {
+            sample_list.validate()?;
+        }
         self.software_list.validate()?;
+        if let Some(ref scan_settings_list) = self.scan_settings_list {
+            scan_settings_list.validate()?;
+        }
         self.instrument_configuration_list.validate()?;
         self.data_processing_list.validate()?;
         self.run.validate()?;
@@ -64,6 +79,57 @@ where
     }
 }
 
+impl<R> MzML<R>
+where
+    R: IsRun,
+{
+    /// Resolves the referenceableParamGroupRefs of all elements, e.g. the instrument configurations,
+    /// with the referenceableParamGroupList of this mzML.
+    /// Spectra and chromatograms of an indexed run are resolved when read by [crate::proteomics::io::mzml::reader::File].
+    ///
+    pub fn resolve_referenceable_param_groups(&mut self) -> Result<()> {
+        let referenceable_param_group_list = &self.referenceable_param_group_list;
+        let file_description = &mut self.file_description;
+        file_description
+            .file_content
+            .resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        if let Some(source_file_list) = file_description.source_file_list.as_mut() {
+            for source_file in source_file_list.source_files.iter_mut() {
+                source_file.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+            }
+        }
+        for contact in file_description.contacts.iter_mut() {
+            contact.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        if let Some(sample_list) = self.sample_list.as_mut() {
+            sample_list.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        for software in self.software_list.softwares.iter_mut() {
+            software.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        if let Some(scan_settings_list) = self.scan_settings_list.as_mut() {
+            scan_settings_list
+                .resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        for instrument_configuration in self
+            .instrument_configuration_list
+            .instrument_configurations
+            .iter_mut()
+        {
+            instrument_configuration
+                .resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        for data_processing in self.data_processing_list.data_processings.iter_mut() {
+            for processing_method in data_processing.processing_methods.iter_mut() {
+                processing_method
+                    .resolve_referenceable_param_groups(referenceable_param_group_list)?;
+            }
+        }
+        self.run
+            .resolve_referenceable_param_groups(referenceable_param_group_list)
+    }
+}
+
 // Needed for separating spectra and chromatograms from an indexed run file
 impl From<MzML<IndexedRun>> for MzML<Run> {
     fn from(mzml: MzML<IndexedRun>) -> Self {
@@ -71,12 +137,15 @@ impl From<MzML<IndexedRun>> for MzML<Run> {
             xmlns: mzml.xmlns,
             xmlns_xsi: mzml.xmlns_xsi,
             xsi_schema_location: mzml.xsi_schema_location,
+            accession: mzml.accession,
             id: mzml.id,
             version: mzml.version,
             cv_list: mzml.cv_list,
             file_description: mzml.file_description,
             referenceable_param_group_list: mzml.referenceable_param_group_list,
+            sample_list: mzml.sample_list,
             software_list: mzml.software_list,
+            scan_settings_list: mzml.scan_settings_list,
             instrument_configuration_list: mzml.instrument_configuration_list,
             data_processing_list: mzml.data_processing_list,
             run: Run::from(mzml.run),
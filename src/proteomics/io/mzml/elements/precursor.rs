@@ -2,17 +2,31 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    activation::Activation, is_element::IsElement, isolation_window::IsolationWindow,
+    activation::Activation, has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
+    is_element::IsElement, isolation_window::IsolationWindow,
+    referenceable_param_group_list::ReferenceableParamGroupList,
     selected_ion_list::SelectedIonList,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Precursor {
-    #[serde(rename = "@spectrumRef")]
+    /// Empty if not referencing a spectrum, e.g. the precursor of an SRM chromatogram
+    #[serde(
+        default,
+        rename = "@spectrumRef",
+        skip_serializing_if = "String::is_empty"
+    )]
     pub spectrum_ref: String,
-    #[serde(rename = "isolationWindow")]
+    #[serde(rename = "@sourceFileRef", skip_serializing_if = "Option::is_none")]
+    pub source_file_ref: Option<String>,
+    #[serde(
+        rename = "@externalSpectrumID",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub external_spectrum_id: Option<String>,
+    #[serde(rename = "isolationWindow", skip_serializing_if = "Option::is_none")]
     pub isolation_window: Option<IsolationWindow>,
-    #[serde(rename = "selectedIonList")]
+    #[serde(rename = "selectedIonList", skip_serializing_if = "Option::is_none")]
     pub selected_ion_list: Option<SelectedIonList>,
     #[serde(rename = "activation")]
     pub activation: Activation,
@@ -30,3 +44,19 @@ impl IsElement for Precursor {
         Ok(())
     }
 }
+
+impl HasReferenceableParamGroupRefs for Precursor {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        if let Some(isolation_window) = self.isolation_window.as_mut() {
+            isolation_window.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        if let Some(selected_ion_list) = self.selected_ion_list.as_mut() {
+            selected_ion_list.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        self.activation
+            .resolve_referenceable_param_groups(referenceable_param_group_list)
+    }
+}
@@ -1,7 +1,11 @@
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use super::{is_element::IsElement, is_list::IsList, precursor::Precursor};
+use super::{
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    is_list::IsList, precursor::Precursor,
+    referenceable_param_group_list::ReferenceableParamGroupList,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PrecursorList {
@@ -23,6 +27,18 @@ impl IsElement for PrecursorList {
     }
 }
 
+impl HasReferenceableParamGroupRefs for PrecursorList {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        for precursor in self.precursors.iter_mut() {
+            precursor.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
+
 impl IsList<'_, Precursor> for PrecursorList {
     fn iter(&self) -> std::slice::Iter<'_, Precursor> {
         self.precursors.iter()
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    isolation_window::IsolationWindow, referenceable_param_group_list::ReferenceableParamGroupList,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Product {
+    #[serde(rename = "isolationWindow", skip_serializing_if = "Option::is_none")]
+    pub isolation_window: Option<IsolationWindow>,
+}
+
+impl IsElement for Product {
+    fn validate(&self) -> Result<()> {
+        if let Some(isolation_window) = self.isolation_window.as_ref() {
+            isolation_window.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for Product {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        if let Some(isolation_window) = self.isolation_window.as_mut() {
+            isolation_window.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
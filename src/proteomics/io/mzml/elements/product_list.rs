@@ -0,0 +1,49 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    is_list::IsList, product::Product, referenceable_param_group_list::ReferenceableParamGroupList,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProductList {
+    #[serde(rename = "@count")]
+    pub count: usize,
+    #[serde(default, rename = "product")]
+    pub products: Vec<Product>,
+}
+
+impl IsElement for ProductList {
+    fn validate(&self) -> Result<()> {
+        if self.count != self.products.len() {
+            bail!(
+                "The count attribute ({}) does not match the number of product elements ({})",
+                self.count,
+                self.products.len()
+            );
+        }
+        for product in &self.products {
+            product.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for ProductList {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        for product in self.products.iter_mut() {
+            product.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
+
+impl IsList<'_, Product> for ProductList {
+    fn iter(&self) -> std::slice::Iter<'_, Product> {
+        self.products.iter()
+    }
+}
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use crate::has_cv_params;
 
 // Local imports
-use super::{cv_param::CvParam, is_element::IsElement};
+use super::{cv_param::CvParam, is_element::IsElement, user_param::UserParam};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReferenceableParamGroup {
@@ -12,6 +12,8 @@ pub struct ReferenceableParamGroup {
     pub id: String,
     #[serde(default, rename = "cvParam")]
     pub cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
 }
 
 impl IsElement for ReferenceableParamGroup {
@@ -1,15 +1,16 @@
 // 3rd party imports
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
 use super::{
-    is_element::IsElement,
-    is_list::IsList,
+    cv_param::CvParam, is_element::IsElement, is_list::IsList,
     referenceable_param_group::ReferenceableParamGroup,
+    referenceable_param_group_ref::ReferenceableParamGroupRef,
 };
 
 // Local imports
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ReferenceableParamGroupList {
     #[serde(rename = "@count")]
     pub count: usize,
@@ -17,6 +18,34 @@ pub struct ReferenceableParamGroupList {
     pub referenceable_param_groups: Vec<ReferenceableParamGroup>,
 }
 
+impl ReferenceableParamGroupList {
+    /// Returns the cvParams of the referenced groups
+    ///
+    /// # Arguments
+    /// * `referenceable_param_group_refs` - References to groups of this list
+    ///
+    pub fn get_referenced_cv_params(
+        &self,
+        referenceable_param_group_refs: &[ReferenceableParamGroupRef],
+    ) -> Result<Vec<CvParam>> {
+        let mut cv_params = Vec::new();
+        for referenceable_param_group_ref in referenceable_param_group_refs {
+            match self
+                .referenceable_param_groups
+                .iter()
+                .find(|group| group.id == referenceable_param_group_ref.r#ref)
+            {
+                Some(group) => cv_params.extend(group.cv_params.iter().cloned()),
+                None => bail!(
+                    "Referenced referenceableParamGroup `{}` not found",
+                    referenceable_param_group_ref.r#ref
+                ),
+            }
+        }
+        Ok(cv_params)
+    }
+}
+
 impl IsElement for ReferenceableParamGroupList {
     fn validate(&self) -> anyhow::Result<()> {
         for referenceable_param_group in &self.referenceable_param_groups {
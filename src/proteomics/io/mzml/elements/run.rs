@@ -2,10 +2,15 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    chromatogram_list::ChromatogramList, is_element::IsElement, spectrum_list::SpectrumList,
+    chromatogram_list::ChromatogramList, cv_param::CvParam,
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    referenceable_param_group_list::ReferenceableParamGroupList,
+    referenceable_param_group_ref::ReferenceableParamGroupRef, spectrum_list::SpectrumList,
+    user_param::UserParam,
 };
+use crate::has_cv_params;
 
-pub trait IsRun: IsElement + Into<Run> {}
+pub trait IsRun: IsElement + HasReferenceableParamGroupRefs + Into<Run> {}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Run {
@@ -13,40 +18,114 @@ pub struct Run {
     pub id: String,
     #[serde(rename = "@defaultInstrumentConfigurationRef")]
     pub default_instrument_configuration_ref: String,
-    #[serde(rename = "@startTimeStamp")]
-    pub start_time_stamp: String,
-    #[serde(rename = "@defaultSourceFileRef")]
+    #[serde(
+        default,
+        rename = "@defaultSourceFileRef",
+        skip_serializing_if = "String::is_empty"
+    )]
     pub default_source_file_ref: String,
-    #[serde(rename = "spectrumList")]
+    #[serde(rename = "@sampleRef", skip_serializing_if = "Option::is_none")]
+    pub sample_ref: Option<String>,
+    #[serde(
+        default,
+        rename = "@startTimeStamp",
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub start_time_stamp: String,
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
+    #[serde(default, rename = "cvParam")]
+    pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
+    #[serde(default, rename = "spectrumList")]
     pub spectrum_list: SpectrumList,
-    #[serde(rename = "chromatogramList")]
+    #[serde(default, rename = "chromatogramList")]
     pub chromatogram_list: ChromatogramList,
 }
 
 impl IsElement for Run {
     fn validate(&self) -> Result<()> {
+        self.validate_cv_params("run")?;
         self.spectrum_list.validate()?;
         self.chromatogram_list.validate()?;
         Ok(())
     }
 }
 
+impl HasReferenceableParamGroupRefs for Run {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        for spectrum in self.spectrum_list.spectra.iter_mut() {
+            spectrum.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        for chromatogram in self.chromatogram_list.chromatograms.iter_mut() {
+            chromatogram.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
+
 impl IsRun for Run {}
 
+has_cv_params! {
+    Run,
+    cv_params,
+    referenced_cv_params,
+    [],
+    [],
+    [],
+    []
+}
+
 // Necessary for separating spectra from an indexed run
 impl From<IndexedRun> for Run {
     fn from(indexed_run: IndexedRun) -> Self {
         Self {
             id: indexed_run.id,
             default_instrument_configuration_ref: indexed_run.default_instrument_configuration_ref,
-            start_time_stamp: indexed_run.start_time_stamp,
             default_source_file_ref: indexed_run.default_source_file_ref,
-            spectrum_list: SpectrumList::default(),
-            chromatogram_list: ChromatogramList::default(),
+            sample_ref: indexed_run.sample_ref,
+            start_time_stamp: indexed_run.start_time_stamp,
+            referenceable_param_group_ref: indexed_run.referenceable_param_group_ref,
+            cv_params: indexed_run.cv_params,
+            referenced_cv_params: indexed_run.referenced_cv_params,
+            user_params: indexed_run.user_params,
+            spectrum_list: SpectrumList {
+                default_data_processing_ref: indexed_run
+                    .spectrum_list
+                    .map(|spectrum_list| spectrum_list.default_data_processing_ref)
+                    .unwrap_or_default(),
+                ..Default::default()
+            },
+            chromatogram_list: ChromatogramList {
+                default_data_processing_ref: indexed_run
+                    .chromatogram_list
+                    .map(|chromatogram_list| chromatogram_list.default_data_processing_ref)
+                    .unwrap_or_default(),
+                ..Default::default()
+            },
         }
     }
 }
 
+/// Attributes of the <spectrumList> or <chromatogramList> element without the spectra or chromatograms.
+///
+#[derive(Clone, Debug, Deserialize)]
+pub struct IndexedList {
+    #[serde(rename = "@count")]
+    pub count: usize,
+    #[serde(rename = "@defaultDataProcessingRef")]
+    pub default_data_processing_ref: String,
+}
+
 /// Implementation of the MzML element <run> without spectrum and chromatogram data.
 /// This is useful for indexing the MzML file.
 ///
@@ -56,16 +135,70 @@ pub struct IndexedRun {
     pub id: String,
     #[serde(rename = "@defaultInstrumentConfigurationRef")]
     pub default_instrument_configuration_ref: String,
-    #[serde(rename = "@startTimeStamp")]
-    pub start_time_stamp: String,
-    #[serde(rename = "@defaultSourceFileRef")]
+    #[serde(default, rename = "@defaultSourceFileRef")]
     pub default_source_file_ref: String,
+    #[serde(rename = "@sampleRef")]
+    pub sample_ref: Option<String>,
+    #[serde(default, rename = "@startTimeStamp")]
+    pub start_time_stamp: String,
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
+    #[serde(default, rename = "cvParam")]
+    pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
+    #[serde(rename = "spectrumList")]
+    pub spectrum_list: Option<IndexedList>,
+    #[serde(rename = "chromatogramList")]
+    pub chromatogram_list: Option<IndexedList>,
 }
 
 impl IsElement for IndexedRun {
     fn validate(&self) -> Result<()> {
+        self.validate_cv_params("run")?;
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for IndexedRun {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
         Ok(())
     }
 }
 
 impl IsRun for IndexedRun {}
+
+// has_cv_params! can only be used once per module, so the rules of the run are reused
+impl HasCvParams for IndexedRun {
+    fn get_cv_params(&self) -> &[CvParam] {
+        &self.cv_params
+    }
+
+    fn get_referenced_cv_params(&self) -> &[CvParam] {
+        &self.referenced_cv_params
+    }
+
+    fn get_parent_accession_for_must_once(&self) -> &'static Vec<&'static str> {
+        &CV_PARAMS_MUST_ONCE
+    }
+
+    fn get_parent_accession_for_must_once_or_many(&self) -> &'static Vec<&'static str> {
+        &CV_PARAMS_MUST_ONCE_OR_MANY
+    }
+
+    fn get_parent_accession_for_may_once(&self) -> &'static Vec<&'static str> {
+        &CV_PARAMS_MAY_ONCE
+    }
+
+    fn get_parent_accession_for_zero_or_many(&self) -> &'static Vec<&'static str> {
+        &CV_PARAMS_MAY_ONCE_OR_MANY
+    }
+}
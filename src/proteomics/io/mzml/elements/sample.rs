@@ -0,0 +1,56 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cv_param::CvParam, has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
+    is_element::IsElement, referenceable_param_group_list::ReferenceableParamGroupList,
+    referenceable_param_group_ref::ReferenceableParamGroupRef, user_param::UserParam,
+};
+use crate::has_cv_params;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sample {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
+    #[serde(default, rename = "cvParam")]
+    pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
+}
+
+impl IsElement for Sample {
+    fn validate(&self) -> Result<()> {
+        self.validate_cv_params("sample")?;
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for Sample {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        Ok(())
+    }
+}
+
+has_cv_params! {
+    Sample,
+    cv_params,
+    referenced_cv_params,
+    [],
+    [],
+    [],
+    [
+        "MS:1000548", // sample attribute
+    ]
+}
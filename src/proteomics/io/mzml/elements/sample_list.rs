@@ -0,0 +1,49 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    is_list::IsList, referenceable_param_group_list::ReferenceableParamGroupList, sample::Sample,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SampleList {
+    #[serde(rename = "@count")]
+    pub count: usize,
+    #[serde(default, rename = "sample")]
+    pub samples: Vec<Sample>,
+}
+
+impl IsElement for SampleList {
+    fn validate(&self) -> Result<()> {
+        if self.count != self.samples.len() {
+            bail!(
+                "The count attribute ({}) does not match the number of sample elements ({})",
+                self.count,
+                self.samples.len()
+            );
+        }
+        for sample in &self.samples {
+            sample.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for SampleList {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        for sample in self.samples.iter_mut() {
+            sample.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
+
+impl IsList<'_, Sample> for SampleList {
+    fn iter(&self) -> std::slice::Iter<'_, Sample> {
+        self.samples.iter()
+    }
+}
@@ -2,19 +2,40 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    cv_param::CvParam, is_element::IsElement, scan_window_list::ScanWindowList,
+    cv_param::CvParam, has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
+    is_element::IsElement, referenceable_param_group_list::ReferenceableParamGroupList,
+    referenceable_param_group_ref::ReferenceableParamGroupRef, scan_window_list::ScanWindowList,
     user_param::UserParam,
 };
 use crate::has_cv_params;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Scan {
+    #[serde(rename = "@spectrumRef", skip_serializing_if = "Option::is_none")]
+    pub spectrum_ref: Option<String>,
+    #[serde(rename = "@sourceFileRef", skip_serializing_if = "Option::is_none")]
+    pub source_file_ref: Option<String>,
+    #[serde(
+        rename = "@externalSpectrumID",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub external_spectrum_id: Option<String>,
+    #[serde(
+        rename = "@instrumentConfigurationRef",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub instrument_configuration_ref: Option<String>,
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
     #[serde(default, rename = "cvParam")]
     pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
     #[serde(default, rename = "userParam")]
     pub user_params: Vec<UserParam>,
-    #[serde(rename = "scanWindowList")]
-    pub scan_window_list: ScanWindowList,
+    #[serde(rename = "scanWindowList", skip_serializing_if = "Option::is_none")]
+    pub scan_window_list: Option<ScanWindowList>,
 }
 
 impl IsElement for Scan {
@@ -23,7 +44,23 @@ impl IsElement for Scan {
         for user_param in &self.user_params {
             user_param.validate()?;
         }
-        self.scan_window_list.validate()?;
+        if let Some(ref scan_window_list) = self.scan_window_list {
+            scan_window_list.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for Scan {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        if let Some(scan_window_list) = self.scan_window_list.as_mut() {
+            scan_window_list.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
         Ok(())
     }
 }
@@ -31,6 +68,7 @@ impl IsElement for Scan {
 has_cv_params! {
     Scan,
     cv_params,
+    referenced_cv_params,
     [],
     [],
     [
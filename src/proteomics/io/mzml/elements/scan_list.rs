@@ -3,14 +3,25 @@ use serde::{Deserialize, Serialize};
 
 use crate::has_cv_params;
 
-use super::{cv_param::CvParam, is_element::IsElement, scan::Scan};
+use super::{
+    cv_param::CvParam, has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
+    is_element::IsElement, referenceable_param_group_list::ReferenceableParamGroupList,
+    referenceable_param_group_ref::ReferenceableParamGroupRef, scan::Scan, user_param::UserParam,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScanList {
     #[serde(rename = "@count")]
     pub count: usize,
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
     #[serde(default, rename = "cvParam")]
     pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
     #[serde(default, rename = "scan")]
     pub scans: Vec<Scan>,
 }
@@ -33,9 +44,24 @@ impl IsElement for ScanList {
     }
 }
 
+impl HasReferenceableParamGroupRefs for ScanList {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        for scan in self.scans.iter_mut() {
+            scan.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
+
 has_cv_params! {
     ScanList,
     cv_params,
+    referenced_cv_params,
     [
         "MS:1000570", // spectra combination
     ],
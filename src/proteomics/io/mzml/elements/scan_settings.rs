@@ -0,0 +1,66 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cv_param::CvParam, has_referenceable_param_group_refs::HasReferenceableParamGroupRefs,
+    is_element::IsElement, referenceable_param_group_list::ReferenceableParamGroupList,
+    referenceable_param_group_ref::ReferenceableParamGroupRef,
+    source_file_ref_list::SourceFileRefList, target_list::TargetList, user_param::UserParam,
+};
+use crate::has_cv_params;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScanSettings {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
+    #[serde(default, rename = "cvParam")]
+    pub cv_params: Vec<CvParam>,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
+    #[serde(rename = "sourceFileRefList", skip_serializing_if = "Option::is_none")]
+    pub source_file_ref_list: Option<SourceFileRefList>,
+    #[serde(rename = "targetList", skip_serializing_if = "Option::is_none")]
+    pub target_list: Option<TargetList>,
+}
+
+impl IsElement for ScanSettings {
+    fn validate(&self) -> Result<()> {
+        self.validate_cv_params("scanSettings")?;
+        if let Some(source_file_ref_list) = self.source_file_ref_list.as_ref() {
+            source_file_ref_list.validate()?;
+        }
+        if let Some(target_list) = self.target_list.as_ref() {
+            target_list.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for ScanSettings {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        if let Some(target_list) = self.target_list.as_mut() {
+            target_list.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
+
+has_cv_params! {
+    ScanSettings,
+    cv_params,
+    referenced_cv_params,
+    [],
+    [],
+    [],
+    []
+}
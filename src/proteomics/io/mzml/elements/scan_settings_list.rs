@@ -0,0 +1,50 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    is_list::IsList, referenceable_param_group_list::ReferenceableParamGroupList,
+    scan_settings::ScanSettings,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScanSettingsList {
+    #[serde(rename = "@count")]
+    pub count: usize,
+    #[serde(default, rename = "scanSettings")]
+    pub scan_settings: Vec<ScanSettings>,
+}
+
+impl IsElement for ScanSettingsList {
+    fn validate(&self) -> Result<()> {
+        if self.count != self.scan_settings.len() {
+            bail!(
+                "The count attribute ({}) does not match the number of scanSettings elements ({})",
+                self.count,
+                self.scan_settings.len()
+            );
+        }
+        for scan_settings in &self.scan_settings {
+            scan_settings.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for ScanSettingsList {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        for scan_settings in self.scan_settings.iter_mut() {
+            scan_settings.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
+
+impl IsList<'_, ScanSettings> for ScanSettingsList {
+    fn iter(&self) -> std::slice::Iter<'_, ScanSettings> {
+        self.scan_settings.iter()
+    }
+}
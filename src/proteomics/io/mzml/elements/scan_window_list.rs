@@ -2,8 +2,8 @@ use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
 use super::{
-    is_element::IsElement,
-    is_list::IsList,
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    is_list::IsList, referenceable_param_group_list::ReferenceableParamGroupList,
     scan_window::ScanWindow,
 };
 
@@ -32,3 +32,15 @@ impl IsList<'_, ScanWindow> for ScanWindowList {
         self.scan_windows.iter()
     }
 }
+
+impl HasReferenceableParamGroupRefs for ScanWindowList {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        for scan_window in self.scan_windows.iter_mut() {
+            scan_window.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use super::{is_element::IsElement, is_list::IsList, selected_ion::SelectedIon};
+use super::{
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    is_list::IsList, referenceable_param_group_list::ReferenceableParamGroupList,
+    selected_ion::SelectedIon,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SelectedIonList {
@@ -28,3 +32,15 @@ impl IsList<'_, SelectedIon> for SelectedIonList {
         self.selected_ions.iter()
     }
 }
+
+impl HasReferenceableParamGroupRefs for SelectedIonList {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        for selected_ion in self.selected_ions.iter_mut() {
+            selected_ion.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::is_element::IsElement;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SoftwareRef {
+    #[serde(rename = "@ref")]
+    pub r#ref: String,
+}
+
+impl IsElement for SoftwareRef {
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+}
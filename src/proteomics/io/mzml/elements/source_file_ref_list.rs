@@ -0,0 +1,34 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{is_element::IsElement, is_list::IsList, source_file_ref::SourceFileRef};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SourceFileRefList {
+    #[serde(rename = "@count")]
+    pub count: usize,
+    #[serde(default, rename = "sourceFileRef")]
+    pub source_file_refs: Vec<SourceFileRef>,
+}
+
+impl IsElement for SourceFileRefList {
+    fn validate(&self) -> Result<()> {
+        if self.count != self.source_file_refs.len() {
+            bail!(
+                "The count attribute ({}) does not match the number of sourceFileRef elements ({})",
+                self.count,
+                self.source_file_refs.len()
+            );
+        }
+        for source_file_ref in &self.source_file_refs {
+            source_file_ref.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl IsList<'_, SourceFileRef> for SourceFileRefList {
+    fn iter(&self) -> std::slice::Iter<'_, SourceFileRef> {
+        self.source_file_refs.iter()
+    }
+}
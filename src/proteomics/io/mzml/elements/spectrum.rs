@@ -5,8 +5,12 @@ use anyhow::Result;
 use crate::has_cv_params;
 
 use super::{
-    binary_data_array_list::BinaryDataArrayList, cv_param::CvParam, is_element::IsElement,
-    precursor_list::PrecursorList, scan_list::ScanList,
+    binary_data_array_list::BinaryDataArrayList, cv_param::CvParam,
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    precursor_list::PrecursorList, product_list::ProductList,
+    referenceable_param_group_list::ReferenceableParamGroupList,
+    referenceable_param_group_ref::ReferenceableParamGroupRef, scan_list::ScanList,
+    user_param::UserParam,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -15,22 +19,39 @@ pub struct Spectrum {
     pub index: usize,
     #[serde(rename = "@id")]
     pub id: String,
+    #[serde(rename = "@spotID", skip_serializing_if = "Option::is_none")]
+    pub spot_id: Option<String>,
     #[serde(rename = "@defaultArrayLength")]
     pub default_array_length: String,
+    #[serde(rename = "@dataProcessingRef", skip_serializing_if = "Option::is_none")]
+    pub data_processing_ref: Option<String>,
+    #[serde(rename = "@sourceFileRef", skip_serializing_if = "Option::is_none")]
+    pub source_file_ref: Option<String>,
+    #[serde(default, rename = "referenceableParamGroupRef")]
+    pub referenceable_param_group_ref: Vec<ReferenceableParamGroupRef>,
     #[serde(default, rename = "cvParam")]
     pub cv_params: Vec<CvParam>,
-    #[serde(rename = "scanList")]
-    pub scan_list: ScanList,
+    /// cvParams of the referenced referenceableParamGroups
+    #[serde(skip)]
+    pub referenced_cv_params: Vec<CvParam>,
+    #[serde(default, rename = "userParam")]
+    pub user_params: Vec<UserParam>,
+    #[serde(rename = "scanList", skip_serializing_if = "Option::is_none")]
+    pub scan_list: Option<ScanList>,
     #[serde(rename = "precursorList", skip_serializing_if = "Option::is_none")]
     pub precursor_list: Option<PrecursorList>,
-    #[serde(rename = "binaryDataArrayList")]
-    pub binary_data_array_list: BinaryDataArrayList,
+    #[serde(rename = "productList", skip_serializing_if = "Option::is_none")]
+    pub product_list: Option<ProductList>,
+    #[serde(
+        rename = "binaryDataArrayList",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub binary_data_array_list: Option<BinaryDataArrayList>,
 }
 
 impl Spectrum {
     pub fn get_ms_level(&self) -> Option<u8> {
-        self.cv_params
-            .iter()
+        self.iter_all_cv_params()
             .find(|cv| cv.accession == "MS:1000511")
             .and_then(|cv| cv.value.parse::<u8>().ok())
     }
@@ -38,11 +59,42 @@ impl Spectrum {
 
 impl IsElement for Spectrum {
     fn validate(&self) -> Result<()> {
-        self.scan_list.validate()?;
+        if let Some(ref scan_list) = self.scan_list {
+            scan_list.validate()?;
+        }
         if let Some(ref precursor_list) = self.precursor_list {
             precursor_list.validate()?;
         }
-        self.binary_data_array_list.validate()?;
+        if let Some(ref product_list) = self.product_list {
+            product_list.validate()?;
+        }
+        if let Some(ref binary_data_array_list) = self.binary_data_array_list {
+            binary_data_array_list.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for Spectrum {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        self.referenced_cv_params = referenceable_param_group_list
+            .get_referenced_cv_params(&self.referenceable_param_group_ref)?;
+        if let Some(scan_list) = self.scan_list.as_mut() {
+            scan_list.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        if let Some(precursor_list) = self.precursor_list.as_mut() {
+            precursor_list.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        if let Some(product_list) = self.product_list.as_mut() {
+            product_list.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        if let Some(binary_data_array_list) = self.binary_data_array_list.as_mut() {
+            binary_data_array_list
+                .resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
         Ok(())
     }
 }
@@ -50,6 +102,7 @@ impl IsElement for Spectrum {
 has_cv_params! {
     Spectrum,
     cv_params,
+    referenced_cv_params,
     [
         "MS:1000559", // spectrum type
         "MS:1000525", // spectrum representation
@@ -0,0 +1,49 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    has_referenceable_param_group_refs::HasReferenceableParamGroupRefs, is_element::IsElement,
+    is_list::IsList, referenceable_param_group_list::ReferenceableParamGroupList, target::Target,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TargetList {
+    #[serde(rename = "@count")]
+    pub count: usize,
+    #[serde(default, rename = "target")]
+    pub targets: Vec<Target>,
+}
+
+impl IsElement for TargetList {
+    fn validate(&self) -> Result<()> {
+        if self.count != self.targets.len() {
+            bail!(
+                "The count attribute ({}) does not match the number of target elements ({})",
+                self.count,
+                self.targets.len()
+            );
+        }
+        for target in &self.targets {
+            target.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl HasReferenceableParamGroupRefs for TargetList {
+    fn resolve_referenceable_param_groups(
+        &mut self,
+        referenceable_param_group_list: &ReferenceableParamGroupList,
+    ) -> Result<()> {
+        for target in self.targets.iter_mut() {
+            target.resolve_referenceable_param_groups(referenceable_param_group_list)?;
+        }
+        Ok(())
+    }
+}
+
+impl IsList<'_, Target> for TargetList {
+    fn iter(&self) -> std::slice::Iter<'_, Target> {
+        self.targets.iter()
+    }
+}
@@ -11,6 +11,12 @@ pub struct UserParam {
     pub value: Option<String>,
     #[serde(rename = "@type", skip_serializing_if = "Option::is_none")]
     pub r#type: Option<String>,
+    #[serde(rename = "@unitCvRef", skip_serializing_if = "Option::is_none")]
+    pub unit_cv_ref: Option<String>,
+    #[serde(rename = "@unitAccession", skip_serializing_if = "Option::is_none")]
+    pub unit_accession: Option<String>,
+    #[serde(rename = "@unitName", skip_serializing_if = "Option::is_none")]
+    pub unit_name: Option<String>,
 }
 
 impl IsElement for UserParam {
@@ -5,5 +5,11 @@ pub mod error;
 pub mod index;
 /// MzML indexer
 pub mod indexer;
+/// MS-Numpress compression of binary data arrays, compatible with the reference implementation
+///
+/// > Teleman et al. (2014) Numerical compression schemes for proteomics mass spectrometry data. https://doi.org/10.1074/mcp.O114.037879
+pub mod numpress;
 /// Functions to read spectra information from MzML files
 pub mod reader;
+/// Streaming writer for (indexed) mzML files
+pub mod writer;
@@ -0,0 +1,436 @@
+// 3rd party imports
+use anyhow::{bail, Result};
+
+/// Size of the encoded fixed point in bytes
+///
+const FIXED_POINT_SIZE: usize = 8;
+
+/// Returns the optimal fixed point for linear prediction, which maximizes the precision
+/// while the differences to the predicted values still fit into 32 bit integers.
+///
+/// # Arguments
+/// * `data` - Values to encode, e.g. m/z values
+///
+pub fn optimal_linear_fixed_point(data: &[f64]) -> f64 {
+    match data.len() {
+        0 => 0.0,
+        1 => (0xFFFFFFFF_u32 as f64 / data[0]).floor(),
+        _ => {
+            let max_value = data
+                .windows(3)
+                .fold(data[0].max(data[1]), |max_value, window| {
+                    let extrapolation = window[1] + (window[1] - window[0]);
+                    let difference = window[2] - extrapolation;
+                    max_value.max((difference.abs() + 1.0).ceil())
+                });
+            (0x7FFFFFFF_u32 as f64 / max_value).floor()
+        }
+    }
+}
+
+/// Encodes the values with linear prediction.
+/// The first two values are stored as fixed point integers,
+/// the following ones as difference to the linear extrapolation of the previous two.
+///
+/// # Arguments
+/// * `data` - Values to encode, e.g. m/z values
+/// * `fixed_point` - Fixed point, see [optimal_linear_fixed_point]
+///
+pub fn encode_linear(data: &[f64], fixed_point: f64) -> Result<Vec<u8>> {
+    let mut result: Vec<u8> = Vec::with_capacity(FIXED_POINT_SIZE + data.len() * 5);
+    result.extend_from_slice(&fixed_point.to_be_bytes());
+
+    let mut ints: Vec<i64> = Vec::with_capacity(data.len());
+    for value in data.iter() {
+        let scaled_value = value * fixed_point + 0.5;
+        if scaled_value > i64::MAX as f64 {
+            bail!(
+                "Value {} overflows when multiplied by the fixed point",
+                value
+            );
+        }
+        ints.push(scaled_value as i64);
+    }
+
+    for int in ints.iter().take(2) {
+        if *int < 0 || *int > u32::MAX as i64 {
+            bail!("First two values must fit into an unsigned 32 bit integer after scaling");
+        }
+        result.extend_from_slice(&(*int as u32).to_le_bytes());
+    }
+
+    let mut half_bytes = HalfByteWriter::new(result);
+    for window in ints.windows(3) {
+        let extrapolation = window[1] + (window[1] - window[0]);
+        let difference = window[2] - extrapolation;
+        if difference > i32::MAX as i64 || difference < i32::MIN as i64 {
+            bail!("Difference to the linear prediction exceeds 32 bit integer range");
+        }
+        half_bytes.write_int(difference as i32 as u32);
+    }
+    Ok(half_bytes.finish())
+}
+
+/// Decodes values encoded with linear prediction
+///
+/// # Arguments
+/// * `data` - Encoded bytes
+///
+pub fn decode_linear(data: &[u8]) -> Result<Vec<f64>> {
+    if data.len() < FIXED_POINT_SIZE {
+        bail!("Corrupt input data: not enough bytes to read fixed point");
+    }
+    let fixed_point = decode_fixed_point(data);
+    if data.len() == FIXED_POINT_SIZE {
+        return Ok(Vec::new());
+    }
+
+    let mut ints: Vec<i64> = Vec::new();
+    for start in [FIXED_POINT_SIZE, FIXED_POINT_SIZE + 4] {
+        if data.len() == start {
+            break;
+        }
+        if data.len() < start + 4 {
+            bail!("Corrupt input data: not enough bytes to read the first values");
+        }
+        ints.push(u32::from_le_bytes(data[start..start + 4].try_into()?) as i64);
+    }
+
+    if ints.len() == 2 {
+        let mut half_bytes = HalfByteReader::new(&data[FIXED_POINT_SIZE + 8..]);
+        while let Some(difference) = half_bytes.read_int()? {
+            let extrapolation =
+                ints[ints.len() - 1] + (ints[ints.len() - 1] - ints[ints.len() - 2]);
+            ints.push(extrapolation + difference as i32 as i64);
+        }
+    }
+    Ok(ints.iter().map(|int| *int as f64 / fixed_point).collect())
+}
+
+/// Encodes the values as positive integers, e.g. ion counts.
+/// The values are rounded.
+///
+/// # Arguments
+/// * `data` - Values to encode, e.g. intensities
+///
+pub fn encode_pic(data: &[f64]) -> Result<Vec<u8>> {
+    let mut half_bytes = HalfByteWriter::new(Vec::with_capacity(data.len() * 5));
+    for value in data.iter() {
+        if *value + 0.5 > i32::MAX as f64 || *value < -0.5 {
+            bail!(
+                "Cannot encode {} as positive integer, value must be within [0, {}]",
+                value,
+                i32::MAX
+            );
+        }
+        half_bytes.write_int((value + 0.5) as u32);
+    }
+    Ok(half_bytes.finish())
+}
+
+/// Decodes values encoded as positive integers
+///
+/// # Arguments
+/// * `data` - Encoded bytes
+///
+pub fn decode_pic(data: &[u8]) -> Result<Vec<f64>> {
+    let mut half_bytes = HalfByteReader::new(data);
+    let mut result: Vec<f64> = Vec::new();
+    while let Some(value) = half_bytes.read_int()? {
+        result.push(value as f64);
+    }
+    Ok(result)
+}
+
+/// Returns the optimal fixed point for the short logged float encoding,
+/// which maximizes the precision while the logged values still fit into 16 bit integers.
+///
+/// # Arguments
+/// * `data` - Values to encode, e.g. intensities
+///
+pub fn optimal_slof_fixed_point(data: &[f64]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let max_value = data.iter().fold(1.0_f64, |max_value, value| {
+        max_value.max((value + 1.0).ln())
+    });
+    (0xFFFF as f64 / max_value).floor()
+}
+
+/// Encodes the values as short logged floats, i.e. `ln(x + 1)` as fixed point 16 bit integers
+///
+/// # Arguments
+/// * `data` - Values to encode, e.g. intensities
+/// * `fixed_point` - Fixed point, see [optimal_slof_fixed_point]
+///
+pub fn encode_slof(data: &[f64], fixed_point: f64) -> Result<Vec<u8>> {
+    let mut result: Vec<u8> = Vec::with_capacity(FIXED_POINT_SIZE + data.len() * 2);
+    result.extend_from_slice(&fixed_point.to_be_bytes());
+    for value in data.iter() {
+        let scaled_value = (value + 1.0).ln() * fixed_point;
+        if scaled_value > u16::MAX as f64 || scaled_value.is_nan() || scaled_value < 0.0 {
+            bail!(
+                "Cannot encode {} as short logged float with fixed point {}",
+                value,
+                fixed_point
+            );
+        }
+        result.extend_from_slice(&((scaled_value + 0.5) as u16).to_le_bytes());
+    }
+    Ok(result)
+}
+
+/// Decodes values encoded as short logged floats
+///
+/// # Arguments
+/// * `data` - Encoded bytes
+///
+pub fn decode_slof(data: &[u8]) -> Result<Vec<f64>> {
+    if data.len() < FIXED_POINT_SIZE {
+        bail!("Corrupt input data: not enough bytes to read fixed point");
+    }
+    if !(data.len() - FIXED_POINT_SIZE).is_multiple_of(2) {
+        bail!("Corrupt input data: odd number of bytes for 16 bit integers");
+    }
+    let fixed_point = decode_fixed_point(data);
+    Ok(data[FIXED_POINT_SIZE..]
+        .chunks(2)
+        .map(|chunk| (u16::from_le_bytes([chunk[0], chunk[1]]) as f64 / fixed_point).exp() - 1.0)
+        .collect())
+}
+
+/// Reads the big endian fixed point from the first 8 bytes
+///
+/// # Arguments
+/// * `data` - Encoded bytes, at least 8
+///
+fn decode_fixed_point(data: &[u8]) -> f64 {
+    let mut bytes = [0_u8; FIXED_POINT_SIZE];
+    bytes.copy_from_slice(&data[..FIXED_POINT_SIZE]);
+    f64::from_be_bytes(bytes)
+}
+
+/// Writes integers as variable number of half bytes.
+/// The first half byte is the number of leading zero half bytes (0-8)
+/// or 8 + the number of leading 0xf half bytes (9-15), followed by the remaining
+/// half bytes starting with the least significant one.
+///
+struct HalfByteWriter {
+    /// Encoded bytes
+    data: Vec<u8>,
+    /// Pending upper half byte
+    pending: Option<u8>,
+}
+
+impl HalfByteWriter {
+    /// Creates a new writer appending to the given bytes
+    ///
+    /// # Arguments
+    /// * `data` - Bytes to append to
+    ///
+    fn new(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            pending: None,
+        }
+    }
+
+    /// Appends a half byte
+    ///
+    /// # Arguments
+    /// * `half_byte` - Value, only the lower 4 bits are used
+    ///
+    fn write_half_byte(&mut self, half_byte: u8) {
+        match self.pending.take() {
+            Some(upper) => self.data.push((upper << 4) | (half_byte & 0xf)),
+            None => self.pending = Some(half_byte & 0xf),
+        }
+    }
+
+    /// Appends an integer
+    ///
+    /// # Arguments
+    /// * `value` - Value
+    ///
+    fn write_int(&mut self, value: u32) {
+        let leading_zeros = (value.leading_zeros() / 4) as u8;
+        let leading_ones = (value.leading_ones() / 4) as u8;
+        let (head, leading) = if leading_zeros > 0 {
+            (leading_zeros, leading_zeros)
+        } else if leading_ones > 0 {
+            // 8 leading ones are stored as 7, as 0xf leading half bytes cannot be encoded as 16
+            let leading_ones = leading_ones.min(7);
+            (leading_ones + 8, leading_ones)
+        } else {
+            (0, 0)
+        };
+        self.write_half_byte(head);
+        for position in 0..(8 - leading) {
+            self.write_half_byte((value >> (4 * position as u32)) as u8);
+        }
+    }
+
+    /// Returns the bytes, a pending half byte is padded with a zero half byte
+    ///
+    fn finish(mut self) -> Vec<u8> {
+        if let Some(upper) = self.pending.take() {
+            self.data.push(upper << 4);
+        }
+        self.data
+    }
+}
+
+/// Reads integers written by [HalfByteWriter]
+///
+struct HalfByteReader<'a> {
+    /// Encoded bytes
+    data: &'a [u8],
+    /// Index of the current byte
+    position: usize,
+    /// Whether the next half byte is the lower one of the current byte
+    lower: bool,
+}
+
+impl<'a> HalfByteReader<'a> {
+    /// Creates a new reader
+    ///
+    /// # Arguments
+    /// * `data` - Encoded bytes
+    ///
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            lower: false,
+        }
+    }
+
+    /// Returns the next half byte
+    ///
+    fn read_half_byte(&mut self) -> Result<u8> {
+        let byte = match self.data.get(self.position) {
+            Some(byte) => *byte,
+            None => bail!("Corrupt input data: unexpected end of data"),
+        };
+        let half_byte = if self.lower {
+            self.position += 1;
+            byte & 0xf
+        } else {
+            byte >> 4
+        };
+        self.lower = !self.lower;
+        Ok(half_byte)
+    }
+
+    /// Returns the next integer or None if the end of the data is reached
+    ///
+    fn read_int(&mut self) -> Result<Option<u32>> {
+        if self.position >= self.data.len() {
+            return Ok(None);
+        }
+        // padding half byte
+        if self.position == self.data.len() - 1 && self.lower && self.data[self.position] & 0xf == 0
+        {
+            return Ok(None);
+        }
+        let head = self.read_half_byte()?;
+        let (mut value, leading) = match head {
+            0..=8 => (0_u32, head),
+            _ => {
+                let leading = head - 8;
+                // fill the leading half bytes with 0xf
+                (!(u32::MAX >> (4 * leading as u32)), leading)
+            }
+        };
+        for position in 0..(8 - leading) {
+            value |= (self.read_half_byte()? as u32) << (4 * position as u32);
+        }
+        Ok(Some(value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_linear() {
+        let data = [100.0, 200.0, 300.00005, 400.00010];
+        let encoded = encode_linear(&data, 100000.0).unwrap();
+        assert_eq!(encoded.len(), 18);
+        assert_eq!(&encoded[..8], &100000.0_f64.to_be_bytes());
+        // 10000000 as little endian
+        assert_eq!(&encoded[8..12], &[0x80, 0x96, 0x98, 0x00]);
+        // difference of 5 and 0 to the prediction, padded
+        assert_eq!(&encoded[16..], &[0x75, 0x80]);
+        let decoded = decode_linear(&encoded).unwrap();
+        assert_eq!(decoded.len(), data.len());
+        for (decoded_value, value) in decoded.iter().zip(data.iter()) {
+            assert!((decoded_value - value).abs() < 1e-5);
+        }
+
+        // negative differences and the optimal fixed point
+        let data = [
+            445.1203, 445.2118, 447.3469, 448.0012, 448.0013, 512.2456, 1024.9998, 1025.0,
+        ];
+        let fixed_point = optimal_linear_fixed_point(&data);
+        let decoded = decode_linear(&encode_linear(&data, fixed_point).unwrap()).unwrap();
+        for (decoded_value, value) in decoded.iter().zip(data.iter()) {
+            assert!((decoded_value - value).abs() < 0.5 / fixed_point + 1e-12);
+        }
+
+        for length in 0..3 {
+            let decoded = decode_linear(&encode_linear(&data[..length], 10000.0).unwrap()).unwrap();
+            assert_eq!(decoded.len(), length);
+        }
+        assert!(decode_linear(&[0, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_pic() {
+        let data = [100.0, 200.0, 300.00005, 400.00010];
+        let encoded = encode_pic(&data).unwrap();
+        // 0x64 => 6 leading zeros, 4, 6; 0xc8 => 6, 8, c; 0x12c => 5, c, 2, 1; 0x190 => 5, 0, 9, 1
+        assert_eq!(encoded, vec![0x64, 0x66, 0x8c, 0x5c, 0x21, 0x50, 0x91]);
+        assert_eq!(
+            decode_pic(&encoded).unwrap(),
+            vec![100.0, 200.0, 300.0, 400.0]
+        );
+
+        // odd number of half bytes is padded
+        let encoded = encode_pic(&[0.0, 15.0, 4294967.0]).unwrap();
+        assert_eq!(decode_pic(&encoded).unwrap(), vec![0.0, 15.0, 4294967.0]);
+        assert!(encode_pic(&[-1.0]).is_err());
+    }
+
+    #[test]
+    fn test_slof() {
+        let data = [0.0, 1.0, 100.0, 12345.6, 1e7];
+        let fixed_point = optimal_slof_fixed_point(&data);
+        let encoded = encode_slof(&data, fixed_point).unwrap();
+        assert_eq!(encoded.len(), 8 + 2 * data.len());
+        let decoded = decode_slof(&encoded).unwrap();
+        assert_eq!(decoded[0], 0.0);
+        for (decoded_value, value) in decoded.iter().zip(data.iter()) {
+            // relative error of the log encoding
+            assert!((decoded_value - value).abs() <= (value + 1.0) * 1e-3);
+        }
+        assert!(encode_slof(&[1e30], 1000.0).is_err());
+        assert!(decode_slof(&encoded[..9]).is_err());
+    }
+
+    #[test]
+    fn test_negative_integers() {
+        let mut writer = HalfByteWriter::new(Vec::new());
+        for value in [-1_i32, -16, -17, i32::MIN, i32::MAX, 0] {
+            writer.write_int(value as u32);
+        }
+        let data = writer.finish();
+        let mut reader = HalfByteReader::new(&data);
+        for value in [-1_i32, -16, -17, i32::MIN, i32::MAX, 0] {
+            assert_eq!(reader.read_int().unwrap(), Some(value as u32));
+        }
+        assert_eq!(reader.read_int().unwrap(), None);
+    }
+}
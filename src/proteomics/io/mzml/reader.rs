@@ -1,46 +1,26 @@
-use core::fmt::Write;
 use std::collections::HashMap;
 use std::io::{BufRead, Seek, SeekFrom};
 use std::ops::Deref;
 use std::vec;
 
-use anyhow::{anyhow, Context, Result};
-use serde::Serialize;
-use sha1::{Digest, Sha1};
+use anyhow::{Context, Result};
 
 use super::elements::chromatogram::Chromatogram;
+use super::elements::has_referenceable_param_group_refs::HasReferenceableParamGroupRefs;
 use super::elements::indexed_mz_ml::IndexedMzML;
 use super::elements::is_element::IsElement;
 use super::elements::mz_ml::MzML;
-use super::elements::run::{IndexedRun, Run};
+use super::elements::referenceable_param_group_list::ReferenceableParamGroupList;
+use super::elements::run::IndexedRun;
 use super::elements::spectrum::Spectrum;
 use super::index::Index;
-use super::indexer::{get_id_attributes, Indexer};
+use super::indexer::get_id_attributes;
+use super::writer::Writer;
 
 /// Default buffer size
 ///
 const DEFAULT_BUFFER_SIZE: usize = 1024 * 1000; // 1MB
 
-/// Opening filechecksum tag
-///
-const OPENING_FILECHECKSUM_TAG: &[u8; 14] = b"<fileChecksum>";
-
-/// Closing filechecksum tag
-///
-const CLOSING_FILECHECKSUM_TAG: &[u8; 15] = b"</fileChecksum>";
-
-/// Opening indexList tag
-///
-const OPENING_INDEX_LIST_TAG: &[u8; 10] = b"<indexList";
-
-/// Opening indexListOffset tag
-///
-const OPENING_INDEX_LIST_OFFSET_TAG: &[u8; 17] = b"<indexListOffset>";
-
-/// Closing indexListOffset tag
-///
-const CLOSING_INDEX_LIST_OFFSET_TAG: &[u8; 18] = b"</indexListOffset>";
-
 #[derive(Clone)]
 pub enum MzMlElement {
     MzML(MzML<IndexedRun>),
@@ -80,7 +60,8 @@ where
         }
     }
 
-    /// Returns a spectrum by ID
+    /// Returns a spectrum by ID.
+    /// referenceableParamGroupRefs are resolved with the referenceableParamGroupList of the mzML.
     ///
     pub fn get_spectrum(&mut self, spectrum_id: &str) -> Result<Spectrum> {
         let offset = match self.index.get_spectra().get(spectrum_id) {
@@ -89,11 +70,33 @@ where
         };
 
         self.reader.seek(SeekFrom::Start(*offset as u64))?;
-        quick_xml::de::from_reader::<_, Spectrum>(&mut self.reader)
-            .context("Failed to parse spectrum")
+        let mut spectrum = quick_xml::de::from_reader::<_, Spectrum>(&mut self.reader)
+            .context("Failed to parse spectrum")?;
+        spectrum
+            .resolve_referenceable_param_groups(&self.get_mzml().referenceable_param_group_list)?;
+        Ok(spectrum)
+    }
+
+    /// Returns an iterator over all spectra in the order they are stored in the file.
+    /// Reading the spectra sequentially avoids a random access seek per spectrum ID,
+    /// which is favourable when most of the run needs to be processed, e.g. for XIC extraction.
+    ///
+    pub fn iter_spectra(&mut self) -> SpectrumIterator<'_, F> {
+        let mut offsets: Vec<usize> = self.index.get_spectra().values().copied().collect();
+        offsets.sort_unstable();
+        let mzml = match self.mzml_element {
+            MzMlElement::MzML(ref mzml) => mzml,
+            MzMlElement::IndexedMzML(ref indexed_mzml) => &indexed_mzml.mz_ml,
+        };
+        SpectrumIterator {
+            reader: self.reader,
+            referenceable_param_group_list: &mzml.referenceable_param_group_list,
+            offsets: offsets.into_iter(),
+        }
     }
 
-    /// Returns a chromatogram by ID
+    /// Returns a chromatogram by ID.
+    /// referenceableParamGroupRefs are resolved with the referenceableParamGroupList of the mzML.
     ///
     pub fn get_chromatogram(&mut self, chromatogram_id: &str) -> Result<Chromatogram> {
         let offset = match self.index.get_chromatograms().get(chromatogram_id) {
@@ -102,8 +105,11 @@ where
         };
 
         self.reader.seek(SeekFrom::Start(*offset as u64))?;
-        quick_xml::de::from_reader::<_, Chromatogram>(&mut self.reader)
-            .context("Failed to parse chromatogram")
+        let mut chromatogram = quick_xml::de::from_reader::<_, Chromatogram>(&mut self.reader)
+            .context("Failed to parse chromatogram")?;
+        chromatogram
+            .resolve_referenceable_param_groups(&self.get_mzml().referenceable_param_group_list)?;
+        Ok(chromatogram)
     }
 
     /// Returns a valid mzML with the given spectrum.
@@ -117,141 +123,69 @@ where
             if include_parents {
                 if let Some(precursor_list) = &spectrum.precursor_list {
                     for precursor in precursor_list.precursors.iter() {
-                        next_spec_ids.push(precursor.spectrum_ref.clone())
+                        if !precursor.spectrum_ref.is_empty() {
+                            next_spec_ids.push(precursor.spectrum_ref.clone())
+                        }
                     }
                 }
             }
             spectra.push(spectrum);
         }
-        spectra.sort_by(|x, y| x.index.cmp(&y.index));
-        let mzml = self.mzml_element.clone();
-
-        match mzml {
-            MzMlElement::MzML(mzml) => self.extract_spectrum_from_mzml(mzml, spectra),
-            MzMlElement::IndexedMzML(indexed_mzml) => {
-                self.extract_spectrum_from_indexed_mzml(indexed_mzml, spectra)
-            }
+        spectra.sort_by_key(|x| x.index);
+
+        let mut xml = std::io::Cursor::new(Vec::new());
+        let mut writer = Writer::new(&mut xml, self.get_mzml().clone())?
+            .with_indexed(matches!(self.mzml_element, MzMlElement::IndexedMzML(_)))
+            .with_spectrum_count(spectra.len());
+        for spectrum in spectra.iter() {
+            writer.write_spectrum(spectrum)?;
         }
+        writer.finish()?;
+        Ok(String::from_utf8(xml.into_inner())?)
     }
+}
 
-    fn extract_spectrum_from_mzml(
-        &mut self,
-        mzml_element: MzML<IndexedRun>,
-        spectra: Vec<Spectrum>,
-    ) -> Result<String> {
-        let mut mzml_element: MzML<Run> = mzml_element.into();
-        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
-        let mut ser = quick_xml::se::Serializer::with_root(&mut xml, Some("mzML"))?;
-        ser.indent(' ', 2);
-        mzml_element.run.spectrum_list.count = spectra.len();
-        mzml_element.run.spectrum_list.spectra = spectra;
-        mzml_element.serialize(ser)?;
-        Ok(xml)
-    }
-
-    fn extract_spectrum_from_indexed_mzml(
-        &mut self,
-        indexed_mzml_element: IndexedMzML<IndexedRun>,
-        spectra: Vec<Spectrum>,
-    ) -> Result<String> {
-        // prepare serialization
-        let mut indexed_mzml_element: IndexedMzML<Run> = indexed_mzml_element.into();
-        indexed_mzml_element.mz_ml.run.spectrum_list.count = spectra.len();
-        indexed_mzml_element.mz_ml.run.spectrum_list.spectra = spectra;
-
-        // serialize
-        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
-        let mut ser = quick_xml::se::Serializer::with_root(&mut xml, Some("indexedmzML"))?;
-        ser.indent(' ', 2);
-        indexed_mzml_element.serialize(ser)?;
-
-        // index the spectra
-        let mut cursor = std::io::Cursor::new(&xml);
-        let index = Indexer::create_index(&mut cursor, None)?;
-        indexed_mzml_element.index_list = index.into();
-
-        // serialize again with new index
-        xml.clear();
-        xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
-        let mut ser = quick_xml::se::Serializer::with_root(&mut xml, Some("indexedmzML"))?;
-        ser.indent(' ', 2);
-        indexed_mzml_element.serialize(ser)?;
-
-        // Set correct indexListOffset
-        let index_list_offset = xml
-            .find(String::from_utf8_lossy(OPENING_INDEX_LIST_TAG).as_ref())
-            .ok_or_else(|| {
-                anyhow!(
-                    "Failed to find `{}` tag",
-                    String::from_utf8_lossy(OPENING_INDEX_LIST_OFFSET_TAG)
-                )
-            })?;
-
-        let index_list_offset_start_position = xml
-            .find(String::from_utf8_lossy(OPENING_INDEX_LIST_OFFSET_TAG).as_ref())
-            .ok_or_else(|| {
-                anyhow!(
-                    "Failed to find `{}` tag",
-                    String::from_utf8_lossy(OPENING_INDEX_LIST_OFFSET_TAG)
-                )
-            })?
-            + OPENING_INDEX_LIST_OFFSET_TAG.len();
-
-        let index_list_offset_end_position = xml[index_list_offset_start_position..]
-            .find(String::from_utf8_lossy(CLOSING_INDEX_LIST_OFFSET_TAG).as_ref())
-            .ok_or_else(|| {
-                anyhow!(
-                    "Failed to find `{}` tag",
-                    String::from_utf8_lossy(CLOSING_INDEX_LIST_OFFSET_TAG)
-                )
-            })?
-            + index_list_offset_start_position;
-
-        xml = format!(
-            "{}{}{}",
-            &xml[..index_list_offset_start_position],
-            index_list_offset,
-            &xml[index_list_offset_end_position..]
-        );
+/// Iterator over the spectra of an mzML file in file order.
+/// Created by [File::iter_spectra].
+///
+pub struct SpectrumIterator<'a, F>
+where
+    F: BufRead + Seek,
+{
+    /// Internal reader
+    reader: &'a mut F,
+    /// referenceableParamGroupList of the mzML to resolve the spectra's references
+    referenceable_param_group_list: &'a ReferenceableParamGroupList,
+    /// Remaining spectrum offsets in ascending order
+    offsets: vec::IntoIter<usize>,
+}
 
-        // Calculate new filechecksum and replace it in the xml
-        let filechecksum_start_position = xml
-            .find(String::from_utf8_lossy(OPENING_FILECHECKSUM_TAG).as_ref())
-            .ok_or_else(|| {
-                anyhow!(
-                    "Failed to find `{}` tag",
-                    String::from_utf8_lossy(OPENING_FILECHECKSUM_TAG)
-                )
-            })?
-            + OPENING_FILECHECKSUM_TAG.len();
-
-        let filechecksum_end_position = xml[filechecksum_start_position..]
-            .find(String::from_utf8_lossy(CLOSING_FILECHECKSUM_TAG).as_ref())
-            .ok_or_else(|| {
-                anyhow!(
-                    "Failed to find `{}` tag",
-                    String::from_utf8_lossy(CLOSING_FILECHECKSUM_TAG)
-                )
-            })?
-            + filechecksum_start_position;
-
-        let mut hasher = Sha1::new();
-        hasher.update(&xml[..filechecksum_start_position]);
-
-        let hash_result = hasher.finalize();
-
-        // hex conversion
-        let mut hex_hash = String::with_capacity(2 * hash_result.len());
-        for byte in hash_result {
-            write!(hex_hash, "{:02x}", byte)?;
-        }
+impl<F> Iterator for SpectrumIterator<'_, F>
+where
+    F: BufRead + Seek,
+{
+    type Item = Result<Spectrum>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offsets.next()?;
+        Some(
+            self.reader
+                .seek(SeekFrom::Start(offset as u64))
+                .context("Failed to seek spectrum")
+                .and_then(|_| {
+                    quick_xml::de::from_reader::<_, Spectrum>(&mut self.reader)
+                        .context("Failed to parse spectrum")
+                })
+                .and_then(|mut spectrum| {
+                    spectrum
+                        .resolve_referenceable_param_groups(self.referenceable_param_group_list)?;
+                    Ok(spectrum)
+                }),
+        )
+    }
 
-        Ok(format!(
-            "{}{}{}",
-            &xml[..filechecksum_start_position],
-            hex_hash,
-            &xml[filechecksum_end_position..]
-        ))
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.offsets.size_hint()
     }
 }
 
@@ -302,16 +236,17 @@ where
         buffer_size: Option<usize>,
         force_reindex: bool,
         validate_mzml: bool,
-    ) -> Result<File<F>> {
+    ) -> Result<File<'_, F>> {
         mzml_file.seek(SeekFrom::Start(0))?;
 
         let (mzml_without_data, spectrum_offsets, chromatogram_offsets, is_indexed_mzml) =
             Reader::get_mzml_without_data(mzml_file, buffer_size, force_reindex)?;
 
         if is_indexed_mzml {
-            let indexed_mzml = quick_xml::de::from_reader::<_, IndexedMzML<IndexedRun>>(
+            let mut indexed_mzml = quick_xml::de::from_reader::<_, IndexedMzML<IndexedRun>>(
                 mzml_without_data.as_slice(),
             )?;
+            indexed_mzml.mz_ml.resolve_referenceable_param_groups()?;
             if validate_mzml {
                 indexed_mzml.validate()?;
             }
@@ -327,8 +262,9 @@ where
                 mzml_element: MzMlElement::IndexedMzML(indexed_mzml),
             })
         } else {
-            let mzml =
+            let mut mzml =
                 quick_xml::de::from_reader::<_, MzML<IndexedRun>>(mzml_without_data.as_slice())?;
+            mzml.resolve_referenceable_param_groups()?;
             if validate_mzml {
                 mzml.validate()?;
             }
@@ -355,17 +291,18 @@ where
         index: Index,
         buffer_size: Option<usize>,
         validate_mzml: bool,
-    ) -> Result<File<F>> {
+    ) -> Result<File<'_, F>> {
         mzml_file.seek(SeekFrom::Start(0))?;
 
         let (mzml_without_data, _, _, is_indexed_mzml) =
             Reader::get_mzml_without_data(mzml_file, buffer_size, false)?;
 
         if is_indexed_mzml {
-            let indexed_mzml = quick_xml::de::from_reader::<_, IndexedMzML<IndexedRun>>(
+            let mut indexed_mzml = quick_xml::de::from_reader::<_, IndexedMzML<IndexedRun>>(
                 mzml_without_data.as_slice(),
             )
             .context("Failed to parse indexed mzML")?;
+            indexed_mzml.mz_ml.resolve_referenceable_param_groups()?;
             if validate_mzml {
                 indexed_mzml.validate()?;
             }
@@ -376,9 +313,10 @@ where
                 mzml_element: MzMlElement::IndexedMzML(indexed_mzml),
             })
         } else {
-            let mzml =
+            let mut mzml =
                 quick_xml::de::from_reader::<_, MzML<IndexedRun>>(mzml_without_data.as_slice())
                     .context("Failed to parse mzML")?;
+            mzml.resolve_referenceable_param_groups()?;
             if validate_mzml {
                 mzml.validate()?;
             }
@@ -491,24 +429,18 @@ where
                         }
                     }
                 },
-                Ok(quick_xml::events::Event::Empty(ref e)) => {
-                    if add_content {
-                        Self::push_empty_event(&mut content, e);
-                    }
+                Ok(quick_xml::events::Event::Empty(ref e)) if add_content => {
+                    Self::push_empty_event(&mut content, e);
                 }
-                Ok(quick_xml::events::Event::Text(ref e)) => {
-                    if add_content {
-                        content.extend_from_slice(e);
-                    }
+                Ok(quick_xml::events::Event::Text(ref e)) if add_content => {
+                    content.extend_from_slice(e);
                 }
-                Ok(quick_xml::events::Event::Decl(ref e)) => {
-                    if add_content {
-                        content.push(b'<');
-                        content.push(b'?');
-                        content.extend_from_slice(e);
-                        content.push(b'?');
-                        content.push(b'>');
-                    }
+                Ok(quick_xml::events::Event::Decl(ref e)) if add_content => {
+                    content.push(b'<');
+                    content.push(b'?');
+                    content.extend_from_slice(e);
+                    content.push(b'?');
+                    content.push(b'>');
                 }
                 Ok(quick_xml::events::Event::Eof) => break,
                 Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
@@ -527,6 +459,8 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::proteomics::io::mzml::elements::has_cv_params::HasCvParams;
+    use crate::proteomics::io::mzml::indexer::Indexer;
 
     /// Check if the spectrum with id `controllerType=0 controllerNumber=1 scan=3865` has the correct
     /// data
@@ -725,4 +659,35 @@ mod test {
             .unwrap();
         test_spectrum_3865(spectrum);
     }
+
+    /// Test iterating the spectra in file order
+    #[test]
+    fn test_iter_spectra() {
+        let mut inner_reader =
+            std::io::BufReader::new(std::fs::File::open("test_files/spectra_small.mzML").unwrap());
+        let mut mzml_file = Reader::read_indexed(&mut inner_reader, None, false, false).unwrap();
+        let spectra = mzml_file
+            .iter_spectra()
+            .collect::<Result<Vec<Spectrum>>>()
+            .unwrap();
+        assert_eq!(spectra.len(), 11);
+        for (expected_index, spectrum) in spectra.iter().enumerate() {
+            assert_eq!(spectrum.index, expected_index);
+        }
+        test_spectrum_3865(spectra[3].clone());
+    }
+
+    /// Test resolving the referenceableParamGroupRef of the instrument configuration
+    #[test]
+    fn test_resolve_referenceable_param_groups() {
+        let mut inner_reader =
+            std::io::BufReader::new(std::fs::File::open("test_files/spectra_small.mzML").unwrap());
+        let mzml_file = Reader::read_indexed(&mut inner_reader, None, false, false).unwrap();
+        let instrument_configuration = &mzml_file
+            .get_mzml()
+            .instrument_configuration_list
+            .instrument_configurations[0];
+        assert!(instrument_configuration.cv_params.is_empty());
+        assert_eq!(instrument_configuration.get_cv_param("MS:1001911").len(), 1);
+    }
 }
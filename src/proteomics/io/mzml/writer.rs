@@ -0,0 +1,806 @@
+// std imports
+use core::fmt::Write as _;
+use std::collections::HashMap;
+use std::io::{Seek, Write};
+
+// 3rd party imports
+use anyhow::{bail, Context, Result};
+use quick_xml::escape::escape;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+
+// internal imports
+use super::elements::chromatogram::Chromatogram;
+use super::elements::index_list::IndexList;
+use super::elements::mz_ml::MzML;
+use super::elements::run::IndexedRun;
+use super::elements::spectrum::Spectrum;
+use super::index::Index;
+
+/// Number of spaces per indention level
+///
+const INDENT_SIZE: usize = 2;
+
+/// XML declaration
+///
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n";
+
+/// Namespace of indexedmzML
+///
+const INDEXED_MZML_XMLNS: &str = "http://psi.hupo.org/ms/mzml";
+
+/// XML schema instance namespace of indexedmzML
+///
+const INDEXED_MZML_XMLNS_XSI: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Schema location of indexedmzML
+///
+const INDEXED_MZML_SCHEMA_LOCATION: &str =
+    "http://psi.hupo.org/ms/mzml http://psidev.info/files/ms/mzML/xsd/mzML1.1.2_idx.xsd";
+
+/// Section of the file the writer is currently in
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WriterState {
+    Start,
+    Spectra,
+    Chromatograms,
+    Finished,
+}
+
+/// Streaming writer for mzML and indexedmzML.
+/// Spectra and chromatograms are written one by one, so runs of arbitrary size can be written
+/// without keeping them in memory. The byte offsets for the index and the SHA-1 file checksum
+/// are tracked while writing.
+/// As the `count` attributes of the spectrum and chromatogram lists precede the elements,
+/// the number of spectra and chromatograms needs to be known in advance.
+///
+pub struct Writer<'a, W>
+where
+    W: Write + Seek,
+{
+    /// Internal writer
+    writer: &'a mut W,
+    /// General information of the file, the run attributes are used for the run element
+    mzml: MzML<IndexedRun>,
+    /// Whether to write an indexedmzML
+    indexed: bool,
+    /// Number of spectra to write
+    spectrum_count: usize,
+    /// Number of chromatograms to write
+    chromatogram_count: usize,
+    /// Default data processing of the spectrum list
+    spectrum_default_data_processing_ref: String,
+    /// Default data processing of the chromatogram list
+    chromatogram_default_data_processing_ref: String,
+    /// Current section
+    state: WriterState,
+    /// Current position in the internal writer
+    position: usize,
+    /// Hasher for the file checksum
+    hasher: Sha1,
+    /// Spectrum offsets
+    spectra: HashMap<String, usize>,
+    /// Chromatogram offsets
+    chromatograms: HashMap<String, usize>,
+}
+
+impl<'a, W> Writer<'a, W>
+where
+    W: Write + Seek,
+{
+    /// Creates a new writer which writes an indexedmzML without spectra and chromatograms.
+    /// The default data processings of the spectrum and chromatogram list are taken from the run,
+    /// falling back to the first data processing.
+    /// Offsets are relative to the start of the internal writer, so it should be positioned at the beginning.
+    ///
+    /// # Arguments
+    /// * `writer` - Internal writer
+    /// * `mzml` - General information, e.g. from [crate::proteomics::io::mzml::reader::File::get_mzml]
+    ///
+    pub fn new(writer: &'a mut W, mzml: MzML<IndexedRun>) -> Result<Self> {
+        let position = writer
+            .stream_position()
+            .context("Failed to get position of writer")? as usize;
+        let first_data_processing_ref = match mzml.data_processing_list.data_processings.first() {
+            Some(data_processing) => data_processing.id.clone(),
+            None => bail!("mzML needs at least one data processing"),
+        };
+        let spectrum_default_data_processing_ref = match mzml.run.spectrum_list.as_ref() {
+            Some(spectrum_list) => spectrum_list.default_data_processing_ref.clone(),
+            None => first_data_processing_ref.clone(),
+        };
+        let chromatogram_default_data_processing_ref = match mzml.run.chromatogram_list.as_ref() {
+            Some(chromatogram_list) => chromatogram_list.default_data_processing_ref.clone(),
+            None => first_data_processing_ref,
+        };
+        Ok(Self {
+            writer,
+            mzml,
+            indexed: true,
+            spectrum_count: 0,
+            chromatogram_count: 0,
+            spectrum_default_data_processing_ref,
+            chromatogram_default_data_processing_ref,
+            state: WriterState::Start,
+            position,
+            hasher: Sha1::new(),
+            spectra: HashMap::new(),
+            chromatograms: HashMap::new(),
+        })
+    }
+
+    /// Sets whether an indexedmzML (default) or a plain mzML is written.
+    ///
+    /// # Arguments
+    /// * `indexed` - Write indexedmzML
+    ///
+    pub fn with_indexed(mut self, indexed: bool) -> Self {
+        self.indexed = indexed;
+        self
+    }
+
+    /// Sets the number of spectra which will be written
+    ///
+    /// # Arguments
+    /// * `spectrum_count` - Number of spectra
+    ///
+    pub fn with_spectrum_count(mut self, spectrum_count: usize) -> Self {
+        self.spectrum_count = spectrum_count;
+        self
+    }
+
+    /// Sets the number of chromatograms which will be written.
+    /// If zero (default) the chromatogram list is omitted.
+    ///
+    /// # Arguments
+    /// * `chromatogram_count` - Number of chromatograms
+    ///
+    pub fn with_chromatogram_count(mut self, chromatogram_count: usize) -> Self {
+        self.chromatogram_count = chromatogram_count;
+        self
+    }
+
+    /// Sets the default data processing of the spectrum and chromatogram list.
+    ///
+    /// # Arguments
+    /// * `default_data_processing_ref` - ID of a data processing
+    ///
+    pub fn with_default_data_processing_ref(
+        self,
+        default_data_processing_ref: String,
+    ) -> Result<Self> {
+        self.with_spectrum_default_data_processing_ref(default_data_processing_ref.clone())?
+            .with_chromatogram_default_data_processing_ref(default_data_processing_ref)
+    }
+
+    /// Sets the default data processing of the spectrum list.
+    ///
+    /// # Arguments
+    /// * `default_data_processing_ref` - ID of a data processing
+    ///
+    pub fn with_spectrum_default_data_processing_ref(
+        mut self,
+        default_data_processing_ref: String,
+    ) -> Result<Self> {
+        self.check_data_processing_ref(&default_data_processing_ref)?;
+        self.spectrum_default_data_processing_ref = default_data_processing_ref;
+        Ok(self)
+    }
+
+    /// Sets the default data processing of the chromatogram list.
+    ///
+    /// # Arguments
+    /// * `default_data_processing_ref` - ID of a data processing
+    ///
+    pub fn with_chromatogram_default_data_processing_ref(
+        mut self,
+        default_data_processing_ref: String,
+    ) -> Result<Self> {
+        self.check_data_processing_ref(&default_data_processing_ref)?;
+        self.chromatogram_default_data_processing_ref = default_data_processing_ref;
+        Ok(self)
+    }
+
+    /// Returns the index of the spectra and chromatograms written so far
+    ///
+    pub fn get_index(&self) -> Index {
+        Index::new(self.spectra.clone(), self.chromatograms.clone())
+    }
+
+    /// Writes a spectrum. All spectra must be written before the chromatograms.
+    ///
+    /// # Arguments
+    /// * `spectrum` - Spectrum
+    ///
+    pub fn write_spectrum(&mut self, spectrum: &Spectrum) -> Result<()> {
+        if self.state == WriterState::Start {
+            self.write_header()?;
+        }
+        if self.state != WriterState::Spectra {
+            bail!("Spectra must be written before the chromatograms");
+        }
+        if self.spectra.len() == self.spectrum_count {
+            bail!("Expected only {} spectra", self.spectrum_count);
+        }
+        if self.spectra.contains_key(&spectrum.id) {
+            bail!("Spectrum `{}` was already written", spectrum.id);
+        }
+        let offset = self
+            .write_element(spectrum, "spectrum", self.get_depth() + 3)
+            .context(format!("Failed to write spectrum `{}`", spectrum.id))?;
+        self.spectra.insert(spectrum.id.clone(), offset);
+        Ok(())
+    }
+
+    /// Writes a chromatogram
+    ///
+    /// # Arguments
+    /// * `chromatogram` - Chromatogram
+    ///
+    pub fn write_chromatogram(&mut self, chromatogram: &Chromatogram) -> Result<()> {
+        if self.state == WriterState::Start {
+            self.write_header()?;
+        }
+        if self.state == WriterState::Spectra {
+            self.close_spectrum_list()?;
+        }
+        if self.chromatograms.len() == self.chromatogram_count {
+            bail!("Expected only {} chromatograms", self.chromatogram_count);
+        }
+        if self.chromatograms.contains_key(&chromatogram.id) {
+            bail!("Chromatogram `{}` was already written", chromatogram.id);
+        }
+        let offset = self
+            .write_element(chromatogram, "chromatogram", self.get_depth() + 3)
+            .context(format!(
+                "Failed to write chromatogram `{}`",
+                chromatogram.id
+            ))?;
+        self.chromatograms.insert(chromatogram.id.clone(), offset);
+        Ok(())
+    }
+
+    /// Closes all open elements and writes the index list, index list offset and file checksum
+    /// for indexedmzML. Returns the index of the written file.
+    ///
+    pub fn finish(mut self) -> Result<Index> {
+        if self.state == WriterState::Start {
+            self.write_header()?;
+        }
+        if self.state == WriterState::Spectra {
+            self.close_spectrum_list()?;
+        }
+        if self.chromatograms.len() != self.chromatogram_count {
+            bail!(
+                "Expected {} chromatograms but got {}",
+                self.chromatogram_count,
+                self.chromatograms.len()
+            );
+        }
+        let depth = self.get_depth();
+        if self.chromatogram_count > 0 {
+            self.write_line("</chromatogramList>", depth + 2)?;
+        }
+        self.write_line("</run>", depth + 1)?;
+        self.write_line("</mzML>", depth)?;
+
+        let index = self.get_index();
+        if self.indexed {
+            let index_list: IndexList = index.clone().into();
+            let index_list_offset = self
+                .write_element(&index_list, "indexList", 1)
+                .context("Failed to write index list")?;
+            self.write_line(
+                &format!("<indexListOffset>{}</indexListOffset>", index_list_offset),
+                1,
+            )?;
+            // checksum covers everything up to and including the opening tag
+            self.write_str(&" ".repeat(INDENT_SIZE))?;
+            self.write_str("<fileChecksum>")?;
+            let hash_result = std::mem::take(&mut self.hasher).finalize();
+            // hex conversion
+            let mut hex_hash = String::with_capacity(2 * hash_result.len());
+            for byte in hash_result {
+                write!(hex_hash, "{:02x}", byte)?;
+            }
+            self.write_str(&hex_hash)?;
+            self.write_str("</fileChecksum>\n")?;
+            self.write_str("</indexedmzML>\n")?;
+        }
+        self.writer.flush()?;
+        self.state = WriterState::Finished;
+        Ok(index)
+    }
+
+    /// Checks if the data processing exists
+    ///
+    /// # Arguments
+    /// * `data_processing_ref` - ID of a data processing
+    ///
+    fn check_data_processing_ref(&self, data_processing_ref: &str) -> Result<()> {
+        if !self
+            .mzml
+            .data_processing_list
+            .data_processings
+            .iter()
+            .any(|data_processing| data_processing.id == data_processing_ref)
+        {
+            bail!("Data processing `{}` does not exist", data_processing_ref);
+        }
+        Ok(())
+    }
+
+    /// Returns the indention depth of the mzML element
+    ///
+    fn get_depth(&self) -> usize {
+        match self.indexed {
+            true => 1,
+            false => 0,
+        }
+    }
+
+    /// Writes everything up to the opening tag of the spectrum list
+    ///
+    fn write_header(&mut self) -> Result<()> {
+        self.write_str(XML_DECLARATION)?;
+        if self.indexed {
+            self.write_line(
+                &format!(
+                    "<indexedmzML xmlns=\"{}\" xmlns:xsi=\"{}\" xsi:schemaLocation=\"{}\">",
+                    INDEXED_MZML_XMLNS, INDEXED_MZML_XMLNS_XSI, INDEXED_MZML_SCHEMA_LOCATION
+                ),
+                0,
+            )?;
+        }
+        let depth = self.get_depth();
+        let mzml = self.mzml.clone();
+        self.write_line(
+            &format!(
+                "<mzML{}>",
+                format_attributes(&[
+                    ("xmlns", &mzml.xmlns),
+                    ("xmlns:xsi", &mzml.xmlns_xsi),
+                    ("xsi:schemaLocation", &mzml.xsi_schema_location),
+                    ("accession", mzml.accession.as_deref().unwrap_or_default()),
+                    ("id", &mzml.id),
+                    ("version", &mzml.version),
+                ])
+            ),
+            depth,
+        )?;
+        self.write_element(&mzml.cv_list, "cvList", depth + 1)?;
+        self.write_element(&mzml.file_description, "fileDescription", depth + 1)?;
+        if !mzml
+            .referenceable_param_group_list
+            .referenceable_param_groups
+            .is_empty()
+        {
+            self.write_element(
+                &mzml.referenceable_param_group_list,
+                "referenceableParamGroupList",
+                depth + 1,
+            )?;
+        }
+        if let Some(sample_list) = mzml.sample_list.as_ref() {
+            self.write_element(sample_list, "sampleList", depth + 1)?;
+        }
+        self.write_element(&mzml.software_list, "softwareList", depth + 1)?;
+        if let Some(scan_settings_list) = mzml.scan_settings_list.as_ref() {
+            self.write_element(scan_settings_list, "scanSettingsList", depth + 1)?;
+        }
+        self.write_element(
+            &mzml.instrument_configuration_list,
+            "instrumentConfigurationList",
+            depth + 1,
+        )?;
+        self.write_element(&mzml.data_processing_list, "dataProcessingList", depth + 1)?;
+        self.write_line(
+            &format!(
+                "<run{}>",
+                format_attributes(&[
+                    ("id", &mzml.run.id),
+                    (
+                        "defaultInstrumentConfigurationRef",
+                        &mzml.run.default_instrument_configuration_ref
+                    ),
+                    ("defaultSourceFileRef", &mzml.run.default_source_file_ref),
+                    (
+                        "sampleRef",
+                        mzml.run.sample_ref.as_deref().unwrap_or_default()
+                    ),
+                    ("startTimeStamp", &mzml.run.start_time_stamp),
+                ])
+            ),
+            depth + 1,
+        )?;
+        for referenceable_param_group_ref in mzml.run.referenceable_param_group_ref.iter() {
+            self.write_element(
+                referenceable_param_group_ref,
+                "referenceableParamGroupRef",
+                depth + 2,
+            )?;
+        }
+        for cv_param in mzml.run.cv_params.iter() {
+            self.write_element(cv_param, "cvParam", depth + 2)?;
+        }
+        for user_param in mzml.run.user_params.iter() {
+            self.write_element(user_param, "userParam", depth + 2)?;
+        }
+        self.write_line(
+            &format!(
+                "<spectrumList{}>",
+                format_attributes(&[
+                    ("count", &self.spectrum_count.to_string()),
+                    (
+                        "defaultDataProcessingRef",
+                        &self.spectrum_default_data_processing_ref
+                    ),
+                ])
+            ),
+            depth + 2,
+        )?;
+        self.state = WriterState::Spectra;
+        Ok(())
+    }
+
+    /// Closes the spectrum list and opens the chromatogram list
+    ///
+    fn close_spectrum_list(&mut self) -> Result<()> {
+        if self.spectra.len() != self.spectrum_count {
+            bail!(
+                "Expected {} spectra but got {}",
+                self.spectrum_count,
+                self.spectra.len()
+            );
+        }
+        let depth = self.get_depth();
+        self.write_line("</spectrumList>", depth + 2)?;
+        if self.chromatogram_count > 0 {
+            self.write_line(
+                &format!(
+                    "<chromatogramList{}>",
+                    format_attributes(&[
+                        ("count", &self.chromatogram_count.to_string()),
+                        (
+                            "defaultDataProcessingRef",
+                            &self.chromatogram_default_data_processing_ref
+                        ),
+                    ])
+                ),
+                depth + 2,
+            )?;
+        }
+        self.state = WriterState::Chromatograms;
+        Ok(())
+    }
+
+    /// Serializes the element and writes it indented to the given depth.
+    /// Returns the offset of the opening tag.
+    ///
+    /// # Arguments
+    /// * `element` - Element to serialize
+    /// * `root` - Tag name of the element
+    /// * `depth` - Indention depth
+    ///
+    fn write_element<T>(&mut self, element: &T, root: &str, depth: usize) -> Result<usize>
+    where
+        T: Serialize,
+    {
+        let mut xml = String::new();
+        let mut serializer = quick_xml::se::Serializer::with_root(&mut xml, Some(root))?;
+        serializer.indent(' ', INDENT_SIZE);
+        element.serialize(serializer)?;
+        let indention = " ".repeat(depth * INDENT_SIZE);
+        self.write_str(&indention)?;
+        let offset = self.position;
+        self.write_str(&xml.replace('\n', &format!("\n{}", indention)))?;
+        self.write_str("\n")?;
+        Ok(offset)
+    }
+
+    /// Writes the line indented to the given depth
+    ///
+    /// # Arguments
+    /// * `line` - Line without line break
+    /// * `depth` - Indention depth
+    ///
+    fn write_line(&mut self, line: &str, depth: usize) -> Result<()> {
+        self.write_str(&" ".repeat(depth * INDENT_SIZE))?;
+        self.write_str(line)?;
+        self.write_str("\n")
+    }
+
+    /// Writes the string, updates the position and the checksum
+    ///
+    /// # Arguments
+    /// * `content` - Content to write
+    ///
+    fn write_str(&mut self, content: &str) -> Result<()> {
+        if self.state == WriterState::Finished {
+            bail!("Writer is already finished");
+        }
+        self.writer.write_all(content.as_bytes())?;
+        self.hasher.update(content.as_bytes());
+        self.position += content.len();
+        Ok(())
+    }
+}
+
+/// Formats the attributes for an opening tag, skipping empty values
+///
+/// # Arguments
+/// * `attributes` - Attribute names and values
+///
+fn format_attributes(attributes: &[(&str, &str)]) -> String {
+    attributes
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| format!(" {}=\"{}\"", name, escape(*value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proteomics::io::mzml::elements::has_cv_params::HasCvParams;
+    use crate::proteomics::io::mzml::indexer::Indexer;
+    use crate::proteomics::io::mzml::reader::Reader;
+
+    /// Returns the general information, spectra and chromatograms of the given test file
+    ///
+    /// # Arguments
+    /// * `path` - Path to the test file
+    ///
+    fn read_test_file(path: &str) -> (MzML<IndexedRun>, Vec<Spectrum>, Vec<Chromatogram>) {
+        let mut inner_reader = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+        let mut mzml_file = Reader::read_indexed(&mut inner_reader, None, false, false).unwrap();
+        let mzml = mzml_file.get_mzml().clone();
+        let spectra = mzml_file
+            .iter_spectra()
+            .collect::<Result<Vec<Spectrum>>>()
+            .unwrap();
+        let mut chromatogram_ids: Vec<String> = mzml_file
+            .get_index()
+            .get_chromatograms()
+            .keys()
+            .cloned()
+            .collect();
+        chromatogram_ids.sort();
+        let chromatograms = chromatogram_ids
+            .iter()
+            .map(|id| mzml_file.get_chromatogram(id).unwrap())
+            .collect();
+        (mzml, spectra, chromatograms)
+    }
+
+    #[test]
+    fn test_write_indexed_mzml() {
+        let (mzml, spectra, chromatograms) = read_test_file("test_files/spectra_small.mzML");
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = Writer::new(&mut cursor, mzml)
+            .unwrap()
+            .with_spectrum_count(spectra.len())
+            .with_chromatogram_count(chromatograms.len());
+        for spectrum in spectra.iter() {
+            writer.write_spectrum(spectrum).unwrap();
+        }
+        for chromatogram in chromatograms.iter() {
+            writer.write_chromatogram(chromatogram).unwrap();
+        }
+        let index = writer.finish().unwrap();
+        let xml = String::from_utf8(cursor.into_inner()).unwrap();
+
+        // offsets match the ones of the indexer
+        let created_index =
+            Indexer::create_index(&mut std::io::Cursor::new(xml.as_bytes()), None).unwrap();
+        assert_eq!(index.get_spectra(), created_index.get_spectra());
+        assert_eq!(index.get_chromatograms(), created_index.get_chromatograms());
+        assert_eq!(index.get_spectra().len(), spectra.len());
+
+        // index list offset points to the index list
+        let index_list_offset: usize = xml
+            .split("<indexListOffset>")
+            .nth(1)
+            .unwrap()
+            .split("</indexListOffset>")
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(xml[index_list_offset..].starts_with("<indexList "));
+
+        // checksum
+        let checksum_start = xml.find("<fileChecksum>").unwrap() + "<fileChecksum>".len();
+        let mut hasher = Sha1::new();
+        hasher.update(&xml[..checksum_start]);
+        let expected_checksum: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        assert_eq!(
+            &xml[checksum_start..checksum_start + expected_checksum.len()],
+            expected_checksum
+        );
+
+        // read the written file with the written index
+        let mut inner_reader = std::io::Cursor::new(xml);
+        let mut mzml_file = Reader::read_indexed(&mut inner_reader, None, false, false).unwrap();
+        let read_spectra = mzml_file
+            .iter_spectra()
+            .collect::<Result<Vec<Spectrum>>>()
+            .unwrap();
+        assert_eq!(read_spectra.len(), spectra.len());
+        for (read_spectrum, spectrum) in read_spectra.iter().zip(spectra.iter()) {
+            assert_eq!(read_spectrum.id, spectrum.id);
+            assert_eq!(read_spectrum.index, spectrum.index);
+        }
+        assert_eq!(
+            mzml_file.get_chromatogram(&chromatograms[0].id).unwrap().id,
+            chromatograms[0].id
+        );
+    }
+
+    #[test]
+    fn test_write_mzml() {
+        let (mzml, spectra, _) = read_test_file("test_files/spectra_small.mzML");
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = Writer::new(&mut cursor, mzml)
+            .unwrap()
+            .with_indexed(false)
+            .with_spectrum_count(2);
+        writer.write_spectrum(&spectra[0]).unwrap();
+        writer.write_spectrum(&spectra[3]).unwrap();
+        writer.finish().unwrap();
+        let xml = String::from_utf8(cursor.into_inner()).unwrap();
+        assert!(!xml.contains("<indexedmzML"));
+        assert!(!xml.contains("<chromatogramList"));
+
+        let mut inner_reader = std::io::Cursor::new(xml);
+        let mut mzml_file = Reader::read_indexed(&mut inner_reader, None, false, false).unwrap();
+        assert_eq!(mzml_file.get_index().get_spectra().len(), 2);
+        assert_eq!(
+            mzml_file.get_spectrum(&spectra[3].id).unwrap().index,
+            spectra[3].index
+        );
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let (mzml, spectra, chromatograms) = read_test_file("test_files/spectra_small.mzML");
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        assert!(Writer::new(&mut cursor, mzml.clone())
+            .unwrap()
+            .with_default_data_processing_ref("unknown".to_string())
+            .is_err());
+
+        // more spectra than announced
+        let mut writer = Writer::new(&mut cursor, mzml.clone())
+            .unwrap()
+            .with_spectrum_count(1)
+            .with_chromatogram_count(1);
+        writer.write_spectrum(&spectra[0]).unwrap();
+        assert!(writer.write_spectrum(&spectra[1]).is_err());
+        // spectra after chromatograms
+        writer.write_chromatogram(&chromatograms[0]).unwrap();
+        assert!(writer.write_spectrum(&spectra[1]).is_err());
+
+        // less spectra than announced
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = Writer::new(&mut cursor, mzml)
+            .unwrap()
+            .with_spectrum_count(2);
+        writer.write_spectrum(&spectra[0]).unwrap();
+        assert!(writer.write_spectrum(&spectra[0]).is_err());
+        assert!(writer.finish().is_err());
+    }
+
+    /// Returns the elements, attributes and texts of the XML in document order,
+    /// ignoring attribute order, whitespace and the difference between empty and start/end tags
+    ///
+    /// # Arguments
+    /// * `xml` - XML document
+    ///
+    fn get_normalized_events(xml: &str) -> Vec<String> {
+        let mut reader = quick_xml::Reader::from_str(xml);
+        let mut events = Vec::new();
+        loop {
+            match reader.read_event().unwrap() {
+                event @ (quick_xml::events::Event::Start(_)
+                | quick_xml::events::Event::Empty(_)) => {
+                    let (e, is_empty) = match event {
+                        quick_xml::events::Event::Start(ref e) => (e.clone(), false),
+                        quick_xml::events::Event::Empty(ref e) => (e.clone(), true),
+                        _ => unreachable!(),
+                    };
+                    let mut attributes: Vec<String> = e
+                        .attributes()
+                        .map(|attribute| {
+                            let attribute = attribute.unwrap();
+                            format!(
+                                "{}=\"{}\"",
+                                String::from_utf8_lossy(attribute.key.as_ref()),
+                                attribute.unescape_value().unwrap()
+                            )
+                        })
+                        .collect();
+                    attributes.sort();
+                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    events.push(format!("<{} {}>", name, attributes.join(" ")));
+                    if is_empty {
+                        events.push(format!("</{}>", name));
+                    }
+                }
+                quick_xml::events::Event::End(ref e) => {
+                    events.push(format!("</{}>", String::from_utf8_lossy(e.name().as_ref())));
+                }
+                quick_xml::events::Event::Text(ref e) => {
+                    let text = e.unescape().unwrap();
+                    if !text.trim().is_empty() {
+                        events.push(text.trim().to_string());
+                    }
+                }
+                quick_xml::events::Event::Eof => break,
+                _ => (),
+            }
+        }
+        events
+    }
+
+    /// Writes the mzML file read from the path and compares the written XML to the original
+    ///
+    /// # Arguments
+    /// * `path` - Path to the mzML file
+    ///
+    fn assert_round_trip(path: &str) {
+        let (mzml, spectra, chromatograms) = read_test_file(path);
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = Writer::new(&mut cursor, mzml)
+            .unwrap()
+            .with_indexed(false)
+            .with_spectrum_count(spectra.len())
+            .with_chromatogram_count(chromatograms.len());
+        for spectrum in spectra.iter() {
+            writer.write_spectrum(spectrum).unwrap();
+        }
+        for chromatogram in chromatograms.iter() {
+            writer.write_chromatogram(chromatogram).unwrap();
+        }
+        writer.finish().unwrap();
+        let xml = String::from_utf8(cursor.into_inner()).unwrap();
+
+        let original = std::fs::read_to_string(path).unwrap();
+        assert_eq!(
+            get_normalized_events(&xml),
+            get_normalized_events(&original)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (mzml, spectra, _) = read_test_file("test_files/full_schema.mzML");
+
+        // referenced groups are resolved
+        assert_eq!(mzml.run.get_cv_param("MS:1001911").len(), 1);
+        assert_eq!(spectra[0].get_cv_param("MS:1000579").len(), 1);
+        assert_eq!(spectra[0].get_ms_level(), Some(1));
+        // optional scan list, scan window list, component list and binary data arrays
+        assert!(spectra[1].scan_list.as_ref().unwrap().scans[0]
+            .scan_window_list
+            .is_none());
+        assert!(
+            mzml.instrument_configuration_list.instrument_configurations[1]
+                .component_list
+                .is_none()
+        );
+        assert!(spectra[2].scan_list.is_none());
+        assert!(spectra[2].binary_data_array_list.is_none());
+        assert_round_trip("test_files/full_schema.mzML");
+
+        // optional source file list
+        let (mzml, _, _) = read_test_file("test_files/optional_elements.mzML");
+        assert!(mzml.file_description.source_file_list.is_none());
+        assert_round_trip("test_files/optional_elements.mzML");
+    }
+}
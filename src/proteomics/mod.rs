@@ -1,3 +1,7 @@
+/// Annotation of spectrum peaks with peptide fragment ions
+pub mod annotation;
+/// Theoretical fragment ions of peptides
+pub mod fragmentation;
 /// I/O for various proteomics data formats   
 /// Note: Most operation on file content is working with byte representation not strings as this get rid of any encoding/decoding issues and overhead(?).
 pub mod io;
@@ -8,3 +12,9 @@ pub mod peptide;
 pub mod post_translational_modifications;
 /// Defines proteases
 pub mod proteases;
+/// Quantification of peptides and proteins
+pub mod quantification;
+/// Peptide-spectrum match scoring functions
+pub mod scoring;
+/// Localisation of modification sites, e.g. phosphorylation sites
+pub mod site_localization;
@@ -6,14 +6,20 @@ use std::str::FromStr;
 use anyhow::{bail, Context, Error, Result};
 
 // internal imports
-use crate::chemistry::amino_acid::{get_amino_acid_by_one_letter_code, get_hydropathicity_kd};
+use crate::chemistry::amino_acid::labeled_amino_acid::LabeledAminoAcid;
+use crate::chemistry::amino_acid::{
+    get_amino_acid_by_one_letter_code, get_hydropathicity_kd, AminoAcid,
+};
 use crate::chemistry::element::get_element_by_symbol;
+use crate::proteomics::post_translational_modifications::{
+    Position, PostTranslationalModification,
+};
 
 lazy_static! {
     /// Monoisotopic mass of water
     /// TODO: If there is any plans to add a molecule module, this constant should be moved there.
     ///
-    static ref WATER_MASS: f64 = get_element_by_symbol("H").unwrap().get_mono_mass() * 2.0
+    pub static ref WATER_MASS: f64 = get_element_by_symbol("H").unwrap().get_mono_mass() * 2.0
         + get_element_by_symbol("O").unwrap().get_mono_mass();
 }
 
@@ -62,6 +68,38 @@ pub fn calculate_mass_of_peptide_sequence(sequence: &str) -> Result<f64> {
             .sum::<Result<f64>>()?)
 }
 
+/// Calculates the mass of a peptide sequence where residues are replaced by labeled amino acids,
+/// e.g. the heavy SILAC channel.
+///
+/// # Arguments
+/// * `sequence` - A peptide sequence
+/// * `labels` - Labeled amino acids, at most one per residue
+///
+pub fn calculate_mass_of_labeled_peptide_sequence(
+    sequence: &str,
+    labels: &[&LabeledAminoAcid],
+) -> Result<f64> {
+    for (idx, label) in labels.iter().enumerate() {
+        if labels[idx + 1..]
+            .iter()
+            .any(|other| other.get_code() == label.get_code())
+        {
+            bail!("Multiple labels for residue {}", label.get_code());
+        }
+    }
+    let mass_shift: f64 = labels
+        .iter()
+        .map(|label| {
+            sequence
+                .chars()
+                .filter(|code| code.to_ascii_uppercase() == *label.get_code())
+                .count() as f64
+                * label.get_mass_shift()
+        })
+        .sum();
+    Ok(calculate_mass_of_peptide_sequence(sequence)? + mass_shift)
+}
+
 /// Calculates the GRand AVerage of hydropathicity (GRAVY) of the peptide sequence.
 /// using the Kyte-Doolittle hydropathicity.
 ///
@@ -86,6 +124,7 @@ pub fn calculate_gravy_kd(sequence: &str) -> Result<f64> {
 pub struct Peptide {
     sequence: String,
     missed_cleavages: usize,
+    labels: Vec<&'static LabeledAminoAcid>,
     mass: f64,
 }
 
@@ -95,10 +134,29 @@ impl Peptide {
         Ok(Self {
             sequence,
             missed_cleavages,
+            labels: Vec::new(),
             mass,
         })
     }
 
+    /// Replaces the residues by the labeled amino acids, e.g. for the heavy SILAC channel.
+    /// The mass is updated accordingly.
+    ///
+    /// # Arguments
+    /// * `labels` - Labeled amino acids, at most one per residue
+    ///
+    pub fn with_labels(mut self, labels: Vec<&'static LabeledAminoAcid>) -> Result<Self> {
+        self.mass = calculate_mass_of_labeled_peptide_sequence(&self.sequence, &labels)?;
+        self.labels = labels;
+        Ok(self)
+    }
+
+    /// Returns the labeled amino acids
+    ///
+    pub fn get_labels(&self) -> &Vec<&'static LabeledAminoAcid> {
+        &self.labels
+    }
+
     /// Returns the sequence
     ///
     pub fn get_sequence(&self) -> &String {
@@ -118,9 +176,218 @@ impl Peptide {
     }
 }
 
+/// One letter code of the unknown amino acid. Terminal modifications targeting it
+/// can be placed on any residue, e.g. labels of the peptide N-terminus.
+///
+const ANY_AMINO_ACID_CODE: char = 'X';
+
+/// Peptide with at most one modification per residue and one per terminus.
+/// Terminal modifications (`Position::Terminus`) are kept separately from the
+/// residue modifications, so e.g. a labeled N-terminus and a labeled lysine side chain
+/// can be placed on the same residue.
+///
+#[derive(Clone)]
+pub struct ModifiedPeptide {
+    peptide: Peptide,
+    /// One entry per residue
+    modifications: Vec<Option<PostTranslationalModification>>,
+    n_terminal_modification: Option<PostTranslationalModification>,
+    c_terminal_modification: Option<PostTranslationalModification>,
+    mass: f64,
+}
+
+impl ModifiedPeptide {
+    /// Creates a new modified peptide.
+    /// Checks if the modifications match the residue and their position.
+    /// Terminal modifications are placed on the first or last residue respectively.
+    ///
+    /// # Arguments
+    /// * `peptide` - Unmodified peptide
+    /// * `modifications` - Modifications with their (0-based) residue index
+    ///
+    pub fn new(
+        peptide: Peptide,
+        modifications: Vec<(usize, PostTranslationalModification)>,
+    ) -> Result<Self> {
+        let sequence: Vec<char> = peptide.get_sequence().chars().collect();
+        let mut residue_modifications: Vec<Option<PostTranslationalModification>> =
+            vec![None; sequence.len()];
+        let mut n_terminal_modification: Option<PostTranslationalModification> = None;
+        let mut c_terminal_modification: Option<PostTranslationalModification> = None;
+        for (idx, modification) in modifications.into_iter() {
+            if idx >= sequence.len() {
+                bail!(
+                    "Modification `{}` at position {} is out of bounds for peptide `{}`",
+                    modification.get_name(),
+                    idx,
+                    peptide.get_sequence()
+                );
+            }
+            let target_code = *modification.get_amino_acid().get_code();
+            let matches_any_residue =
+                modification.is_terminus() && target_code == ANY_AMINO_ACID_CODE;
+            if !matches_any_residue && !sequence[idx].eq_ignore_ascii_case(&target_code) {
+                bail!(
+                    "Modification `{}` targets `{}` but residue {} of `{}` is `{}`",
+                    modification.get_name(),
+                    target_code,
+                    idx,
+                    peptide.get_sequence(),
+                    sequence[idx]
+                );
+            }
+            let slot = match modification.get_position() {
+                Position::Terminus(Terminus::N) if idx != 0 => bail!(
+                    "N-terminal modification `{}` must be placed on the first residue",
+                    modification.get_name()
+                ),
+                Position::Terminus(Terminus::C) if idx != sequence.len() - 1 => bail!(
+                    "C-terminal modification `{}` must be placed on the last residue",
+                    modification.get_name()
+                ),
+                Position::Terminus(Terminus::N) => &mut n_terminal_modification,
+                Position::Terminus(Terminus::C) => &mut c_terminal_modification,
+                _ => &mut residue_modifications[idx],
+            };
+            if slot.is_some() {
+                bail!(
+                    "Residue {} of `{}` is modified more than once",
+                    idx,
+                    peptide.get_sequence()
+                );
+            }
+            *slot = Some(modification);
+        }
+        let mass = peptide.get_mass()
+            + residue_modifications
+                .iter()
+                .chain([&n_terminal_modification, &c_terminal_modification])
+                .flatten()
+                .map(|modification| modification.get_mass_delta())
+                .sum::<f64>();
+        Ok(Self {
+            peptide,
+            modifications: residue_modifications,
+            n_terminal_modification,
+            c_terminal_modification,
+            mass,
+        })
+    }
+
+    /// Returns the unmodified peptide
+    ///
+    pub fn get_peptide(&self) -> &Peptide {
+        &self.peptide
+    }
+
+    /// Returns the sequence
+    ///
+    pub fn get_sequence(&self) -> &String {
+        self.peptide.get_sequence()
+    }
+
+    /// Returns the modification of each residue, without terminal modifications
+    ///
+    pub fn get_modifications(&self) -> &[Option<PostTranslationalModification>] {
+        &self.modifications
+    }
+
+    /// Returns the modification of the N-terminus
+    ///
+    pub fn get_n_terminal_modification(&self) -> Option<&PostTranslationalModification> {
+        self.n_terminal_modification.as_ref()
+    }
+
+    /// Returns the modification of the C-terminus
+    ///
+    pub fn get_c_terminal_modification(&self) -> Option<&PostTranslationalModification> {
+        self.c_terminal_modification.as_ref()
+    }
+
+    /// Returns the mass including the modifications
+    ///
+    pub fn get_mass(&self) -> f64 {
+        self.mass
+    }
+
+    /// Returns the monoisotopic masses of each residue including the labels and modifications.
+    /// Terminal modifications are added to the first or last residue respectively.
+    ///
+    pub fn get_residue_masses(&self) -> Result<Vec<f64>> {
+        let last_idx = self.modifications.len().saturating_sub(1);
+        self.get_sequence()
+            .chars()
+            .zip(self.modifications.iter())
+            .enumerate()
+            .map(|(idx, (code, modification))| {
+                let terminal_modifications = [
+                    self.n_terminal_modification.as_ref().filter(|_| idx == 0),
+                    self.c_terminal_modification
+                        .as_ref()
+                        .filter(|_| idx == last_idx),
+                ];
+                let label_mass_shift = self
+                    .peptide
+                    .get_labels()
+                    .iter()
+                    .find(|label| *label.get_code() == code.to_ascii_uppercase())
+                    .map(|label| label.get_mass_shift())
+                    .unwrap_or(0.0);
+                Ok(*get_amino_acid_by_one_letter_code(code)?.get_mono_mass()
+                    + label_mass_shift
+                    + modification
+                        .iter()
+                        .chain(terminal_modifications.into_iter().flatten())
+                        .map(|modification| modification.get_mass_delta())
+                        .sum::<f64>())
+            })
+            .collect()
+    }
+}
+
+impl Display for ModifiedPeptide {
+    /// Writes the sequence with the modification names in brackets after the modified residue,
+    /// e.g. `PEPM[Oxidation]TIDE`. Terminal modifications are written before or after the sequence
+    /// separated by a dash, e.g. `[TMT6plex]-K[TMT6plex]PEPTIDE`
+    ///
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(modification) = self.n_terminal_modification.as_ref() {
+            write!(f, "[{}]-", modification.get_name())?;
+        }
+        for (code, modification) in self.get_sequence().chars().zip(self.modifications.iter()) {
+            write!(f, "{}", code)?;
+            if let Some(modification) = modification {
+                write!(f, "[{}]", modification.get_name())?;
+            }
+        }
+        if let Some(modification) = self.c_terminal_modification.as_ref() {
+            write!(f, "-[{}]", modification.get_name())?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Peptide> for ModifiedPeptide {
+    fn from(peptide: Peptide) -> Self {
+        let modifications = vec![None; peptide.get_sequence().len()];
+        let mass = peptide.get_mass();
+        Self {
+            peptide,
+            modifications,
+            n_terminal_modification: None,
+            c_terminal_modification: None,
+            mass,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chemistry::amino_acid::labeled_amino_acid::{
+        ARGININE_13C6_15N4, LYSINE_13C6, LYSINE_13C6_15N2,
+    };
+    use crate::proteomics::post_translational_modifications::ModificationType;
 
     #[test]
     fn test_terminus_from_str() {
@@ -148,6 +415,52 @@ mod tests {
         assert_eq!(mass, 5285.286805615);
     }
 
+    #[test]
+    fn test_calculate_mass_of_labeled_peptide_sequence() {
+        let mass = calculate_mass_of_peptide_sequence("PEPKTIDER").unwrap();
+        let labeled_mass = calculate_mass_of_labeled_peptide_sequence(
+            "PEPKTIDER",
+            &[&LYSINE_13C6_15N2, &ARGININE_13C6_15N4],
+        )
+        .unwrap();
+        assert!((labeled_mass - mass - 8.014199 - 10.008269).abs() < 1e-6);
+        assert!(calculate_mass_of_labeled_peptide_sequence(
+            "PEPKTIDER",
+            &[&LYSINE_13C6_15N2, &LYSINE_13C6]
+        )
+        .is_err());
+
+        // same mass when using the label as static modification
+        let label = PostTranslationalModification::from_labeled_amino_acid(
+            &LYSINE_13C6_15N2,
+            ModificationType::Static,
+        );
+        let peptide = Peptide::new("PEPKTIDER".to_string(), 0).unwrap();
+        let modified_peptide = ModifiedPeptide::new(peptide, vec![(3, label)]).unwrap();
+        assert!((modified_peptide.get_mass() - mass - 8.014199).abs() < 1e-6);
+        assert_eq!(modified_peptide.to_string(), "PEPK[Lys8]TIDER");
+
+        // labeled peptide
+        let peptide = Peptide::new("PEPKTIDER".to_string(), 0)
+            .unwrap()
+            .with_labels(vec![&LYSINE_13C6_15N2, &ARGININE_13C6_15N4])
+            .unwrap();
+        assert_eq!(peptide.get_mass(), labeled_mass);
+        assert_eq!(peptide.get_labels().len(), 2);
+        let modified_peptide = ModifiedPeptide::from(peptide);
+        assert_eq!(modified_peptide.get_mass(), labeled_mass);
+        let residue_mass_sum: f64 = modified_peptide.get_residue_masses().unwrap().iter().sum();
+        assert!(
+            (residue_mass_sum + calculate_mass_of_peptide_sequence("").unwrap() - labeled_mass)
+                .abs()
+                < 1e-6
+        );
+        assert!(Peptide::new("PEPKTIDER".to_string(), 0)
+            .unwrap()
+            .with_labels(vec![&LYSINE_13C6_15N2, &LYSINE_13C6])
+            .is_err());
+    }
+
     #[test]
     fn test_calculate_gravy_kd() {
         let gravy = calculate_gravy_kd("EQKLISEEDL").unwrap();
@@ -157,4 +470,79 @@ mod tests {
 
         assert!(calculate_gravy_kd("EQKLISEEDLO").is_err());
     }
+
+    #[test]
+    fn test_modified_peptide() {
+        let oxidation = PostTranslationalModification::new(
+            "Oxidation",
+            get_amino_acid_by_one_letter_code('M').unwrap(),
+            15.994915,
+            ModificationType::Variable,
+            Position::Anywhere,
+        );
+        let peptide = Peptide::new("PEPMTIDEK".to_string(), 0).unwrap();
+        let modified_peptide =
+            ModifiedPeptide::new(peptide.clone(), vec![(3, oxidation.clone())]).unwrap();
+        assert_eq!(modified_peptide.get_mass(), peptide.get_mass() + 15.994915);
+        assert!(modified_peptide.get_modifications()[3].is_some());
+        assert_eq!(modified_peptide.to_string(), "PEPM[Oxidation]TIDEK");
+        let residue_mass_sum: f64 = modified_peptide.get_residue_masses().unwrap().iter().sum();
+        assert!((residue_mass_sum + *WATER_MASS - modified_peptide.get_mass()).abs() < 1e-9);
+        // wrong residue
+        assert!(ModifiedPeptide::new(peptide.clone(), vec![(2, oxidation.clone())]).is_err());
+        // out of bounds
+        assert!(ModifiedPeptide::new(peptide, vec![(9, oxidation)]).is_err());
+    }
+
+    #[test]
+    fn test_modified_peptide_with_terminal_modification() {
+        let n_terminal_label = PostTranslationalModification::new(
+            "TMT6plex",
+            get_amino_acid_by_one_letter_code('X').unwrap(),
+            229.162932,
+            ModificationType::Static,
+            Position::Terminus(Terminus::N),
+        );
+        let lysine_label = PostTranslationalModification::new(
+            "TMT6plex",
+            get_amino_acid_by_one_letter_code('K').unwrap(),
+            229.162932,
+            ModificationType::Static,
+            Position::Anywhere,
+        );
+        let peptide = Peptide::new("KPEPTIDEK".to_string(), 0).unwrap();
+        // N-terminal and side chain label on the same lysine
+        let modified_peptide = ModifiedPeptide::new(
+            peptide.clone(),
+            vec![
+                (0, n_terminal_label.clone()),
+                (0, lysine_label.clone()),
+                (8, lysine_label.clone()),
+            ],
+        )
+        .unwrap();
+        assert!((modified_peptide.get_mass() - peptide.get_mass() - 3.0 * 229.162932).abs() < 1e-9);
+        assert!(modified_peptide.get_n_terminal_modification().is_some());
+        assert!(modified_peptide.get_c_terminal_modification().is_none());
+        assert!(modified_peptide.get_modifications()[0].is_some());
+        assert_eq!(
+            modified_peptide.to_string(),
+            "[TMT6plex]-K[TMT6plex]PEPTIDEK[TMT6plex]"
+        );
+        let residue_masses = modified_peptide.get_residue_masses().unwrap();
+        let lysine_mass = *get_amino_acid_by_one_letter_code('K')
+            .unwrap()
+            .get_mono_mass();
+        assert!((residue_masses[0] - lysine_mass - 2.0 * 229.162932).abs() < 1e-9);
+        let residue_mass_sum: f64 = residue_masses.iter().sum();
+        assert!((residue_mass_sum + *WATER_MASS - modified_peptide.get_mass()).abs() < 1e-9);
+        // only one modification per terminus
+        assert!(ModifiedPeptide::new(
+            peptide.clone(),
+            vec![(0, n_terminal_label.clone()), (0, n_terminal_label.clone())]
+        )
+        .is_err());
+        // N-terminal modification not on the first residue
+        assert!(ModifiedPeptide::new(peptide, vec![(1, n_terminal_label)]).is_err());
+    }
 }
@@ -4,6 +4,7 @@ use std::string::ToString;
 
 use anyhow::{bail, Error};
 
+use crate::chemistry::amino_acid::labeled_amino_acid::LabeledAminoAcid;
 use crate::chemistry::amino_acid::AminoAcid;
 use crate::proteomics::peptide::Terminus;
 
@@ -126,6 +127,23 @@ impl PostTranslationalModification {
         }
     }
 
+    /// Creates a modification from an isotope label, e.g. for SILAC.
+    /// The mass delta is the mass shift of the label on the unlabeled amino acid.
+    ///
+    /// # Arguments
+    /// * `label` - Labeled amino acid
+    /// * `mod_type` - Modification type, usually static for the heavy channel
+    ///
+    pub fn from_labeled_amino_acid(label: &LabeledAminoAcid, mod_type: ModificationType) -> Self {
+        Self::new(
+            label.get_name(),
+            label.get_unlabeled_amino_acid(),
+            label.get_mass_shift(),
+            mod_type,
+            Position::Anywhere,
+        )
+    }
+
     /// Returns the name
     ///
     pub fn get_name(&self) -> &str {
@@ -0,0 +1,239 @@
+// 3rd party imports
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::proteomics::quantification::matrix::QuantificationMatrix;
+use crate::tools::random::SeededRandom;
+use crate::tools::statistics::{mean, median, quantile, standard_deviation};
+
+/// Imputation of missing values. The intensities should be log transformed first.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Imputation {
+    /// Draws from a normal distribution around a low quantile of each sample.
+    /// The standard deviation is the median of the row standard deviations times the sigma factor.
+    /// Common values are a quantile of 0.01 and a sigma factor of 1.0.
+    ///
+    /// > Lazar et al. (2016) Accounting for the Multiple Natures of Missing Values in Label-Free Quantitative Proteomics Data Sets to Compare Imputation Strategies. https://doi.org/10.1021/acs.jproteome.5b00981
+    MinProb { quantile: f64, sigma_factor: f64 },
+    /// Mean of the values of the k nearest rows (euclidean distance on the shared samples)
+    /// which have a value in the sample. Values without such neighbours stay missing.
+    ///
+    /// > Troyanskaya et al. (2001) Missing value estimation methods for DNA microarrays. https://doi.org/10.1093/bioinformatics/17.6.520
+    Knn { neighbours: usize },
+    /// Draws from a normal distribution which is shifted down by `downshift` standard deviations of the sample
+    /// and narrowed to `width` standard deviations, e.g. 1.8 and 0.3 as in Perseus.
+    ///
+    /// > Tyanova et al. (2016) The Perseus computational platform for comprehensive analysis of (prote)omics data. https://doi.org/10.1038/nmeth.3901
+    LeftCensoredGaussian { downshift: f64, width: f64 },
+}
+
+impl Imputation {
+    /// Returns the matrix with imputed values
+    ///
+    /// # Arguments
+    /// * `matrix` - Log transformed intensities
+    /// * `seed` - Seed for the random number generator, the same seed gives the same imputed values
+    ///
+    pub fn apply(&self, matrix: &QuantificationMatrix, seed: u64) -> Result<QuantificationMatrix> {
+        let mut random = SeededRandom::new(seed);
+        match self {
+            Self::MinProb {
+                quantile: probability,
+                sigma_factor,
+            } => {
+                let mut row_deviations: Vec<f64> = matrix
+                    .get_values()
+                    .iter()
+                    .filter_map(|row| {
+                        standard_deviation(&row.iter().flatten().copied().collect::<Vec<f64>>())
+                    })
+                    .collect();
+                let sigma = match median(&mut row_deviations) {
+                    Some(deviation) => deviation * sigma_factor,
+                    None => {
+                        bail!("No row with at least two values to estimate the standard deviation")
+                    }
+                };
+                let means = (0..matrix.get_samples().len())
+                    .map(|column| {
+                        match quantile(&mut matrix.get_sample_values(column), *probability) {
+                            Some(mean) => Ok(mean),
+                            None => {
+                                bail!("Sample `{}` has no values", matrix.get_samples()[column])
+                            }
+                        }
+                    })
+                    .collect::<Result<Vec<f64>>>()?;
+                Ok(impute_gaussian(
+                    matrix,
+                    &means,
+                    &vec![sigma; means.len()],
+                    &mut random,
+                ))
+            }
+            Self::Knn { neighbours } => {
+                if *neighbours == 0 {
+                    bail!("At least one neighbour is required");
+                }
+                Ok(impute_knn(matrix, *neighbours))
+            }
+            Self::LeftCensoredGaussian { downshift, width } => {
+                let mut means: Vec<f64> = Vec::with_capacity(matrix.get_samples().len());
+                let mut deviations: Vec<f64> = Vec::with_capacity(matrix.get_samples().len());
+                for column in 0..matrix.get_samples().len() {
+                    let values = matrix.get_sample_values(column);
+                    match (mean(&values), standard_deviation(&values)) {
+                        (Some(mean), Some(deviation)) => {
+                            means.push(mean - downshift * deviation);
+                            deviations.push(width * deviation);
+                        }
+                        _ => bail!(
+                            "Sample `{}` has less than two values",
+                            matrix.get_samples()[column]
+                        ),
+                    }
+                }
+                Ok(impute_gaussian(matrix, &means, &deviations, &mut random))
+            }
+        }
+    }
+}
+
+/// Replaces missing values with values drawn from a normal distribution for each sample
+///
+/// # Arguments
+/// * `matrix` - Intensities
+/// * `means` - Mean of each sample
+/// * `deviations` - Standard deviation of each sample
+/// * `random` - Random number generator
+///
+fn impute_gaussian(
+    matrix: &QuantificationMatrix,
+    means: &[f64],
+    deviations: &[f64],
+    random: &mut SeededRandom,
+) -> QuantificationMatrix {
+    let mut imputed = matrix.clone();
+    for row in imputed.get_values_mut() {
+        for (column, value) in row.iter_mut().enumerate() {
+            if value.is_none() {
+                *value = Some(random.next_gaussian(means[column], deviations[column]));
+            }
+        }
+    }
+    imputed
+}
+
+/// Replaces missing values with the mean of the k nearest rows
+///
+/// # Arguments
+/// * `matrix` - Intensities
+/// * `neighbours` - Number of neighbours
+///
+fn impute_knn(matrix: &QuantificationMatrix, neighbours: usize) -> QuantificationMatrix {
+    let values = matrix.get_values();
+    let mut imputed = matrix.clone();
+    for (row_idx, row) in values.iter().enumerate() {
+        if row.iter().all(|value| value.is_some()) {
+            continue;
+        }
+        // root mean squared difference on the shared samples
+        let distances: Vec<(usize, f64)> = values
+            .iter()
+            .enumerate()
+            .filter(|(other_idx, _)| *other_idx != row_idx)
+            .filter_map(|(other_idx, other)| {
+                let squared_differences: Vec<f64> = row
+                    .iter()
+                    .zip(other.iter())
+                    .filter_map(|(value, other_value)| match (value, other_value) {
+                        (Some(value), Some(other_value)) => Some((value - other_value).powi(2)),
+                        _ => None,
+                    })
+                    .collect();
+                mean(&squared_differences).map(|distance| (other_idx, distance.sqrt()))
+            })
+            .collect();
+        for column in 0..row.len() {
+            if row[column].is_some() {
+                continue;
+            }
+            let mut candidates: Vec<(usize, f64)> = distances
+                .iter()
+                .filter(|(other_idx, _)| values[*other_idx][column].is_some())
+                .copied()
+                .collect();
+            candidates.sort_by(|(_, x), (_, y)| x.total_cmp(y));
+            let neighbour_values: Vec<f64> = candidates
+                .iter()
+                .take(neighbours)
+                .filter_map(|(other_idx, _)| values[*other_idx][column])
+                .collect();
+            imputed.get_values_mut()[row_idx][column] = mean(&neighbour_values);
+        }
+    }
+    imputed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_matrix() -> QuantificationMatrix {
+        QuantificationMatrix::new(
+            (0..5).map(|row| format!("row{}", row)).collect(),
+            vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            vec![
+                vec![Some(20.0), Some(21.0), Some(20.5)],
+                vec![Some(25.0), Some(26.0), None],
+                vec![Some(24.0), Some(25.0), Some(24.5)],
+                vec![Some(18.0), None, Some(18.5)],
+                vec![Some(26.0), Some(27.0), Some(26.5)],
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_min_prob() {
+        let matrix = create_matrix();
+        let imputation = Imputation::MinProb {
+            quantile: 0.01,
+            sigma_factor: 1.0,
+        };
+        let imputed = imputation.apply(&matrix, 42).unwrap();
+        assert_eq!(imputed.count_missing(), 0);
+        // reproducible
+        assert_eq!(imputation.apply(&matrix, 42).unwrap(), imputed);
+        assert_ne!(imputation.apply(&matrix, 1).unwrap(), imputed);
+        // low values
+        assert!(imputed.get("row1", "C").unwrap() < 22.0);
+        assert_eq!(imputed.get("row0", "A"), Some(20.0));
+    }
+
+    #[test]
+    fn test_left_censored_gaussian() {
+        let matrix = create_matrix();
+        let imputation = Imputation::LeftCensoredGaussian {
+            downshift: 1.8,
+            width: 0.3,
+        };
+        let imputed = imputation.apply(&matrix, 42).unwrap();
+        assert_eq!(imputed.count_missing(), 0);
+        assert_eq!(imputation.apply(&matrix, 42).unwrap(), imputed);
+        assert!(imputed.get("row3", "B").unwrap() < 23.0);
+    }
+
+    #[test]
+    fn test_knn() {
+        let matrix = create_matrix();
+        let imputed = Imputation::Knn { neighbours: 2 }.apply(&matrix, 0).unwrap();
+        // nearest rows of row1 are row4 (26.5) and row2 (24.5)
+        assert_eq!(imputed.get("row1", "C"), Some(25.5));
+        // nearest rows of row3 are row0 (21.0) and row2 (25.0)
+        assert_eq!(imputed.get("row3", "B"), Some(23.0));
+        assert!(Imputation::Knn { neighbours: 0 }.apply(&matrix, 0).is_err());
+    }
+}
@@ -0,0 +1,552 @@
+// std imports
+use std::fmt::Display;
+use std::str::FromStr;
+
+// 3rd party imports
+use anyhow::{bail, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::chemistry::amino_acid::get_amino_acid_by_one_letter_code;
+use crate::mass_spectrometry::processing::deisotoping::ISOTOPE_MASS_DIFFERENCE;
+use crate::mass_spectrometry::spectrum::Spectrum;
+use crate::mass_spectrometry::tolerance::{find_closest_peak, Tolerance};
+use crate::proteomics::peptide::Terminus;
+use crate::proteomics::post_translational_modifications::{
+    ModificationType, Position, PostTranslationalModification,
+};
+use crate::tools::linear_algebra::solve_linear_system;
+
+/// Maximum mass difference when assigning isotope impurities to the neighbouring channel
+const IMPURITY_CHANNEL_TOLERANCE: f64 = 0.01;
+
+/// Margin around the reporter ions for calculating the interference in the reporter region
+const REPORTER_REGION_MARGIN: f64 = 0.5;
+
+/// TMT reporter ions (name, m/z)
+const TMT_126: (&str, f64) = ("126", 126.127726);
+const TMT_127N: (&str, f64) = ("127N", 127.124761);
+const TMT_127C: (&str, f64) = ("127C", 127.131081);
+const TMT_128N: (&str, f64) = ("128N", 128.128116);
+const TMT_128C: (&str, f64) = ("128C", 128.134436);
+const TMT_129N: (&str, f64) = ("129N", 129.131471);
+const TMT_129C: (&str, f64) = ("129C", 129.137790);
+const TMT_130N: (&str, f64) = ("130N", 130.134825);
+const TMT_130C: (&str, f64) = ("130C", 130.141145);
+const TMT_131N: (&str, f64) = ("131N", 131.138180);
+const TMT_131C: (&str, f64) = ("131C", 131.144500);
+const TMT_132N: (&str, f64) = ("132N", 132.141535);
+const TMT_132C: (&str, f64) = ("132C", 132.147855);
+const TMT_133N: (&str, f64) = ("133N", 133.144890);
+const TMT_133C: (&str, f64) = ("133C", 133.151210);
+const TMT_134N: (&str, f64) = ("134N", 134.148245);
+const TMT_134C: (&str, f64) = ("134C", 134.154565);
+const TMT_135N: (&str, f64) = ("135N", 135.151600);
+
+/// iTRAQ reporter ions (name, m/z)
+const ITRAQ_113: (&str, f64) = ("113", 113.107873);
+const ITRAQ_114: (&str, f64) = ("114", 114.111228);
+const ITRAQ_115: (&str, f64) = ("115", 115.108263);
+const ITRAQ_116: (&str, f64) = ("116", 116.111618);
+const ITRAQ_117: (&str, f64) = ("117", 117.114973);
+const ITRAQ_118: (&str, f64) = ("118", 118.112008);
+const ITRAQ_119: (&str, f64) = ("119", 119.115363);
+const ITRAQ_121: (&str, f64) = ("121", 121.122072);
+
+const TMT_6_PLEX: [(&str, f64); 6] = [TMT_126, TMT_127N, TMT_128C, TMT_129N, TMT_130C, TMT_131N];
+const TMT_10_PLEX: [(&str, f64); 10] = [
+    TMT_126, TMT_127N, TMT_127C, TMT_128N, TMT_128C, TMT_129N, TMT_129C, TMT_130N, TMT_130C,
+    TMT_131N,
+];
+const TMT_11_PLEX: [(&str, f64); 11] = [
+    TMT_126, TMT_127N, TMT_127C, TMT_128N, TMT_128C, TMT_129N, TMT_129C, TMT_130N, TMT_130C,
+    TMT_131N, TMT_131C,
+];
+const TMT_PRO_16_PLEX: [(&str, f64); 16] = [
+    TMT_126, TMT_127N, TMT_127C, TMT_128N, TMT_128C, TMT_129N, TMT_129C, TMT_130N, TMT_130C,
+    TMT_131N, TMT_131C, TMT_132N, TMT_132C, TMT_133N, TMT_133C, TMT_134N,
+];
+const TMT_PRO_18_PLEX: [(&str, f64); 18] = [
+    TMT_126, TMT_127N, TMT_127C, TMT_128N, TMT_128C, TMT_129N, TMT_129C, TMT_130N, TMT_130C,
+    TMT_131N, TMT_131C, TMT_132N, TMT_132C, TMT_133N, TMT_133C, TMT_134N, TMT_134C, TMT_135N,
+];
+const ITRAQ_4_PLEX: [(&str, f64); 4] = [ITRAQ_114, ITRAQ_115, ITRAQ_116, ITRAQ_117];
+const ITRAQ_8_PLEX: [(&str, f64); 8] = [
+    ITRAQ_113, ITRAQ_114, ITRAQ_115, ITRAQ_116, ITRAQ_117, ITRAQ_118, ITRAQ_119, ITRAQ_121,
+];
+
+/// Isobaric labelling reagents
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum IsobaricReagent {
+    Tmt6Plex,
+    Tmt10Plex,
+    Tmt11Plex,
+    /// TMTpro 16-plex
+    TmtPro16Plex,
+    /// TMTpro 18-plex
+    TmtPro18Plex,
+    Itraq4Plex,
+    Itraq8Plex,
+}
+
+impl IsobaricReagent {
+    /// Returns the reporter ions (name, m/z) in ascending m/z order
+    ///
+    pub fn get_reporter_ions(&self) -> &'static [(&'static str, f64)] {
+        match self {
+            Self::Tmt6Plex => &TMT_6_PLEX,
+            Self::Tmt10Plex => &TMT_10_PLEX,
+            Self::Tmt11Plex => &TMT_11_PLEX,
+            Self::TmtPro16Plex => &TMT_PRO_16_PLEX,
+            Self::TmtPro18Plex => &TMT_PRO_18_PLEX,
+            Self::Itraq4Plex => &ITRAQ_4_PLEX,
+            Self::Itraq8Plex => &ITRAQ_8_PLEX,
+        }
+    }
+
+    /// Returns the number of channels
+    ///
+    pub fn get_channel_count(&self) -> usize {
+        self.get_reporter_ions().len()
+    }
+
+    /// Returns the monoisotopic mass delta of the label
+    ///
+    pub fn get_mass_delta(&self) -> f64 {
+        match self {
+            Self::Tmt6Plex | Self::Tmt10Plex | Self::Tmt11Plex => 229.162932,
+            Self::TmtPro16Plex | Self::TmtPro18Plex => 304.207146,
+            Self::Itraq4Plex => 144.102063,
+            Self::Itraq8Plex => 304.205360,
+        }
+    }
+
+    /// Returns the name of the label modification
+    ///
+    pub fn get_modification_name(&self) -> &'static str {
+        match self {
+            Self::Tmt6Plex | Self::Tmt10Plex | Self::Tmt11Plex => "TMT6plex",
+            Self::TmtPro16Plex | Self::TmtPro18Plex => "TMTpro",
+            Self::Itraq4Plex => "iTRAQ4plex",
+            Self::Itraq8Plex => "iTRAQ8plex",
+        }
+    }
+
+    /// Returns the static label modifications: lysine side chains and the peptide N-terminus.
+    /// The N-terminal label targets any amino acid (`X`) at the N-terminus.
+    ///
+    pub fn get_modifications(&self) -> Result<Vec<PostTranslationalModification>> {
+        Ok(vec![
+            PostTranslationalModification::new(
+                self.get_modification_name(),
+                get_amino_acid_by_one_letter_code('K')?,
+                self.get_mass_delta(),
+                ModificationType::Static,
+                Position::Anywhere,
+            ),
+            PostTranslationalModification::new(
+                self.get_modification_name(),
+                get_amino_acid_by_one_letter_code('X')?,
+                self.get_mass_delta(),
+                ModificationType::Static,
+                Position::Terminus(Terminus::N),
+            ),
+        ])
+    }
+}
+
+impl FromStr for IsobaricReagent {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', ' ', '_'], "").as_str() {
+            "tmt6plex" | "tmt6" => Ok(Self::Tmt6Plex),
+            "tmt10plex" | "tmt10" => Ok(Self::Tmt10Plex),
+            "tmt11plex" | "tmt11" => Ok(Self::Tmt11Plex),
+            "tmtpro16plex" | "tmtpro16" | "tmtpro" | "tmt16plex" | "tmt16" => Ok(Self::TmtPro16Plex),
+            "tmtpro18plex" | "tmtpro18" | "tmt18plex" | "tmt18" => Ok(Self::TmtPro18Plex),
+            "itraq4plex" | "itraq4" => Ok(Self::Itraq4Plex),
+            "itraq8plex" | "itraq8" => Ok(Self::Itraq8Plex),
+            _ => bail!(
+                "Invalid isobaric reagent `{}`. Valid reagents are `TMT6plex`, `TMT10plex`, `TMT11plex`, `TMTpro16plex`, `TMTpro18plex`, `iTRAQ4plex` or `iTRAQ8plex`",
+                s
+            ),
+        }
+    }
+}
+
+impl Display for IsobaricReagent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tmt6Plex => write!(f, "TMT6plex"),
+            Self::Tmt10Plex => write!(f, "TMT10plex"),
+            Self::Tmt11Plex => write!(f, "TMT11plex"),
+            Self::TmtPro16Plex => write!(f, "TMTpro16plex"),
+            Self::TmtPro18Plex => write!(f, "TMTpro18plex"),
+            Self::Itraq4Plex => write!(f, "iTRAQ4plex"),
+            Self::Itraq8Plex => write!(f, "iTRAQ8plex"),
+        }
+    }
+}
+
+/// Isotope impurity correction of reporter ion intensities.
+/// The matrix contains the fraction of the signal of channel `i` (row) observed in channel `j` (column).
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImpurityCorrection {
+    matrix: Vec<Vec<f64>>,
+}
+
+impl ImpurityCorrection {
+    /// Creates a new correction from the impurity matrix
+    ///
+    /// # Arguments
+    /// * `matrix` - Fraction of the signal of channel `i` (row) observed in channel `j` (column)
+    ///
+    pub fn new(matrix: Vec<Vec<f64>>) -> Result<Self> {
+        if matrix.iter().any(|row| row.len() != matrix.len()) {
+            bail!("Impurity matrix must be square");
+        }
+        Ok(Self { matrix })
+    }
+
+    /// Creates the correction from the isotope impurities of a manufacturer lot sheet.
+    /// Impurities are assigned to the channel at the respective 13C isotope distance,
+    /// impurities without such a channel are lost.
+    ///
+    /// # Arguments
+    /// * `reagent` - Isobaric reagent
+    /// * `impurities` - Impurities in percent for the -2, -1, +1 and +2 isotopes of each channel
+    ///
+    pub fn from_isotope_impurities(
+        reagent: &IsobaricReagent,
+        impurities: &[[f64; 4]],
+    ) -> Result<Self> {
+        let reporter_ions = reagent.get_reporter_ions();
+        if impurities.len() != reporter_ions.len() {
+            bail!(
+                "{} has {} channels but {} impurity rows were given",
+                reagent,
+                reporter_ions.len(),
+                impurities.len()
+            );
+        }
+        let mut matrix = vec![vec![0.0; reporter_ions.len()]; reporter_ions.len()];
+        for (channel, channel_impurities) in impurities.iter().enumerate() {
+            matrix[channel][channel] = 1.0 - channel_impurities.iter().sum::<f64>() / 100.0;
+            for (impurity, shift) in channel_impurities.iter().zip([-2.0, -1.0, 1.0, 2.0]) {
+                let target_mz = reporter_ions[channel].1 + shift * ISOTOPE_MASS_DIFFERENCE;
+                let target_channel = reporter_ions
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (_, mz))| (idx, (mz - target_mz).abs()))
+                    .filter(|(_, difference)| *difference <= IMPURITY_CHANNEL_TOLERANCE)
+                    .min_by(|(_, x), (_, y)| x.total_cmp(y));
+                if let Some((target_channel, _)) = target_channel {
+                    matrix[channel][target_channel] += impurity / 100.0;
+                }
+            }
+        }
+        Self::new(matrix)
+    }
+
+    /// Returns the impurity matrix
+    ///
+    pub fn get_matrix(&self) -> &Vec<Vec<f64>> {
+        &self.matrix
+    }
+
+    /// Returns the corrected intensities. Negative intensities are set to 0.
+    ///
+    /// # Arguments
+    /// * `observed` - Observed reporter ion intensities
+    ///
+    pub fn correct(&self, observed: &[f64]) -> Result<Vec<f64>> {
+        if observed.len() != self.matrix.len() {
+            bail!(
+                "Impurity matrix has {} channels but {} intensities were given",
+                self.matrix.len(),
+                observed.len()
+            );
+        }
+        // observed_j = sum_i true_i * matrix[i][j]
+        let transposed: Vec<Vec<f64>> = (0..self.matrix.len())
+            .map(|col| self.matrix.iter().map(|row| row[col]).collect())
+            .collect();
+        Ok(solve_linear_system(transposed, observed.to_vec())
+            .context("Failed to solve the impurity correction")?
+            .into_iter()
+            .map(|intensity| intensity.max(0.0))
+            .collect())
+    }
+}
+
+/// Reporter ion intensities of a spectrum
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReporterIons {
+    spectrum_id: String,
+    intensities: Vec<f64>,
+    missing_channels: usize,
+    reporter_interference: f64,
+}
+
+impl ReporterIons {
+    /// Returns the spectrum ID
+    ///
+    pub fn get_spectrum_id(&self) -> &String {
+        &self.spectrum_id
+    }
+
+    /// Returns the (corrected) intensity of each channel
+    ///
+    pub fn get_intensities(&self) -> &Vec<f64> {
+        &self.intensities
+    }
+
+    /// Returns the summed intensity of all channels
+    ///
+    pub fn get_total_intensity(&self) -> f64 {
+        self.intensities.iter().sum()
+    }
+
+    /// Returns the number of channels without reporter ion peak
+    ///
+    pub fn get_missing_channels(&self) -> usize {
+        self.missing_channels
+    }
+
+    /// Returns the fraction of the intensity in the reporter ion region
+    /// which is not explained by reporter ions
+    ///
+    pub fn get_reporter_interference(&self) -> f64 {
+        self.reporter_interference
+    }
+}
+
+/// Extracts reporter ion intensities from MS2 or SPS-MS3 spectra
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReporterIonQuantifier {
+    reagent: IsobaricReagent,
+    tolerance: Tolerance,
+    impurity_correction: Option<ImpurityCorrection>,
+}
+
+impl ReporterIonQuantifier {
+    /// Creates a new quantifier without impurity correction
+    ///
+    /// # Arguments
+    /// * `reagent` - Isobaric reagent
+    /// * `tolerance` - Tolerance for the reporter ions, needs to be below ~6 mDa to
+    ///   separate N- and C-channels of TMT 10-plex and higher
+    ///
+    pub fn new(reagent: IsobaricReagent, tolerance: Tolerance) -> Self {
+        Self {
+            reagent,
+            tolerance,
+            impurity_correction: None,
+        }
+    }
+
+    /// Sets the isotope impurity correction
+    ///
+    /// # Arguments
+    /// * `impurity_correction` - Impurity correction with one row per channel
+    ///
+    pub fn with_impurity_correction(
+        mut self,
+        impurity_correction: ImpurityCorrection,
+    ) -> Result<Self> {
+        if impurity_correction.get_matrix().len() != self.reagent.get_channel_count() {
+            bail!(
+                "Impurity matrix has {} channels but {} has {}",
+                impurity_correction.get_matrix().len(),
+                self.reagent,
+                self.reagent.get_channel_count()
+            );
+        }
+        self.impurity_correction = Some(impurity_correction);
+        Ok(self)
+    }
+
+    /// Returns the reagent
+    ///
+    pub fn get_reagent(&self) -> &IsobaricReagent {
+        &self.reagent
+    }
+
+    /// Extracts the reporter ion intensities from the spectrum
+    ///
+    /// # Arguments
+    /// * `spectrum` - MS2 or SPS-MS3 spectrum with sorted m/z values
+    ///
+    pub fn quantify<S>(&self, spectrum: &S) -> Result<ReporterIons>
+    where
+        S: Spectrum,
+    {
+        let mz = spectrum.get_mz();
+        let intensity = spectrum.get_intensity();
+        let reporter_ions = self.reagent.get_reporter_ions();
+        let peak_indices: Vec<Option<usize>> = reporter_ions
+            .iter()
+            .map(|(_, reporter_mz)| find_closest_peak(mz, *reporter_mz, &self.tolerance))
+            .collect();
+        let observed: Vec<f64> = peak_indices
+            .iter()
+            .map(|idx| idx.map(|idx| intensity[idx]).unwrap_or(0.0))
+            .collect();
+
+        // interference in the reporter region
+        let region_start = reporter_ions[0].1 - REPORTER_REGION_MARGIN;
+        let region_end = reporter_ions[reporter_ions.len() - 1].1 + REPORTER_REGION_MARGIN;
+        let region_intensity: f64 = mz
+            .iter()
+            .zip(intensity.iter())
+            .filter(|(peak_mz, _)| region_start <= **peak_mz && **peak_mz <= region_end)
+            .map(|(_, peak_intensity)| *peak_intensity)
+            .sum();
+        let reporter_interference = if region_intensity > 0.0 {
+            1.0 - observed.iter().sum::<f64>() / region_intensity
+        } else {
+            0.0
+        };
+
+        let intensities = match &self.impurity_correction {
+            Some(impurity_correction) => impurity_correction.correct(&observed)?,
+            None => observed,
+        };
+        Ok(ReporterIons {
+            spectrum_id: spectrum.get_id().clone(),
+            intensities,
+            missing_channels: peak_indices.iter().filter(|idx| idx.is_none()).count(),
+            reporter_interference,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mass_spectrometry::spectrum::SimpleSpectrum;
+    use crate::proteomics::peptide::{ModifiedPeptide, Peptide};
+    use crate::proteomics::quantification::matrix::QuantificationMatrix;
+
+    #[test]
+    fn test_reagents() {
+        for reagent in [
+            IsobaricReagent::Tmt6Plex,
+            IsobaricReagent::Tmt10Plex,
+            IsobaricReagent::Tmt11Plex,
+            IsobaricReagent::TmtPro16Plex,
+            IsobaricReagent::TmtPro18Plex,
+            IsobaricReagent::Itraq4Plex,
+            IsobaricReagent::Itraq8Plex,
+        ] {
+            assert_eq!(
+                IsobaricReagent::from_str(&reagent.to_string()).unwrap(),
+                reagent
+            );
+            let reporter_ions = reagent.get_reporter_ions();
+            assert!(reporter_ions.windows(2).all(|ions| ions[0].1 < ions[1].1));
+            let modifications = reagent.get_modifications().unwrap();
+            assert_eq!(modifications.len(), 2);
+            assert_eq!(
+                modifications[1].get_position(),
+                &Position::Terminus(Terminus::N)
+            );
+            assert!(modifications
+                .iter()
+                .all(|modification| *modification.get_mass_delta() == reagent.get_mass_delta()));
+        }
+        assert_eq!(IsobaricReagent::TmtPro18Plex.get_channel_count(), 18);
+
+        // the N-terminus and the lysine of the same peptide can both be labelled
+        let reagent = IsobaricReagent::Tmt10Plex;
+        let modifications = reagent.get_modifications().unwrap();
+        let peptide = Peptide::new("KPEPTIDEK".to_string(), 0).unwrap();
+        let unlabelled_mass = peptide.get_mass();
+        let labelled = ModifiedPeptide::new(
+            peptide,
+            vec![
+                (0, modifications[1].clone()),
+                (0, modifications[0].clone()),
+                (8, modifications[0].clone()),
+            ],
+        )
+        .unwrap();
+        assert!(labelled.get_n_terminal_modification().is_some());
+        assert!(
+            (labelled.get_mass() - unlabelled_mass - 3.0 * reagent.get_mass_delta()).abs() < 1e-9
+        );
+        assert!(IsobaricReagent::from_str("TMT7plex").is_err());
+    }
+
+    #[test]
+    fn test_impurity_correction() {
+        // 5 % of 126 is observed in 127C (+1), 2 % of 127C in 126 (-1)
+        let mut impurities = vec![[0.0; 4]; 10];
+        impurities[0] = [0.0, 0.0, 5.0, 0.0];
+        impurities[2] = [0.0, 2.0, 0.0, 0.0];
+        let correction =
+            ImpurityCorrection::from_isotope_impurities(&IsobaricReagent::Tmt10Plex, &impurities)
+                .unwrap();
+        assert_eq!(correction.get_matrix()[0][2], 0.05);
+        assert_eq!(correction.get_matrix()[0][0], 0.95);
+        assert_eq!(correction.get_matrix()[2][0], 0.02);
+
+        let true_intensities = [1000.0, 0.0, 500.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let mut observed = vec![0.0; 10];
+        for (channel, intensity) in true_intensities.iter().enumerate() {
+            for (target, fraction) in correction.get_matrix()[channel].iter().enumerate() {
+                observed[target] += intensity * fraction;
+            }
+        }
+        let corrected = correction.correct(&observed).unwrap();
+        for (corrected, expected) in corrected.iter().zip(true_intensities.iter()) {
+            assert!((corrected - expected).abs() < 1e-9);
+        }
+
+        // TMT 6-plex has no C-channels, the +1 isotope of 126 goes to 127N
+        let mut impurities = vec![[0.0; 4]; 6];
+        impurities[0] = [0.0, 0.0, 5.0, 0.0];
+        let correction =
+            ImpurityCorrection::from_isotope_impurities(&IsobaricReagent::Tmt6Plex, &impurities)
+                .unwrap();
+        assert_eq!(correction.get_matrix()[0][1], 0.05);
+    }
+
+    #[test]
+    fn test_quantify() {
+        let spectrum = SimpleSpectrum::new(
+            "spectrum".to_string(),
+            3,
+            vec![114.1112, 115.1083, 115.5, 117.1150, 500.0],
+            vec![100.0, 200.0, 100.0, 400.0, 10000.0],
+        );
+        let quantifier =
+            ReporterIonQuantifier::new(IsobaricReagent::Itraq4Plex, Tolerance::Ppm(20.0));
+        let reporter_ions = quantifier.quantify(&spectrum).unwrap();
+        assert_eq!(reporter_ions.get_spectrum_id(), "spectrum");
+        assert_eq!(
+            reporter_ions.get_intensities(),
+            &vec![100.0, 200.0, 0.0, 400.0]
+        );
+        assert_eq!(reporter_ions.get_missing_channels(), 1);
+        assert_eq!(reporter_ions.get_total_intensity(), 700.0);
+        assert!((reporter_ions.get_reporter_interference() - 0.125).abs() < 1e-9);
+
+        let matrix = QuantificationMatrix::from_reporter_ions(
+            &IsobaricReagent::Itraq4Plex,
+            &[reporter_ions],
+        )
+        .unwrap();
+        assert_eq!(matrix.get_samples(), &vec!["114", "115", "116", "117"]);
+        assert_eq!(matrix.get("spectrum", "115"), Some(200.0));
+        assert_eq!(matrix.get("spectrum", "116"), None);
+
+        let correction = ImpurityCorrection::new(vec![vec![1.0; 2]; 2]).unwrap();
+        assert!(quantifier.with_impurity_correction(correction).is_err());
+    }
+}
@@ -0,0 +1,530 @@
+// std imports
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// 3rd party imports
+use anyhow::{bail, Context, Result};
+use fallible_iterator::FallibleIterator;
+
+// internal imports
+use crate::proteomics::peptide::Peptide;
+use crate::proteomics::proteases::protease::Protease;
+use crate::proteomics::quantification::matrix::QuantificationMatrix;
+use crate::tools::linear_algebra::solve_linear_system;
+use crate::tools::statistics::{mean, median};
+
+/// Number of most intense peptides used by Top3
+const TOP_N: usize = 3;
+
+/// Default minimum number of peptide ratios for a protein ratio in MaxLFQ
+const DEFAULT_MIN_RATIO_COUNT: usize = 2;
+
+/// Returns the natural logarithm of the intensity or None if it is missing or not positive
+///
+fn get_log_intensity(intensity: &Option<f64>) -> Option<f64> {
+    intensity.filter(|intensity| *intensity > 0.0).map(f64::ln)
+}
+
+/// Returns groups of samples connected by at least one ratio
+///
+/// # Arguments
+/// * `weights` - Number of ratios between sample a and b (symmetric)
+///
+fn get_connected_samples(weights: &[Vec<f64>]) -> Vec<Vec<usize>> {
+    let sample_count = weights.len();
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    let mut visited = vec![false; sample_count];
+    for start in 0..sample_count {
+        if visited[start] {
+            continue;
+        }
+        let mut component = vec![start];
+        visited[start] = true;
+        let mut next = 0;
+        while next < component.len() {
+            let sample = component[next];
+            for other in 0..sample_count {
+                if !visited[other] && weights[sample][other] > 0.0 {
+                    visited[other] = true;
+                    component.push(other);
+                }
+            }
+            next += 1;
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// Solves the least squares problem `x_a - x_b ≈ r_ab` over all sample pairs.
+/// Each connected component of samples is solved separately with the values summing up to 0.
+/// Samples without any ratio are None.
+///
+/// # Arguments
+/// * `weights` - Number of ratios between sample a and b (symmetric)
+/// * `ratio_sums` - Sum of the log ratios of sample a to b (antisymmetric)
+///
+fn solve_pairwise_log_ratios(
+    weights: &[Vec<f64>],
+    ratio_sums: &[Vec<f64>],
+) -> Result<Vec<Option<f64>>> {
+    let mut solution = vec![None; weights.len()];
+    for component in get_connected_samples(weights) {
+        if component.len() < 2 {
+            continue;
+        }
+        // Normal equations of the least squares problem, the added 1s fix the sum to 0
+        let matrix: Vec<Vec<f64>> = component
+            .iter()
+            .map(|a| {
+                component
+                    .iter()
+                    .map(|b| match a == b {
+                        true => {
+                            1.0 + component
+                                .iter()
+                                .map(|other| weights[*a][*other])
+                                .sum::<f64>()
+                        }
+                        false => 1.0 - weights[*a][*b],
+                    })
+                    .collect()
+            })
+            .collect();
+        let rhs: Vec<f64> = component
+            .iter()
+            .map(|a| component.iter().map(|b| ratio_sums[*a][*b]).sum())
+            .collect();
+        for (sample, value) in component.iter().zip(solve_linear_system(matrix, rhs)?) {
+            solution[*sample] = Some(value);
+        }
+    }
+    Ok(solution)
+}
+
+/// Calculates normalisation factors for the samples, which minimise the squared log ratios
+/// of all peptides between all samples, similar to the delayed normalisation of MaxLFQ.
+/// The factors have a geometric mean of 1 for each group of samples sharing peptides.
+/// Samples without shared peptides get a factor of 1.
+///
+/// > Cox et al. (2014) Accurate Proteome-wide Label-free Quantification by Delayed Normalization and Maximal Peptide Ratio Extraction, Termed MaxLFQ. https://doi.org/10.1074/mcp.M113.031591
+///
+/// # Arguments
+/// * `peptides` - Peptide intensities
+///
+pub fn calculate_delayed_normalization_factors(
+    peptides: &QuantificationMatrix,
+) -> Result<Vec<f64>> {
+    let sample_count = peptides.get_samples().len();
+    let mut weights = vec![vec![0.0; sample_count]; sample_count];
+    let mut ratio_sums = vec![vec![0.0; sample_count]; sample_count];
+    for row in peptides.get_values() {
+        let log_intensities: Vec<Option<f64>> = row.iter().map(get_log_intensity).collect();
+        for a in 0..sample_count {
+            for b in 0..sample_count {
+                if a == b {
+                    continue;
+                }
+                if let (Some(log_a), Some(log_b)) = (log_intensities[a], log_intensities[b]) {
+                    // n_a + log_a ≈ n_b + log_b
+                    weights[a][b] += 1.0;
+                    ratio_sums[a][b] += log_b - log_a;
+                }
+            }
+        }
+    }
+    Ok(solve_pairwise_log_ratios(&weights, &ratio_sums)
+        .context("Error when calculating normalization factors")?
+        .into_iter()
+        .map(|log_factor| log_factor.map(f64::exp).unwrap_or(1.0))
+        .collect())
+}
+
+/// Rolls peptide intensities up to protein abundances using Top3, iBAQ or MaxLFQ.
+///
+pub struct ProteinRollup {
+    peptide_to_proteins: HashMap<String, Vec<String>>,
+    use_shared_peptides: bool,
+    min_ratio_count: usize,
+}
+
+impl ProteinRollup {
+    /// Creates a new rollup using only peptides unique to a protein
+    ///
+    /// # Arguments
+    /// * `peptide_to_proteins` - Proteins (e.g. accessions) of each peptide, keys need to match the row names of the peptide matrix
+    ///
+    pub fn new(peptide_to_proteins: HashMap<String, Vec<String>>) -> Self {
+        Self {
+            peptide_to_proteins,
+            use_shared_peptides: false,
+            min_ratio_count: DEFAULT_MIN_RATIO_COUNT,
+        }
+    }
+
+    /// Sets if peptides shared between proteins are used for each of their proteins
+    ///
+    /// # Arguments
+    /// * `use_shared_peptides` - Use shared peptides
+    ///
+    pub fn with_shared_peptides(mut self, use_shared_peptides: bool) -> Self {
+        self.use_shared_peptides = use_shared_peptides;
+        self
+    }
+
+    /// Sets the minimum number of peptide ratios for a protein ratio between two samples in MaxLFQ
+    ///
+    /// # Arguments
+    /// * `min_ratio_count` - Minimum ratio count (default: 2)
+    ///
+    pub fn with_min_ratio_count(mut self, min_ratio_count: usize) -> Result<Self> {
+        if min_ratio_count == 0 {
+            bail!("Minimum ratio count must be at least 1");
+        }
+        self.min_ratio_count = min_ratio_count;
+        Ok(self)
+    }
+
+    /// Returns the row indices of the peptides of each protein, sorted by protein
+    ///
+    /// # Arguments
+    /// * `peptides` - Peptide intensities
+    ///
+    fn get_protein_peptides(
+        &self,
+        peptides: &QuantificationMatrix,
+    ) -> BTreeMap<String, Vec<usize>> {
+        let mut protein_peptides: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (row, peptide) in peptides.get_row_names().iter().enumerate() {
+            let proteins = match self.peptide_to_proteins.get(peptide) {
+                Some(proteins) => proteins,
+                None => continue,
+            };
+            let unique_proteins: HashSet<&String> = proteins.iter().collect();
+            if unique_proteins.len() > 1 && !self.use_shared_peptides {
+                continue;
+            }
+            for protein in unique_proteins {
+                protein_peptides
+                    .entry(protein.clone())
+                    .or_default()
+                    .push(row);
+            }
+        }
+        protein_peptides
+    }
+
+    /// Protein abundance as mean of the (up to) 3 most intense peptides in each sample
+    ///
+    /// > Silva et al. (2006) Absolute Quantification of Proteins by LCMSE: A Virtue of Parallel ms Acquisition. https://doi.org/10.1074/mcp.M500230-MCP200
+    ///
+    /// # Arguments
+    /// * `peptides` - Peptide intensities
+    ///
+    pub fn top3(&self, peptides: &QuantificationMatrix) -> Result<QuantificationMatrix> {
+        let protein_peptides = self.get_protein_peptides(peptides);
+        let sample_count = peptides.get_samples().len();
+        let values = protein_peptides
+            .values()
+            .map(|rows| {
+                (0..sample_count)
+                    .map(|sample| {
+                        let mut intensities: Vec<f64> = rows
+                            .iter()
+                            .filter_map(|row| peptides.get_values()[*row][sample])
+                            .filter(|intensity| *intensity > 0.0)
+                            .collect();
+                        intensities.sort_by(|x, y| y.total_cmp(x));
+                        intensities.truncate(TOP_N);
+                        mean(&intensities)
+                    })
+                    .collect()
+            })
+            .collect();
+        QuantificationMatrix::new(
+            protein_peptides.into_keys().collect(),
+            peptides.get_samples().clone(),
+            values,
+        )
+    }
+
+    /// Intensity based absolute quantification: summed peptide intensities
+    /// divided by the number of theoretically observable peptides.
+    /// The observable peptides are the unique peptides returned by `Protease::cleave`,
+    /// so the protease should be configured with the length limits and without missed cleavages.
+    ///
+    /// > Schwanhäusser et al. (2011) Global quantification of mammalian gene expression control. https://doi.org/10.1038/nature10098
+    ///
+    /// # Arguments
+    /// * `peptides` - Peptide intensities
+    /// * `protein_sequences` - Amino acid sequence of each protein
+    /// * `protease` - Protease for the theoretical digest
+    ///
+    pub fn ibaq(
+        &self,
+        peptides: &QuantificationMatrix,
+        protein_sequences: &HashMap<String, String>,
+        protease: &dyn Protease,
+    ) -> Result<QuantificationMatrix> {
+        let protein_peptides = self.get_protein_peptides(peptides);
+        let sample_count = peptides.get_samples().len();
+        let mut values: Vec<Vec<Option<f64>>> = Vec::with_capacity(protein_peptides.len());
+        for (protein, rows) in protein_peptides.iter() {
+            let sequence = match protein_sequences.get(protein) {
+                Some(sequence) => sequence,
+                None => bail!("No sequence for protein `{}`", protein),
+            };
+            let observable_peptides: HashSet<String> = protease
+                .cleave(sequence)
+                .context(format!("Error when digesting protein `{}`", protein))?
+                .map(|peptide: Peptide| Ok(peptide.get_sequence().clone()))
+                .collect()?;
+            values.push(
+                (0..sample_count)
+                    .map(|sample| {
+                        let intensities: Vec<f64> = rows
+                            .iter()
+                            .filter_map(|row| peptides.get_values()[*row][sample])
+                            .collect();
+                        if intensities.is_empty() || observable_peptides.is_empty() {
+                            return None;
+                        }
+                        Some(intensities.iter().sum::<f64>() / observable_peptides.len() as f64)
+                    })
+                    .collect(),
+            );
+        }
+        QuantificationMatrix::new(
+            protein_peptides.into_keys().collect(),
+            peptides.get_samples().clone(),
+            values,
+        )
+    }
+
+    /// MaxLFQ: peptide intensities are normalised with the delayed normalisation,
+    /// protein ratios between samples are the median of the peptide ratios and the abundances
+    /// are the least squares solution of all protein ratios, rescaled to the summed peptide intensities.
+    /// Samples without a protein ratio to any other sample are missing.
+    ///
+    /// > Cox et al. (2014) Accurate Proteome-wide Label-free Quantification by Delayed Normalization and Maximal Peptide Ratio Extraction, Termed MaxLFQ. https://doi.org/10.1074/mcp.M113.031591
+    ///
+    /// # Arguments
+    /// * `peptides` - Peptide intensities
+    ///
+    pub fn max_lfq(&self, peptides: &QuantificationMatrix) -> Result<QuantificationMatrix> {
+        let normalization_factors = calculate_delayed_normalization_factors(peptides)?;
+        let protein_peptides = self.get_protein_peptides(peptides);
+        let sample_count = peptides.get_samples().len();
+        let mut values: Vec<Vec<Option<f64>>> = Vec::with_capacity(protein_peptides.len());
+        for (protein, rows) in protein_peptides.iter() {
+            let normalized: Vec<Vec<Option<f64>>> = rows
+                .iter()
+                .map(|row| {
+                    peptides.get_values()[*row]
+                        .iter()
+                        .zip(normalization_factors.iter())
+                        .map(|(intensity, factor)| {
+                            intensity
+                                .filter(|intensity| *intensity > 0.0)
+                                .map(|intensity| intensity * factor)
+                        })
+                        .collect()
+                })
+                .collect();
+
+            let mut weights = vec![vec![0.0; sample_count]; sample_count];
+            let mut ratio_sums = vec![vec![0.0; sample_count]; sample_count];
+            for a in 0..sample_count {
+                for b in a + 1..sample_count {
+                    let mut log_ratios: Vec<f64> = normalized
+                        .iter()
+                        .filter_map(|intensities| match (intensities[a], intensities[b]) {
+                            (Some(intensity_a), Some(intensity_b)) => {
+                                Some(intensity_a.ln() - intensity_b.ln())
+                            }
+                            _ => None,
+                        })
+                        .collect();
+                    if log_ratios.len() < self.min_ratio_count {
+                        continue;
+                    }
+                    if let Some(log_ratio) = median(&mut log_ratios) {
+                        weights[a][b] = 1.0;
+                        weights[b][a] = 1.0;
+                        ratio_sums[a][b] = log_ratio;
+                        ratio_sums[b][a] = -log_ratio;
+                    }
+                }
+            }
+            let log_abundances = solve_pairwise_log_ratios(&weights, &ratio_sums)
+                .context(format!("Error when calculating MaxLFQ for `{}`", protein))?;
+
+            // Rescale each group of connected samples to its summed peptide intensity
+            let mut protein_values: Vec<Option<f64>> = vec![None; sample_count];
+            for component in get_connected_samples(&weights) {
+                if component.len() < 2 {
+                    continue;
+                }
+                let summed_intensity: f64 = component
+                    .iter()
+                    .map(|sample| {
+                        normalized
+                            .iter()
+                            .filter_map(|intensities| intensities[*sample])
+                            .sum::<f64>()
+                    })
+                    .sum();
+                let summed_abundance: f64 = component
+                    .iter()
+                    .filter_map(|sample| log_abundances[*sample].map(f64::exp))
+                    .sum();
+                for sample in component {
+                    protein_values[sample] = log_abundances[sample].map(|log_abundance| {
+                        log_abundance.exp() * summed_intensity / summed_abundance
+                    });
+                }
+            }
+            values.push(protein_values);
+        }
+        QuantificationMatrix::new(
+            protein_peptides.into_keys().collect(),
+            peptides.get_samples().clone(),
+            values,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proteomics::proteases::trypsin::Trypsin;
+
+    fn create_peptides() -> QuantificationMatrix {
+        QuantificationMatrix::new(
+            vec![
+                "MPEPTIDEK".to_string(),
+                "AAAAAAK".to_string(),
+                "LLLLLLR".to_string(),
+                "GGGGGGK".to_string(),
+                "SHAREDK".to_string(),
+                "SINGLEK".to_string(),
+            ],
+            vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            vec![
+                vec![Some(100.0), Some(200.0), Some(100.0)],
+                vec![Some(10.0), Some(20.0), Some(10.0)],
+                vec![Some(100.0), Some(50.0), Some(100.0)],
+                vec![Some(40.0), Some(20.0), Some(40.0)],
+                vec![Some(1000.0), Some(1000.0), Some(1000.0)],
+                vec![Some(5.0), None, None],
+            ],
+        )
+        .unwrap()
+    }
+
+    fn create_rollup() -> ProteinRollup {
+        ProteinRollup::new(HashMap::from([
+            ("MPEPTIDEK".to_string(), vec!["P1".to_string()]),
+            ("AAAAAAK".to_string(), vec!["P1".to_string()]),
+            ("LLLLLLR".to_string(), vec!["P2".to_string()]),
+            ("GGGGGGK".to_string(), vec!["P2".to_string()]),
+            (
+                "SHAREDK".to_string(),
+                vec!["P1".to_string(), "P2".to_string()],
+            ),
+            ("SINGLEK".to_string(), vec!["P3".to_string()]),
+        ]))
+    }
+
+    fn assert_approx(value: Option<f64>, expected: f64) {
+        assert!(
+            (value.unwrap() - expected).abs() < 1e-6,
+            "{:?} != {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_delayed_normalization() {
+        let peptides = create_peptides();
+        for factor in calculate_delayed_normalization_factors(&peptides).unwrap() {
+            assert!((factor - 1.0).abs() < 1e-9);
+        }
+        // loading difference of factor 2 in sample B
+        let peptides = QuantificationMatrix::new(
+            vec!["PEPTIDE".to_string(), "PEPTIDER".to_string()],
+            vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            vec![
+                vec![Some(100.0), Some(200.0), None],
+                vec![Some(10.0), Some(20.0), None],
+            ],
+        )
+        .unwrap();
+        let factors = calculate_delayed_normalization_factors(&peptides).unwrap();
+        assert!((factors[0] / factors[1] - 2.0).abs() < 1e-9);
+        assert!((factors[0] * factors[1] - 1.0).abs() < 1e-9);
+        assert_eq!(factors[2], 1.0);
+    }
+
+    #[test]
+    fn test_top3() {
+        let proteins = create_rollup().top3(&create_peptides()).unwrap();
+        assert_eq!(proteins.get_row_names(), &vec!["P1", "P2", "P3"]);
+        assert_approx(proteins.get("P1", "A"), 55.0);
+        assert_approx(proteins.get("P1", "B"), 110.0);
+        assert_approx(proteins.get("P2", "B"), 35.0);
+        assert_approx(proteins.get("P3", "A"), 5.0);
+        assert_eq!(proteins.get("P3", "B"), None);
+
+        // shared peptide is the most intense one for both proteins
+        let proteins = create_rollup()
+            .with_shared_peptides(true)
+            .top3(&create_peptides())
+            .unwrap();
+        assert_approx(proteins.get("P1", "A"), 370.0);
+    }
+
+    #[test]
+    fn test_ibaq() {
+        let protein_sequences = HashMap::from([
+            ("P1".to_string(), "MPEPTIDEKAAAAAAKLLR".to_string()),
+            ("P2".to_string(), "LLLLLLRGGGGGGK".to_string()),
+            ("P3".to_string(), "SINGLEK".to_string()),
+        ]);
+        let trypsin = Trypsin::new(Some(6), Some(30), Some(0)).unwrap();
+        let proteins = create_rollup()
+            .ibaq(&create_peptides(), &protein_sequences, &trypsin)
+            .unwrap();
+        assert_approx(proteins.get("P1", "A"), 55.0);
+        assert_approx(proteins.get("P2", "C"), 70.0);
+        assert_approx(proteins.get("P3", "A"), 5.0);
+
+        let protein_sequences = HashMap::from([("P1".to_string(), "MPEPTIDEK".to_string())]);
+        assert!(create_rollup()
+            .ibaq(&create_peptides(), &protein_sequences, &trypsin)
+            .is_err());
+    }
+
+    #[test]
+    fn test_max_lfq() {
+        let proteins = create_rollup().max_lfq(&create_peptides()).unwrap();
+        assert_approx(proteins.get("P1", "A"), 110.0);
+        assert_approx(proteins.get("P1", "B"), 220.0);
+        assert_approx(proteins.get("P1", "C"), 110.0);
+        assert_approx(proteins.get("P2", "A"), 140.0);
+        assert_approx(proteins.get("P2", "B"), 70.0);
+        assert_approx(proteins.get("P2", "C"), 140.0);
+        // no ratios
+        assert_eq!(proteins.get_row("P3").unwrap(), &vec![None, None, None]);
+
+        // single peptide ratios are sufficient with a minimum ratio count of 1
+        let proteins = create_rollup()
+            .with_min_ratio_count(1)
+            .unwrap()
+            .max_lfq(&create_peptides())
+            .unwrap();
+        assert_eq!(proteins.get_row("P3").unwrap(), &vec![None, None, None]);
+        assert_approx(proteins.get("P1", "B"), 220.0);
+        assert!(create_rollup().with_min_ratio_count(0).is_err());
+    }
+}
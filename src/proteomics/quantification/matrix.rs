@@ -0,0 +1,281 @@
+// std imports
+use std::collections::{HashMap, HashSet};
+
+// 3rd party imports
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::proteomics::quantification::isobaric::{IsobaricReagent, ReporterIons};
+use crate::proteomics::quantification::silac::SilacGroup;
+
+/// Intensities of peptides or proteins (rows) in samples (columns).
+/// Missing values are `None`.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuantificationMatrix {
+    row_names: Vec<String>,
+    samples: Vec<String>,
+    values: Vec<Vec<Option<f64>>>,
+}
+
+impl QuantificationMatrix {
+    /// Creates a new matrix
+    ///
+    /// # Arguments
+    /// * `row_names` - Unique names of the rows, e.g. peptide sequences or protein accessions
+    /// * `samples` - Sample names
+    /// * `values` - One row per row name with one value per sample
+    ///
+    pub fn new(
+        row_names: Vec<String>,
+        samples: Vec<String>,
+        values: Vec<Vec<Option<f64>>>,
+    ) -> Result<Self> {
+        if row_names.len() != values.len() {
+            bail!(
+                "Got {} row names but {} rows",
+                row_names.len(),
+                values.len()
+            );
+        }
+        let mut unique_row_names = HashSet::with_capacity(row_names.len());
+        if let Some(row_name) = row_names
+            .iter()
+            .find(|row_name| !unique_row_names.insert(row_name.as_str()))
+        {
+            bail!("Row name `{}` is not unique", row_name);
+        }
+        if let Some(row) = values.iter().position(|row| row.len() != samples.len()) {
+            bail!(
+                "Row `{}` has {} values but there are {} samples",
+                row_names[row],
+                values[row].len(),
+                samples.len()
+            );
+        }
+        Ok(Self {
+            row_names,
+            samples,
+            values,
+        })
+    }
+
+    /// Creates a matrix from reporter ion intensities with one row per spectrum and one column per channel,
+    /// e.g. for normalisation and imputation. Intensities of 0 are missing values.
+    ///
+    /// # Arguments
+    /// * `reagent` - Isobaric reagent
+    /// * `reporter_ions` - Reporter ions of each spectrum
+    ///
+    pub fn from_reporter_ions(
+        reagent: &IsobaricReagent,
+        reporter_ions: &[ReporterIons],
+    ) -> Result<Self> {
+        Self::new(
+            reporter_ions
+                .iter()
+                .map(|ions| ions.get_spectrum_id().clone())
+                .collect(),
+            reagent
+                .get_reporter_ions()
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            reporter_ions
+                .iter()
+                .map(|ions| {
+                    ions.get_intensities()
+                        .iter()
+                        .map(|intensity| (*intensity > 0.0).then_some(*intensity))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// Creates a matrix from SILAC groups with one row per group and one column per channel,
+    /// e.g. for normalisation and imputation. The rows are named by the peptide sequence and
+    /// the charge of the first detected feature of the group, e.g. `PEPTIDEK_2`.
+    ///
+    /// # Arguments
+    /// * `groups` - SILAC groups
+    /// * `sequences` - Identified peptide sequence of each group
+    /// * `channel_names` - Name of each channel, e.g. `light` and `heavy`
+    ///
+    pub fn from_silac_groups(
+        groups: &[SilacGroup],
+        sequences: &[String],
+        channel_names: Vec<String>,
+    ) -> Result<Self> {
+        if groups.len() != sequences.len() {
+            bail!(
+                "Got {} SILAC groups but {} peptide sequences",
+                groups.len(),
+                sequences.len()
+            );
+        }
+        let row_names = groups
+            .iter()
+            .zip(sequences.iter())
+            .map(
+                |(group, sequence)| match group.get_features().iter().flatten().next() {
+                    Some(feature) => Ok(format!("{}_{}", sequence, feature.get_charge())),
+                    None => bail!("SILAC group of `{}` has no feature", sequence),
+                },
+            )
+            .collect::<Result<Vec<String>>>()?;
+        Self::new(
+            row_names,
+            channel_names,
+            groups
+                .iter()
+                .map(|group| {
+                    group
+                        .get_features()
+                        .iter()
+                        .map(|feature| feature.as_ref().map(|feature| feature.get_intensity()))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the row names
+    ///
+    pub fn get_row_names(&self) -> &Vec<String> {
+        &self.row_names
+    }
+
+    /// Returns the sample names
+    ///
+    pub fn get_samples(&self) -> &Vec<String> {
+        &self.samples
+    }
+
+    /// Returns the values, one row per row name
+    ///
+    pub fn get_values(&self) -> &Vec<Vec<Option<f64>>> {
+        &self.values
+    }
+
+    /// Returns the mutable values
+    ///
+    pub fn get_values_mut(&mut self) -> &mut Vec<Vec<Option<f64>>> {
+        &mut self.values
+    }
+
+    /// Returns the present values of a sample
+    ///
+    /// # Arguments
+    /// * `column` - Index of the sample
+    ///
+    pub fn get_sample_values(&self, column: usize) -> Vec<f64> {
+        self.values.iter().filter_map(|row| row[column]).collect()
+    }
+
+    /// Returns a new matrix with the same row and sample names and the function applied to each present value.
+    /// The function may return None to remove a value.
+    ///
+    /// # Arguments
+    /// * `function` - Function to apply
+    ///
+    pub fn map_values<T>(&self, function: T) -> Self
+    where
+        T: Fn(f64) -> Option<f64>,
+    {
+        Self {
+            row_names: self.row_names.clone(),
+            samples: self.samples.clone(),
+            values: self
+                .values
+                .iter()
+                .map(|row| row.iter().map(|value| value.and_then(&function)).collect())
+                .collect(),
+        }
+    }
+
+    /// Returns the number of missing values
+    ///
+    pub fn count_missing(&self) -> usize {
+        self.values
+            .iter()
+            .flatten()
+            .filter(|value| value.is_none())
+            .count()
+    }
+
+    /// Returns the values of the row with the given name
+    ///
+    /// # Arguments
+    /// * `row_name` - Row name
+    ///
+    pub fn get_row(&self, row_name: &str) -> Option<&Vec<Option<f64>>> {
+        self.row_names
+            .iter()
+            .position(|name| name == row_name)
+            .map(|row| &self.values[row])
+    }
+
+    /// Returns the value of the given row and sample
+    ///
+    /// # Arguments
+    /// * `row_name` - Row name
+    /// * `sample` - Sample name
+    ///
+    pub fn get(&self, row_name: &str, sample: &str) -> Option<f64> {
+        let column = self.samples.iter().position(|name| name == sample)?;
+        self.get_row(row_name)?[column]
+    }
+
+    /// Returns the row index of each row name
+    ///
+    pub fn get_row_indices(&self) -> HashMap<&str, usize> {
+        self.row_names
+            .iter()
+            .enumerate()
+            .map(|(row, name)| (name.as_str(), row))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matrix() {
+        let matrix = QuantificationMatrix::new(
+            vec!["PEPTIDE".to_string(), "PEPTIDER".to_string()],
+            vec!["A".to_string(), "B".to_string()],
+            vec![vec![Some(1.0), None], vec![Some(2.0), Some(3.0)]],
+        )
+        .unwrap();
+        assert_eq!(matrix.get("PEPTIDER", "B"), Some(3.0));
+        assert_eq!(matrix.get("PEPTIDE", "B"), None);
+        assert_eq!(matrix.get("PEPTIDE", "C"), None);
+        assert_eq!(matrix.get_row_indices()["PEPTIDER"], 1);
+        assert_eq!(matrix.get_sample_values(1), vec![3.0]);
+        assert_eq!(matrix.count_missing(), 1);
+        let mapped = matrix.map_values(|value| (value > 1.0).then_some(value * 2.0));
+        assert_eq!(mapped.get_row("PEPTIDE").unwrap(), &vec![None, None]);
+        assert_eq!(mapped.get("PEPTIDER", "A"), Some(4.0));
+
+        let json = serde_json::to_string(&matrix).unwrap();
+        let deserialized: QuantificationMatrix = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, matrix);
+
+        assert!(QuantificationMatrix::new(
+            vec!["PEPTIDE".to_string()],
+            vec!["A".to_string(), "B".to_string()],
+            vec![vec![Some(1.0)]],
+        )
+        .is_err());
+        assert!(QuantificationMatrix::new(
+            vec!["PEPTIDE".to_string(), "PEPTIDE".to_string()],
+            vec!["A".to_string()],
+            vec![vec![Some(1.0)], vec![Some(2.0)]],
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,12 @@
+/// Imputation of missing values
+pub mod imputation;
+/// Isobaric labelling (TMT / iTRAQ) reporter ion quantification
+pub mod isobaric;
+/// Label-free protein quantification (Top3, iBAQ, MaxLFQ)
+pub mod label_free;
+/// Matrix of peptide or protein intensities across samples
+pub mod matrix;
+/// Normalisation and variance stabilising transformations
+pub mod normalization;
+/// SILAC and other metabolic labelling
+pub mod silac;
@@ -0,0 +1,239 @@
+// 3rd party imports
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::proteomics::quantification::matrix::QuantificationMatrix;
+use crate::tools::statistics::{mean, median, quantile_of_sorted};
+
+/// Variance stabilising transformations of intensities.
+/// Values which cannot be transformed (e.g. log2 of 0) become missing.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Transformation {
+    Log2,
+    /// Inverse hyperbolic sine of the intensity divided by the cofactor,
+    /// which is linear for low and logarithmic for high intensities
+    Asinh(f64),
+}
+
+impl Transformation {
+    /// Returns the transformed matrix
+    ///
+    /// # Arguments
+    /// * `matrix` - Intensities
+    ///
+    pub fn apply(&self, matrix: &QuantificationMatrix) -> Result<QuantificationMatrix> {
+        match self {
+            Self::Log2 => Ok(matrix.map_values(|value| (value > 0.0).then(|| value.log2()))),
+            Self::Asinh(cofactor) => {
+                if *cofactor <= 0.0 {
+                    bail!("Cofactor must be positive");
+                }
+                Ok(matrix.map_values(|value| Some((value / cofactor).asinh())))
+            }
+        }
+    }
+}
+
+/// Normalisation between samples. Centering is additive, so the intensities should be log transformed first.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Normalization {
+    /// Shifts each sample so its median equals the mean of all sample medians
+    MedianCentering,
+    /// Shifts each sample so its mean equals the mean of all sample means
+    MeanCentering,
+    /// Replaces each value by the mean of the values with the same quantile in all samples.
+    /// Ties get the average and samples with missing values are interpolated to their own number of values.
+    ///
+    /// > Bolstad et al. (2003) A comparison of normalization methods for high density oligonucleotide array data based on variance and bias. https://doi.org/10.1093/bioinformatics/19.2.185
+    Quantile,
+}
+
+impl Normalization {
+    /// Returns the normalised matrix
+    ///
+    /// # Arguments
+    /// * `matrix` - Intensities, one column per sample
+    ///
+    pub fn apply(&self, matrix: &QuantificationMatrix) -> Result<QuantificationMatrix> {
+        match self {
+            Self::MedianCentering => center(matrix, median),
+            Self::MeanCentering => center(matrix, |values| mean(values)),
+            Self::Quantile => Ok(quantile_normalization(matrix)),
+        }
+    }
+}
+
+/// Shifts each sample so its location equals the mean location of all samples
+///
+/// # Arguments
+/// * `matrix` - Intensities
+/// * `location` - Location function, e.g. median
+///
+fn center<T>(matrix: &QuantificationMatrix, location: T) -> Result<QuantificationMatrix>
+where
+    T: Fn(&mut [f64]) -> Option<f64>,
+{
+    let locations: Vec<Option<f64>> = (0..matrix.get_samples().len())
+        .map(|column| location(&mut matrix.get_sample_values(column)))
+        .collect();
+    let present_locations: Vec<f64> = locations.iter().flatten().copied().collect();
+    let target = match mean(&present_locations) {
+        Some(target) => target,
+        None => bail!("Matrix has no values to center"),
+    };
+    let mut normalized = matrix.clone();
+    for row in normalized.get_values_mut() {
+        for (value, location) in row.iter_mut().zip(locations.iter()) {
+            if let (Some(value), Some(location)) = (value.as_mut(), location) {
+                *value += target - location;
+            }
+        }
+    }
+    Ok(normalized)
+}
+
+/// Quantile normalisation supporting missing values
+///
+/// # Arguments
+/// * `matrix` - Intensities
+///
+fn quantile_normalization(matrix: &QuantificationMatrix) -> QuantificationMatrix {
+    let sample_count = matrix.get_samples().len();
+    let sorted_samples: Vec<Vec<f64>> = (0..sample_count)
+        .map(|column| {
+            let mut values = matrix.get_sample_values(column);
+            values.sort_by(|x, y| x.total_cmp(y));
+            values
+        })
+        .filter(|values| !values.is_empty())
+        .collect();
+    let reference = |probability: f64| -> f64 {
+        sorted_samples
+            .iter()
+            .filter_map(|values| quantile_of_sorted(values, probability))
+            .sum::<f64>()
+            / sorted_samples.len() as f64
+    };
+
+    let mut normalized = matrix.clone();
+    for column in 0..sample_count {
+        let mut rows: Vec<usize> = (0..matrix.get_values().len())
+            .filter(|row| matrix.get_values()[*row][column].is_some())
+            .collect();
+        rows.sort_by(|x, y| {
+            matrix.get_values()[*x][column]
+                .unwrap_or_default()
+                .total_cmp(&matrix.get_values()[*y][column].unwrap_or_default())
+        });
+        let value_count = rows.len();
+        let get_probability = |rank: usize| match value_count {
+            1 => 0.5,
+            _ => rank as f64 / (value_count - 1) as f64,
+        };
+        let mut start = 0;
+        while start < value_count {
+            // group ties
+            let value = matrix.get_values()[rows[start]][column];
+            let mut end = start + 1;
+            while end < value_count && matrix.get_values()[rows[end]][column] == value {
+                end += 1;
+            }
+            let normalized_value = (start..end)
+                .map(|rank| reference(get_probability(rank)))
+                .sum::<f64>()
+                / (end - start) as f64;
+            for row in rows[start..end].iter() {
+                normalized.get_values_mut()[*row][column] = Some(normalized_value);
+            }
+            start = end;
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_matrix(values: Vec<Vec<Option<f64>>>) -> QuantificationMatrix {
+        QuantificationMatrix::new(
+            (0..values.len()).map(|row| format!("row{}", row)).collect(),
+            (0..values[0].len())
+                .map(|column| format!("sample{}", column))
+                .collect(),
+            values,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_transformation() {
+        let matrix = create_matrix(vec![vec![Some(8.0), Some(0.0), None]]);
+        let transformed = Transformation::Log2.apply(&matrix).unwrap();
+        assert_eq!(transformed.get_values()[0], vec![Some(3.0), None, None]);
+        let transformed = Transformation::Asinh(2.0).apply(&matrix).unwrap();
+        assert_eq!(
+            transformed.get_values()[0],
+            vec![Some(4.0_f64.asinh()), Some(0.0), None]
+        );
+        assert!(Transformation::Asinh(0.0).apply(&matrix).is_err());
+    }
+
+    #[test]
+    fn test_centering() {
+        let matrix = create_matrix(vec![
+            vec![Some(1.0), Some(3.0)],
+            vec![Some(2.0), Some(4.0)],
+            vec![Some(3.0), Some(11.0)],
+            vec![None, Some(5.0)],
+        ]);
+        // medians: 2 and 4.5 => target 3.25
+        let normalized = Normalization::MedianCentering.apply(&matrix).unwrap();
+        assert_eq!(normalized.get_values()[0], vec![Some(2.25), Some(1.75)]);
+        assert_eq!(normalized.get_values()[3], vec![None, Some(3.75)]);
+        // means: 2 and 5.75 => target 3.875
+        let normalized = Normalization::MeanCentering.apply(&matrix).unwrap();
+        assert_eq!(normalized.get_values()[0], vec![Some(2.875), Some(1.125)]);
+
+        assert!(Normalization::MedianCentering
+            .apply(&create_matrix(vec![vec![None]]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_quantile_normalization() {
+        // example from https://en.wikipedia.org/wiki/Quantile_normalization
+        let matrix = create_matrix(vec![
+            vec![Some(5.0), Some(4.0), Some(3.0)],
+            vec![Some(2.0), Some(1.0), Some(4.0)],
+            vec![Some(3.0), Some(4.0), Some(6.0)],
+            vec![Some(4.0), Some(2.0), Some(8.0)],
+        ]);
+        let normalized = Normalization::Quantile.apply(&matrix).unwrap();
+        let expected = [
+            [5.666667, 5.166667, 2.0],
+            [2.0, 2.0, 3.0],
+            [3.0, 5.166667, 4.666667],
+            [4.666667, 3.0, 5.666667],
+        ];
+        for (row, expected_row) in normalized.get_values().iter().zip(expected.iter()) {
+            for (value, expected_value) in row.iter().zip(expected_row.iter()) {
+                assert!((value.unwrap() - expected_value).abs() < 1e-6);
+            }
+        }
+
+        // missing values keep missing, the present values are mapped on the full distribution
+        let matrix = create_matrix(vec![
+            vec![Some(1.0), Some(10.0)],
+            vec![Some(2.0), None],
+            vec![Some(3.0), Some(30.0)],
+        ]);
+        let normalized = Normalization::Quantile.apply(&matrix).unwrap();
+        assert_eq!(normalized.get_values()[1][1], None);
+        assert_eq!(normalized.get_values()[0][1], normalized.get_values()[0][0]);
+        assert_eq!(normalized.get_values()[2][1], normalized.get_values()[2][0]);
+    }
+}
@@ -0,0 +1,423 @@
+// 3rd party imports
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::chemistry::amino_acid::labeled_amino_acid::LabeledAminoAcid;
+use crate::chemistry::amino_acid::AminoAcid;
+use crate::mass_spectrometry::feature_detection::Feature;
+use crate::mass_spectrometry::tolerance::Tolerance;
+
+/// Default maximum retention time difference of the feature apices in seconds
+const DEFAULT_RETENTION_TIME_TOLERANCE: f64 = 30.0;
+
+/// Default maximum number of labeled residues (K + R) per peptide
+const DEFAULT_MAX_LABELED_RESIDUES: usize = 2;
+
+/// Matched features for one number of lysines and arginines
+///
+struct GroupCandidate {
+    matched_channels: usize,
+    mass_error: f64,
+    lysine_count: usize,
+    arginine_count: usize,
+    matches: Vec<Option<usize>>,
+}
+
+/// Group of co-eluting MS1 features of the same peptide in different SILAC channels
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SilacGroup {
+    lysine_count: usize,
+    arginine_count: usize,
+    features: Vec<Option<Feature>>,
+}
+
+impl SilacGroup {
+    /// Returns the number of lysines assumed for the mass offsets
+    ///
+    pub fn get_lysine_count(&self) -> usize {
+        self.lysine_count
+    }
+
+    /// Returns the number of arginines assumed for the mass offsets
+    ///
+    pub fn get_arginine_count(&self) -> usize {
+        self.arginine_count
+    }
+
+    /// Returns the feature of each channel, the first one is the light channel
+    ///
+    pub fn get_features(&self) -> &Vec<Option<Feature>> {
+        &self.features
+    }
+
+    /// Returns the intensity ratio of the given channel to the light channel
+    ///
+    /// # Arguments
+    /// * `channel` - Index of the channel
+    ///
+    pub fn get_ratio(&self, channel: usize) -> Option<f64> {
+        let light = self.features.first()?.as_ref()?;
+        let other = self.features.get(channel)?.as_ref()?;
+        match light.get_intensity() > 0.0 {
+            true => Some(other.get_intensity() / light.get_intensity()),
+            false => None,
+        }
+    }
+}
+
+/// Finds SILAC pairs or triplets in MS1 features by the expected mass offsets
+/// for a given number of lysines and arginines.
+///
+pub struct SilacGroupFinder {
+    channels: Vec<Vec<&'static LabeledAminoAcid>>,
+    tolerance: Tolerance,
+    retention_time_tolerance: f64,
+    max_labeled_residues: usize,
+}
+
+impl SilacGroupFinder {
+    /// Creates a new finder
+    ///
+    /// # Arguments
+    /// * `channels` - Labeled lysines and arginines of each channel, starting with the light channel (usually empty)
+    /// * `tolerance` - Mass tolerance for the expected neutral mass of the labeled features
+    ///
+    pub fn new(
+        channels: Vec<Vec<&'static LabeledAminoAcid>>,
+        tolerance: Tolerance,
+    ) -> Result<Self> {
+        if channels.len() < 2 {
+            bail!("At least two channels are required");
+        }
+        for channel in channels.iter() {
+            for (idx, label) in channel.iter().enumerate() {
+                if !matches!(label.get_code(), 'K' | 'R') {
+                    bail!(
+                        "Label {} is neither on lysine nor arginine",
+                        label.get_name()
+                    );
+                }
+                if channel[idx + 1..]
+                    .iter()
+                    .any(|other| other.get_code() == label.get_code())
+                {
+                    bail!("Multiple labels for residue {}", label.get_code());
+                }
+            }
+        }
+        Ok(Self {
+            channels,
+            tolerance,
+            retention_time_tolerance: DEFAULT_RETENTION_TIME_TOLERANCE,
+            max_labeled_residues: DEFAULT_MAX_LABELED_RESIDUES,
+        })
+    }
+
+    /// Sets the maximum retention time difference of the feature apices
+    ///
+    /// # Arguments
+    /// * `retention_time_tolerance` - Retention time tolerance in seconds
+    ///
+    pub fn with_retention_time_tolerance(mut self, retention_time_tolerance: f64) -> Self {
+        self.retention_time_tolerance = retention_time_tolerance;
+        self
+    }
+
+    /// Sets the maximum number of labeled residues (K + R) per peptide,
+    /// e.g. 2 for tryptic peptides with one missed cleavage
+    ///
+    /// # Arguments
+    /// * `max_labeled_residues` - Maximum number of labeled residues
+    ///
+    pub fn with_max_labeled_residues(mut self, max_labeled_residues: usize) -> Result<Self> {
+        if max_labeled_residues == 0 {
+            bail!("At least one labeled residue is required");
+        }
+        self.max_labeled_residues = max_labeled_residues;
+        Ok(self)
+    }
+
+    /// Returns the mass shift of a channel for the given number of lysines and arginines
+    ///
+    /// # Arguments
+    /// * `channel` - Index of the channel
+    /// * `lysine_count` - Number of lysines
+    /// * `arginine_count` - Number of arginines
+    ///
+    pub fn get_mass_shift(
+        &self,
+        channel: usize,
+        lysine_count: usize,
+        arginine_count: usize,
+    ) -> f64 {
+        self.channels[channel]
+            .iter()
+            .map(|label| match label.get_code() {
+                'K' => lysine_count as f64 * label.get_mass_shift(),
+                _ => arginine_count as f64 * label.get_mass_shift(),
+            })
+            .sum()
+    }
+
+    /// Returns the mass offset of a channel relative to the light channel
+    ///
+    /// # Arguments
+    /// * `channel` - Index of the channel
+    /// * `lysine_count` - Number of lysines
+    /// * `arginine_count` - Number of arginines
+    ///
+    pub fn get_mass_offset(
+        &self,
+        channel: usize,
+        lysine_count: usize,
+        arginine_count: usize,
+    ) -> f64 {
+        self.get_mass_shift(channel, lysine_count, arginine_count)
+            - self.get_mass_shift(0, lysine_count, arginine_count)
+    }
+
+    /// Groups the features. Each light feature is combined with the co-eluting features of the same charge
+    /// matching the expected mass offsets. If several numbers of lysines and arginines match,
+    /// the one with the most matched channels and the lowest mass error is chosen.
+    /// Each feature is used in at most one group.
+    ///
+    /// # Arguments
+    /// * `features` - MS1 features, e.g. from `FeatureFinder`
+    ///
+    pub fn find_groups(&self, features: &[Feature]) -> Vec<SilacGroup> {
+        let mut order: Vec<usize> = (0..features.len()).collect();
+        order.sort_by(|x, y| {
+            features[*x]
+                .get_neutral_mass()
+                .total_cmp(&features[*y].get_neutral_mass())
+        });
+        let masses: Vec<f64> = order
+            .iter()
+            .map(|idx| features[*idx].get_neutral_mass())
+            .collect();
+        let mut used = vec![false; features.len()];
+        let mut groups: Vec<SilacGroup> = Vec::new();
+
+        for light_idx in order.iter().copied() {
+            if used[light_idx] {
+                continue;
+            }
+            let light = &features[light_idx];
+            let mut best: Option<GroupCandidate> = None;
+            for lysine_count in 0..=self.max_labeled_residues {
+                for arginine_count in 0..=self.max_labeled_residues - lysine_count {
+                    if lysine_count + arginine_count == 0 {
+                        continue;
+                    }
+                    let mut matches: Vec<Option<usize>> = vec![Some(light_idx)];
+                    let mut mass_error = 0.0;
+                    for channel in 1..self.channels.len() {
+                        let offset = self.get_mass_offset(channel, lysine_count, arginine_count);
+                        if offset.abs() < f64::EPSILON {
+                            matches.push(None);
+                            continue;
+                        }
+                        let expected_mass = light.get_neutral_mass() + offset;
+                        let (lower, upper) = self.tolerance.get_bounds(expected_mass);
+                        let start = masses.partition_point(|mass| *mass < lower);
+                        let end = masses.partition_point(|mass| *mass <= upper);
+                        let closest = order[start..end]
+                            .iter()
+                            .copied()
+                            .filter(|idx| {
+                                !used[*idx]
+                                    && *idx != light_idx
+                                    && !matches.contains(&Some(*idx))
+                                    && features[*idx].get_charge() == light.get_charge()
+                                    && (features[*idx].get_retention_time_apex()
+                                        - light.get_retention_time_apex())
+                                    .abs()
+                                        <= self.retention_time_tolerance
+                            })
+                            .map(|idx| {
+                                (
+                                    idx,
+                                    (features[idx].get_neutral_mass() - expected_mass).abs(),
+                                )
+                            })
+                            .min_by(|(_, x), (_, y)| x.total_cmp(y));
+                        match closest {
+                            Some((idx, error)) => {
+                                mass_error += error;
+                                matches.push(Some(idx));
+                            }
+                            None => matches.push(None),
+                        }
+                    }
+                    let matched_channels = matches[1..].iter().flatten().count();
+                    if matched_channels == 0 {
+                        continue;
+                    }
+                    let is_better = match &best {
+                        Some(best) => {
+                            matched_channels > best.matched_channels
+                                || (matched_channels == best.matched_channels
+                                    && mass_error < best.mass_error)
+                        }
+                        None => true,
+                    };
+                    if is_better {
+                        best = Some(GroupCandidate {
+                            matched_channels,
+                            mass_error,
+                            lysine_count,
+                            arginine_count,
+                            matches,
+                        });
+                    }
+                }
+            }
+            if let Some(best) = best {
+                for idx in best.matches.iter().flatten() {
+                    used[*idx] = true;
+                }
+                groups.push(SilacGroup {
+                    lysine_count: best.lysine_count,
+                    arginine_count: best.arginine_count,
+                    features: best
+                        .matches
+                        .into_iter()
+                        .map(|idx| idx.map(|idx| features[idx].clone()))
+                        .collect(),
+                });
+            }
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chemistry::amino_acid::labeled_amino_acid::{
+        ARGININE_13C6, ARGININE_13C6_15N4, LYSINE_13C6_15N2, LYSINE_D4,
+    };
+    use crate::chemistry::subatomic_particle::PROTON;
+    use crate::proteomics::quantification::matrix::QuantificationMatrix;
+
+    fn create_feature(
+        neutral_mass: f64,
+        charge: u8,
+        retention_time: f64,
+        intensity: f64,
+    ) -> Feature {
+        serde_json::from_value(serde_json::json!({
+            "monoisotopic_mz": (neutral_mass + charge as f64 * PROTON.get_mass()) / charge as f64,
+            "charge": charge,
+            "retention_time_apex": retention_time,
+            "retention_time_start": retention_time - 10.0,
+            "retention_time_end": retention_time + 10.0,
+            "intensity": intensity,
+            "isotope_fit_score": 0.99,
+            "scan_count": 10,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_find_pairs() {
+        let finder = SilacGroupFinder::new(
+            vec![vec![], vec![&LYSINE_13C6_15N2, &ARGININE_13C6_15N4]],
+            Tolerance::Ppm(10.0),
+        )
+        .unwrap();
+        assert!((finder.get_mass_offset(1, 1, 1) - 18.022468).abs() < 1e-6);
+        let features = vec![
+            // peptide with one lysine
+            create_feature(1000.0, 2, 600.0, 1000.0),
+            create_feature(1000.0 + LYSINE_13C6_15N2.get_mass_shift(), 2, 602.0, 2000.0),
+            // peptide with two arginines, heavy elutes too late
+            create_feature(1500.0, 3, 900.0, 1000.0),
+            create_feature(
+                1500.0 + 2.0 * ARGININE_13C6_15N4.get_mass_shift(),
+                3,
+                1000.0,
+                500.0,
+            ),
+            // wrong charge
+            create_feature(2000.0, 2, 1200.0, 1000.0),
+            create_feature(
+                2000.0 + ARGININE_13C6_15N4.get_mass_shift(),
+                3,
+                1200.0,
+                500.0,
+            ),
+        ];
+        let groups = finder.find_groups(&features);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].get_lysine_count(), 1);
+        assert_eq!(groups[0].get_arginine_count(), 0);
+        assert_eq!(groups[0].get_ratio(1), Some(2.0));
+        assert_eq!(groups[0].get_features()[0].as_ref(), Some(&features[0]));
+
+        let finder = finder.with_retention_time_tolerance(120.0);
+        let groups = finder.find_groups(&features);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].get_arginine_count(), 2);
+        assert_eq!(groups[1].get_ratio(1), Some(0.5));
+
+        let sequences = vec!["PEPTIDEK".to_string(), "PEPTIDERR".to_string()];
+        let matrix = QuantificationMatrix::from_silac_groups(
+            &groups,
+            &sequences,
+            vec!["light".to_string(), "heavy".to_string()],
+        )
+        .unwrap();
+        assert_eq!(matrix.get_row_names()[1], "PEPTIDERR_3");
+        assert_eq!(matrix.get_values()[1], vec![Some(1000.0), Some(500.0)]);
+        assert!(QuantificationMatrix::from_silac_groups(
+            &groups,
+            &sequences,
+            vec!["light".to_string()]
+        )
+        .is_err());
+        assert!(QuantificationMatrix::from_silac_groups(
+            &groups,
+            &[],
+            vec!["light".to_string(), "heavy".to_string()]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_find_triplets() {
+        let finder = SilacGroupFinder::new(
+            vec![
+                vec![],
+                vec![&LYSINE_D4, &ARGININE_13C6],
+                vec![&LYSINE_13C6_15N2, &ARGININE_13C6_15N4],
+            ],
+            Tolerance::Ppm(10.0),
+        )
+        .unwrap();
+        let features = vec![
+            create_feature(1200.0, 2, 600.0, 1000.0),
+            create_feature(1200.0 + ARGININE_13C6.get_mass_shift(), 2, 600.0, 1500.0),
+            create_feature(
+                1200.0 + ARGININE_13C6_15N4.get_mass_shift(),
+                2,
+                600.0,
+                3000.0,
+            ),
+        ];
+        let groups = finder.find_groups(&features);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].get_arginine_count(), 1);
+        assert_eq!(groups[0].get_ratio(1), Some(1.5));
+        assert_eq!(groups[0].get_ratio(2), Some(3.0));
+
+        assert!(SilacGroupFinder::new(vec![vec![]], Tolerance::Ppm(10.0)).is_err());
+        assert!(SilacGroupFinder::new(
+            vec![vec![], vec![&LYSINE_D4, &LYSINE_13C6_15N2]],
+            Tolerance::Ppm(10.0)
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,85 @@
+// 3rd party imports
+use anyhow::Result;
+
+// internal imports
+use crate::mass_spectrometry::processing::filter::filter_top_n_peaks_per_window;
+use crate::mass_spectrometry::spectrum::Spectrum;
+use crate::mass_spectrometry::tolerance::Tolerance;
+use crate::proteomics::fragmentation::{calculate_fragment_ions, IonType};
+use crate::proteomics::peptide::ModifiedPeptide;
+use crate::proteomics::scoring::functions::{binomial_tail_probability, match_fragment_ions};
+
+/// Maximum number of peaks per window tested
+///
+pub const MAX_PEAKS_PER_WINDOW: usize = 10;
+
+/// Width of the windows used for peak filtering
+///
+const WINDOW_WIDTH: f64 = 100.0;
+
+/// Calculates an Andromeda-like binomial score.
+/// For 1 to `MAX_PEAKS_PER_WINDOW` peaks per 100 m/z window, the spectrum is filtered and
+/// the probability of matching at least as many b- and y-ions by chance is calculated.
+/// The score is the best `-10 * log10(probability)`.
+///
+/// > Cox J, Neuhauser N, Michalski A, Scheltema RA, Olsen JV, Mann M.
+/// > Andromeda: a peptide search engine integrated into the MaxQuant environment.
+/// > J Proteome Res. 2011;10(4):1794-1805. https://doi.org/10.1021/pr101065j
+///
+/// # Arguments
+/// * `spectrum` - MS2 spectrum with sorted m/z values
+/// * `peptide` - Peptide
+/// * `tolerance` - Fragment tolerance
+/// * `max_fragment_charge` - Maximum fragment charge
+///
+pub fn binomial_score<S>(
+    spectrum: &S,
+    peptide: &ModifiedPeptide,
+    tolerance: &Tolerance,
+    max_fragment_charge: u8,
+) -> Result<f64>
+where
+    S: Spectrum,
+{
+    let fragment_ions =
+        calculate_fragment_ions(peptide, &[IonType::B, IonType::Y], max_fragment_charge)?;
+    let mut best_score: f64 = 0.0;
+    for peaks_per_window in 1..=MAX_PEAKS_PER_WINDOW {
+        let filtered_spectrum =
+            filter_top_n_peaks_per_window(spectrum, peaks_per_window, WINDOW_WIDTH);
+        let matches = match_fragment_ions(&filtered_spectrum, &fragment_ions, tolerance).len();
+        let probability = binomial_tail_probability(
+            fragment_ions.len(),
+            matches,
+            peaks_per_window as f64 / WINDOW_WIDTH,
+        );
+        best_score = best_score.max(-10.0 * probability.log10());
+    }
+    Ok(best_score)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mass_spectrometry::spectrum::SimpleSpectrum;
+    use crate::proteomics::peptide::Peptide;
+
+    #[test]
+    fn test_binomial_score() {
+        let peptide: ModifiedPeptide = Peptide::new("PEPTIDE".to_string(), 0).unwrap().into();
+        let other_peptide: ModifiedPeptide = Peptide::new("GGGGGGG".to_string(), 0).unwrap().into();
+        let spectrum = SimpleSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![148.06043, 227.10263, 263.08738, 300.0, 324.15539],
+            vec![50.0, 100.0, 25.0, 200.0, 25.0],
+        );
+        let score = binomial_score(&spectrum, &peptide, &Tolerance::Da(0.02), 1).unwrap();
+        let other_score =
+            binomial_score(&spectrum, &other_peptide, &Tolerance::Da(0.02), 1).unwrap();
+        // 2 peaks per window match y1, b2, y2 and b3 out of 12 ions with p = 0.02:
+        // -10 * log10(P(X >= 4)) = 41.5718
+        assert!((score - 41.5718).abs() < 1e-4);
+        assert_eq!(other_score, 0.0);
+    }
+}
@@ -0,0 +1,93 @@
+// internal imports
+use crate::mass_spectrometry::spectrum::Spectrum;
+use crate::mass_spectrometry::tolerance::{find_closest_peak, Tolerance};
+use crate::proteomics::fragmentation::FragmentIon;
+
+/// Returns the natural logarithm of n!
+///
+/// # Arguments
+/// * `n` - Number
+///
+pub fn ln_factorial(n: usize) -> f64 {
+    (2..=n).map(|i| (i as f64).ln()).sum()
+}
+
+/// Returns the natural logarithm of the binomial coefficient `n over k`
+///
+/// # Arguments
+/// * `n` - Number of trials
+/// * `k` - Number of successes
+///
+pub fn ln_binomial_coefficient(n: usize, k: usize) -> f64 {
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+/// Returns the probability to observe `k` or more successes in `n` trials
+///
+/// # Arguments
+/// * `n` - Number of trials
+/// * `k` - Number of successes
+/// * `p` - Probability of a success
+///
+pub fn binomial_tail_probability(n: usize, k: usize, p: f64) -> f64 {
+    if k == 0 {
+        return 1.0;
+    }
+    if p >= 1.0 {
+        return 1.0;
+    }
+    (k..=n)
+        .map(|j| {
+            (ln_binomial_coefficient(n, j) + j as f64 * p.ln() + (n - j) as f64 * (1.0 - p).ln())
+                .exp()
+        })
+        .sum::<f64>()
+        .min(1.0)
+}
+
+/// Matches each fragment ion to the closest peak within the tolerance.
+/// Returns a list of (fragment ion index, peak index).
+/// The spectrum's m/z values need to be sorted ascending.
+///
+/// # Arguments
+/// * `spectrum` - Spectrum
+/// * `fragment_ions` - Theoretical fragment ions
+/// * `tolerance` - Fragment tolerance
+///
+pub fn match_fragment_ions<S>(
+    spectrum: &S,
+    fragment_ions: &[FragmentIon],
+    tolerance: &Tolerance,
+) -> Vec<(usize, usize)>
+where
+    S: Spectrum,
+{
+    fragment_ions
+        .iter()
+        .enumerate()
+        .filter_map(|(ion_idx, ion)| {
+            find_closest_peak(spectrum.get_mz(), ion.get_mz(), tolerance)
+                .map(|peak_idx| (ion_idx, peak_idx))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ln_factorial() {
+        assert_eq!(ln_factorial(0), 0.0);
+        assert_eq!(ln_factorial(1), 0.0);
+        assert!((ln_factorial(5) - 120.0_f64.ln()).abs() < 1e-12);
+        assert!((ln_binomial_coefficient(5, 2) - 10.0_f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_binomial_tail_probability() {
+        assert_eq!(binomial_tail_probability(10, 0, 0.1), 1.0);
+        assert!((binomial_tail_probability(2, 2, 0.5) - 0.25).abs() < 1e-12);
+        assert!((binomial_tail_probability(2, 1, 0.5) - 0.75).abs() < 1e-12);
+    }
+}
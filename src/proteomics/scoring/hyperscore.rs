@@ -0,0 +1,90 @@
+// 3rd party imports
+use anyhow::Result;
+
+// internal imports
+use crate::mass_spectrometry::spectrum::Spectrum;
+use crate::mass_spectrometry::tolerance::Tolerance;
+use crate::proteomics::fragmentation::{calculate_fragment_ions, IonType};
+use crate::proteomics::peptide::ModifiedPeptide;
+use crate::proteomics::scoring::functions::{ln_factorial, match_fragment_ions};
+
+/// Intensity of the base peak after normalization
+///
+const BASE_PEAK_INTENSITY: f64 = 100.0;
+
+/// Calculates the X!Tandem hyperscore:
+/// `ln(N_b! * N_y! * sum(I_matched))`
+/// where intensities are normalized to a base peak intensity of 100.
+/// Returns 0.0 if no fragment ion is matched.
+///
+/// > Craig R, Beavis RC. TANDEM: matching proteins with tandem mass spectra.
+/// > Bioinformatics. 2004;20(9):1466-1467. https://doi.org/10.1093/bioinformatics/bth092
+///
+/// # Arguments
+/// * `spectrum` - MS2 spectrum with sorted m/z values
+/// * `peptide` - Peptide
+/// * `tolerance` - Fragment tolerance
+/// * `max_fragment_charge` - Maximum fragment charge
+///
+pub fn hyperscore<S>(
+    spectrum: &S,
+    peptide: &ModifiedPeptide,
+    tolerance: &Tolerance,
+    max_fragment_charge: u8,
+) -> Result<f64>
+where
+    S: Spectrum,
+{
+    let base_peak_intensity = spectrum.get_intensity().iter().cloned().fold(0.0, f64::max);
+    if base_peak_intensity <= 0.0 {
+        return Ok(0.0);
+    }
+
+    let fragment_ions =
+        calculate_fragment_ions(peptide, &[IonType::B, IonType::Y], max_fragment_charge)?;
+    let mut matched_b_ions = 0;
+    let mut matched_y_ions = 0;
+    let mut matched_intensity = 0.0;
+    for (ion_idx, peak_idx) in match_fragment_ions(spectrum, &fragment_ions, tolerance) {
+        match fragment_ions[ion_idx].get_ion_type() {
+            IonType::B => matched_b_ions += 1,
+            _ => matched_y_ions += 1,
+        }
+        matched_intensity +=
+            spectrum.get_intensity()[peak_idx] / base_peak_intensity * BASE_PEAK_INTENSITY;
+    }
+
+    if matched_intensity == 0.0 {
+        return Ok(0.0);
+    }
+
+    Ok(ln_factorial(matched_b_ions) + ln_factorial(matched_y_ions) + matched_intensity.ln())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mass_spectrometry::spectrum::SimpleSpectrum;
+    use crate::proteomics::peptide::Peptide;
+
+    #[test]
+    fn test_hyperscore() {
+        let peptide: ModifiedPeptide = Peptide::new("PEPTIDE".to_string(), 0).unwrap().into();
+        // b2, b3, y1, y2 + noise
+        let spectrum = SimpleSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![148.06043, 227.10263, 263.08738, 300.0, 324.15539],
+            vec![50.0, 100.0, 25.0, 200.0, 25.0],
+        );
+        let score = hyperscore(&spectrum, &peptide, &Tolerance::Da(0.02), 1).unwrap();
+        // 2! * 2! * (25 + 50 + 12.5 + 12.5)
+        assert!((score - (2.0_f64 * 2.0 * 100.0).ln()).abs() < 1e-9);
+
+        let empty_spectrum = SimpleSpectrum::new("test".to_string(), 2, vec![300.0], vec![1.0]);
+        assert_eq!(
+            hyperscore(&empty_spectrum, &peptide, &Tolerance::Da(0.02), 1).unwrap(),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,189 @@
+// std imports
+use std::collections::HashSet;
+
+// 3rd party imports
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::mass_spectrometry::spectrum::Spectrum;
+use crate::mass_spectrometry::tolerance::Tolerance;
+use crate::proteomics::fragmentation::{calculate_fragment_ions, IonType};
+use crate::proteomics::peptide::ModifiedPeptide;
+use crate::proteomics::scoring::functions::match_fragment_ions;
+
+/// Statistics about the fragment ions of a peptide matched to a spectrum
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MatchedIonStatistics {
+    /// Number of theoretical fragment ions
+    theoretical_ions: usize,
+    /// Number of theoretical fragment ions with a matching peak
+    matched_ions: usize,
+    /// Number of peaks explained by at least one fragment ion
+    matched_peaks: usize,
+    /// Intensity of the matched peaks divided by the total ion current
+    fraction_explained_intensity: f64,
+    /// Longest series of consecutive N-terminal fragments (a, b or c) with at least one matched ion
+    longest_n_terminal_ladder: usize,
+    /// Longest series of consecutive C-terminal fragments (x, y or z) with at least one matched ion
+    longest_c_terminal_ladder: usize,
+}
+
+impl MatchedIonStatistics {
+    /// Returns the number of theoretical fragment ions
+    ///
+    pub fn get_theoretical_ions(&self) -> usize {
+        self.theoretical_ions
+    }
+
+    /// Returns the number of theoretical fragment ions with a matching peak
+    ///
+    pub fn get_matched_ions(&self) -> usize {
+        self.matched_ions
+    }
+
+    /// Returns the number of peaks explained by at least one fragment ion
+    ///
+    pub fn get_matched_peaks(&self) -> usize {
+        self.matched_peaks
+    }
+
+    /// Returns the intensity of the matched peaks divided by the total ion current
+    ///
+    pub fn get_fraction_explained_intensity(&self) -> f64 {
+        self.fraction_explained_intensity
+    }
+
+    /// Returns the longest series of consecutive N-terminal fragments
+    ///
+    pub fn get_longest_n_terminal_ladder(&self) -> usize {
+        self.longest_n_terminal_ladder
+    }
+
+    /// Returns the longest series of consecutive C-terminal fragments
+    ///
+    pub fn get_longest_c_terminal_ladder(&self) -> usize {
+        self.longest_c_terminal_ladder
+    }
+
+    /// Returns the longest ion ladder of both termini
+    ///
+    pub fn get_longest_ion_ladder(&self) -> usize {
+        usize::max(
+            self.longest_n_terminal_ladder,
+            self.longest_c_terminal_ladder,
+        )
+    }
+}
+
+/// Returns the length of the longest run of consecutive ordinals
+///
+/// # Arguments
+/// * `ordinals` - Matched ordinals
+///
+fn longest_ladder(ordinals: &HashSet<usize>) -> usize {
+    ordinals
+        .iter()
+        .filter(|ordinal| !ordinals.contains(&(**ordinal - 1)))
+        .map(|ordinal| (*ordinal..).take_while(|o| ordinals.contains(o)).count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Calculates statistics about the matched fragment ions
+///
+/// # Arguments
+/// * `spectrum` - MS2 spectrum with sorted m/z values
+/// * `peptide` - Peptide
+/// * `tolerance` - Fragment tolerance
+/// * `ion_types` - Ion types to match
+/// * `max_fragment_charge` - Maximum fragment charge
+///
+pub fn calculate_matched_ion_statistics<S>(
+    spectrum: &S,
+    peptide: &ModifiedPeptide,
+    tolerance: &Tolerance,
+    ion_types: &[IonType],
+    max_fragment_charge: u8,
+) -> Result<MatchedIonStatistics>
+where
+    S: Spectrum,
+{
+    let fragment_ions = calculate_fragment_ions(peptide, ion_types, max_fragment_charge)?;
+    let matches = match_fragment_ions(spectrum, &fragment_ions, tolerance);
+
+    let matched_peaks: HashSet<usize> = matches.iter().map(|(_, peak_idx)| *peak_idx).collect();
+    let total_ion_current: f64 = spectrum.get_intensity().iter().sum();
+    let matched_intensity: f64 = matched_peaks
+        .iter()
+        .map(|peak_idx| spectrum.get_intensity()[*peak_idx])
+        .sum();
+    let fraction_explained_intensity = match total_ion_current > 0.0 {
+        true => matched_intensity / total_ion_current,
+        false => 0.0,
+    };
+
+    let (n_terminal_ordinals, c_terminal_ordinals): (Vec<_>, Vec<_>) = matches
+        .iter()
+        .map(|(ion_idx, _)| &fragment_ions[*ion_idx])
+        .partition(|ion| ion.get_ion_type().is_n_terminal());
+    let n_terminal_ordinals: HashSet<usize> = n_terminal_ordinals
+        .iter()
+        .map(|ion| ion.get_ordinal())
+        .collect();
+    let c_terminal_ordinals: HashSet<usize> = c_terminal_ordinals
+        .iter()
+        .map(|ion| ion.get_ordinal())
+        .collect();
+
+    Ok(MatchedIonStatistics {
+        theoretical_ions: fragment_ions.len(),
+        matched_ions: matches.len(),
+        matched_peaks: matched_peaks.len(),
+        fraction_explained_intensity,
+        longest_n_terminal_ladder: longest_ladder(&n_terminal_ordinals),
+        longest_c_terminal_ladder: longest_ladder(&c_terminal_ordinals),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mass_spectrometry::spectrum::SimpleSpectrum;
+    use crate::proteomics::peptide::Peptide;
+
+    #[test]
+    fn test_longest_ladder() {
+        assert_eq!(longest_ladder(&HashSet::new()), 0);
+        assert_eq!(longest_ladder(&HashSet::from([1, 2, 3, 5, 6])), 3);
+        assert_eq!(longest_ladder(&HashSet::from([4])), 1);
+    }
+
+    #[test]
+    fn test_calculate_matched_ion_statistics() {
+        let peptide: ModifiedPeptide = Peptide::new("PEPTIDE".to_string(), 0).unwrap().into();
+        // b2, b3, y1, y2 + noise
+        let spectrum = SimpleSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![148.06043, 227.10263, 263.08738, 300.0, 324.15539],
+            vec![50.0, 100.0, 25.0, 200.0, 25.0],
+        );
+        let statistics = calculate_matched_ion_statistics(
+            &spectrum,
+            &peptide,
+            &Tolerance::Ppm(10.0),
+            &[IonType::B, IonType::Y],
+            1,
+        )
+        .unwrap();
+        assert_eq!(statistics.get_theoretical_ions(), 12);
+        assert_eq!(statistics.get_matched_ions(), 4);
+        assert_eq!(statistics.get_matched_peaks(), 4);
+        assert!((statistics.get_fraction_explained_intensity() - 0.5).abs() < 1e-12);
+        assert_eq!(statistics.get_longest_n_terminal_ladder(), 2);
+        assert_eq!(statistics.get_longest_c_terminal_ladder(), 2);
+        assert_eq!(statistics.get_longest_ion_ladder(), 2);
+    }
+}
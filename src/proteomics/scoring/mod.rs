@@ -0,0 +1,10 @@
+/// Andromeda-like binomial score
+pub mod binomial;
+/// Common functions used by the different scores, like fragment ion matching
+pub mod functions;
+/// X!Tandem hyperscore
+pub mod hyperscore;
+/// Matched ion statistics like fraction of explained intensity and ion ladders
+pub mod matched_ion_statistics;
+/// SEQUEST XCorr with fast preprocessing
+pub mod xcorr;
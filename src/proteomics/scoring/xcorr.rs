@@ -0,0 +1,198 @@
+// std imports
+use std::collections::HashSet;
+
+// 3rd party imports
+use anyhow::{bail, Result};
+
+// internal imports
+use crate::mass_spectrometry::spectrum::Spectrum;
+use crate::mass_spectrometry::tolerance::Tolerance;
+use crate::proteomics::fragmentation::{calculate_fragment_ions, IonType};
+use crate::proteomics::peptide::ModifiedPeptide;
+
+/// Default bin offset as used by Comet
+///
+pub const DEFAULT_BIN_OFFSET: f64 = 0.4;
+
+/// Number of windows used for intensity normalization
+///
+const NORMALIZATION_WINDOWS: usize = 10;
+
+/// Maximum intensity of each normalization window
+///
+const NORMALIZATION_WINDOW_INTENSITY: f64 = 50.0;
+
+/// Number of bins left and right of a bin which are used for the background subtraction
+///
+const BACKGROUND_OFFSET: usize = 75;
+
+/// Intensity of the theoretical peaks
+///
+const THEORETICAL_PEAK_INTENSITY: f64 = 50.0;
+
+/// Scaling of the final score
+///
+const SCORE_SCALING: f64 = 10_000.0;
+
+/// Spectrum preprocessed for the fast SEQUEST XCorr calculation.
+/// The background subtraction is done once, so the XCorr for each peptide
+/// is a simple sum over the bins of the theoretical fragment ions.
+///
+/// > Eng JK, Fischer B, Grossmann J, MacCoss MJ. A fast SEQUEST cross correlation algorithm.
+/// > J Proteome Res. 2008;7(10):4598-4602. https://doi.org/10.1021/pr800420s
+///
+pub struct XCorrSpectrum {
+    bin_width: f64,
+    bin_offset: f64,
+    preprocessed_intensities: Vec<f64>,
+}
+
+impl XCorrSpectrum {
+    /// Bins and preprocesses the given spectrum
+    ///
+    /// # Arguments
+    /// * `spectrum` - MS2 spectrum
+    /// * `bin_width` - Width of a bin, e.g. 1.0005079 for low resolution or 0.02 for high resolution data
+    /// * `bin_offset` - Offset of the bins, see `DEFAULT_BIN_OFFSET`
+    ///
+    pub fn new<S>(spectrum: &S, bin_width: f64, bin_offset: f64) -> Result<Self>
+    where
+        S: Spectrum,
+    {
+        if bin_width <= 0.0 {
+            bail!("Bin width must be greater than 0");
+        }
+        let max_mz = spectrum.get_mz().iter().cloned().fold(0.0, f64::max);
+        let num_bins = Self::get_bin(max_mz, bin_width, bin_offset) + 1;
+
+        // bin square root intensities, keep the max
+        let mut binned_intensities = vec![0.0; num_bins];
+        for (mz, intensity) in spectrum.get_mz().iter().zip(spectrum.get_intensity()) {
+            let bin = Self::get_bin(*mz, bin_width, bin_offset);
+            binned_intensities[bin] = f64::max(binned_intensities[bin], intensity.sqrt());
+        }
+
+        // normalize intensities in windows
+        let window_size = num_bins / NORMALIZATION_WINDOWS + 1;
+        for window in binned_intensities.chunks_mut(window_size) {
+            let max_intensity = window.iter().cloned().fold(0.0, f64::max);
+            if max_intensity > 0.0 {
+                window.iter_mut().for_each(|intensity| {
+                    *intensity = *intensity / max_intensity * NORMALIZATION_WINDOW_INTENSITY
+                });
+            }
+        }
+
+        // subtract the mean of the surrounding bins
+        let mut prefix_sums = vec![0.0; num_bins + 1];
+        for (bin, intensity) in binned_intensities.iter().enumerate() {
+            prefix_sums[bin + 1] = prefix_sums[bin] + intensity;
+        }
+        let preprocessed_intensities = binned_intensities
+            .iter()
+            .enumerate()
+            .map(|(bin, intensity)| {
+                let start = bin.saturating_sub(BACKGROUND_OFFSET);
+                let end = usize::min(bin + BACKGROUND_OFFSET + 1, num_bins);
+                let background = prefix_sums[end] - prefix_sums[start] - intensity;
+                intensity - background / (2 * BACKGROUND_OFFSET) as f64
+            })
+            .collect();
+
+        Ok(Self {
+            bin_width,
+            bin_offset,
+            preprocessed_intensities,
+        })
+    }
+
+    /// Returns the bin of the given m/z
+    ///
+    fn get_bin(mz: f64, bin_width: f64, bin_offset: f64) -> usize {
+        (mz / bin_width + 1.0 - bin_offset).max(0.0) as usize
+    }
+
+    /// Returns the preprocessed intensities
+    ///
+    pub fn get_preprocessed_intensities(&self) -> &[f64] {
+        &self.preprocessed_intensities
+    }
+
+    /// Calculates the XCorr using b- and y-ions
+    ///
+    /// # Arguments
+    /// * `peptide` - Peptide
+    /// * `max_fragment_charge` - Maximum fragment charge
+    ///
+    pub fn score(&self, peptide: &ModifiedPeptide, max_fragment_charge: u8) -> Result<f64> {
+        let fragment_ions =
+            calculate_fragment_ions(peptide, &[IonType::B, IonType::Y], max_fragment_charge)?;
+        let bins: HashSet<usize> = fragment_ions
+            .iter()
+            .map(|ion| Self::get_bin(ion.get_mz(), self.bin_width, self.bin_offset))
+            .filter(|bin| *bin < self.preprocessed_intensities.len())
+            .collect();
+        let dot_product: f64 = bins
+            .iter()
+            .map(|bin| self.preprocessed_intensities[*bin] * THEORETICAL_PEAK_INTENSITY)
+            .sum();
+        Ok(dot_product / SCORE_SCALING)
+    }
+}
+
+/// Calculates the SEQUEST XCorr using the fast preprocessing.
+/// The bin width is twice the absolute tolerance, e.g. `Tolerance::Da(0.50025)` results in
+/// the common low resolution bin width of 1.0005.
+/// When scoring multiple peptides against the same spectrum use `XCorrSpectrum` directly.
+///
+/// # Arguments
+/// * `spectrum` - MS2 spectrum
+/// * `peptide` - Peptide
+/// * `tolerance` - Fragment tolerance, needs to be absolute
+/// * `max_fragment_charge` - Maximum fragment charge
+///
+pub fn xcorr<S>(
+    spectrum: &S,
+    peptide: &ModifiedPeptide,
+    tolerance: &Tolerance,
+    max_fragment_charge: u8,
+) -> Result<f64>
+where
+    S: Spectrum,
+{
+    let bin_width = match tolerance {
+        Tolerance::Da(da) => 2.0 * da,
+        Tolerance::Ppm(_) => bail!("XCorr requires an absolute fragment tolerance (Da)"),
+    };
+    XCorrSpectrum::new(spectrum, bin_width, DEFAULT_BIN_OFFSET)?.score(peptide, max_fragment_charge)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mass_spectrometry::spectrum::SimpleSpectrum;
+    use crate::proteomics::peptide::Peptide;
+
+    #[test]
+    fn test_xcorr() {
+        let peptide: ModifiedPeptide = Peptide::new("PEPTIDE".to_string(), 0).unwrap().into();
+        let other_peptide: ModifiedPeptide = Peptide::new("GGGGGGG".to_string(), 0).unwrap().into();
+        // b2, b3, y1, y2 + noise
+        let spectrum = SimpleSpectrum::new(
+            "test".to_string(),
+            2,
+            vec![148.06043, 227.10263, 263.08738, 300.0, 324.15539, 700.0],
+            vec![50.0, 100.0, 25.0, 200.0, 25.0, 10.0],
+        );
+        let tolerance = Tolerance::Da(0.50025);
+        let score = xcorr(&spectrum, &peptide, &tolerance, 1).unwrap();
+        let other_score = xcorr(&spectrum, &other_peptide, &tolerance, 1).unwrap();
+        assert!(score > 0.0);
+        assert!(score > other_score);
+
+        let preprocessed = XCorrSpectrum::new(&spectrum, 1.0005079, DEFAULT_BIN_OFFSET).unwrap();
+        assert!((preprocessed.score(&peptide, 1).unwrap() - score).abs() < 0.01);
+
+        assert!(xcorr(&spectrum, &peptide, &Tolerance::Ppm(10.0), 1).is_err());
+    }
+}
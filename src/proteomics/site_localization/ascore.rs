@@ -0,0 +1,265 @@
+// std imports
+use std::collections::HashSet;
+
+// 3rd party imports
+use anyhow::Result;
+
+// internal imports
+use crate::mass_spectrometry::processing::filter::filter_top_n_peaks_per_window;
+use crate::mass_spectrometry::spectrum::{SimpleSpectrum, Spectrum};
+use crate::mass_spectrometry::tolerance::Tolerance;
+use crate::proteomics::fragmentation::{calculate_fragment_ions, FragmentIon, IonType};
+use crate::proteomics::peptide::ModifiedPeptide;
+use crate::proteomics::post_translational_modifications::PostTranslationalModification;
+use crate::proteomics::scoring::binomial::MAX_PEAKS_PER_WINDOW;
+use crate::proteomics::scoring::functions::{binomial_tail_probability, match_fragment_ions};
+use crate::proteomics::site_localization::isoforms::{
+    generate_isoforms, get_modified_sites, SiteScore,
+};
+
+/// Ascore of sites without any competing isoform
+///
+pub const UNAMBIGUOUS_ASCORE: f64 = 1000.0;
+
+/// Width of the windows used for peak filtering
+///
+const WINDOW_WIDTH: f64 = 100.0;
+
+/// Weights of the peak depths 1 to 10 for the peptide score
+///
+const PEAK_DEPTH_WEIGHTS: [f64; MAX_PEAKS_PER_WINDOW] =
+    [0.5, 0.75, 1.0, 1.0, 1.0, 1.0, 0.75, 0.5, 0.25, 0.25];
+
+/// Minimum m/z difference of site determining ions
+///
+const SITE_DETERMINING_MZ_DIFFERENCE: f64 = 1e-6;
+
+/// Isoform with its fragment ions and the peptide score
+///
+struct ScoredIsoform {
+    isoform: ModifiedPeptide,
+    modified_sites: Vec<usize>,
+    fragment_ions: Vec<FragmentIon>,
+    peptide_score: f64,
+}
+
+/// Calculates `-10 * log10(P)` where `P` is the probability to match at least as many of the
+/// given fragment ions by chance with `peak depth / 100` as probability of a random match.
+///
+fn score_fragment_ions(
+    filtered_spectrum: &SimpleSpectrum,
+    fragment_ions: &[FragmentIon],
+    peaks_per_window: usize,
+    tolerance: &Tolerance,
+) -> f64 {
+    let matches = match_fragment_ions(filtered_spectrum, fragment_ions, tolerance).len();
+    -10.0
+        * binomial_tail_probability(
+            fragment_ions.len(),
+            matches,
+            peaks_per_window as f64 / WINDOW_WIDTH,
+        )
+        .max(f64::MIN_POSITIVE)
+        .log10()
+}
+
+/// Returns the fragment ions of the isoform whose m/z differ from the same ion of the other
+/// isoform. Both isoforms need to be generated from the same peptide, so the ion lists align.
+///
+fn get_site_determining_ions(isoform: &ScoredIsoform, other: &ScoredIsoform) -> Vec<FragmentIon> {
+    isoform
+        .fragment_ions
+        .iter()
+        .zip(other.fragment_ions.iter())
+        .filter(|(ion, other_ion)| {
+            (ion.get_mz() - other_ion.get_mz()).abs() > SITE_DETERMINING_MZ_DIFFERENCE
+        })
+        .map(|(ion, _)| ion.clone())
+        .collect()
+}
+
+/// Calculates the Ascore of each modified site of the best scoring isoform.
+///
+/// All positional isoforms are ranked by a peptide score, which is the weighted mean of the
+/// binomial scores over the peak depths of 1 to 10 peaks per 100 m/z.
+/// For each modified site of the best isoform, the best isoform without this site is used
+/// as competitor. Both are scored using only their site determining ions at the peak depth
+/// with the highest score difference, which is the Ascore.
+/// Sites without a competing isoform get an Ascore of `UNAMBIGUOUS_ASCORE`.
+///
+/// > Beausoleil SA, Villén J, Gerber SA, Rush J, Gygi SP. A probability-based approach for
+/// > high-throughput protein phosphorylation analysis and site localization.
+/// > Nat Biotechnol. 2006;24(10):1285-1292. https://doi.org/10.1038/nbt1240
+///
+/// # Arguments
+/// * `spectrum` - MS2 spectrum with sorted m/z values
+/// * `peptide` - Peptide carrying the modifications to localise at arbitrary candidate sites
+/// * `localized_modifications` - Modifications to localise, e.g. phosphorylation of S, T and Y
+/// * `tolerance` - Fragment tolerance
+/// * `max_fragment_charge` - Maximum fragment charge
+///
+pub fn ascore<S>(
+    spectrum: &S,
+    peptide: &ModifiedPeptide,
+    localized_modifications: &[PostTranslationalModification],
+    tolerance: &Tolerance,
+    max_fragment_charge: u8,
+) -> Result<Vec<SiteScore>>
+where
+    S: Spectrum,
+{
+    let filtered_spectra: Vec<SimpleSpectrum> = (1..=MAX_PEAKS_PER_WINDOW)
+        .map(|peaks_per_window| {
+            filter_top_n_peaks_per_window(spectrum, peaks_per_window, WINDOW_WIDTH)
+        })
+        .collect();
+    let weight_sum: f64 = PEAK_DEPTH_WEIGHTS.iter().sum();
+
+    let mut scored_isoforms = generate_isoforms(peptide, localized_modifications)?
+        .into_iter()
+        .map(|isoform| {
+            let fragment_ions =
+                calculate_fragment_ions(&isoform, &[IonType::B, IonType::Y], max_fragment_charge)?;
+            let peptide_score = filtered_spectra
+                .iter()
+                .zip(PEAK_DEPTH_WEIGHTS.iter())
+                .enumerate()
+                .map(|(depth_idx, (filtered_spectrum, weight))| {
+                    weight
+                        * score_fragment_ions(
+                            filtered_spectrum,
+                            &fragment_ions,
+                            depth_idx + 1,
+                            tolerance,
+                        )
+                })
+                .sum::<f64>()
+                / weight_sum;
+            Ok(ScoredIsoform {
+                modified_sites: get_modified_sites(&isoform, localized_modifications),
+                isoform,
+                fragment_ions,
+                peptide_score,
+            })
+        })
+        .collect::<Result<Vec<ScoredIsoform>>>()?;
+    // stable sort keeps the site order for equally scoring isoforms
+    scored_isoforms.sort_by(|x, y| y.peptide_score.total_cmp(&x.peptide_score));
+
+    let best_isoform = &scored_isoforms[0];
+    let sequence: Vec<char> = best_isoform.isoform.get_sequence().chars().collect();
+    let best_sites: HashSet<usize> = best_isoform.modified_sites.iter().cloned().collect();
+    Ok(best_isoform
+        .modified_sites
+        .iter()
+        .map(|site| {
+            // prefer competitors which only move this site
+            let competitors: Vec<&ScoredIsoform> = scored_isoforms[1..]
+                .iter()
+                .filter(|other| !other.modified_sites.contains(site))
+                .collect();
+            let competitor = competitors
+                .iter()
+                .find(|other| {
+                    other
+                        .modified_sites
+                        .iter()
+                        .filter(|other_site| !best_sites.contains(other_site))
+                        .count()
+                        == 1
+                })
+                .or(competitors.first());
+            let competitor = match competitor {
+                Some(competitor) => competitor,
+                None => return SiteScore::new(*site, sequence[*site], UNAMBIGUOUS_ASCORE),
+            };
+
+            let site_determining_ions = get_site_determining_ions(best_isoform, competitor);
+            let competitor_site_determining_ions =
+                get_site_determining_ions(competitor, best_isoform);
+            let score = filtered_spectra
+                .iter()
+                .enumerate()
+                .map(|(depth_idx, filtered_spectrum)| {
+                    score_fragment_ions(
+                        filtered_spectrum,
+                        &site_determining_ions,
+                        depth_idx + 1,
+                        tolerance,
+                    ) - score_fragment_ions(
+                        filtered_spectrum,
+                        &competitor_site_determining_ions,
+                        depth_idx + 1,
+                        tolerance,
+                    )
+                })
+                .fold(f64::NEG_INFINITY, f64::max);
+            SiteScore::new(*site, sequence[*site], score)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chemistry::amino_acid::get_amino_acid_by_one_letter_code;
+    use crate::proteomics::peptide::Peptide;
+    use crate::proteomics::post_translational_modifications::{ModificationType, Position};
+
+    #[test]
+    fn test_ascore() {
+        let phospho: Vec<PostTranslationalModification> = ['S', 'T', 'Y']
+            .into_iter()
+            .map(|code| {
+                PostTranslationalModification::new(
+                    "Phospho",
+                    get_amino_acid_by_one_letter_code(code).unwrap(),
+                    79.966331,
+                    ModificationType::Variable,
+                    Position::Anywhere,
+                )
+            })
+            .collect();
+        // phosphorylated on T, but reported on S
+        let true_isoform = ModifiedPeptide::new(
+            Peptide::new("PESTIDEK".to_string(), 0).unwrap(),
+            vec![(3, phospho[1].clone())],
+        )
+        .unwrap();
+        let reported_isoform = ModifiedPeptide::new(
+            Peptide::new("PESTIDEK".to_string(), 0).unwrap(),
+            vec![(2, phospho[0].clone())],
+        )
+        .unwrap();
+        let mut mz: Vec<f64> = calculate_fragment_ions(&true_isoform, &[IonType::B, IonType::Y], 1)
+            .unwrap()
+            .iter()
+            .map(|ion| ion.get_mz())
+            .collect();
+        mz.sort_by(|x, y| x.total_cmp(y));
+        let intensity = vec![100.0; mz.len()];
+        let spectrum = SimpleSpectrum::new("test".to_string(), 2, mz, intensity);
+
+        let site_scores = ascore(
+            &spectrum,
+            &reported_isoform,
+            &phospho,
+            &Tolerance::Da(0.02),
+            1,
+        )
+        .unwrap();
+        assert_eq!(site_scores.len(), 1);
+        assert_eq!(site_scores[0].get_position(), 3);
+        assert_eq!(site_scores[0].get_residue(), 'T');
+        assert!(site_scores[0].get_score() > 20.0);
+
+        // single candidate site
+        let peptide = ModifiedPeptide::new(
+            Peptide::new("PEPTIDEK".to_string(), 0).unwrap(),
+            vec![(3, phospho[1].clone())],
+        )
+        .unwrap();
+        let site_scores = ascore(&spectrum, &peptide, &phospho, &Tolerance::Da(0.02), 1).unwrap();
+        assert_eq!(site_scores[0].get_score(), UNAMBIGUOUS_ASCORE);
+    }
+}
@@ -0,0 +1,293 @@
+// 3rd party imports
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::proteomics::peptide::{ModifiedPeptide, Terminus};
+use crate::proteomics::post_translational_modifications::{
+    Position, PostTranslationalModification,
+};
+
+/// Score of a single candidate site, e.g. an Ascore or a localisation probability
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SiteScore {
+    position: usize,
+    residue: char,
+    score: f64,
+}
+
+impl SiteScore {
+    /// Creates a new site score
+    ///
+    /// # Arguments
+    /// * `position` - 0-based residue index
+    /// * `residue` - One letter code of the residue
+    /// * `score` - Score
+    ///
+    pub fn new(position: usize, residue: char, score: f64) -> Self {
+        Self {
+            position,
+            residue,
+            score,
+        }
+    }
+
+    /// Returns the 0-based residue index
+    ///
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the one letter code of the residue
+    ///
+    pub fn get_residue(&self) -> char {
+        self.residue
+    }
+
+    /// Returns the score
+    ///
+    pub fn get_score(&self) -> f64 {
+        self.score
+    }
+}
+
+/// Returns true if both modifications have the same name, amino acid and mass delta
+///
+fn is_same_modification(
+    modification: &PostTranslationalModification,
+    other: &PostTranslationalModification,
+) -> bool {
+    modification.get_name() == other.get_name()
+        && modification.get_amino_acid().get_code() == other.get_amino_acid().get_code()
+        && (modification.get_mass_delta() - other.get_mass_delta()).abs() < 1e-6
+}
+
+/// Returns true if the modification is one of the modifications to localise
+///
+fn is_localized_modification(
+    modification: &PostTranslationalModification,
+    localized_modifications: &[PostTranslationalModification],
+) -> bool {
+    localized_modifications
+        .iter()
+        .any(|localized_modification| is_same_modification(modification, localized_modification))
+}
+
+/// Returns the first of the given modifications which can be placed on the residue
+/// according to its amino acid and position.
+///
+/// # Arguments
+/// * `modifications` - Modifications
+/// * `sequence` - Peptide sequence
+/// * `idx` - Residue index
+///
+fn get_applicable_modification<'a>(
+    modifications: &'a [PostTranslationalModification],
+    sequence: &[char],
+    idx: usize,
+) -> Option<&'a PostTranslationalModification> {
+    modifications.iter().find(|modification| {
+        if !sequence[idx].eq_ignore_ascii_case(modification.get_amino_acid().get_code()) {
+            return false;
+        }
+        match modification.get_position() {
+            Position::Terminus(Terminus::N) => idx == 0,
+            Position::Terminus(Terminus::C) => idx == sequence.len() - 1,
+            _ => true,
+        }
+    })
+}
+
+/// Returns the residue indices which carry one of the modifications to localise
+///
+/// # Arguments
+/// * `peptide` - Modified peptide
+/// * `localized_modifications` - Modifications to localise, e.g. phosphorylation of S, T and Y
+///
+pub fn get_modified_sites(
+    peptide: &ModifiedPeptide,
+    localized_modifications: &[PostTranslationalModification],
+) -> Vec<usize> {
+    peptide
+        .get_modifications()
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, modification)| match modification {
+            Some(modification)
+                if is_localized_modification(modification, localized_modifications) =>
+            {
+                Some(idx)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the residue indices which can carry one of the modifications to localise.
+/// Residues carrying other modifications are excluded.
+///
+/// # Arguments
+/// * `peptide` - Modified peptide
+/// * `localized_modifications` - Modifications to localise, e.g. phosphorylation of S, T and Y
+///
+pub fn get_candidate_sites(
+    peptide: &ModifiedPeptide,
+    localized_modifications: &[PostTranslationalModification],
+) -> Vec<usize> {
+    let sequence: Vec<char> = peptide.get_sequence().chars().collect();
+    peptide
+        .get_modifications()
+        .iter()
+        .enumerate()
+        .filter(|(_, modification)| match modification {
+            Some(modification) => is_localized_modification(modification, localized_modifications),
+            None => true,
+        })
+        .filter(|(idx, _)| {
+            get_applicable_modification(localized_modifications, &sequence, *idx).is_some()
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Returns all combinations of `k` elements of the given list
+///
+fn get_combinations(elements: &[usize], k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    if elements.len() < k {
+        return Vec::new();
+    }
+    let mut combinations = Vec::new();
+    for (idx, element) in elements.iter().enumerate() {
+        for mut combination in get_combinations(&elements[idx + 1..], k - 1) {
+            combination.insert(0, *element);
+            combinations.push(combination);
+        }
+    }
+    combinations
+}
+
+/// Generates all positional isoforms of the peptide by distributing the modifications to
+/// localise over all candidate sites. The number of placed modifications equals the number
+/// of these modifications in the given peptide, other modifications, including terminal
+/// modifications, are kept.
+/// The isoforms are sorted by their modified sites.
+///
+/// # Arguments
+/// * `peptide` - Modified peptide
+/// * `localized_modifications` - Modifications to localise, e.g. phosphorylation of S, T and Y
+///
+pub fn generate_isoforms(
+    peptide: &ModifiedPeptide,
+    localized_modifications: &[PostTranslationalModification],
+) -> Result<Vec<ModifiedPeptide>> {
+    let modification_count = get_modified_sites(peptide, localized_modifications).len();
+    if modification_count == 0 {
+        bail!(
+            "Peptide `{}` does not carry any of the modifications to localise",
+            peptide
+        );
+    }
+    let sequence: Vec<char> = peptide.get_sequence().chars().collect();
+    let fixed_modifications: Vec<(usize, PostTranslationalModification)> = peptide
+        .get_modifications()
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, modification)| match modification {
+            Some(modification)
+                if !is_localized_modification(modification, localized_modifications) =>
+            {
+                Some((idx, modification.clone()))
+            }
+            _ => None,
+        })
+        .chain(
+            peptide
+                .get_n_terminal_modification()
+                .map(|modification| (0, modification.clone())),
+        )
+        .chain(
+            peptide
+                .get_c_terminal_modification()
+                .map(|modification| (sequence.len().saturating_sub(1), modification.clone())),
+        )
+        .collect();
+
+    get_combinations(
+        &get_candidate_sites(peptide, localized_modifications),
+        modification_count,
+    )
+    .into_iter()
+    .map(|sites| {
+        let mut modifications = fixed_modifications.clone();
+        for site in sites {
+            // candidate sites always have an applicable modification
+            if let Some(modification) =
+                get_applicable_modification(localized_modifications, &sequence, site)
+            {
+                modifications.push((site, modification.clone()));
+            }
+        }
+        ModifiedPeptide::new(peptide.get_peptide().clone(), modifications)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chemistry::amino_acid::get_amino_acid_by_one_letter_code;
+    use crate::proteomics::peptide::Peptide;
+    use crate::proteomics::post_translational_modifications::ModificationType;
+
+    #[test]
+    fn test_generate_isoforms() {
+        let phospho: Vec<PostTranslationalModification> = ['S', 'T', 'Y']
+            .into_iter()
+            .map(|code| {
+                PostTranslationalModification::new(
+                    "Phospho",
+                    get_amino_acid_by_one_letter_code(code).unwrap(),
+                    79.966331,
+                    ModificationType::Variable,
+                    Position::Anywhere,
+                )
+            })
+            .collect();
+        let oxidation = PostTranslationalModification::new(
+            "Oxidation",
+            get_amino_acid_by_one_letter_code('M').unwrap(),
+            15.994915,
+            ModificationType::Variable,
+            Position::Anywhere,
+        );
+        let peptide = ModifiedPeptide::new(
+            Peptide::new("SMTPYK".to_string(), 0).unwrap(),
+            vec![(0, phospho[0].clone()), (1, oxidation)],
+        )
+        .unwrap();
+
+        assert_eq!(get_modified_sites(&peptide, &phospho), vec![0]);
+        assert_eq!(get_candidate_sites(&peptide, &phospho), vec![0, 2, 4]);
+
+        let isoforms = generate_isoforms(&peptide, &phospho).unwrap();
+        let isoform_strings: Vec<String> = isoforms.iter().map(|x| x.to_string()).collect();
+        assert_eq!(
+            isoform_strings,
+            vec![
+                "S[Phospho]M[Oxidation]TPYK",
+                "SM[Oxidation]T[Phospho]PYK",
+                "SM[Oxidation]TPY[Phospho]K",
+            ]
+        );
+        for isoform in isoforms.iter() {
+            assert!((isoform.get_mass() - peptide.get_mass()).abs() < 1e-9);
+        }
+
+        let unmodified: ModifiedPeptide = Peptide::new("SMTPYK".to_string(), 0).unwrap().into();
+        assert!(generate_isoforms(&unmodified, &phospho).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+/// Ascore for the localisation of modification sites
+pub mod ascore;
+/// Candidate sites, isoform generation and the site score type shared by the localisation scores
+pub mod isoforms;
+/// phosphoRS-like isoform and site probabilities
+pub mod probability;
@@ -0,0 +1,234 @@
+// 3rd party imports
+use anyhow::Result;
+
+// internal imports
+use crate::mass_spectrometry::processing::filter::filter_top_n_peaks_per_window;
+use crate::mass_spectrometry::spectrum::Spectrum;
+use crate::mass_spectrometry::tolerance::Tolerance;
+use crate::proteomics::fragmentation::{calculate_fragment_ions, FragmentIon, IonType};
+use crate::proteomics::peptide::ModifiedPeptide;
+use crate::proteomics::post_translational_modifications::PostTranslationalModification;
+use crate::proteomics::scoring::binomial::MAX_PEAKS_PER_WINDOW;
+use crate::proteomics::scoring::functions::{binomial_tail_probability, match_fragment_ions};
+use crate::proteomics::site_localization::isoforms::{
+    generate_isoforms, get_candidate_sites, get_modified_sites, SiteScore,
+};
+
+/// Width of the windows used for peak filtering
+///
+const WINDOW_WIDTH: f64 = 100.0;
+
+/// Calculates the phosphoRS-like score `-log10(P)` of each isoform for the given peak depth,
+/// where `P` is the probability to match at least as many fragment ions by chance.
+/// The probability of a random match is `peak depth * 2 * tolerance / window width`
+/// using the absolute tolerance at the mean fragment m/z.
+///
+fn score_isoforms<S>(
+    spectrum: &S,
+    isoform_fragment_ions: &[Vec<FragmentIon>],
+    tolerance: &Tolerance,
+    peaks_per_window: usize,
+) -> Vec<f64>
+where
+    S: Spectrum,
+{
+    let filtered_spectrum = filter_top_n_peaks_per_window(spectrum, peaks_per_window, WINDOW_WIDTH);
+    isoform_fragment_ions
+        .iter()
+        .map(|fragment_ions| {
+            if fragment_ions.is_empty() {
+                return 0.0;
+            }
+            let mean_mz = fragment_ions.iter().map(|ion| ion.get_mz()).sum::<f64>()
+                / fragment_ions.len() as f64;
+            let random_match_probability =
+                (peaks_per_window as f64 * 2.0 * tolerance.get_absolute(mean_mz) / WINDOW_WIDTH)
+                    .min(1.0);
+            let matches = match_fragment_ions(&filtered_spectrum, fragment_ions, tolerance).len();
+            -binomial_tail_probability(fragment_ions.len(), matches, random_match_probability)
+                .max(f64::MIN_POSITIVE)
+                .log10()
+        })
+        .collect()
+}
+
+/// Calculates the probability of each positional isoform of the peptide, similar to phosphoRS.
+/// For each isoform the probability `P` to match at least as many b- and y-ions by chance is
+/// calculated, using the peak depth (1 to `MAX_PEAKS_PER_WINDOW` peaks per 100 m/z)
+/// which separates the isoforms best. The isoform probabilities are `1/P` normalized to a sum of 1.
+///
+/// > Taus T, Köcher T, Pichler P, et al. Universal and confident phosphorylation site localization
+/// > using phosphoRS. J Proteome Res. 2011;10(12):5354-5362. https://doi.org/10.1021/pr200611n
+///
+/// # Arguments
+/// * `spectrum` - MS2 spectrum with sorted m/z values
+/// * `peptide` - Peptide carrying the modifications to localise at arbitrary candidate sites
+/// * `localized_modifications` - Modifications to localise, e.g. phosphorylation of S, T and Y
+/// * `tolerance` - Fragment tolerance
+/// * `max_fragment_charge` - Maximum fragment charge
+///
+pub fn calculate_isoform_probabilities<S>(
+    spectrum: &S,
+    peptide: &ModifiedPeptide,
+    localized_modifications: &[PostTranslationalModification],
+    tolerance: &Tolerance,
+    max_fragment_charge: u8,
+) -> Result<Vec<(ModifiedPeptide, f64)>>
+where
+    S: Spectrum,
+{
+    let isoforms = generate_isoforms(peptide, localized_modifications)?;
+    let isoform_fragment_ions = isoforms
+        .iter()
+        .map(|isoform| {
+            calculate_fragment_ions(isoform, &[IonType::B, IonType::Y], max_fragment_charge)
+        })
+        .collect::<Result<Vec<Vec<FragmentIon>>>>()?;
+
+    // use the peak depth with the highest score difference between the isoforms
+    let mut best_scores = vec![0.0; isoforms.len()];
+    let mut best_separation = f64::NEG_INFINITY;
+    for peaks_per_window in 1..=MAX_PEAKS_PER_WINDOW {
+        let scores = score_isoforms(
+            spectrum,
+            &isoform_fragment_ions,
+            tolerance,
+            peaks_per_window,
+        );
+        let max_score = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let min_score = scores.iter().cloned().fold(f64::INFINITY, f64::min);
+        if max_score - min_score > best_separation {
+            best_separation = max_score - min_score;
+            best_scores = scores;
+        }
+    }
+
+    // 1/P = 10^score, shifted by the max score to prevent overflows
+    let max_score = best_scores
+        .iter()
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max);
+    let weights: Vec<f64> = best_scores
+        .iter()
+        .map(|score| 10.0_f64.powf(score - max_score))
+        .collect();
+    let weight_sum: f64 = weights.iter().sum();
+    Ok(isoforms
+        .into_iter()
+        .zip(weights)
+        .map(|(isoform, weight)| (isoform, weight / weight_sum))
+        .collect())
+}
+
+/// Calculates the localisation probability of each candidate site, which is the sum of the
+/// probabilities of all isoforms modified at this site, see `calculate_isoform_probabilities`.
+/// The site probabilities sum up to the number of modifications to localise.
+///
+/// # Arguments
+/// * `spectrum` - MS2 spectrum with sorted m/z values
+/// * `peptide` - Peptide carrying the modifications to localise at arbitrary candidate sites
+/// * `localized_modifications` - Modifications to localise, e.g. phosphorylation of S, T and Y
+/// * `tolerance` - Fragment tolerance
+/// * `max_fragment_charge` - Maximum fragment charge
+///
+pub fn calculate_site_probabilities<S>(
+    spectrum: &S,
+    peptide: &ModifiedPeptide,
+    localized_modifications: &[PostTranslationalModification],
+    tolerance: &Tolerance,
+    max_fragment_charge: u8,
+) -> Result<Vec<SiteScore>>
+where
+    S: Spectrum,
+{
+    let isoform_probabilities = calculate_isoform_probabilities(
+        spectrum,
+        peptide,
+        localized_modifications,
+        tolerance,
+        max_fragment_charge,
+    )?;
+    let sequence: Vec<char> = peptide.get_sequence().chars().collect();
+    Ok(get_candidate_sites(peptide, localized_modifications)
+        .into_iter()
+        .map(|site| {
+            let probability = isoform_probabilities
+                .iter()
+                .filter(|(isoform, _)| {
+                    get_modified_sites(isoform, localized_modifications).contains(&site)
+                })
+                .map(|(_, probability)| probability)
+                .sum();
+            SiteScore::new(site, sequence[site], probability)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chemistry::amino_acid::get_amino_acid_by_one_letter_code;
+    use crate::mass_spectrometry::spectrum::SimpleSpectrum;
+    use crate::proteomics::peptide::Peptide;
+    use crate::proteomics::post_translational_modifications::{ModificationType, Position};
+
+    /// Returns the phosphorylations of S, T and Y
+    ///
+    fn get_phosphorylations() -> Vec<PostTranslationalModification> {
+        ['S', 'T', 'Y']
+            .into_iter()
+            .map(|code| {
+                PostTranslationalModification::new(
+                    "Phospho",
+                    get_amino_acid_by_one_letter_code(code).unwrap(),
+                    79.966331,
+                    ModificationType::Variable,
+                    Position::Anywhere,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_calculate_site_probabilities() {
+        let phospho = get_phosphorylations();
+        // phosphorylated on T, but reported on S
+        let true_isoform = ModifiedPeptide::new(
+            Peptide::new("PESTIDEK".to_string(), 0).unwrap(),
+            vec![(3, phospho[1].clone())],
+        )
+        .unwrap();
+        let reported_isoform = ModifiedPeptide::new(
+            Peptide::new("PESTIDEK".to_string(), 0).unwrap(),
+            vec![(2, phospho[0].clone())],
+        )
+        .unwrap();
+        let mut mz: Vec<f64> = calculate_fragment_ions(&true_isoform, &[IonType::B, IonType::Y], 1)
+            .unwrap()
+            .iter()
+            .map(|ion| ion.get_mz())
+            .collect();
+        mz.sort_by(|x, y| x.total_cmp(y));
+        let intensity = vec![100.0; mz.len()];
+        let spectrum = SimpleSpectrum::new("test".to_string(), 2, mz, intensity);
+
+        let site_probabilities = calculate_site_probabilities(
+            &spectrum,
+            &reported_isoform,
+            &phospho,
+            &Tolerance::Da(0.02),
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            site_probabilities
+                .iter()
+                .map(|site| (site.get_position(), site.get_residue()))
+                .collect::<Vec<(usize, char)>>(),
+            vec![(2, 'S'), (3, 'T')]
+        );
+        assert!(site_probabilities[1].get_score() > 0.99);
+        let probability_sum: f64 = site_probabilities.iter().map(|x| x.get_score()).sum();
+        assert!((probability_sum - 1.0).abs() < 1e-9);
+    }
+}
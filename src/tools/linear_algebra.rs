@@ -0,0 +1,58 @@
+// 3rd party imports
+use anyhow::{bail, Result};
+
+/// Solves the linear equation system `matrix * x = rhs` by Gaussian elimination with partial pivoting
+///
+/// # Arguments
+/// * `matrix` - Square coefficient matrix (row major)
+/// * `rhs` - Right hand side
+///
+pub fn solve_linear_system(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Result<Vec<f64>> {
+    let n = rhs.len();
+    if matrix.len() != n || matrix.iter().any(|row| row.len() != n) {
+        bail!("Matrix must be square and match the right hand side");
+    }
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|x, y| matrix[*x][col].abs().total_cmp(&matrix[*y][col].abs()))
+            .unwrap_or(col);
+        if matrix[pivot][col].abs() < f64::EPSILON {
+            bail!("Matrix is singular");
+        }
+        matrix.swap(col, pivot);
+        rhs.swap(col, pivot);
+        for row in col + 1..n {
+            let factor = matrix[row][col] / matrix[col][col];
+            let pivot_row = matrix[col].clone();
+            for (value, pivot_value) in matrix[row].iter_mut().zip(pivot_row).skip(col) {
+                *value -= factor * pivot_value;
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (rhs[row] - sum) / matrix[row][row];
+    }
+    Ok(solution)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_solve_linear_system() {
+        let matrix = vec![
+            vec![0.0, 2.0, 1.0],
+            vec![1.0, 1.0, 0.0],
+            vec![3.0, 0.0, 1.0],
+        ];
+        let solution = solve_linear_system(matrix, vec![7.0, 3.0, 6.0]).unwrap();
+        for (value, expected) in solution.iter().zip([1.0, 2.0, 3.0]) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        assert!(solve_linear_system(vec![vec![1.0, 2.0], vec![2.0, 4.0]], vec![1.0, 2.0]).is_err());
+    }
+}
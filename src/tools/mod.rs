@@ -1,7 +1,13 @@
 /// Functions which involves fancy_regex
 pub mod fancy_regex;
+/// Linear algebra functions, e.g. for solving small linear equation systems
+pub mod linear_algebra;
 /// Macros for making life easier
 #[macro_use]
 pub mod macros;
 /// Functions for processing data data of quick_xml
 pub mod quick_xml;
+/// Seeded pseudo random number generator
+pub mod random;
+/// Descriptive statistics, e.g. median
+pub mod statistics;
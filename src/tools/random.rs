@@ -0,0 +1,79 @@
+/// Small reproducible pseudo random number generator (SplitMix64),
+/// e.g. for imputation. Not suitable for cryptography.
+///
+/// > Steele et al. (2014) Fast splittable pseudorandom number generators. https://doi.org/10.1145/2714064.2660195
+///
+#[derive(Clone, Debug)]
+pub struct SeededRandom {
+    state: u64,
+}
+
+impl SeededRandom {
+    /// Creates a new generator
+    ///
+    /// # Arguments
+    /// * `seed` - Seed, the same seed generates the same sequence
+    ///
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns the next random 64 bit integer
+    ///
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed number in [0, 1)
+    ///
+    pub fn next_f64(&mut self) -> f64 {
+        // 53 bits of precision
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a normally distributed number using the Box-Muller transform
+    ///
+    /// # Arguments
+    /// * `mean` - Mean
+    /// * `standard_deviation` - Standard deviation
+    ///
+    pub fn next_gaussian(&mut self, mean: f64, standard_deviation: f64) -> f64 {
+        // 1 - [0, 1) to avoid ln(0)
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        mean + z * standard_deviation
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seeded_random() {
+        let mut random = SeededRandom::new(42);
+        let mut other = SeededRandom::new(42);
+        let values: Vec<u64> = (0..10).map(|_| random.next_u64()).collect();
+        let other_values: Vec<u64> = (0..10).map(|_| other.next_u64()).collect();
+        assert_eq!(values, other_values);
+        assert_ne!(SeededRandom::new(43).next_u64(), values[0]);
+
+        let samples: Vec<f64> = (0..10000).map(|_| random.next_f64()).collect();
+        assert!(samples.iter().all(|value| (0.0..1.0).contains(value)));
+
+        let samples: Vec<f64> = (0..10000).map(|_| random.next_gaussian(5.0, 2.0)).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / samples.len() as f64;
+        assert!((mean - 5.0).abs() < 0.1);
+        assert!((variance.sqrt() - 2.0).abs() < 0.1);
+    }
+}
@@ -0,0 +1,102 @@
+/// Returns the median of the values or None if there are no values.
+/// The values are sorted in place.
+///
+/// # Arguments
+/// * `values` - Values
+///
+pub fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|x, y| x.total_cmp(y));
+    let center = values.len() / 2;
+    match values.len().is_multiple_of(2) {
+        true => Some((values[center - 1] + values[center]) / 2.0),
+        false => Some(values[center]),
+    }
+}
+
+/// Returns the arithmetic mean of the values or None if there are no values.
+///
+/// # Arguments
+/// * `values` - Values
+///
+pub fn mean(values: &[f64]) -> Option<f64> {
+    match values.is_empty() {
+        true => None,
+        false => Some(values.iter().sum::<f64>() / values.len() as f64),
+    }
+}
+
+/// Returns the sample standard deviation of the values or None if there are less than 2 values.
+///
+/// # Arguments
+/// * `values` - Values
+///
+pub fn standard_deviation(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values)?;
+    let sum_of_squares: f64 = values.iter().map(|value| (value - mean).powi(2)).sum();
+    Some((sum_of_squares / (values.len() - 1) as f64).sqrt())
+}
+
+/// Returns the quantile of the values with linear interpolation between the closest ranks
+/// or None if there are no values. The values are sorted in place.
+///
+/// # Arguments
+/// * `values` - Values
+/// * `probability` - Probability in [0, 1], e.g. 0.5 for the median
+///
+pub fn quantile(values: &mut [f64], probability: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|x, y| x.total_cmp(y));
+    quantile_of_sorted(values, probability)
+}
+
+/// Same as `quantile` but for already sorted values
+///
+/// # Arguments
+/// * `sorted_values` - Values sorted in ascending order
+/// * `probability` - Probability in [0, 1], e.g. 0.5 for the median
+///
+pub fn quantile_of_sorted(sorted_values: &[f64], probability: f64) -> Option<f64> {
+    if sorted_values.is_empty() {
+        return None;
+    }
+    let position = probability.clamp(0.0, 1.0) * (sorted_values.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    Some(
+        sorted_values[lower]
+            + (sorted_values[upper] - sorted_values[lower]) * (position - lower as f64),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_median_and_mean() {
+        assert_eq!(median(&mut [3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&mut [4.0, 1.0, 3.0, 2.0]), Some(2.5));
+        assert_eq!(median(&mut []), None);
+        assert_eq!(mean(&[1.0, 2.0, 6.0]), Some(3.0));
+        assert_eq!(mean(&[]), None);
+    }
+
+    #[test]
+    fn test_standard_deviation_and_quantile() {
+        assert_eq!(standard_deviation(&[2.0, 4.0, 6.0]), Some(2.0));
+        assert_eq!(standard_deviation(&[2.0]), None);
+        assert_eq!(quantile(&mut [4.0, 1.0, 3.0, 2.0], 0.5), Some(2.5));
+        assert_eq!(quantile(&mut [4.0, 1.0, 3.0, 2.0], 0.0), Some(1.0));
+        assert_eq!(quantile(&mut [4.0, 1.0, 3.0, 2.0], 1.0), Some(4.0));
+        assert_eq!(quantile(&mut [5.0, 1.0], 0.25), Some(2.0));
+        assert_eq!(quantile(&mut [], 0.25), None);
+    }
+}